@@ -0,0 +1,352 @@
+use super::kb::KnowledgeBase;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use sbsearch::{Entry, Level};
+use std::collections::BTreeMap;
+
+/// Content markers that suggest a component crashed or restarted.
+const RESTART_MARKERS: &[&str] = &["panic", "restart", "oomkill", "crashloop"];
+
+/// Only gaps between consecutive entries longer than this are anomalies.
+const MIN_GAP_SECONDS: i64 = 60;
+
+/// A gap is an anomaly when it's this many times longer than the median gap.
+const GAP_FACTOR: i32 = 10;
+
+/// How suspicious a component looks, based on the matched entries of its logs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Suspect {
+    pub component: String,
+    pub score: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub restarts: usize,
+    pub gaps: usize,
+    pub known_issues: usize,
+}
+
+impl Suspect {
+    fn compute_score(&mut self) {
+        self.score = self.errors * 3
+            + self.warnings
+            + self.restarts * 5
+            + self.gaps * 2
+            + self.known_issues * 4;
+    }
+}
+
+/// Ranks the components of the entries by their suspicion score, highest
+/// first. Components with a zero score are left out.
+pub fn suspects(entries: &[Entry], root_dir: &str, kb: &KnowledgeBase) -> Vec<Suspect> {
+    let mut suspects: Vec<(Suspect, Vec<Entry>)> = Vec::new();
+    for entry in entries {
+        let name = component(&entry.path, root_dir);
+        let index = match suspects.iter().position(|(s, _)| s.component == name) {
+            Some(index) => index,
+            None => {
+                let suspect = Suspect {
+                    component: name,
+                    ..Default::default()
+                };
+                suspects.push((suspect, Vec::new()));
+                suspects.len() - 1
+            }
+        };
+
+        let (suspect, component_entries) = &mut suspects[index];
+        match entry.level {
+            Level::Error | Level::Fatal => suspect.errors += 1,
+            Level::Warn => suspect.warnings += 1,
+            _ => {}
+        }
+
+        let content = entry.content.to_lowercase();
+        if RESTART_MARKERS.iter().any(|m| content.contains(m)) {
+            suspect.restarts += 1;
+        }
+        if kb.lookup(&entry.content).is_some() {
+            suspect.known_issues += 1;
+        }
+        component_entries.push(entry.clone());
+    }
+
+    let mut ranked: Vec<Suspect> = suspects
+        .into_iter()
+        .map(|(mut suspect, component_entries)| {
+            suspect.gaps = count_gaps(&component_entries);
+            suspect.compute_score();
+            suspect
+        })
+        .filter(|s| s.score > 0)
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.component.cmp(&b.component))
+    });
+    ranked
+}
+
+/// Matches per minute of the timestamped entries, from the minute of the
+/// first entry to the minute of the last one. Only the minutes with matches
+/// are kept, so that a bogus timestamp years off doesn't take up a slot for
+/// each minute in between.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rate {
+    pub counts: BTreeMap<DateTime<Utc>, u64>,
+}
+
+impl Rate {
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        self.counts.keys().next().copied()
+    }
+
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        self.counts.keys().next_back().copied()
+    }
+
+    pub fn peak(&self) -> Option<u64> {
+        self.counts.values().max().copied()
+    }
+
+    /// Returns the number of minutes from the first minute to the last one.
+    pub fn minutes(&self) -> usize {
+        match (self.start(), self.end()) {
+            (Some(start), Some(end)) => (end - start).num_minutes() as usize + 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns the number of matches in the minute of the timestamp.
+    pub fn count_at(&self, timestamp: DateTime<Utc>) -> u64 {
+        let minute = timestamp.duration_trunc(TimeDelta::minutes(1)).ok();
+        minute
+            .and_then(|minute| self.counts.get(&minute))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the index of the minute of the timestamp.
+    pub fn index_of(&self, timestamp: DateTime<Utc>) -> Option<usize> {
+        let start = self.start().filter(|start| timestamp >= *start)?;
+        let index = (timestamp - start).num_minutes();
+        (0..self.minutes() as i64)
+            .contains(&index)
+            .then_some(index as usize)
+    }
+
+    /// Merges adjacent minutes so that the counts fit in `width` columns.
+    /// Returns the counts and the number of minutes per column.
+    pub fn resample(&self, width: usize) -> (Vec<u64>, usize) {
+        let Some(start) = self.start() else {
+            return (Vec::new(), 1);
+        };
+        let minutes = self.minutes();
+        let per_column = minutes.div_ceil(width.max(1)).max(1);
+        let mut counts = vec![0; minutes.div_ceil(per_column)];
+        for (minute, count) in &self.counts {
+            counts[(*minute - start).num_minutes() as usize / per_column] += count;
+        }
+        (counts, per_column)
+    }
+}
+
+pub fn rate_per_minute<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Rate {
+    let mut counts = BTreeMap::new();
+    for minute in entries
+        .into_iter()
+        .filter_map(|e| e.timestamp)
+        .filter_map(|ts| ts.duration_trunc(TimeDelta::minutes(1)).ok())
+    {
+        *counts.entry(minute).or_default() += 1;
+    }
+    Rate { counts }
+}
+
+/// Derives the component name from the log path, e.g. `<namespace>/<pod>` for
+/// pod logs and `<node>/<file>` for node logs.
+pub fn component(path: &str, root_dir: &str) -> String {
+    let relative = path.strip_prefix(root_dir).unwrap_or(path);
+    let parts: Vec<&str> = relative.split('/').filter(|p| !p.is_empty()).collect();
+    match parts.as_slice() {
+        ["logs", namespace, pod, ..] => format!("{}/{}", namespace, pod),
+        ["nodes", _, node, "logs", file, ..] => {
+            format!("{}/{}", node, file.trim_end_matches(".log"))
+        }
+        _ => String::from(relative.trim_start_matches('/')),
+    }
+}
+
+/// Counts the unusually long gaps between consecutive timestamped entries.
+fn count_gaps(entries: &[Entry]) -> usize {
+    let mut timestamps: Vec<_> = entries.iter().filter_map(|e| e.timestamp).collect();
+    timestamps.sort();
+
+    let mut gaps: Vec<TimeDelta> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+    if gaps.len() < 2 {
+        return 0;
+    }
+
+    let mut sorted = gaps.clone();
+    sorted.sort();
+    let median = sorted[sorted.len() / 2];
+    let threshold = (median * GAP_FACTOR).max(TimeDelta::seconds(MIN_GAP_SECONDS));
+    gaps.retain(|gap| *gap > threshold);
+    gaps.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn entry(path: &str, level: &str, content: &str, ts: &str) -> Entry {
+        Entry {
+            level: Level::normalize(level),
+            path: String::from(path),
+            content: String::from(content),
+            timestamp: Some(ts.parse::<DateTime<Utc>>().unwrap()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_component() {
+        assert_eq!(
+            component(
+                "testdata/support_bundle/logs/default/virt-launcher-vm-00-pb825/compute.log",
+                "testdata/support_bundle"
+            ),
+            "default/virt-launcher-vm-00-pb825"
+        );
+        assert_eq!(
+            component(
+                "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+                "testdata/support_bundle"
+            ),
+            "isim-dev/containerd"
+        );
+        assert_eq!(
+            component(
+                "testdata/support_bundle/metadata.yaml",
+                "testdata/support_bundle"
+            ),
+            "metadata.yaml"
+        );
+    }
+
+    #[test]
+    fn test_suspects() {
+        let root = "sb";
+        let entries = vec![
+            entry(
+                "sb/logs/ns/a/c.log",
+                "error",
+                "failed",
+                "2025-12-30T21:00:00Z",
+            ),
+            entry(
+                "sb/logs/ns/a/c.log",
+                "info",
+                "panic: boom",
+                "2025-12-30T21:00:01Z",
+            ),
+            entry(
+                "sb/logs/ns/b/c.log",
+                "warning",
+                "slow",
+                "2025-12-30T21:00:00Z",
+            ),
+            entry(
+                "sb/logs/ns/c/c.log",
+                "info",
+                "all good",
+                "2025-12-30T21:00:00Z",
+            ),
+        ];
+
+        let ranked = suspects(&entries, root, &KnowledgeBase::default());
+        assert_eq!(
+            ranked,
+            vec![
+                Suspect {
+                    component: String::from("ns/a"),
+                    score: 8,
+                    errors: 1,
+                    restarts: 1,
+                    ..Default::default()
+                },
+                Suspect {
+                    component: String::from("ns/b"),
+                    score: 1,
+                    warnings: 1,
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate_per_minute() {
+        let entries: Vec<Entry> = [
+            "2025-12-30T21:00:10Z",
+            "2025-12-30T21:00:50Z",
+            "2025-12-30T21:03:00Z",
+            "2025-12-30T21:01:30Z",
+        ]
+        .iter()
+        .map(|ts| entry("sb/logs/ns/a/c.log", "error", "", ts))
+        .collect();
+
+        let rate = rate_per_minute(&entries);
+        let start = "2025-12-30T21:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(rate.start(), Some(start));
+        assert_eq!(rate.minutes(), 4);
+        assert_eq!(rate.peak(), Some(2));
+        assert_eq!(rate.count_at(start + TimeDelta::seconds(95)), 1);
+        assert_eq!(rate.count_at(start + TimeDelta::minutes(2)), 0);
+        assert_eq!(rate.index_of(start + TimeDelta::seconds(95)), Some(1));
+        assert_eq!(rate.index_of(start - TimeDelta::seconds(1)), None);
+        assert_eq!(rate.index_of(start + TimeDelta::minutes(4)), None);
+
+        assert_eq!(rate.resample(10), (vec![2, 1, 0, 1], 1));
+        assert_eq!(rate.resample(2), (vec![3, 1], 2));
+        assert_eq!(rate.resample(3), (vec![3, 1], 2));
+
+        assert_eq!(rate_per_minute(&[]), Rate::default());
+        assert_eq!(Rate::default().resample(10), (vec![], 1));
+
+        // a bogus timestamp decades off only adds a single minute
+        let mut entries = entries;
+        entries.push(entry(
+            "sb/logs/ns/a/c.log",
+            "error",
+            "",
+            "1970-01-01T00:00:00Z",
+        ));
+        let rate = rate_per_minute(&entries);
+        assert_eq!(rate.counts.len(), 4);
+        let (counts, per_column) = rate.resample(80);
+        assert!(counts.len() <= 80);
+        assert_eq!(counts.iter().sum::<u64>(), 5);
+        assert_eq!(rate.index_of(start), Some(rate.minutes() - 4));
+        assert!(per_column > 1);
+    }
+
+    #[test]
+    fn test_count_gaps() {
+        let entries: Vec<Entry> = [
+            "2025-12-30T21:00:00Z",
+            "2025-12-30T21:00:01Z",
+            "2025-12-30T21:00:02Z",
+            "2025-12-30T21:00:03Z",
+            "2025-12-30T21:30:00Z",
+        ]
+        .iter()
+        .map(|ts| entry("sb/logs/ns/a/c.log", "info", "", ts))
+        .collect();
+        assert_eq!(count_gaps(&entries), 1);
+        assert_eq!(count_gaps(&entries[..4]), 0);
+    }
+}
@@ -0,0 +1,304 @@
+use chrono::Utc;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use log::*;
+use regex::Regex;
+use sbsearch::Session;
+use sbsearch::{self, Entry};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+/// The directory at the root of the escalation archive.
+const ARCHIVE_ROOT: &str = "escalation";
+
+/// Sensitive values masked in everything that goes into the escalation
+/// archive, with their replacement.
+const BUILTIN_REDACTIONS: &[(&str, &str)] = &[
+    (
+        r#"(?i)\b(password|passwd|secret|token|api[_-]?key)(["']?\s*[:=]\s*["']?)[^\s"',}]+"#,
+        "$1$2[REDACTED]",
+    ),
+    (r"(?i)\bbearer\s+[a-z0-9._~+/-]+=*", "Bearer [REDACTED]"),
+    (r"[\w.+-]+@[\w-]+\.[\w.-]+", "[REDACTED-EMAIL]"),
+    (r"\b(?:\d{1,3}\.){3}\d{1,3}\b", "[REDACTED-IP]"),
+];
+
+/// Masks sensitive values, like passwords, tokens, email and IP addresses, in
+/// the text shared with an escalation.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// Creates a redactor with the builtin rules, and `patterns` whose matches
+    /// are replaced with `[REDACTED]`.
+    pub fn new(patterns: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut rules = Vec::new();
+        for (pattern, replacement) in BUILTIN_REDACTIONS {
+            rules.push((Regex::new(pattern)?, String::from(*replacement)));
+        }
+        for pattern in patterns {
+            let regex = Regex::new(pattern)
+                .map_err(|e| format!("invalid redact pattern '{}': {}", pattern, e))?;
+            rules.push((regex, String::from("[REDACTED]")));
+        }
+        Ok(Redactor { rules })
+    }
+
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (regex, replacement) in &self.rules {
+            if let Cow::Owned(replaced) = regex.replace_all(&text, replacement.as_str()) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+}
+
+/// Packages the matching entries of the session for an upstream escalation,
+/// into a tar.gz archive at `output` holding:
+///
+/// - `query.txt`: the keyword, filter and search options
+/// - `metadata.yaml`: the metadata of the support bundle
+/// - `results.log`: the matching entries in time order, across all sources
+/// - `excerpts/<path>.txt`: the lines around the matches in each source file
+///
+/// Everything, including the file names, is redacted first. Returns the number
+/// of entries packaged.
+pub fn escalate(
+    session: &Session,
+    output: &Path,
+    context: usize,
+    redactor: &Redactor,
+) -> Result<usize, Box<dyn Error>> {
+    let root_dir = session.root_dir();
+    let mut entries: Vec<&Entry> = session.matching().collect();
+    entries.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.path.cmp(&b.path))
+            .then(a.line_number.cmp(&b.line_number))
+    });
+    info!(
+        "packaging {} entries for escalation into {}",
+        entries.len(),
+        output.display()
+    );
+
+    let encoder = GzEncoder::new(File::create(output)?, Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+    // the names are redacted too, as they hold the names of pods, namespaces
+    // and hosts; names redacted the same are numbered to keep them apart
+    let mut names = HashSet::new();
+    let mut append = |name: &str, content: &str| {
+        let redacted = redactor.redact(name);
+        let mut name = redacted.to_string();
+        let mut n = 1;
+        while !names.insert(name.clone()) {
+            n += 1;
+            name = match redacted.rsplit_once('.') {
+                Some((stem, extension)) => format!("{}-{}.{}", stem, n, extension),
+                None => format!("{}-{}", redacted, n),
+            };
+        }
+        let content = redactor.redact(content);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp() as u64);
+        header.set_cksum();
+        archive.append_data(
+            &mut header,
+            format!("{}/{}", ARCHIVE_ROOT, name),
+            content.as_bytes(),
+        )
+    };
+
+    let mut query = String::new();
+    writeln!(query, "keyword: {}", session.keyword())?;
+    writeln!(query, "filter: {}", session.query())?;
+    writeln!(query, "options: {:?}", session.options())?;
+    writeln!(query, "support bundle: {}", root_dir)?;
+    writeln!(query, "entries: {}", entries.len())?;
+    writeln!(query, "created at: {}", Utc::now().to_rfc3339())?;
+    append("query.txt", &query)?;
+
+    match fs::read_to_string(Path::new(root_dir).join("metadata.yaml")) {
+        Ok(metadata) => append("metadata.yaml", &metadata)?,
+        Err(e) => warn!("failed to read the support bundle metadata: {}", e),
+    }
+
+    let mut results = String::new();
+    let mut lines_by_path: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for entry in &entries {
+        let relative = relative_path(&entry.path, root_dir);
+        write!(results, "{}:{}: {}", relative, entry.line_number, entry)?;
+        if !entry.content.ends_with('\n') {
+            results.push('\n');
+        }
+        lines_by_path
+            .entry(entry.path.as_str())
+            .or_default()
+            .push(entry.line_number);
+    }
+    append("results.log", &results)?;
+
+    for (path, lines) in lines_by_path {
+        let source = match sbsearch::read_source(Path::new(path)) {
+            Ok(source) => source,
+            Err(e) => {
+                warn!("failed to read the excerpts of {}: {}", path, e);
+                continue;
+            }
+        };
+        let excerpt = excerpt(&String::from_utf8_lossy(&source), &lines, context);
+        let name = format!("excerpts/{}.txt", relative_path(path, root_dir));
+        append(&name, &excerpt)?;
+    }
+
+    archive.into_inner()?.finish()?.flush()?;
+    Ok(entries.len())
+}
+
+fn relative_path<'a>(path: &'a str, root_dir: &str) -> &'a str {
+    path.strip_prefix(root_dir)
+        .unwrap_or(path)
+        .trim_start_matches('/')
+}
+
+/// Returns the lines within `context` lines of the 1-based `matches`, with
+/// line numbers. Matching lines are marked with `>`, and gaps between the
+/// excerpts with `--`.
+fn excerpt(source: &str, matches: &[u64], context: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut keep = vec![false; lines.len()];
+    let mut matched = vec![false; lines.len()];
+    for &line in matches {
+        let index = (line as usize).saturating_sub(1);
+        if index >= lines.len() {
+            continue;
+        }
+        matched[index] = true;
+        let end = (index + context).min(lines.len() - 1);
+        for k in &mut keep[index.saturating_sub(context)..=end] {
+            *k = true;
+        }
+    }
+
+    let mut excerpt = String::new();
+    let mut last: Option<usize> = None;
+    for (index, line) in lines.iter().enumerate().filter(|(i, _)| keep[*i]) {
+        if last.is_some_and(|l| l + 1 != index) {
+            excerpt.push_str("--\n");
+        }
+        let marker = if matched[index] { '>' } else { ' ' };
+        let _ = writeln!(excerpt, "{}{:>7}  {}", marker, index + 1, line);
+        last = Some(index);
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_redact() {
+        let redactor = Redactor::new(&[String::from("vm-\\d+")]).unwrap();
+        assert_eq!(
+            redactor.redact(r#"password=hunter2 token: "abc" host 10.52.0.3 vm-00"#),
+            r#"password=[REDACTED] token: "[REDACTED]" host [REDACTED-IP] [REDACTED]"#
+        );
+        assert_eq!(
+            redactor.redact("Authorization: Bearer eyJhbGci.x-y mail admin@example.com"),
+            "Authorization: Bearer [REDACTED] mail [REDACTED-EMAIL]"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+        assert!(Redactor::new(&[String::from("(")]).is_err());
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let source = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        assert_eq!(
+            excerpt(source, &[2, 8], 1),
+            "       1  1\n>      2  2\n       3  3\n--\n       7  7\n>      8  8\n       9  9\n"
+        );
+        assert_eq!(excerpt(source, &[100], 2), "");
+    }
+
+    #[test]
+    fn test_escalate() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("escalation.tar.gz");
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.filter("ns=default".parse().unwrap());
+        session.search(0, 100).unwrap();
+
+        let redactor = Redactor::new(&[String::from("vm-00")]).unwrap();
+        let count = escalate(&session, &output, 3, &redactor).unwrap();
+        assert_eq!(count, session.stats().matching);
+        assert!(count > 0);
+
+        let mut files = BTreeMap::new();
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&output).unwrap()));
+        for file in archive.entries().unwrap() {
+            let mut file = file.unwrap();
+            let name = file.path().unwrap().to_string_lossy().into_owned();
+            let mut content = String::new();
+            file.read_to_string(&mut content).unwrap();
+            files.insert(name, content);
+        }
+
+        assert!(files["escalation/query.txt"].contains("filter: ns=default"));
+        assert!(files["escalation/metadata.yaml"].contains("bundlename"));
+        let results = &files["escalation/results.log"];
+        assert_eq!(
+            results.lines().filter(|l| l.starts_with("logs/")).count(),
+            count
+        );
+        assert!(
+            files
+                .keys()
+                .any(|name| name.starts_with("escalation/excerpts/logs/default/"))
+        );
+        assert!(files.values().all(|content| !content.contains("vm-00")));
+        assert!(files.keys().all(|name| !name.contains("vm-00")));
+        assert!(files.contains_key(
+            "escalation/excerpts/logs/default/virt-launcher-[REDACTED]-pb825/compute.log.txt"
+        ));
+    }
+
+    #[test]
+    fn test_escalate_redacted_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("escalation.tar.gz");
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.search(0, 100).unwrap();
+
+        // the excerpts of different files redacted to the same name are kept
+        let redactor = Redactor::new(&[String::from(r"logs/.*\.log")]).unwrap();
+        escalate(&session, &output, 3, &redactor).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&output).unwrap()));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|file| file.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("escalation/excerpts/"))
+            .collect();
+        assert!(names.len() > 1);
+        assert!(names.contains(&String::from("escalation/excerpts/[REDACTED].txt")));
+        assert!(names.contains(&String::from("escalation/excerpts/[REDACTED]-2.txt")));
+        assert_eq!(names.len(), names.iter().collect::<HashSet<_>>().len());
+    }
+}
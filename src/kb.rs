@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Well-known error messages and the documentation that helps to triage them.
+/// The links are to pinned versions of the documentation, so that they keep
+/// pointing to the same pages; the others go in a `--kb-file`.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "already mounted or mount point busy",
+        "https://longhorn.io/kb/troubleshooting-volume-with-multipath/",
+    ),
+    (
+        "unable to attach or mount volumes",
+        "https://longhorn.io/docs/1.7.2/troubleshoot/troubleshooting/",
+    ),
+    (
+        "failed to start domain",
+        "https://docs.harvesterhci.io/v1.4/troubleshooting/vm",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    pattern: String,
+    url: String,
+}
+
+/// Maps log messages to knowledge base URLs. A rule matches an entry when its
+/// pattern is a case-insensitive substring of the entry content.
+#[derive(Debug, Clone)]
+pub struct KnowledgeBase {
+    rules: Vec<Rule>,
+}
+
+impl Default for KnowledgeBase {
+    fn default() -> Self {
+        let rules = BUILTIN_RULES
+            .iter()
+            .map(|(pattern, url)| Rule {
+                pattern: pattern.to_lowercase(),
+                url: String::from(*url),
+            })
+            .collect();
+        KnowledgeBase { rules }
+    }
+}
+
+impl KnowledgeBase {
+    /// Loads additional rules from a file. Each non-empty line that doesn't
+    /// start with `#` has the form `<pattern> => <url>`. Rules from the file
+    /// take precedence over the built-in ones.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let mut rules = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once("=>") {
+                Some((pattern, url)) if !pattern.trim().is_empty() && !url.trim().is_empty() => {
+                    rules.push(Rule {
+                        pattern: pattern.trim().to_lowercase(),
+                        url: String::from(url.trim()),
+                    })
+                }
+                _ => {
+                    return Err(format!(
+                        "{}:{}: expected '<pattern> => <url>'",
+                        path.display(),
+                        index + 1
+                    )
+                    .into());
+                }
+            }
+        }
+
+        rules.extend(KnowledgeBase::default().rules);
+        Ok(KnowledgeBase { rules })
+    }
+
+    pub fn lookup(&self, content: &str) -> Option<&str> {
+        let content = content.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| content.contains(rule.pattern.as_str()))
+            .map(|rule| rule.url.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_lookup_builtin() {
+        let kb = KnowledgeBase::default();
+        let line = r#"E1230 21:58:14.297331 MountVolume.SetUp failed for volume "pvc-1" : mount failed: exit status 32: /dev/longhorn/pvc-1 is Already Mounted or mount point busy"#;
+        assert_eq!(
+            kb.lookup(line),
+            Some("https://longhorn.io/kb/troubleshooting-volume-with-multipath/")
+        );
+        assert_eq!(kb.lookup("level=info msg=\"all good\""), None);
+    }
+
+    #[test]
+    fn test_load() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# custom rules").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "Reconcile Loop => https://example.com/reconcile").unwrap();
+        writeln!(file, "mount point busy => https://example.com/busy").unwrap();
+
+        let kb = KnowledgeBase::load(file.path()).unwrap();
+        assert_eq!(
+            kb.lookup("stuck in reconcile loop"),
+            Some("https://example.com/reconcile")
+        );
+        assert_eq!(
+            kb.lookup("already mounted or mount point busy"),
+            Some("https://example.com/busy")
+        );
+        assert_eq!(
+            kb.lookup("failed to start domain"),
+            Some("https://docs.harvesterhci.io/v1.4/troubleshooting/vm")
+        );
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "missing separator").unwrap();
+        assert!(KnowledgeBase::load(file.path()).is_err());
+    }
+}
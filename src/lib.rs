@@ -0,0 +1,183 @@
+//! Searches the logs of Harvester support bundles.
+//!
+//! A [`Bundle`] is opened from an unarchived support bundle, or its `.tar.zst`
+//! archive, and searched for a keyword. The matching [`Entry`]s come out of
+//! [`Results`] as the files are searched:
+//!
+//! ```no_run
+//! use sbsearch::{Bundle, SearchOptions};
+//!
+//! let bundle = Bundle::open("supportbundle_2025-12-30")?;
+//! for entry in bundle.search("vm-00", &SearchOptions::default())? {
+//!     let entry = entry?;
+//!     print!("{}:{}: {}", entry.path, entry.line_number, entry.content);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! A [`Session`] keeps the entries of a search of a bundle to narrow them down
+//! with a [`Query`], page through them and save them, like the TUI does. The
+//! lower-level functions, such as [`search`] that sorts and pages the
+//! entries, and [`search_streaming`] that the `Results` are built on, are
+//! exported too. The `cli` feature, on by default, builds the `sbsearch` TUI;
+//! embed the library with `default-features = false` to leave it out.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::vec;
+use tempfile::TempDir;
+
+pub mod format;
+pub mod query;
+mod sbsearch;
+pub mod session;
+
+pub use query::Query;
+pub use sbsearch::*;
+pub use session::{ExportFormat, Session};
+
+/// A support bundle. Bundles opened from a `.tar.zst` archive are extracted
+/// into a temporary directory, which is removed when the bundle is dropped.
+#[derive(Debug)]
+pub struct Bundle {
+    root: PathBuf,
+    _extracted: Option<TempDir>,
+}
+
+impl Bundle {
+    /// Opens the support bundle at `path`, either its directory or its
+    /// `.tar.zst` archive.
+    pub fn open(path: impl AsRef<Path>) -> Result<Bundle, Box<dyn Error>> {
+        let path = path.as_ref();
+        if is_compressed_bundle(path)? {
+            let dir = tempfile::tempdir()?;
+            let root = extract_bundle(path, dir.path())?;
+            return Ok(Bundle {
+                root,
+                _extracted: Some(dir),
+            });
+        }
+        if !path.is_dir() {
+            return Err(format!(
+                "{} is neither a support bundle directory nor a .tar.zst archive",
+                path.display()
+            )
+            .into());
+        }
+        Ok(Bundle {
+            root: path.to_path_buf(),
+            _extracted: None,
+        })
+    }
+
+    /// Returns the root directory of the support bundle.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Lists the virtual machines, PVCs, volumes and nodes of the bundle.
+    pub fn resources(&self) -> Vec<Resource> {
+        resources(&self.root)
+    }
+
+    /// Searches the bundle for the entries matching `keyword` in the
+    /// background. Invalid keywords and exclude patterns are reported here,
+    /// before the search starts.
+    pub fn search(
+        &self,
+        keyword: &str,
+        options: &SearchOptions,
+    ) -> Result<Results, Box<dyn Error>> {
+        let cancel = CancelToken::default();
+        let receiver = search_streaming(&self.root, keyword, options, &cancel)?;
+        Ok(Results {
+            receiver,
+            cancel,
+            entries: Vec::new().into_iter(),
+            warnings: Vec::new(),
+            progress: Progress::default(),
+        })
+    }
+}
+
+/// The entries matching a search, in the order their files finish. Entries of
+/// the same file come in order. Dropping the results cancels the search.
+pub struct Results {
+    receiver: Receiver<SearchBatch>,
+    cancel: CancelToken,
+    entries: vec::IntoIter<Entry>,
+    warnings: Vec<SearchWarning>,
+    progress: Progress,
+}
+
+impl Results {
+    /// Returns the files that were skipped or only partly searched so far.
+    pub fn warnings(&self) -> &[SearchWarning] {
+        &self.warnings
+    }
+
+    /// Returns how far the search is.
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    /// Stops the search. The entries already found are still returned.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl Iterator for Results {
+    type Item = Result<Entry, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(Ok(entry));
+            }
+            match self.receiver.recv().ok()? {
+                Ok(SearchEvent::Entries(entries)) => self.entries = entries.into_iter(),
+                Ok(SearchEvent::Warning(warning)) => self.warnings.push(warning),
+                Ok(SearchEvent::Progress(progress)) => self.progress = progress,
+                Err(_) if self.cancel.is_cancelled() => return None,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+impl Drop for Results {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_search() {
+        let bundle = Bundle::open("testdata/support_bundle").unwrap();
+        assert!(!bundle.resources().is_empty());
+
+        let mut results = bundle.search("vm-00", &SearchOptions::default()).unwrap();
+        let entries: Vec<Entry> = results.by_ref().map(Result::unwrap).collect();
+        assert_eq!(entries.len(), 244);
+        assert!(results.warnings().is_empty());
+        assert_eq!(results.progress().files, results.progress().total_files);
+
+        // a cancelled search stops quietly
+        let results = bundle.search("vm-00", &SearchOptions::default()).unwrap();
+        results.cancel();
+        assert!(results.count() <= 244);
+
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        assert!(bundle.search("vm-(", &options).is_err());
+        assert!(Bundle::open("testdata/no_such_bundle").is_err());
+    }
+}
@@ -1,18 +1,22 @@
-use chrono::Local;
-use clap::Parser;
+use chrono::{DateTime, Local, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
 use log::*;
+use sbsearch::{format, session};
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
-mod sbsearch;
+mod analyze;
+mod escalate;
+mod kb;
 mod tui;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    let keyword = args.keyword.as_str();
-    let root_dir = args.support_bundle_path.as_str();
+    let root_dir = args.support_bundle_path.clone().unwrap_or_default();
 
     let mut log_level = String::new();
     if let Some(l) = args.log_level {
@@ -39,24 +43,567 @@ fn main() -> Result<(), Box<dyn Error>> {
     info!("starting sbsearch TUI");
     info!(
         "args: root_dir: {}, keyword: {}, log_level: {}",
-        root_dir, keyword, log_level
+        root_dir,
+        args.keyword.as_deref().unwrap_or_default(),
+        log_level
     );
 
-    let mut terminal = ratatui::init();
-    tui::Tui::new(root_dir, keyword).run(&mut terminal)?;
-    ratatui::restore();
+    let mut options = sbsearch::SearchOptions {
+        sort: args.sort,
+        since: args.since,
+        until: args.until,
+        levels: args.level,
+        regex: args.regex,
+        fuzzy: args.fuzzy,
+        line_regexp: args.line_regexp,
+        word_regexp: args.word_regexp,
+        assume_tz: args.assume_tz,
+        exclude: args.exclude,
+        fields: args.field,
+        pods: args.pod,
+        nodes: args.node,
+        json_filter: args.json_filter,
+        memory_budget: args.memory_budget * 1024 * 1024,
+        max_archive_depth: args.max_archive_depth,
+        max_file_size: args.max_file_size * 1024 * 1024,
+        include_yamls: args.include_yamls,
+        follow_symlinks: args.follow_symlinks,
+        threads: args.threads,
+        // the index is of the bundle as it was, not as it grows
+        index: args.index && !args.watch,
+        dedupe: args.dedupe,
+        scan_timeout: (args.scan_timeout > 0).then(|| Duration::from_secs(args.scan_timeout)),
+        timeout: args.timeout.map(Duration::from_secs),
+        heap_limit: Some(args.heap_limit as usize * 1024 * 1024),
+        no_mmap: args.no_mmap,
+    };
+    if args.errors_only {
+        options.levels = vec![
+            sbsearch::Level::Fatal,
+            sbsearch::Level::Error,
+            sbsearch::Level::Warn,
+        ];
+        options.sort = "ts:desc".parse()?;
+    }
+
+    // report invalid patterns before entering the TUI
+    if let Some(keyword) = &args.keyword {
+        sbsearch::keyword_matcher(keyword, &options)?;
+    }
+    sbsearch::exclude_matcher(&options.exclude)?;
+    sbsearch::pod_matcher(&options.pods)?;
+
+    let knowledge_base = match &args.kb_file {
+        Some(path) => kb::KnowledgeBase::load(Path::new(path))?,
+        None => kb::KnowledgeBase::default(),
+    };
+
+    // each session autosaves to a directory of its own, kept for the
+    // autosaves to be replayed
+    let autosave_dir = match args.autosave {
+        Some(_) => {
+            let dir = session::autosave_dir(args.replay.as_deref().unwrap_or(root_dir.as_str()))?;
+            eprintln!("autosaving to {}", dir.display());
+            Some(dir)
+        }
+        None => None,
+    };
+
+    // the display and save options of the TUI, for both searches and replays
+    let run_tui = |tui: tui::Tui, terminal: &mut ratatui::DefaultTerminal, capabilities| {
+        let mut tui = tui
+            .with_capabilities(capabilities)
+            .with_display_timezone(args.display_timezone)
+            .with_normalized_timestamps(args.normalize_timestamps)
+            .with_saved_locations(args.save_locations)
+            .with_export_format(args.save_format)
+            .with_watch(args.watch)
+            .with_context_lines(args.context);
+        if let Some(path) = &args.kb_file {
+            tui = tui.with_knowledge_base_file(PathBuf::from(path));
+        }
+        if let (Some(seconds), Some(dir)) = (args.autosave, &autosave_dir) {
+            tui = tui.with_autosave(dir.clone(), Duration::from_secs(seconds));
+        }
+        if let Some(home) = std::env::var_os("HOME") {
+            tui = tui.with_search_history(PathBuf::from(home).join(".sbsearch_history"));
+        }
+        if let Some(seconds) = args.refresh {
+            tui = tui.with_refresh(Duration::from_secs(seconds));
+        }
+        tui.run(terminal).map(|()| tui)
+    };
+
+    // saved results are browsed without the support bundle
+    if let Some(path) = &args.replay {
+        let session = session::Session::replay(Path::new(path))?;
+        let tui = tui::Tui::new(session.root_dir(), session.keyword())
+            .with_session(session)
+            .with_knowledge_base(knowledge_base);
+        let mut terminal = TerminalGuard::init();
+        run_tui(tui, &mut terminal.0, tui::Capabilities::detect())?;
+        return Ok(());
+    }
+
+    // compressed bundles are extracted into a temporary directory that's
+    // removed on exit
+    if sbsearch::is_compressed_bundle(Path::new(&root_dir))? {
+        println!("extracting {}...", root_dir);
+    }
+    let bundle = sbsearch::Bundle::open(&root_dir)?;
+    let root_dir = bundle.root().to_string_lossy();
+    let root_dir = root_dir.as_ref();
+
+    if let Some(Command::Escalate {
+        output,
+        filter,
+        context,
+        redact,
+    }) = &args.command
+    {
+        let Some(keyword) = &args.keyword else {
+            return Err("escalate needs a --keyword".into());
+        };
+        let redactor = escalate::Redactor::new(redact)?;
+        let mut session = session::Session::new(root_dir, keyword).with_options(options);
+        session.filter(filter.as_deref().unwrap_or_default().parse()?);
+        session.search(0, 0)?;
+        let count = escalate::escalate(&session, Path::new(output), *context, &redactor)?;
+        for warning in session.warnings() {
+            eprintln!("warning: {}", warning);
+        }
+        println!("packaged {} entries into {}", format::count(count), output);
+        return Ok(());
+    }
+
+    if args.mode != Mode::Tui {
+        let Some(keyword) = &args.keyword else {
+            return Err("a --keyword is needed outside of the TUI".into());
+        };
+        let mut session = session::Session::new(root_dir, keyword).with_options(options);
+        match args.mode {
+            Mode::Summary => {
+                session.search(0, 0)?;
+                print_summary(root_dir, keyword, session.entries(), args.include_yamls);
+            }
+            Mode::Scan => {
+                session.search(0, 0)?;
+                print_scan(&session, &knowledge_base);
+            }
+            Mode::Grep => print_matches(&mut session)?,
+            Mode::Tui => {}
+        }
+        for warning in session.warnings() {
+            eprintln!("warning: {}", warning);
+        }
+        return Ok(());
+    }
+
+    let mut terminal = TerminalGuard::init();
+    let capabilities = tui::Capabilities::detect();
+
+    // without a keyword, pick one of the resources in the support bundle,
+    // while the index is built in the background
+    let keyword = match args.keyword.clone() {
+        Some(keyword) => keyword,
+        None => {
+            let cancel = sbsearch::CancelToken::default();
+            let warming = sbsearch::warm_index(Path::new(root_dir), &options, &cancel);
+            let resources = bundle.resources();
+            let picked = tui::Picker::new(resources).run(&mut terminal.0, capabilities);
+            let Some(keyword) = picked? else {
+                // the unfinished index is removed rather than left behind
+                cancel.cancel();
+                if let Some(warming) = warming {
+                    let _ = warming.join();
+                }
+                return Ok(());
+            };
+            sbsearch::keyword_matcher(&keyword, &options)?;
+            keyword
+        }
+    };
+    let keyword = keyword.as_str();
+
+    let tui = tui::Tui::new(root_dir, keyword)
+        .with_options(options)
+        .with_knowledge_base(knowledge_base);
+    let tui = run_tui(tui, &mut terminal.0, capabilities)?;
+    drop(terminal);
+
+    if !args.no_summary {
+        print_summary(root_dir, keyword, tui.entries(), args.include_yamls);
+    }
     Ok(())
 }
 
+/// Restores the terminal when dropped, so that an error returned from the TUI
+/// doesn't leave the terminal in raw mode on the alternate screen.
+struct TerminalGuard(ratatui::DefaultTerminal);
+
+impl TerminalGuard {
+    fn init() -> Self {
+        TerminalGuard(ratatui::init())
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+fn print_summary(root_dir: &str, keyword: &str, entries: &[sbsearch::Entry], include_yamls: bool) {
+    let summaries = sbsearch::summarize(entries);
+    println!(
+        "found {} entries matching '{}' in {} files ({})",
+        format::count(entries.len()),
+        keyword,
+        format::count(summaries.len()),
+        format::bytes(sbsearch::cache_size(entries))
+    );
+
+    let format_ts = |ts: Option<DateTime<Utc>>| match ts {
+        Some(ts) => ts.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        None => String::from("-"),
+    };
+    for source in sbsearch::summarize_sources(&summaries, root_dir) {
+        let searched = include_yamls
+            || !matches!(
+                source.class,
+                sbsearch::SourceClass::Manifest | sbsearch::SourceClass::Event
+            );
+        if !searched {
+            println!(
+                "{:>8}  {:<20}  {:<20}  {} (not searched, see --include-yamls)",
+                "-", "-", "-", source.class
+            );
+            continue;
+        }
+        println!(
+            "{:>8}  {:<20}  {:<20}  {} ({} files)",
+            format::count(source.matches),
+            format_ts(source.first),
+            format_ts(source.last),
+            source.class,
+            format::count(source.files)
+        );
+        if source.class == sbsearch::SourceClass::Node && source.matches == 0 {
+            let has_archives = std::fs::read_dir(Path::new(root_dir).join("nodes"))
+                .is_ok_and(|mut dir| dir.next().is_some());
+            match has_archives {
+                true => println!("warning: no matches in the node logs"),
+                false => println!("warning: the support bundle has no node logs"),
+            }
+        }
+    }
+    println!();
+
+    for summary in summaries {
+        let path = summary
+            .path
+            .strip_prefix(root_dir)
+            .unwrap_or(summary.path.as_str());
+        println!(
+            "{:>8}  {:<20}  {:<20}  {}",
+            format::count(summary.matches),
+            format_ts(summary.first),
+            format_ts(summary.last),
+            path
+        );
+    }
+}
+
+/// Prints the top suspects of the matching entries, and the entries matching
+/// the knowledge base rules, grouped by rule.
+fn print_scan(session: &session::Session, knowledge_base: &kb::KnowledgeBase) {
+    let root_dir = session.root_dir();
+    let entries: Vec<sbsearch::Entry> = session.matching().cloned().collect();
+    let suspects = analyze::suspects(&entries, root_dir, knowledge_base);
+    println!(
+        "{:>5}  {:<48} {:>6} {:>8} {:>8} {:>5} {:>6}",
+        "SCORE", "COMPONENT", "ERRORS", "WARNINGS", "RESTARTS", "GAPS", "KNOWN"
+    );
+    for suspect in &suspects {
+        println!(
+            "{:>5}  {:<48} {:>6} {:>8} {:>8} {:>5} {:>6}",
+            suspect.score,
+            suspect.component,
+            suspect.errors,
+            suspect.warnings,
+            suspect.restarts,
+            suspect.gaps,
+            suspect.known_issues
+        );
+    }
+
+    let mut known: Vec<(&str, Vec<&sbsearch::Entry>)> = Vec::new();
+    for entry in &entries {
+        let Some(url) = knowledge_base.lookup(&entry.content) else {
+            continue;
+        };
+        match known.iter_mut().find(|(u, _)| *u == url) {
+            Some((_, matches)) => matches.push(entry),
+            None => known.push((url, vec![entry])),
+        }
+    }
+    println!();
+    println!("{} known issues", format::count(known.len()));
+    for (url, matches) in known {
+        println!("{:>8}  {}", format::count(matches.len()), url);
+        for entry in matches {
+            let path = entry.path.strip_prefix(root_dir).unwrap_or(&entry.path);
+            println!(
+                "          {}:{}",
+                path.trim_start_matches('/'),
+                entry.line_number
+            );
+        }
+    }
+}
+
+/// Prints the matching entries as their files are searched, grep style.
+fn print_matches(session: &mut session::Session) -> Result<(), Box<dyn Error>> {
+    let root_dir = String::from(session.root_dir());
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    let result = session.search_each(|entry| {
+        let path = entry.path.strip_prefix(&root_dir).unwrap_or(&entry.path);
+        let path = path.trim_start_matches('/');
+        write!(stdout, "{}:{}:{}", path, entry.line_number, entry)?;
+        if !entry.content.ends_with('\n') {
+            writeln!(stdout)?;
+        }
+        Ok(())
+    });
+
+    // stop quietly when the output is closed, e.g. piped into head
+    let result = result.and_then(|_| Ok(stdout.flush()?));
+    match result {
+        Err(e)
+            if e.downcast_ref::<io::Error>().map(|e| e.kind())
+                == Some(io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// What `sbsearch` does with the matching entries.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Mode {
+    /// Browse the entries in the interactive TUI
+    Tui,
+    /// Print the summary of the matching files
+    Summary,
+    /// Print the top suspects and the known issues
+    Scan,
+    /// Print the matching entries as they're found
+    Grep,
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    support_bundle_path: String,
+    /// Path to the unarchived support bundle, or its .tar.zst archive
+    #[arg(short, long, required_unless_present = "replay")]
+    support_bundle_path: Option<String>,
 
+    /// Keyword to search for. Without one, pick from the VMs, PVCs, volumes and nodes in the support bundle
     #[arg(short, long)]
-    keyword: String,
+    keyword: Option<String>,
+
+    /// What to do with the matching entries
+    #[arg(long, value_enum, default_value_t = Mode::Tui)]
+    mode: Mode,
+
+    /// Treat the keyword as a regular expression
+    #[arg(long)]
+    regex: bool,
+
+    /// Match the keyword as a subsequence of a word, e.g. vm00disk matches vm-00-disk-0
+    #[arg(long, conflicts_with = "regex")]
+    fuzzy: bool,
+
+    /// Only match the keyword against whole lines
+    #[arg(long)]
+    line_regexp: bool,
+
+    /// Only match the keyword against whole words, so that etcd doesn't match etcdserver
+    #[arg(short, long, visible_alias = "word", conflicts_with = "line_regexp")]
+    word_regexp: bool,
+
+    /// Drop entries matching this regular expression (can be repeated)
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Only search the logs of pods matching this glob, e.g. 'virt-launcher-*' (can be repeated)
+    #[arg(long, value_name = "GLOB")]
+    pod: Vec<String>,
+
+    /// Only search the logs of this node under nodes/, plus the cluster logs (can be repeated)
+    #[arg(long, value_name = "NAME")]
+    node: Vec<String>,
 
     #[arg(short, long)]
     log_level: Option<String>,
+
+    /// Sort keys, e.g. ts,level:desc
+    #[arg(long, default_value = "ts")]
+    sort: sbsearch::SortSpec,
+
+    /// Only show error and warning entries, latest first
+    #[arg(long, conflicts_with_all = ["sort", "level"])]
+    errors_only: bool,
+
+    /// Only show entries from workloads with this field value, e.g. app=longhorn-manager (can be repeated)
+    #[arg(long, value_parser = parse_field)]
+    field: Vec<(String, String)>,
+
+    /// Only show JSON log lines matching this jq-like filter, e.g. '.component=="virt-launcher" and .msg contains "Domain"'
+    #[arg(long)]
+    json_filter: Option<sbsearch::JsonFilter>,
+
+    /// Only show entries with these comma-separated log levels, e.g. error,warn
+    #[arg(long, value_delimiter = ',')]
+    level: Vec<sbsearch::Level>,
+
+    /// Only show entries at or after this RFC 3339 timestamp
+    #[arg(long)]
+    since: Option<DateTime<Utc>>,
+
+    /// Only show entries at or before this RFC 3339 timestamp
+    #[arg(long)]
+    until: Option<DateTime<Utc>>,
+
+    /// Timezone of timestamps without one: UTC, local or ±HH:MM, optionally per layout (naive=local)
+    #[arg(long, default_value = "UTC")]
+    assume_tz: sbsearch::TimezoneHints,
+
+    /// Timezone the TUI shows the timestamps in: UTC, local or ±HH:MM
+    #[arg(long, default_value = "UTC")]
+    display_timezone: sbsearch::AssumeTz,
+
+    /// Stop searching more files once the matched entries take up this many MB; the cache size only warns once this is set (0 for no limit)
+    #[arg(long, default_value_t = 0)]
+    memory_budget: usize,
+
+    /// Follow symlinks to files and directories, searching each directory once
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Also search the resource manifests in the yamls/ directory
+    #[arg(long)]
+    include_yamls: bool,
+
+    /// How many levels of archives nested in the node archives to unpack and search
+    #[arg(long, default_value_t = sbsearch::DEFAULT_MAX_ARCHIVE_DEPTH)]
+    max_archive_depth: usize,
+
+    /// Only search the first this many MB of each file, e.g. of multi-GB audit logs (0 for no limit)
+    #[arg(long, default_value_t = 0)]
+    max_file_size: u64,
+
+    /// Stop searching a file after SECONDS, keeping the entries found so far (0 for no limit)
+    #[arg(long, value_name = "SECONDS", default_value_t = sbsearch::DEFAULT_SCAN_TIMEOUT.as_secs())]
+    scan_timeout: u64,
+
+    /// Stop the whole search after SECONDS, e.g. on slow NFS mounts, keeping the entries found so far
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// The most memory in MB the search of a file takes up to hold its lines; files with longer lines are reported as warnings
+    #[arg(long, value_name = "MB", default_value_t = 256, value_parser = clap::value_parser!(u64).range(1..))]
+    heap_limit: u64,
+
+    /// Read the files instead of memory mapping them, e.g. on network filesystems where mmap misbehaves
+    #[arg(long)]
+    no_mmap: bool,
+
+    /// The number of threads searching the files in parallel (0 for one per CPU core)
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+
+    /// Read and build the search index in the .sbsearch directory next to the support bundle
+    #[arg(long)]
+    index: bool,
+
+    /// Collapse the entries with identical content into one, e.g. a line logged by both a pod and containerd
+    #[arg(long)]
+    dedupe: bool,
+
+    /// Keep searching the lines appended to the files, and the files rewritten, of a live log directory in the TUI
+    #[arg(long)]
+    watch: bool,
+
+    /// The number of lines of their file before and after the entries expanded in place in the TUI
+    #[arg(long, value_name = "LINES", default_value_t = tui::CONTEXT_LINES)]
+    context: usize,
+
+    /// Search the support bundle again every SECONDS in the TUI, e.g. while an updated bundle is extracted over it
+    #[arg(long, value_name = "SECONDS", conflicts_with = "replay")]
+    refresh: Option<u64>,
+
+    /// File with additional '<pattern> => <url>' knowledge base rules, reloaded by the TUI when it changes
+    #[arg(long)]
+    kb_file: Option<String>,
+
+    /// Save the current filtered view every SECONDS to a rotating file, in a directory of the session under the temp directory, to be opened with --replay
+    #[arg(long, value_name = "SECONDS")]
+    autosave: Option<u64>,
+
+    /// Format of the saved entries
+    #[arg(long, value_enum, default_value_t = session::ExportFormat::Log)]
+    save_format: session::ExportFormat,
+
+    /// Prefix each saved line with the UTC timestamp of its entry, so saved files sort and diff line by line
+    #[arg(long)]
+    normalize_timestamps: bool,
+
+    /// Prefix each saved line with its path:line in the support bundle, like grep
+    #[arg(long)]
+    save_locations: bool,
+
+    /// Browse the entries saved to this file in the TUI, without the support bundle
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["keyword", "mode"])]
+    replay: Option<String>,
+
+    /// Don't print the summary of matched files on exit
+    #[arg(long)]
+    no_summary: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Package the matching entries, the lines around them, the bundle metadata and the query into a tar.gz for an upstream escalation
+    Escalate {
+        /// Path of the tar.gz to create
+        #[arg(short, long, default_value = "sbsearch_escalation.tar.gz")]
+        output: String,
+
+        /// Only package the entries matching this filter query, e.g. 'ns=default level=error'
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Number of lines around each match to include in the excerpts
+        #[arg(long, default_value_t = 5)]
+        context: usize,
+
+        /// Also redact the matches of this regular expression (can be repeated)
+        #[arg(long)]
+        redact: Vec<String>,
+    },
+}
+
+fn parse_field(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((String::from(key), String::from(value))),
+        _ => Err(format!(
+            "invalid field filter '{}', expected <key>=<value>",
+            s
+        )),
+    }
 }
@@ -0,0 +1,368 @@
+use crate::sbsearch::{self, Entry, Level};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// A filter over the search results, written as space-separated terms, e.g.
+/// `level=error,warn ns=longhorn-system since=2025-12-30T21:50:00Z`.
+///
+/// Terms:
+///   * `level=<level>,...`: entries with one of the log levels
+///   * `ns=<namespace>,...`: pod logs of one of the namespaces
+///   * `pod=<pod>,...`: pod logs of one of the pods
+///   * `node=<node>,...`: logs of one of the nodes
+///   * `since=<timestamp>`, `until=<timestamp>`: entries within the time range
+///   * `<field>~<pattern>`: entries whose field matches the regular expression,
+///     e.g. `msg~"context deadline"`. Patterns with spaces are double quoted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub levels: Vec<Level>,
+    pub namespaces: Vec<String>,
+    pub pods: Vec<String>,
+    pub nodes: Vec<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub patterns: Vec<FieldPattern>,
+}
+
+/// Matches a field of the entries against a regular expression. The fields
+/// are `msg` (the content), `path`, `level`, `ns`, `pod`, `node`, and the
+/// workload fields of the entries, e.g. `app`.
+#[derive(Debug, Clone)]
+pub struct FieldPattern {
+    pub field: String,
+    pub pattern: Regex,
+}
+
+impl FieldPattern {
+    pub fn matches(&self, entry: &Entry, root_dir: &str) -> bool {
+        let value: Option<Cow<str>> = match self.field.as_str() {
+            "msg" => Some(Cow::Borrowed(&entry.content)),
+            "path" => Some(Cow::Borrowed(
+                entry.path.strip_prefix(root_dir).unwrap_or(&entry.path),
+            )),
+            "level" => Some(Cow::Borrowed(entry.level.as_str())),
+            "ns" | "pod" | "node" => {
+                let source = Source::from_path(&entry.path, root_dir);
+                match self.field.as_str() {
+                    "ns" => source.namespace,
+                    "pod" => source.pod,
+                    _ => source.node,
+                }
+                .map(Cow::Owned)
+            }
+            field => entry.fields.get(field).map(|v| Cow::Borrowed(v.as_str())),
+        };
+        value.is_some_and(|v| self.pattern.is_match(&v))
+    }
+}
+
+impl PartialEq for FieldPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field && self.pattern.as_str() == other.pattern.as_str()
+    }
+}
+
+impl fmt::Display for FieldPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}~\"{}\"", self.field, self.pattern.as_str())
+    }
+}
+
+/// Where an entry comes from, derived from the support bundle path layout.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Source {
+    pub namespace: Option<String>,
+    pub pod: Option<String>,
+    pub node: Option<String>,
+}
+
+impl Source {
+    /// Parses `<root_dir>/logs/<namespace>/<pod>/...` and
+    /// `<root_dir>/nodes/<archive>/<node>/...` paths.
+    pub fn from_path(path: &str, root_dir: &str) -> Self {
+        let relative = path.strip_prefix(root_dir).unwrap_or(path);
+        let parts: Vec<&str> = relative.split('/').filter(|p| !p.is_empty()).collect();
+        match parts.as_slice() {
+            ["logs", namespace, pod, ..] => Source {
+                namespace: Some(String::from(*namespace)),
+                pod: Some(String::from(*pod)),
+                node: None,
+            },
+            ["nodes", _, node, ..] => Source {
+                node: Some(String::from(*node)),
+                ..Default::default()
+            },
+            _ => Source::default(),
+        }
+    }
+}
+
+impl Query {
+    pub fn is_empty(&self) -> bool {
+        *self == Query::default()
+    }
+
+    /// Keeps the entries at `level` or more severe, or of any level if there's
+    /// none, replacing the levels of the query.
+    pub fn set_min_level(&mut self, level: Option<Level>) {
+        self.levels = match level {
+            Some(level) => Level::ALL.into_iter().filter(|l| *l >= level).collect(),
+            None => Vec::new(),
+        };
+    }
+
+    pub fn matches(&self, entry: &Entry, root_dir: &str) -> bool {
+        if !self.levels.is_empty() && !self.levels.contains(&entry.level) {
+            return false;
+        }
+
+        if self.since.is_some() || self.until.is_some() {
+            let Some(timestamp) = entry.timestamp else {
+                return false;
+            };
+            if self.since.is_some_and(|since| timestamp < since)
+                || self.until.is_some_and(|until| timestamp > until)
+            {
+                return false;
+            }
+        }
+
+        if !self.patterns.iter().all(|p| p.matches(entry, root_dir)) {
+            return false;
+        }
+
+        if self.namespaces.is_empty() && self.pods.is_empty() && self.nodes.is_empty() {
+            return true;
+        }
+        let source = Source::from_path(&entry.path, root_dir);
+        let any_of = |values: &Vec<String>, value: &Option<String>| {
+            values.is_empty() || value.as_ref().is_some_and(|v| values.contains(v))
+        };
+        any_of(&self.namespaces, &source.namespace)
+            && any_of(&self.pods, &source.pod)
+            && any_of(&self.nodes, &source.node)
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Query::default();
+        for term in terms(s)? {
+            let Some(at) = term.find(['=', '~']) else {
+                return Err(format!(
+                    "invalid term '{}', expected <key>=<value> or <field>~<pattern>",
+                    term
+                ));
+            };
+            let (key, value) = (&term[..at], &term[at + 1..]);
+            if term[at..].starts_with('~') {
+                let pattern = sbsearch::user_regex(value)
+                    .map_err(|e| format!("invalid pattern '{}': {}", value, e))?;
+                query.patterns.push(FieldPattern {
+                    field: String::from(key),
+                    pattern,
+                });
+                continue;
+            }
+
+            let values = || {
+                value
+                    .split(',')
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<String>>()
+            };
+            let timestamp = || {
+                value
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|e| format!("invalid timestamp '{}': {}", value, e))
+            };
+            match key {
+                "level" => {
+                    for level in values() {
+                        query.levels.push(level.parse()?);
+                    }
+                }
+                "ns" => query.namespaces.extend(values()),
+                "pod" => query.pods.extend(values()),
+                "node" => query.nodes.extend(values()),
+                "since" => query.since = Some(timestamp()?),
+                "until" => query.until = Some(timestamp()?),
+                _ => return Err(format!("unknown query key '{}'", key)),
+            }
+        }
+        Ok(query)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        let levels: Vec<String> = self.levels.iter().map(Level::to_string).collect();
+        for (key, values) in [
+            ("level", &levels),
+            ("ns", &self.namespaces),
+            ("pod", &self.pods),
+            ("node", &self.nodes),
+        ] {
+            if !values.is_empty() {
+                terms.push(format!("{}={}", key, values.join(",")));
+            }
+        }
+        if let Some(since) = self.since {
+            terms.push(format!("since={}", since.to_rfc3339()));
+        }
+        if let Some(until) = self.until {
+            terms.push(format!("until={}", until.to_rfc3339()));
+        }
+        terms.extend(self.patterns.iter().map(|p| p.to_string()));
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+/// Splits the query into whitespace-separated terms, with the double-quoted
+/// parts of a term unquoted.
+fn terms(s: &str) -> Result<Vec<String>, String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote in '{}'", s));
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, level: &str, ts: &str) -> Entry {
+        Entry {
+            level: Level::normalize(level),
+            path: String::from(path),
+            content: String::new(),
+            timestamp: Some(ts.parse::<DateTime<Utc>>().unwrap()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let query = "level=error,warn ns=default since=2025-12-30T21:50:00Z"
+            .parse::<Query>()
+            .unwrap();
+        assert_eq!(query.levels, vec![Level::Error, Level::Warn]);
+        assert_eq!(query.namespaces, vec!["default"]);
+        assert_eq!(
+            query.since,
+            Some("2025-12-30T21:50:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(
+            query.to_string(),
+            "level=error,warn ns=default since=2025-12-30T21:50:00+00:00"
+        );
+        assert_eq!(query.to_string().parse::<Query>().unwrap(), query);
+
+        assert!("".parse::<Query>().unwrap().is_empty());
+        assert!("level".parse::<Query>().is_err());
+        assert!("color=red".parse::<Query>().is_err());
+        assert!("since=yesterday".parse::<Query>().is_err());
+
+        let query = r#"level=error msg~"context deadline" app~^longhorn"#
+            .parse::<Query>()
+            .unwrap();
+        assert_eq!(query.patterns.len(), 2);
+        assert_eq!(query.patterns[0].field, "msg");
+        assert_eq!(query.patterns[0].pattern.as_str(), "context deadline");
+        assert_eq!(
+            query.to_string(),
+            r#"level=error msg~"context deadline" app~"^longhorn""#
+        );
+        assert_eq!(query.to_string().parse::<Query>().unwrap(), query);
+        assert!(r#"msg~"timeout"#.parse::<Query>().is_err());
+        assert!("msg~(".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_set_min_level() {
+        let mut query: Query = "level=info ns=default".parse().unwrap();
+        query.set_min_level(Some(Level::Warn));
+        assert_eq!(query.to_string(), "level=fatal,error,warn ns=default");
+        query.set_min_level(None);
+        assert_eq!(query.to_string(), "ns=default");
+    }
+
+    #[test]
+    fn test_matches() {
+        let root = "sb";
+        let pod_entry = entry(
+            "sb/logs/default/vm-00/compute.log",
+            "error",
+            "2025-12-30T21:58:00Z",
+        );
+        let node_entry = entry(
+            "sb/nodes/n1.zip/n1/logs/kubelet.log",
+            "info",
+            "2025-12-30T21:59:00Z",
+        );
+
+        let query = Query::default();
+        assert!(query.matches(&pod_entry, root));
+        assert!(query.matches(&node_entry, root));
+
+        let query = "level=err".parse::<Query>().unwrap();
+        assert!(query.matches(&pod_entry, root));
+        assert!(!query.matches(&node_entry, root));
+
+        let query = "ns=default pod=vm-00".parse::<Query>().unwrap();
+        assert!(query.matches(&pod_entry, root));
+        assert!(!query.matches(&node_entry, root));
+
+        let query = "node=n1".parse::<Query>().unwrap();
+        assert!(!query.matches(&pod_entry, root));
+        assert!(query.matches(&node_entry, root));
+
+        let query = "since=2025-12-30T21:58:30Z".parse::<Query>().unwrap();
+        assert!(!query.matches(&pod_entry, root));
+        assert!(query.matches(&node_entry, root));
+
+        let mut pod_entry = pod_entry;
+        pod_entry.content = String::from("volume pvc-3f2a attach timeout");
+        pod_entry
+            .fields
+            .insert(String::from("app"), String::from("virt-launcher"));
+        let query = r#"msg~"attach timeout" app~virt"#.parse::<Query>().unwrap();
+        assert!(query.matches(&pod_entry, root));
+        assert!(!query.matches(&node_entry, root));
+
+        // the path contains the keyword, but the message doesn't
+        let query = "msg~vm-00".parse::<Query>().unwrap();
+        assert!(!query.matches(&pod_entry, root));
+        let query = "path~vm-00 node~^n".parse::<Query>().unwrap();
+        assert!(!query.matches(&pod_entry, root));
+        assert!(!query.matches(&node_entry, root));
+        let query = "path~vm-00 pod~vm".parse::<Query>().unwrap();
+        assert!(query.matches(&pod_entry, root));
+    }
+}
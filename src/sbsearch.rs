@@ -1,47 +1,385 @@
 use chrono::{self, DateTime, Utc};
+use flate2::read::GzDecoder;
 use grep_matcher::Matcher;
-use grep_regex::RegexMatcher;
-use grep_searcher::{Searcher, SearcherBuilder, sinks::UTF8};
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{Searcher, SearcherBuilder, Sink, SinkContext, SinkFinish, SinkMatch};
 use log::*;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
 use std::fs::{self};
-use std::io::{self, Read};
-use std::path::Path;
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tar::Archive;
 use zip::ZipArchive;
 
+mod bundle;
+mod encoding;
+mod index;
+mod json_filter;
+mod level;
+mod manifest;
+mod saved;
+mod sort;
+mod source;
+mod stats;
+mod tz;
+mod warning;
+mod watch;
+
+pub use bundle::{extract as extract_bundle, is_compressed_bundle};
+pub use json_filter::{JsonFilter, JsonValue, json_fields, json_value};
+pub use level::Level;
+pub use manifest::{Resource, resources};
+pub use saved::{SavedResults, read_saved, write_saved};
+pub use sort::SortSpec;
+pub use source::read_source;
+pub use stats::SearchStats;
+pub use tz::{AssumeTz, TimezoneHints};
+pub use warning::SearchWarning;
+pub use watch::{Changes, WatchStream, Watcher, watch_streaming};
+
 #[derive(Debug, Clone)]
 pub struct Entry {
-    pub level: String,
+    pub level: Level,
     pub path: String,
     pub content: String,
     pub timestamp: Option<DateTime<Utc>>,
+    pub line_number: u64,
+    /// Workload metadata of the log source, e.g. `kind`, `workload` and `app`.
+    pub fields: BTreeMap<String, String>,
+    /// The number of entries with the same content collapsed into this one
+    /// by `dedupe`.
+    pub duplicates: usize,
 }
 
 impl Entry {
-    fn from_str(s: &str, path: &str, sbsearch: &SBSearch) -> Entry {
+    fn from_str(s: &str, path: &str, line_number: u64, sbsearch: &SBSearch) -> Entry {
         let mut timestamp: Option<DateTime<Utc>> = None;
         if let Ok(t) = sbsearch.find_timestamp(s) {
             timestamp = t;
         }
 
-        let mut level = "UNKNOWN";
-        if let Ok(r) = sbsearch.find_log_level(s) {
-            level = r;
-        }
+        // the level of a multiline entry is the one of its first line
+        let level = sbsearch.find_log_level(s.lines().next().unwrap_or_default());
 
         Entry {
             content: String::from(s),
-            level: String::from(level),
+            level,
             path: String::from(path),
             timestamp,
+            line_number,
+            fields: sbsearch.fields_for(path),
+            duplicates: 0,
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.fields.get("namespace").map(String::as_str)
+    }
+
+    pub fn pod(&self) -> Option<&str> {
+        self.fields.get("pod").map(String::as_str)
+    }
+
+    pub fn container(&self) -> Option<&str> {
+        self.fields.get("container").map(String::as_str)
+    }
+
+    pub fn node(&self) -> Option<&str> {
+        self.fields.get("node").map(String::as_str)
+    }
+
+    /// Where the entry comes from, e.g. `default › virt-launcher-vm-00 ›
+    /// compute` for pod logs, or the node for node logs.
+    pub fn breadcrumb(&self) -> Option<String> {
+        let parts: Vec<&str> = [self.namespace(), self.pod(), self.container(), self.node()]
+            .into_iter()
+            .flatten()
+            .collect();
+        match parts.is_empty() {
+            true => None,
+            false => Some(parts.join(" › ")),
+        }
+    }
+
+    /// The rotation generation of the log file of the entry: 0 for the
+    /// current log, and higher for older ones, e.g. 1 for `*.log.1` and the
+    /// logs of the previous container, `*.previous`.
+    pub fn rotation(&self) -> u32 {
+        rotated_log(&self.path).1
+    }
+}
+
+/// Derives the `namespace`, `pod` and `container` fields of pod logs, i.e.
+/// `logs/<namespace>/<pod>/<container>.log`, and the `node` field of node logs,
+/// i.e. `nodes/<node>.zip/...`, from the support bundle layout. Rotated logs
+/// also get their `rotation`.
+fn source_fields(path: &str, root_dir: &str) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let relative = path.strip_prefix(root_dir).unwrap_or(path);
+    let parts: Vec<&str> = relative.split('/').filter(|p| !p.is_empty()).collect();
+    match parts.as_slice() {
+        ["logs", namespace, pod, file, ..] => {
+            let (current, _) = rotated_log(file);
+            let container = current.strip_suffix(".log").unwrap_or(current);
+            fields.insert(String::from("namespace"), String::from(*namespace));
+            fields.insert(String::from("pod"), String::from(*pod));
+            fields.insert(String::from("container"), String::from(container));
+        }
+        ["nodes", archive, ..] => {
+            fields.insert(String::from("node"), String::from(node_name(archive)));
+        }
+        _ => {}
+    }
+    match rotated_log(path).1 {
+        0 => {}
+        generation => {
+            fields.insert(String::from("rotation"), generation.to_string());
+        }
+    }
+    fields
+}
+
+/// The name of the node whose logs are under `nodes/<name>`, which is either
+/// the archive of the node or its extracted directory.
+fn node_name(name: &str) -> &str {
+    [".zip", ".tar.gz", ".tgz"]
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .unwrap_or(name)
+}
+
+/// Splits the path of a rotated log into the path of the current log and the
+/// rotation generation, e.g. `compute.log.2` into `compute.log` and 2. Only
+/// small numbers are generations, so that date suffixes like
+/// `compute.log.20251230` are logs of their own.
+fn rotated_log(path: &str) -> (&str, u32) {
+    const MAX_GENERATION_DIGITS: usize = 3;
+
+    if let Some(current) = path.strip_suffix(".previous") {
+        return (current, 1);
+    }
+    if let Some((current, generation)) = path.rsplit_once('.')
+        && current.ends_with(".log")
+        && (1..=MAX_GENERATION_DIGITS).contains(&generation.len())
+        && generation.bytes().all(|b| b.is_ascii_digit())
+        && let Ok(generation) = generation.parse()
+    {
+        return (current, generation);
+    }
+    (path, 0)
+}
+
+/// Options that control how the support bundle is searched and how the
+/// matching entries are ordered. The defaults are the ones of the CLI.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub sort: SortSpec,
+    /// Only keep entries with one of these log levels. An empty list keeps
+    /// entries of all levels.
+    pub levels: Vec<Level>,
+    /// Only keep entries with a timestamp at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only keep entries with a timestamp at or before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Treat the keyword as a regular expression instead of a literal string.
+    pub regex: bool,
+    /// Match the keyword as a subsequence of a word, for when the exact
+    /// resource name is unknown, e.g. `vm00disk` matches `vm-00-disk-0`.
+    pub fuzzy: bool,
+    /// Only match the keyword against whole lines.
+    pub line_regexp: bool,
+    /// Only match the keyword against whole words.
+    pub word_regexp: bool,
+    /// Timezones assumed for timestamps that don't specify one.
+    pub assume_tz: TimezoneHints,
+    /// Drop entries that match any of these regular expressions.
+    pub exclude: Vec<String>,
+    /// Only keep entries whose fields have all of these values.
+    pub fields: Vec<(String, String)>,
+    /// Only search the logs of the pods whose names match one of these globs,
+    /// e.g. `virt-launcher-*`. An empty list searches all logs.
+    pub pods: Vec<String>,
+    /// Only search the logs of these nodes under `nodes/`, either archived or
+    /// extracted. An empty list searches all nodes.
+    pub nodes: Vec<String>,
+    /// Only keep JSON log lines whose fields match this filter.
+    pub json_filter: Option<JsonFilter>,
+    /// Stop searching more files once the cached entries take up this many
+    /// bytes. Zero means no limit.
+    pub memory_budget: usize,
+    /// The number of threads searching the files. Zero uses one thread per
+    /// CPU core.
+    pub threads: usize,
+    /// Follow symlinks to files and directories. Directories are searched once,
+    /// so symlink loops are skipped.
+    pub follow_symlinks: bool,
+    /// Also search the resource manifests under `yamls/`.
+    pub include_yamls: bool,
+    /// How many levels of archives nested in the node archives are unpacked
+    /// and searched. Deeper archives are skipped.
+    pub max_archive_depth: usize,
+    /// Only search the first this many bytes of each file, so that a
+    /// multi-GB file can't stall the search. Zero means no limit.
+    pub max_file_size: u64,
+    /// Search the on-disk index of the support bundle, building it on the
+    /// first search.
+    pub index: bool,
+    /// Collapse the entries with identical content into one.
+    pub dedupe: bool,
+    /// Stop searching a file once it takes longer than this, keeping the
+    /// entries found so far. `None` means no limit.
+    pub scan_timeout: Option<Duration>,
+    /// Stop the whole search once it takes longer than this, keeping the
+    /// entries found so far, e.g. on slow network filesystems. `None` means
+    /// no limit.
+    pub timeout: Option<Duration>,
+    /// The most memory, in bytes, the searcher of a file takes up to hold
+    /// its lines. Files with a longer line are reported as a warning. `None`
+    /// uses `DEFAULT_HEAP_LIMIT`.
+    pub heap_limit: Option<usize>,
+    /// Read the files instead of memory mapping them, e.g. on network
+    /// filesystems where mmap misbehaves.
+    pub no_mmap: bool,
+}
+
+/// How many levels of archives nested in the node archives are unpacked,
+/// unless `max_archive_depth` says otherwise.
+pub const DEFAULT_MAX_ARCHIVE_DEPTH: usize = 2;
+
+/// How long the search of a file takes at most, unless `scan_timeout` says
+/// otherwise.
+pub const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            sort: SortSpec::default(),
+            levels: Vec::new(),
+            since: None,
+            until: None,
+            regex: false,
+            fuzzy: false,
+            line_regexp: false,
+            word_regexp: false,
+            assume_tz: TimezoneHints::default(),
+            exclude: Vec::new(),
+            fields: Vec::new(),
+            pods: Vec::new(),
+            nodes: Vec::new(),
+            json_filter: None,
+            memory_budget: 0,
+            threads: 0,
+            follow_symlinks: false,
+            include_yamls: false,
+            max_archive_depth: DEFAULT_MAX_ARCHIVE_DEPTH,
+            max_file_size: 0,
+            index: false,
+            dedupe: false,
+            scan_timeout: Some(DEFAULT_SCAN_TIMEOUT),
+            timeout: None,
+            heap_limit: None,
+            no_mmap: false,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Describes how the keyword is matched, e.g. `regex, word`.
+    pub fn keyword_mode(&self) -> String {
+        let mut mode = vec![match (self.regex, self.fuzzy) {
+            (true, _) => "regex",
+            (_, true) => "fuzzy",
+            _ => "literal",
+        }];
+        if self.line_regexp {
+            mode.push("line");
+        } else if self.word_regexp {
+            mode.push("word");
         }
+        mode.join(", ")
     }
 }
 
 pub struct SearchResult {
     pub entries_offset: Vec<Entry>,
+    /// The statistics of all the matching entries, not only of the page. Like
+    /// the warnings, only the search that reads the files computes them, as
+    /// the cached entries don't change.
+    pub stats: Option<SearchStats>,
+    /// The files that were skipped or only partly searched, e.g. unreadable
+    /// files and bad archives, while the other files were searched. Only the
+    /// search that reads the files reports them, not the ones served from the
+    /// cache.
+    pub warnings: Vec<SearchWarning>,
+}
+
+/// Stops a running search when cancelled. Clones share the same state, so
+/// the search can be cancelled from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What `search_streaming` found in the support bundle.
+#[derive(Debug)]
+pub enum SearchEvent {
+    /// The entries of a file.
+    Entries(Vec<Entry>),
+    /// A file that was skipped or only partly searched.
+    Warning(SearchWarning),
+    /// The files searched so far.
+    Progress(Progress),
+}
+
+/// How far the search of the support bundle is.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    /// The number of files searched, counting an archive as one file.
+    pub files: usize,
+    /// The number of files to search.
+    pub total_files: usize,
+    /// The size of the files searched, in bytes.
+    pub bytes: u64,
+}
+
+/// An event of the search, or the error that stopped it, sent by
+/// `search_streaming`.
+pub type SearchBatch = Result<SearchEvent, String>;
+
+impl Entry {
+    /// Approximates the heap and inline memory held by the entry.
+    pub fn approx_size(&self) -> usize {
+        std::mem::size_of::<Entry>()
+            + self.path.capacity()
+            + self.content.capacity()
+            + self
+                .fields
+                .iter()
+                .map(|(k, v)| k.capacity() + v.capacity())
+                .sum::<usize>()
+    }
+}
+
+/// Approximates the memory held by the entries, in bytes.
+pub fn cache_size(entries: &[Entry]) -> usize {
+    entries.iter().map(Entry::approx_size).sum()
 }
 
 impl fmt::Display for Entry {
@@ -51,27 +389,206 @@ impl fmt::Display for Entry {
     }
 }
 
+/// Aggregated matches of a single file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub path: String,
+    pub matches: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+/// Groups the entries by file, ordered by the number of matches (most first).
+pub fn summarize(entries: &[Entry]) -> Vec<FileSummary> {
+    let mut summaries: Vec<FileSummary> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    for entry in entries {
+        let index = *indices.entry(&entry.path).or_insert_with(|| {
+            summaries.push(FileSummary {
+                path: entry.path.clone(),
+                matches: 0,
+                first: None,
+                last: None,
+            });
+            summaries.len() - 1
+        });
+
+        let summary = &mut summaries[index];
+        summary.matches += 1;
+        if let Some(timestamp) = entry.timestamp {
+            summary.first = Some(summary.first.map_or(timestamp, |t| t.min(timestamp)));
+            summary.last = Some(summary.last.map_or(timestamp, |t| t.max(timestamp)));
+        }
+    }
+    summaries.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.path.cmp(&b.path)));
+    summaries
+}
+
+/// Collapses the entries with identical content into the first of them, e.g.
+/// the same line logged in both a pod log and the node's containerd log. The
+/// number of entries collapsed is added to the `duplicates` of the one kept,
+/// so entries already collapsed can be deduplicated again.
+pub fn dedupe(entries: &mut Vec<Entry>) {
+    let mut first: HashMap<&str, usize> = HashMap::new();
+    let mut targets = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let target = *first.entry(entry.content.trim_end()).or_insert(index);
+        targets.push(target);
+    }
+
+    let mut collapsed = vec![0; entries.len()];
+    for (index, &target) in targets.iter().enumerate() {
+        if index != target {
+            collapsed[target] += entries[index].duplicates + 1;
+        }
+    }
+    let mut index = 0;
+    entries.retain_mut(|entry| {
+        let keep = targets[index] == index;
+        entry.duplicates += collapsed[index];
+        index += 1;
+        keep
+    });
+}
+
+/// The class of source an entry comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SourceClass {
+    /// Pod logs of the management cluster, under `logs/`.
+    Management,
+    /// Logs of the node archives, under `nodes/`.
+    Node,
+    /// Resource manifests, under `yamls/`.
+    Manifest,
+    /// Kubernetes events, in the `events.yaml` manifests.
+    Event,
+    Other,
+}
+
+impl SourceClass {
+    pub const ALL: [SourceClass; 5] = [
+        SourceClass::Management,
+        SourceClass::Node,
+        SourceClass::Manifest,
+        SourceClass::Event,
+        SourceClass::Other,
+    ];
+
+    pub fn of(path: &str, root_dir: &str) -> Self {
+        let relative = path.strip_prefix(root_dir).unwrap_or(path);
+        let relative = relative.trim_start_matches('/');
+        if relative.starts_with("logs/") {
+            SourceClass::Management
+        } else if relative.starts_with("nodes/") {
+            SourceClass::Node
+        } else if relative.starts_with("yamls/") && relative.ends_with("/events.yaml") {
+            SourceClass::Event
+        } else if relative.starts_with("yamls/") {
+            SourceClass::Manifest
+        } else {
+            SourceClass::Other
+        }
+    }
+}
+
+impl fmt::Display for SourceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SourceClass::Management => "management",
+            SourceClass::Node => "node",
+            SourceClass::Manifest => "manifests",
+            SourceClass::Event => "events",
+            SourceClass::Other => "other",
+        };
+        f.pad(name)
+    }
+}
+
+/// Aggregated matches of a class of sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceSummary {
+    pub class: SourceClass,
+    pub matches: usize,
+    pub files: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+/// Groups the file summaries by source class. All the classes but `Other`
+/// are listed, with or without matches, so missing evidence stands out.
+pub fn summarize_sources(summaries: &[FileSummary], root_dir: &str) -> Vec<SourceSummary> {
+    let mut sources: Vec<SourceSummary> = SourceClass::ALL
+        .iter()
+        .map(|&class| SourceSummary {
+            class,
+            matches: 0,
+            files: 0,
+            first: None,
+            last: None,
+        })
+        .collect();
+    for summary in summaries {
+        let class = SourceClass::of(&summary.path, root_dir);
+        let source = &mut sources[class as usize];
+        source.matches += summary.matches;
+        source.files += 1;
+        source.first = source
+            .first
+            .min(summary.first)
+            .or(source.first.or(summary.first));
+        source.last = source.last.max(summary.last);
+    }
+    sources.retain(|s| s.class != SourceClass::Other || s.matches > 0);
+    sources
+}
+
 pub fn search(
     dir: &Path,
     keyword: &str,
     offset: usize,
     limit: usize,
     cache: &mut Vec<Entry>,
+    options: &SearchOptions,
 ) -> Result<SearchResult, Box<dyn Error>> {
-    if cache.is_empty() {
-        let root_dir = dir.to_str().unwrap();
-        let mut sbsearch = SBSearch::new(root_dir, keyword)?;
-        sbsearch.search_tree(dir, cache)?;
-        cache.sort_by(|a, b| {
-            // entries with incomplete timestamp are placed at the end
-            if a.timestamp.is_none() && b.timestamp.is_some() {
-                std::cmp::Ordering::Greater
-            } else if b.timestamp.is_none() && a.timestamp.is_some() {
-                std::cmp::Ordering::Less
-            } else {
-                a.timestamp.cmp(&b.timestamp)
-            }
-        });
+    let mut warnings = Vec::new();
+    let searched = cache.is_empty();
+    if cache.is_empty()
+        && let Some(index) = locate_index(dir, options)
+    {
+        // the entries come in any order, and stop being kept once they reach
+        // the memory budget
+        let found = Mutex::new((Vec::new(), 0));
+        let budget = options.memory_budget;
+        warnings = search_indexed(
+            dir,
+            keyword,
+            options,
+            &index,
+            &CancelToken::default(),
+            |_| {},
+            |entries| {
+                let (found, bytes) = &mut *found.lock().unwrap();
+                if budget == 0 || *bytes < budget {
+                    *bytes += cache_size(&entries);
+                    found.extend(entries);
+                }
+                Ok(())
+            },
+        )?;
+        *cache = found.into_inner().unwrap().0;
+        options.sort.sort_by_position(cache);
+        if options.dedupe {
+            dedupe(cache);
+        }
+    } else if cache.is_empty() {
+        let mut sbsearch = SBSearch::new(root_dir(dir)?, keyword, options)?;
+        sbsearch.manifests = manifest::Manifests::load(dir);
+        sbsearch.search_tree(dir, cache, |_| {})?;
+        warnings = sbsearch.take_warnings();
+        options.sort.sort_by_position(cache);
+        if options.dedupe {
+            dedupe(cache);
+        }
     } else {
         debug!(
             "using cached search results, total entries: {}",
@@ -79,15 +596,233 @@ pub fn search(
         );
     }
 
-    let limit = limit.min(cache.len().saturating_sub(offset));
-    let entries_offset: Vec<Entry> = cache.iter().skip(offset).take(limit).cloned().collect();
+    let entries_offset = page(cache, offset, limit);
     info!(
         "showing {} entries on page {}",
         entries_offset.len(),
-        offset / limit + 1
+        offset / limit.max(1) + 1
     );
 
-    Ok(SearchResult { entries_offset })
+    Ok(SearchResult {
+        entries_offset,
+        stats: searched.then(|| SearchStats::of(cache.iter())),
+        warnings,
+    })
+}
+
+/// Searches the support bundle in the background, sending the entries of each
+/// file over the returned channel as soon as the file is searched. The batches
+/// arrive in no particular order and aren't sorted. The channel is closed once
+/// the search is done. Cancelling the token stops the search mid-file, while
+/// dropping the receiver stops it once the files in flight are searched.
+pub fn search_streaming(
+    dir: &Path,
+    keyword: &str,
+    options: &SearchOptions,
+    cancel: &CancelToken,
+) -> Result<Receiver<SearchBatch>, Box<dyn Error>> {
+    let mut sbsearch = SBSearch::new(root_dir(dir)?, keyword, options)?;
+    sbsearch.manifests = manifest::Manifests::load(dir);
+    sbsearch.cancel = cancel.clone();
+    let index = locate_index(dir, options);
+
+    let cancel = cancel.clone();
+    let dir = dir.to_path_buf();
+    let keyword = String::from(keyword);
+    let options = options.clone();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let emit = |entries| {
+            sender
+                .send(Ok(SearchEvent::Entries(entries)))
+                .map_err(|_| String::from("search cancelled"))
+        };
+        let progress = |progress| {
+            let _ = sender.send(Ok(SearchEvent::Progress(progress)));
+        };
+        let result = match &index {
+            Some(index) => search_indexed(&dir, &keyword, &options, index, &cancel, progress, emit),
+            None => sbsearch
+                .search_tree_streaming(&dir, progress, emit)
+                .map(|_| sbsearch.take_warnings()),
+        };
+        match result {
+            Ok(warnings) => {
+                for warning in warnings {
+                    let _ = sender.send(Ok(SearchEvent::Warning(warning)));
+                }
+            }
+            Err(e) => {
+                debug!("streaming search stopped: {}", e);
+                let _ = sender.send(Err(e.to_string()));
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Locates the index of the support bundle if indexing is enabled. Bundles
+/// that can't be indexed, e.g. on a read-only filesystem, are searched
+/// without an index.
+fn locate_index(dir: &Path, options: &SearchOptions) -> Option<index::Index> {
+    if !options.index {
+        return None;
+    }
+    match index::Index::locate(dir, options) {
+        Ok(index) => Some(index),
+        Err(e) => {
+            warn!("can't index {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Searches the support bundle through its index, passing the matching
+/// entries to `emit` in batches. Without a valid index, the bundle is searched
+/// for all its entries to build the index along the way, and the files that
+/// were skipped or only partly searched are returned.
+fn search_indexed(
+    dir: &Path,
+    keyword: &str,
+    options: &SearchOptions,
+    index: &index::Index,
+    cancel: &CancelToken,
+    progress: impl Fn(Progress) + Sync,
+    emit: impl Fn(Vec<Entry>) -> Result<(), String> + Sync,
+) -> Result<Vec<SearchWarning>, Box<dyn Error>> {
+    let (builder, files) = index_builder(dir, options, cancel)?;
+    let mut query = SBSearch::new(root_dir(dir)?, keyword, options)?;
+    query.manifests = builder.manifests.clone();
+    let matching = |mut entries: Vec<Entry>| {
+        if cancel.is_cancelled() {
+            return Err(String::from("search cancelled"));
+        }
+        entries.retain(|entry| query.matches(entry));
+        match entries.is_empty() {
+            true => Ok(()),
+            false => emit(entries),
+        }
+    };
+
+    // the index being built, e.g. by the warm-up, is read once built rather
+    // than built again
+    let building = index.lock();
+    if index.is_valid(&files) {
+        drop(building);
+        info!("searching the index {}", index.path().display());
+        return index.read(&progress, |mut entries| {
+            for entry in entries.iter_mut() {
+                entry.fields = query.fields_for(&entry.path);
+            }
+            matching(entries)
+        });
+    }
+    build_index(builder, files, index, progress, matching)
+}
+
+/// Returns the search finding every entry of the support bundle, which builds
+/// its index, and the files it searches.
+fn index_builder(
+    dir: &Path,
+    options: &SearchOptions,
+    cancel: &CancelToken,
+) -> Result<(SBSearch, Vec<PathBuf>), Box<dyn Error>> {
+    // the index holds every entry, so the keyword and the filters are only
+    // applied to the entries passed on
+    let index_options = SearchOptions {
+        regex: false,
+        fuzzy: false,
+        line_regexp: false,
+        word_regexp: false,
+        levels: Vec::new(),
+        since: None,
+        until: None,
+        exclude: Vec::new(),
+        fields: Vec::new(),
+        pods: Vec::new(),
+        nodes: Vec::new(),
+        json_filter: None,
+        memory_budget: 0,
+        scan_timeout: None,
+        ..options.clone()
+    };
+    let mut builder = SBSearch::new(root_dir(dir)?, "", &index_options)?;
+    builder.manifests = manifest::Manifests::load(dir);
+    builder.cancel = cancel.clone();
+    let mut files = Vec::new();
+    builder.collect_files(dir, &mut files)?;
+    Ok((builder, files))
+}
+
+/// Builds the index of `files`, passing their entries on to `emit` as they
+/// are found, and returns the files that were skipped or only partly
+/// searched.
+fn build_index(
+    mut builder: SBSearch,
+    files: Vec<PathBuf>,
+    index: &index::Index,
+    progress: impl Fn(Progress) + Sync,
+    emit: impl Fn(Vec<Entry>) -> Result<(), String> + Sync,
+) -> Result<Vec<SearchWarning>, Box<dyn Error>> {
+    info!("building the index {}", index.path().display());
+    let writer = index.create(&files)?;
+    builder.search_files_streaming(files, progress, |entries| {
+        writer.write(&entries).map_err(|e| e.to_string())?;
+        emit(entries)
+    })?;
+    // an index missing the files left by the timeout would hide them from
+    // the next searches
+    let warnings = builder.take_warnings();
+    match builder.timed_out.load(Ordering::Relaxed) {
+        true => info!("not saving the index of the search that timed out"),
+        false => writer.finish(&warnings)?,
+    }
+    Ok(warnings)
+}
+
+/// Builds the index of the support bundle on a background thread if indexing
+/// is enabled and the index isn't built yet, so that the node archives are
+/// already unpacked and indexed by the time they are searched, e.g. once a
+/// resource is picked. The searches started meanwhile wait for the index and
+/// read it. Cancelling the token stops the build, leaving no index behind.
+pub fn warm_index(
+    dir: &Path,
+    options: &SearchOptions,
+    cancel: &CancelToken,
+) -> Option<thread::JoinHandle<()>> {
+    let index = locate_index(dir, options)?;
+    let dir = dir.to_path_buf();
+    let options = options.clone();
+    let cancel = cancel.clone();
+    Some(thread::spawn(move || {
+        let result = index_builder(&dir, &options, &cancel).and_then(|(builder, files)| {
+            let _building = index.lock();
+            match index.is_valid(&files) {
+                true => Ok(()),
+                false => build_index(builder, files, &index, |_| {}, |_| Ok(())).map(|_| ()),
+            }
+        });
+        if let Err(e) = result {
+            debug!(
+                "stopped warming the index {}: {}",
+                index.path().display(),
+                e
+            );
+        }
+    }))
+}
+
+/// Returns the `limit` entries starting at `offset`. Offsets past the end of
+/// the entries return an empty page.
+pub fn page(entries: &[Entry], offset: usize, limit: usize) -> Vec<Entry> {
+    entries.iter().skip(offset).take(limit).cloned().collect()
+}
+
+/// Returns the root directory of the support bundle as the prefix of the
+/// entry paths, which are UTF-8.
+fn root_dir(dir: &Path) -> Result<&str, Box<dyn Error>> {
+    dir.to_str()
+        .ok_or_else(|| format!("{} isn't a UTF-8 path", dir.display()).into())
 }
 
 fn is_zip(path: &Path) -> io::Result<bool> {
@@ -99,51 +834,357 @@ fn is_zip(path: &Path) -> io::Result<bool> {
     }
 }
 
+fn is_tar_gz(path: &Path) -> io::Result<bool> {
+    let name = path.to_str().unwrap_or_default();
+    if !name.ends_with(".tar.gz") && !name.ends_with(".tgz") {
+        return Ok(false);
+    }
+
+    let mut file = File::open(path)?;
+    let mut signature = [0u8; 2];
+    match file.read_exact(&mut signature) {
+        Ok(_) => Ok(signature == [0x1F, 0x8B]),
+        Err(_) => Ok(false),
+    }
+}
+
+/// The limits on the size and the nesting of the compiled user patterns, so
+/// that an accidental pathological pattern fails fast instead of taking up
+/// all the memory.
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+const PATTERN_DFA_SIZE_LIMIT: usize = 10 << 20;
+const PATTERN_NEST_LIMIT: u32 = 64;
+
+/// Returns a matcher builder for the patterns given by the user, within the
+/// pattern limits.
+fn user_matcher_builder() -> RegexMatcherBuilder {
+    let mut builder = RegexMatcherBuilder::new();
+    builder
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_DFA_SIZE_LIMIT)
+        .nest_limit(PATTERN_NEST_LIMIT);
+    builder
+}
+
+fn user_matcher(pattern: &str) -> Result<RegexMatcher, grep_regex::Error> {
+    user_matcher_builder().build(pattern)
+}
+
+/// Compiles a regular expression given by the user, e.g. in a query, within
+/// the pattern limits.
+pub fn user_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::RegexBuilder::new(pattern)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_DFA_SIZE_LIMIT)
+        .nest_limit(PATTERN_NEST_LIMIT)
+        .build()
+}
+
+/// Builds the matcher of the search keyword, reporting invalid patterns with
+/// the offending keyword. The keyword matches anywhere in a line, or only
+/// whole lines or words with `line_regexp` and `word_regexp`.
+pub fn keyword_matcher(
+    keyword: &str,
+    options: &SearchOptions,
+) -> Result<RegexMatcher, Box<dyn Error>> {
+    let pattern = if options.regex {
+        String::from(keyword)
+    } else if options.fuzzy {
+        fuzzy_pattern(keyword)
+    } else {
+        regex::escape(keyword)
+    };
+    user_matcher_builder()
+        .whole_line(options.line_regexp)
+        .word(options.word_regexp && !options.line_regexp)
+        .build(pattern.as_str())
+        .map_err(|e| format!("invalid keyword pattern '{}': {}", keyword, e).into())
+}
+
+/// Turns the keyword into a case-insensitive pattern matching its characters
+/// in order within a single word, skipping the characters in between. The
+/// whitespace in the keyword is ignored.
+fn fuzzy_pattern(keyword: &str) -> String {
+    let chars: Vec<String> = keyword
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| regex::escape(c.encode_utf8(&mut [0; 4])))
+        .collect();
+    format!("(?i){}", chars.join(r"\S*?"))
+}
+
+/// Builds a single matcher out of the exclusion patterns, if there are any.
+pub fn exclude_matcher(patterns: &[String]) -> Result<Option<RegexMatcher>, Box<dyn Error>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    for pattern in patterns {
+        user_matcher(pattern)
+            .map_err(|e| format!("invalid exclude pattern '{}': {}", pattern, e))?;
+    }
+    let alternation: Vec<String> = patterns.iter().map(|p| format!("(?:{})", p)).collect();
+    let matcher = user_matcher(alternation.join("|").as_str())
+        .map_err(|e| format!("invalid exclude patterns: {}", e))?;
+    Ok(Some(matcher))
+}
+
+/// Builds a single regular expression out of the pod name globs, if there are
+/// any. `*` matches any characters and `?` a single one.
+pub fn pod_matcher(globs: &[String]) -> Result<Option<regex::Regex>, Box<dyn Error>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+
+    let alternation: Vec<String> = globs
+        .iter()
+        .map(|glob| {
+            glob.split('*')
+                .map(|part| {
+                    let literals: Vec<String> = part.split('?').map(regex::escape).collect();
+                    literals.join(".")
+                })
+                .collect::<Vec<String>>()
+                .join(".*")
+        })
+        .collect();
+    let regex = user_regex(&format!("^(?:{})$", alternation.join("|")))
+        .map_err(|e| format!("invalid pod patterns: {}", e))?;
+    Ok(Some(regex))
+}
+
+/// Identifies a directory regardless of the symlinks leading to it.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = std::path::PathBuf;
+
+#[cfg(unix)]
+fn dir_id(dir: &Path) -> io::Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(dir)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(dir: &Path) -> io::Result<DirId> {
+    fs::canonicalize(dir)
+}
+
+/// The most memory the searcher of a file takes up to hold its lines, unless
+/// `heap_limit` says otherwise.
+pub const DEFAULT_HEAP_LIMIT: usize = 256 * 1024 * 1024;
+
+/// The maximum number of continuation lines joined onto an entry.
+const MAX_CONTINUATION_LINES: usize = 500;
+
 struct SBSearch {
-    searcher: Searcher,
+    searcher_builder: SearcherBuilder,
     root_dir: String,
+    keyword: String,
+    options: SearchOptions,
+    manifests: manifest::Manifests,
     matcher_keyword: RegexMatcher,
+    matcher_exclude: Option<RegexMatcher>,
+    matcher_pod: Option<regex::Regex>,
     matcher_log_level1: RegexMatcher,
     matcher_log_level2: RegexMatcher,
     matcher_log_level3: RegexMatcher,
     matcher_log_level4: RegexMatcher,
+    matcher_log_level_klog: RegexMatcher,
     matcher_timestamp1: RegexMatcher,
     matcher_timestamp2: RegexMatcher,
+    matcher_epoch: RegexMatcher,
+    matcher_syslog: RegexMatcher,
+    matcher_klog_header: RegexMatcher,
+    /// The time the year of syslog timestamps is inferred from.
+    reference_time: DateTime<Utc>,
+    cache_bytes: AtomicUsize,
+    visited_dirs: HashSet<DirId>,
+    /// The files that were skipped or only partly searched.
+    warnings: Mutex<Vec<SearchWarning>>,
+    /// The first file turned down for the memory budget, and the number of
+    /// files turned down, reported as a single warning.
+    over_budget: Mutex<Option<(String, usize)>>,
+    cancel: CancelToken,
+    /// When the search runs out of time, if it has a `timeout`.
+    deadline: Option<Instant>,
+    timed_out: AtomicBool,
 }
 
 impl SBSearch {
-    fn new(root_dir: &str, keyword: &str) -> Result<Self, Box<dyn Error>> {
-        let searcher: Searcher;
-        unsafe {
-            let mmap_choice = grep_searcher::MmapChoice::auto();
-            searcher = SearcherBuilder::new()
-                .memory_map(mmap_choice)
-                .heap_limit(Some(268435456))
-                .build();
-        }
-        let pattern = String::from(".*") + keyword + ".*";
-        let matcher_keyword = RegexMatcher::new(pattern.as_str())?;
+    fn new(root_dir: &str, keyword: &str, options: &SearchOptions) -> Result<Self, Box<dyn Error>> {
+        let mut searcher_builder = SearcherBuilder::new();
+        let mmap_choice = match options.no_mmap {
+            true => grep_searcher::MmapChoice::never(),
+            // SAFETY: the files of the support bundle don't change while
+            // they're searched
+            false => unsafe { grep_searcher::MmapChoice::auto() },
+        };
+        searcher_builder
+            .memory_map(mmap_choice)
+            .heap_limit(Some(options.heap_limit.unwrap_or(DEFAULT_HEAP_LIMIT)))
+            // only the lines around the matches are grouped into entries: the
+            // entry of a matching continuation line starts at most
+            // `MAX_CONTINUATION_LINES` before it, and ends as many after it
+            .before_context(MAX_CONTINUATION_LINES)
+            .after_context(MAX_CONTINUATION_LINES);
+        let matcher_keyword = keyword_matcher(keyword, options)?;
+        let matcher_exclude = exclude_matcher(&options.exclude)?;
+        let matcher_pod = pod_matcher(&options.pods)?;
         let matcher_log_level1 = RegexMatcher::new(r"level=([^\s]+)")?;
         let matcher_log_level2 = RegexMatcher::new(r#""level":"([^"]+)""#)?;
         let matcher_log_level3 = RegexMatcher::new(r"err=")?;
-        let matcher_log_level4 = RegexMatcher::new(r"(?i)\[error\]")?;
-        let matcher_timestamp1 =
-            RegexMatcher::new(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?Z")?;
+        let matcher_log_level4 =
+            RegexMatcher::new(r"(?i)\[(?:fatal|error|warn|warning|info|debug)\]")?;
+        // klog headers at the start of the line, or after the prefix of
+        // container runtime or journald logs
+        let matcher_log_level_klog = RegexMatcher::new(
+            r"^(?:\d{4}-\d{2}-\d{2}T\S+ (?:(?:stdout|stderr) [FP] )?|[A-Z][a-z]{2} [ \d]\d [\d:.]+ \S+ \S+: )?[IWEF]\d{4} \d{2}:\d{2}:\d{2}\.\d+",
+        )?;
+        let matcher_timestamp1 = RegexMatcher::new(
+            r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})",
+        )?;
         let matcher_timestamp2 = RegexMatcher::new(r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}")?;
+        let matcher_epoch = RegexMatcher::new(r#"(?:\bts=|"ts":)(?:\d{13}|\d{10}(?:\.\d+)?)\b"#)?;
+        let matcher_syslog =
+            RegexMatcher::new(r"^[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}(?:\.\d+)?")?;
+        let matcher_klog_header = RegexMatcher::new(r"^[IWEF]\d{4} \d{2}:\d{2}:\d{2}\.\d+")?;
+        let reference_time =
+            manifest::bundle_created_at(Path::new(root_dir)).unwrap_or_else(Utc::now);
         Ok(SBSearch {
-            searcher,
+            searcher_builder,
             root_dir: String::from(root_dir),
+            keyword: String::from(keyword),
+            options: options.clone(),
+            manifests: manifest::Manifests::default(),
             matcher_keyword,
+            matcher_exclude,
+            matcher_pod,
             matcher_log_level1,
             matcher_log_level2,
             matcher_log_level3,
             matcher_log_level4,
+            matcher_log_level_klog,
             matcher_timestamp1,
             matcher_timestamp2,
+            matcher_epoch,
+            matcher_syslog,
+            matcher_klog_header,
+            reference_time,
+            cache_bytes: AtomicUsize::new(0),
+            visited_dirs: HashSet::new(),
+            warnings: Mutex::new(Vec::new()),
+            over_budget: Mutex::new(None),
+            cancel: CancelToken::default(),
+            deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+            timed_out: AtomicBool::new(false),
         })
     }
 
-    fn search_tree(&mut self, dir: &Path, entries: &mut Vec<Entry>) -> Result<(), Box<dyn Error>> {
+    /// Searches the files under `dir`, passing the `progress` of the search
+    /// on as each file is searched.
+    fn search_tree(
+        &mut self,
+        dir: &Path,
+        entries: &mut Vec<Entry>,
+        progress: impl Fn(Progress) + Sync,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut files = Vec::new();
+        self.collect_files(dir, &mut files)?;
+        info!("searching {} files", files.len());
+        let counter = ProgressCounter::new(files.len(), progress);
+
+        let pool = self.thread_pool()?;
+        let this = &*self;
+
+        // each file is searched on the thread pool with its own searcher; the
+        // results are merged in the order of the files
+        let results: Vec<Result<Vec<Entry>, String>> = pool.install(|| {
+            files
+                .par_iter()
+                .map(|path| {
+                    let entries = this.search_path(path).map_err(|e| e.to_string());
+                    counter.searched(path);
+                    entries
+                })
+                .collect()
+        });
+        for result in results {
+            entries.extend(result?);
+        }
+        self.record_timed_out();
+        Ok(())
+    }
+
+    /// Searches the files under `dir` like `search_tree`, but passes the
+    /// entries of each file to `emit` as soon as the file is searched.
+    fn search_tree_streaming(
+        &mut self,
+        dir: &Path,
+        progress: impl Fn(Progress) + Sync,
+        emit: impl Fn(Vec<Entry>) -> Result<(), String> + Sync,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut files = Vec::new();
+        self.collect_files(dir, &mut files)?;
+        self.search_files_streaming(files, progress, emit)
+    }
+
+    /// Searches the `files` like `search_tree_streaming`.
+    fn search_files_streaming(
+        &mut self,
+        files: Vec<PathBuf>,
+        progress: impl Fn(Progress) + Sync,
+        emit: impl Fn(Vec<Entry>) -> Result<(), String> + Sync,
+    ) -> Result<(), Box<dyn Error>> {
+        info!("streaming the search of {} files", files.len());
+        let counter = ProgressCounter::new(files.len(), progress);
+
+        let pool = self.thread_pool()?;
+        let this = &*self;
+        pool.install(|| {
+            files.par_iter().try_for_each(|path| {
+                let entries = this.search_path(path).map_err(|e| e.to_string());
+                counter.searched(path);
+                let entries = entries?;
+                match entries.is_empty() {
+                    true => Ok(()),
+                    false => emit(entries),
+                }
+            })
+        })?;
+        self.record_timed_out();
+        Ok(())
+    }
+
+    /// Returns the thread pool with `threads` threads. The pools are shared
+    /// by the searches, so that each search doesn't spawn threads of its own.
+    fn thread_pool(&self) -> Result<Arc<rayon::ThreadPool>, Box<dyn Error>> {
+        static POOLS: OnceLock<Mutex<HashMap<usize, Arc<rayon::ThreadPool>>>> = OnceLock::new();
+        let mut pools = POOLS.get_or_init(Mutex::default).lock().unwrap();
+        let threads = self.options.threads;
+        if let Some(pool) = pools.get(&threads) {
+            return Ok(pool.clone());
+        }
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?,
+        );
+        pools.insert(threads, pool.clone());
+        Ok(pool)
+    }
+
+    /// Collects the files to search under `dir`.
+    fn collect_files(
+        &mut self,
+        dir: &Path,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.is_past_deadline() {
+            return Ok(());
+        }
+
         // only search '/logs' and '/nodes/*/logs' directories
         if !self.is_log_dir(dir) {
             debug!("skipping directory: {}", dir.display());
@@ -151,95 +1192,468 @@ impl SBSearch {
         }
         info!("search directory: {}", dir.display());
 
+        // guard against symlink loops by searching each directory only once
+        if self.options.follow_symlinks && !self.visited_dirs.insert(dir_id(dir)?) {
+            warn!("skipping already searched directory: {}", dir.display());
+            return Ok(());
+        }
+
         for entry in fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
 
+            if entry.file_type()?.is_symlink() && !self.options.follow_symlinks {
+                debug!("skipping symlink: {}", path.display());
+                continue;
+            }
+
             if path.is_dir() {
                 debug!("entering directory: {}", path.display());
-                self.search_tree(&path, entries)?;
+                if let Err(e) = self.collect_files(&path, files) {
+                    self.record_warning(SearchWarning::from_error(&path, e.as_ref()));
+                }
                 continue;
             }
 
-            if path.is_file() {
-                let searcher = &mut self.searcher.clone();
-                if is_zip(path.as_path())? {
-                    debug!("examining zip archive: {}", path.display());
-                    let zipfile = File::open(&path)?;
-                    let mut archive = ZipArchive::new(zipfile)?;
-
-                    // examine each file in the zip archive in memory
-                    for index in 0..archive.len() {
-                        let reader = archive.by_index(index)?;
-                        let path = path.join(Path::new(reader.name()));
-
-                        debug!("examining archive file: {}", path.display());
-                        self.search_reader(reader, path.as_path(), entries, searcher)?;
-                    }
-                    continue;
-                }
+            if path.is_file() && self.is_included(&path) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Searches a file, or the files of an archive, returning the entries.
+    fn search_path(&self, path: &Path) -> Result<Vec<Entry>, Box<dyn Error>> {
+        self.search_path_head(path, u64::MAX)
+    }
+
+    /// Searches the file at `path` like `search_path`, but only its first
+    /// `len` bytes unless it's an archive, e.g. the part of a growing log that
+    /// a watcher marked.
+    fn search_path_head(&self, path: &Path, len: u64) -> Result<Vec<Entry>, Box<dyn Error>> {
+        if self.cancel.is_cancelled() {
+            return Err("search cancelled".into());
+        }
+
+        let mut entries = Vec::new();
+        if self.is_past_deadline() {
+            debug!("out of time, skipping file: {}", path.display());
+            return Ok(entries);
+        }
+        if self.is_over_budget() {
+            self.record_over_budget(path.to_str().unwrap_or_default());
+            return Ok(entries);
+        }
+
+        // a file that can't be searched doesn't stop the search of the
+        // others, and the entries found before the error are kept
+        if let Err(e) = self.search_any(path, len, &mut entries) {
+            if self.cancel.is_cancelled() {
+                return Err(e);
+            }
+            self.record_warning(SearchWarning::from_error(path, e.as_ref()));
+        }
+        Ok(entries)
+    }
+
+    fn search_any(
+        &self,
+        path: &Path,
+        len: u64,
+        entries: &mut Vec<Entry>,
+    ) -> Result<(), Box<dyn Error>> {
+        let searcher = &mut self.searcher_builder.build();
+        if is_zip(path)? {
+            debug!("examining zip archive: {}", path.display());
+            self.search_zip(File::open(path)?, path, entries, searcher, 0)
+        } else if is_tar_gz(path)? {
+            debug!("examining tar.gz archive: {}", path.display());
+            self.search_tar_gz(&mut File::open(path)?, path, entries, searcher, 0)
+        } else {
+            debug!("examining file: {}", path.display());
+            self.search_file(path, len, entries, searcher)
+        }
+    }
+
+    fn search_file(
+        &self,
+        path: &Path,
+        len: u64,
+        entries: &mut Vec<Entry>,
+        searcher: &mut Searcher,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut head = Vec::new();
+        File::open(path)?
+            .take(encoding::SNIFF_LEN)
+            .read_to_end(&mut head)?;
+        let mut transcoding = self.transcoding_searcher(path, &head);
+        let searcher = transcoding.as_mut().unwrap_or(searcher);
+
+        let path_str = path.to_str().unwrap_or("");
+        let sink = EntrySink::new(self, path_str, entries);
+        let limit = self.options.max_file_size;
+        let size = fs::metadata(path)?.len();
+        let timed = |len| -> io::Result<_> {
+            Ok(TimedReader::new(
+                self,
+                path_str,
+                File::open(path)?.take(len),
+            ))
+        };
+        // a memory mapped file is searched at once, so it's only read in
+        // chunks, checking the time in between, with a scan timeout
+        let result = if limit > 0 && size.min(len) > limit {
+            self.record_truncated(path, limit);
+            searcher.search_reader(&self.matcher_keyword, timed(limit)?, sink)
+        } else if size > len || self.options.scan_timeout.is_some() {
+            searcher.search_reader(&self.matcher_keyword, timed(len)?, sink)
+        } else {
+            searcher.search_path(&self.matcher_keyword, path, sink)
+        };
+        self.skip_timed_out(path, result)
+    }
+
+    /// Searches the lines appended to a file, numbered from the line after
+    /// the `line_offset` lines before them.
+    fn search_appended(
+        &self,
+        path: &Path,
+        appended: &[u8],
+        line_offset: u64,
+    ) -> Result<Vec<Entry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        let path_str = path.to_str().unwrap_or("");
+        let mut sink = EntrySink::new(self, path_str, &mut entries);
+        sink.line_offset = line_offset;
+        let result =
+            self.searcher_builder
+                .build()
+                .search_slice(&self.matcher_keyword, appended, sink);
+        self.skip_timed_out(path, result)?;
+        Ok(entries)
+    }
+
+    /// Logs a warning about a file that was skipped or only partly searched,
+    /// and keeps it to report with the results.
+    fn record_warning(&self, warning: SearchWarning) {
+        warn!("{}", warning);
+        self.warnings.lock().unwrap().push(warning);
+    }
+
+    fn is_over_budget(&self) -> bool {
+        let budget = self.options.memory_budget;
+        budget > 0 && self.cache_bytes.load(Ordering::Relaxed) >= budget
+    }
+
+    /// Reserves `bytes` of the memory budget for an entry, and returns false
+    /// once the budget is used up. The entries are reserved one at a time
+    /// across the threads, so the budget is overshot by one entry at most.
+    fn reserve(&self, bytes: usize) -> bool {
+        let budget = self.options.memory_budget;
+        budget == 0
+            || self
+                .cache_bytes
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                    (used < budget).then_some(used + bytes)
+                })
+                .is_ok()
+    }
+
+    /// Counts a file skipped or cut short for the memory budget. Once the
+    /// budget is reached, every file left is, so they are reported together
+    /// by `take_warnings`.
+    fn record_over_budget(&self, path: &str) {
+        let mut over_budget = self.over_budget.lock().unwrap();
+        match &mut *over_budget {
+            Some((_, files)) => *files += 1,
+            None => {
+                warn!(
+                    "memory budget of {} reached at file: {}",
+                    crate::format::bytes(self.options.memory_budget),
+                    path
+                );
+                *over_budget = Some((String::from(path), 1));
+            }
+        }
+    }
+
+    fn record_truncated(&self, path: &Path, limit: u64) {
+        self.record_warning(SearchWarning::Truncated {
+            path: path.display().to_string(),
+            limit,
+        });
+    }
+
+    /// Returns true once the search runs out of time. The files left are
+    /// skipped, and the ones in flight are cut short.
+    fn is_past_deadline(&self) -> bool {
+        let past = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        if past {
+            self.timed_out.store(true, Ordering::Relaxed);
+        }
+        past
+    }
+
+    /// Reports that the search ran out of time, if it did, once it's done.
+    fn record_timed_out(&self) {
+        if let Some(timeout) = self.options.timeout
+            && self.timed_out.load(Ordering::Relaxed)
+        {
+            self.record_warning(SearchWarning::SearchTimedOut {
+                path: self.root_dir.clone(),
+                timeout,
+            });
+        }
+    }
+
+    fn take_warnings(&self) -> Vec<SearchWarning> {
+        let mut warnings = std::mem::take(&mut *self.warnings.lock().unwrap());
+        if let Some((path, files)) = self.over_budget.lock().unwrap().take() {
+            warnings.push(SearchWarning::MemoryBudget {
+                path,
+                budget: self.options.memory_budget,
+                files,
+            });
+        }
+        warnings
+    }
+
+    /// Keeps searching the other files when the search of a file timed out.
+    /// The entries found before the timeout are kept, and the timeout is
+    /// reported as a warning.
+    fn skip_timed_out(&self, path: &Path, result: io::Result<()>) -> Result<(), Box<dyn Error>> {
+        match result {
+            // the files cut short by the search timeout are reported at once
+            Err(e) if e.kind() == io::ErrorKind::TimedOut && self.is_past_deadline() => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                self.record_warning(SearchWarning::from_error(path, &e));
+                Ok(())
+            }
+            result => Ok(result?),
+        }
+    }
 
-                debug!("examining file: {}", path.display());
-                self.search_file(&path, entries, searcher)?;
+    /// Returns a searcher that transcodes the file to UTF-8, if the start of
+    /// the file shows it's UTF-16 without a byte order mark.
+    fn transcoding_searcher(&self, path: &Path, head: &[u8]) -> Option<Searcher> {
+        let encoding = encoding::sniff(head)?;
+        debug!("transcoding {:?} file: {}", encoding, path.display());
+        Some(
+            self.searcher_builder
+                .clone()
+                .encoding(Some(encoding))
+                .build(),
+        )
+    }
+
+    /// Examines each file in the zip archive in memory. `depth` is the number
+    /// of archives the archive is nested in.
+    fn search_zip<R>(
+        &self,
+        archive: R,
+        path: &Path,
+        entries: &mut Vec<Entry>,
+        searcher: &mut Searcher,
+        depth: usize,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        R: Read + Seek,
+    {
+        let mut archive = ZipArchive::new(archive)?;
+        for index in 0..archive.len() {
+            let mut reader = archive.by_index(index)?;
+            if !reader.is_file() {
                 continue;
             }
+            let path = path.join(Path::new(reader.name()));
+
+            debug!("examining archive file: {}", path.display());
+            self.search_reader(&mut reader, path.as_path(), entries, searcher, depth)?;
         }
         Ok(())
     }
 
-    fn search_file(
+    /// Examines each file in the tar.gz archive as it's decompressed. `depth`
+    /// is the number of archives the archive is nested in.
+    fn search_tar_gz(
+        &self,
+        archive: &mut dyn Read,
+        path: &Path,
+        entries: &mut Vec<Entry>,
+        searcher: &mut Searcher,
+        depth: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut archive = Archive::new(GzDecoder::new(archive));
+        for member in archive.entries()? {
+            let mut reader = member?;
+            if !reader.header().entry_type().is_file() {
+                continue;
+            }
+            let path = path.join(reader.path()?);
+
+            debug!("examining archive file: {}", path.display());
+            self.search_reader(&mut reader, path.as_path(), entries, searcher, depth)?;
+        }
+        Ok(())
+    }
+
+    /// Searches a file of an archive. Archives nested in it are unpacked and
+    /// searched too, up to `max_archive_depth` levels deep.
+    fn search_reader(
         &self,
+        read_from: &mut dyn Read,
         path: &Path,
         entries: &mut Vec<Entry>,
         searcher: &mut Searcher,
+        depth: usize,
     ) -> Result<(), Box<dyn Error>> {
-        searcher.search_path(
+        // peek at the start to detect nested archives and the encoding, then
+        // put it back
+        let mut head = Vec::new();
+        read_from.take(encoding::SNIFF_LEN).read_to_end(&mut head)?;
+        let signature = &head[..head.len().min(4)];
+        let mut read_from = io::Cursor::new(head.clone()).chain(read_from);
+
+        let name = path.to_str().unwrap_or_default();
+        let nested_zip = signature == [0x50, 0x4B, 0x03, 0x04];
+        let nested_tar_gz = signature.starts_with(&[0x1F, 0x8B])
+            && (name.ends_with(".tar.gz") || name.ends_with(".tgz"));
+        if nested_zip || nested_tar_gz {
+            if depth >= self.options.max_archive_depth {
+                debug!(
+                    "skipping archive nested {} levels deep: {}",
+                    depth + 1,
+                    path.display()
+                );
+                return Ok(());
+            }
+
+            debug!("examining nested archive: {}", path.display());
+            if nested_zip {
+                // zip archives need to seek, so read it into memory first,
+                // unless it's over the max file size
+                let limit = match self.options.max_file_size {
+                    0 => u64::MAX,
+                    limit => limit,
+                };
+                let mut buf = Vec::new();
+                (&mut read_from)
+                    .take(limit.saturating_add(1))
+                    .read_to_end(&mut buf)?;
+                if buf.len() as u64 > limit {
+                    self.record_warning(SearchWarning::TooLarge {
+                        path: path.display().to_string(),
+                        limit,
+                    });
+                    return Ok(());
+                }
+                return self.search_zip(io::Cursor::new(buf), path, entries, searcher, depth + 1);
+            }
+            return self.search_tar_gz(&mut read_from, path, entries, searcher, depth + 1);
+        }
+
+        let mut transcoding = self.transcoding_searcher(path, &head);
+        let searcher = transcoding.as_mut().unwrap_or(searcher);
+
+        // archive members are searched as they're read, so a truncated
+        // member is only told apart once the limit is reached
+        let limit = match self.options.max_file_size {
+            0 => u64::MAX,
+            limit => limit,
+        };
+        let mut limited = read_from.take(limit);
+        let path_str = path.to_str().unwrap_or("");
+        let result = searcher.search_reader(
             &self.matcher_keyword,
-            path,
-            UTF8(|_lnum, line| {
-                let path = path.to_str().unwrap_or("");
-                debug!("found matching entry in file {}", path);
+            TimedReader::new(self, path_str, &mut limited),
+            EntrySink::new(self, path_str, entries),
+        );
+        if limited.limit() == 0 && limited.into_inner().read(&mut [0])? > 0 {
+            self.record_truncated(path, limit);
+        }
+        self.skip_timed_out(path, result)
+    }
+
+    /// Returns true if a line of the entry matches the keyword, and the
+    /// entry passes the filters.
+    fn matches(&self, entry: &Entry) -> bool {
+        entry
+            .content
+            .lines()
+            .any(|line| matches!(self.matcher_keyword.is_match(line.as_bytes()), Ok(true)))
+            && self.accepts(entry)
+    }
+
+    fn fields_for(&self, path: &str) -> BTreeMap<String, String> {
+        if self.is_manifest(path) {
+            return BTreeMap::from([(String::from("kind"), String::from("manifest"))]);
+        }
+        let mut fields = self.manifests.fields_for(path, &self.root_dir);
+        fields.extend(source_fields(path, &self.root_dir));
+        fields
+    }
 
-                let entry = Entry::from_str(line, path, self);
-                debug!("entry: {:?}", entry);
+    fn accepts(&self, entry: &Entry) -> bool {
+        self.is_included(Path::new(&entry.path))
+            && !self.is_excluded(entry)
+            && self.passes_filters(entry)
+    }
 
-                entries.push(entry);
-                Ok(true)
-            }),
-        )?;
-        Ok(())
+    /// Returns true if the entry matches the exclude patterns.
+    fn is_excluded(&self, entry: &Entry) -> bool {
+        self.matcher_exclude
+            .as_ref()
+            .is_some_and(|matcher| matches!(matcher.is_match(entry.content.as_bytes()), Ok(true)))
     }
 
-    fn search_reader<R>(
-        &mut self,
-        read_from: R,
-        path: &Path,
-        entries: &mut Vec<Entry>,
-        searcher: &mut Searcher,
-    ) -> Result<(), Box<dyn Error>>
-    where
-        R: Read,
-    {
-        searcher.search_reader(
-            &self.matcher_keyword,
-            read_from,
-            UTF8(|_lnum, line| {
-                let path = path.to_str().unwrap_or("");
-                debug!("found matching entry in file {}", path);
+    /// Returns true if the entry passes the level, field, JSON and time
+    /// filters.
+    fn passes_filters(&self, entry: &Entry) -> bool {
+        if !self.options.levels.is_empty() && !self.options.levels.contains(&entry.level) {
+            return false;
+        }
 
-                let entry = Entry::from_str(line, path, self);
-                debug!("entry: {:?}", entry);
+        if !self
+            .options
+            .fields
+            .iter()
+            .all(|(key, value)| entry.fields.get(key) == Some(value))
+        {
+            return false;
+        }
 
-                entries.push(entry);
-                Ok(true)
-            }),
-        )?;
-        Ok(())
+        if let Some(filter) = &self.options.json_filter
+            && !filter.matches(entry.content.lines().next().unwrap_or_default())
+        {
+            return false;
+        }
+
+        // entries without timestamp can't be placed within a time window
+        if self.options.since.is_some() || self.options.until.is_some() {
+            let Some(timestamp) = entry.timestamp else {
+                return false;
+            };
+            if self.options.since.is_some_and(|since| timestamp < since)
+                || self.options.until.is_some_and(|until| timestamp > until)
+            {
+                return false;
+            }
+        }
+        true
     }
 
     fn is_log_dir(&self, dir: &Path) -> bool {
         let root_dir = Path::new(self.root_dir.as_str());
-        if dir == root_dir || dir == root_dir.join("logs") || dir == root_dir.join("nodes") {
+        if !self.is_included(dir) {
+            return false;
+        }
+        if dir == root_dir
+            || dir == root_dir.join("logs")
+            || dir == root_dir.join("nodes")
+            || (self.options.include_yamls && dir.starts_with(root_dir.join("yamls")))
+        {
             return true;
         } else {
             for ancestor in dir.ancestors() {
@@ -253,48 +1667,404 @@ impl SBSearch {
         false
     }
 
-    fn find_log_level<'a>(&self, line: &'a str) -> Result<&'a str, Box<dyn Error>> {
-        if let Ok(opt) = self.matcher_log_level1.find(line.as_bytes())
-            && let Some(m) = opt
-        {
-            Ok(line[m.start()..m.end()].split('=').nth(1).unwrap())
-        } else if let Ok(opt) = self.matcher_log_level2.find(line.as_bytes())
-            && let Some(m) = opt
-        {
-            Ok(line[m.start()..m.end()]
-                .split(':')
-                .nth(1)
-                .unwrap()
-                .trim_matches('"'))
-        } else if let Ok(opt) = self.matcher_log_level3.find(line.as_bytes())
-            && opt.is_some()
-        {
-            Ok("error")
-        } else if let Ok(opt) = self.matcher_log_level4.find(line.as_bytes())
-            && opt.is_some()
-        {
-            Ok("error")
+    /// Returns true if the path isn't left out by `--pod` or `--node`.
+    fn is_included(&self, path: &Path) -> bool {
+        self.is_pod_included(path) && self.is_node_included(path)
+    }
+
+    /// Returns true if the path isn't under `nodes/`, or belongs to one of the
+    /// nodes given by `--node`, i.e. `nodes/<node>`, `nodes/<node>.zip` or
+    /// `nodes/<node>.tar.gz`.
+    fn is_node_included(&self, path: &Path) -> bool {
+        if self.options.nodes.is_empty() {
+            return true;
+        }
+        let Ok(relative) = path.strip_prefix(self.root_dir.as_str()) else {
+            return false;
+        };
+        let mut components = relative.iter().filter_map(|c| c.to_str());
+        if components.next() != Some("nodes") {
+            return true;
+        }
+        let Some(name) = components.next() else {
+            return true;
+        };
+        self.options.nodes.iter().any(|n| n == node_name(name))
+    }
+
+    /// Returns true if the path leads to or is under the directory of a pod
+    /// matching `--pod`, i.e. `logs/<namespace>/<pod>`. The logs of the nodes
+    /// aren't searched when pods are given, but the manifests still are.
+    fn is_pod_included(&self, path: &Path) -> bool {
+        let Some(matcher) = &self.matcher_pod else {
+            return true;
+        };
+        let Ok(relative) = path.strip_prefix(self.root_dir.as_str()) else {
+            return false;
+        };
+        let components: Vec<&str> = relative.iter().filter_map(|c| c.to_str()).collect();
+        match components[..] {
+            [] | ["logs"] | ["logs", _] | ["yamls", ..] => true,
+            ["logs", _, pod, ..] => matcher.is_match(pod),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the path is a resource manifest under `yamls/`.
+    fn is_manifest(&self, path: &str) -> bool {
+        Path::new(path).starts_with(Path::new(self.root_dir.as_str()).join("yamls"))
+    }
+
+    fn find_log_level(&self, line: &str) -> Level {
+        // the klog header starts with the initial of the level, e.g. `E1230`,
+        // and wins over the fields of the message
+        if let Ok(Some(m)) = self.matcher_log_level_klog.find(line.as_bytes()) {
+            let header = line[m].rsplit(' ').nth(1).unwrap();
+            Level::normalize(&header[..1])
+        } else if let Ok(Some(m)) = self.matcher_log_level1.find(line.as_bytes()) {
+            Level::normalize(line[m].split('=').nth(1).unwrap())
+        } else if let Ok(Some(m)) = self.matcher_log_level2.find(line.as_bytes()) {
+            Level::normalize(line[m].split(':').nth(1).unwrap().trim_matches('"'))
+        } else if let Ok(Some(_)) = self.matcher_log_level3.find(line.as_bytes()) {
+            Level::Error
+        } else if let Ok(Some(m)) = self.matcher_log_level4.find(line.as_bytes()) {
+            Level::normalize(line[m].trim_matches(['[', ']']))
         } else {
-            Ok("UNKNOWN")
+            Level::Unknown
         }
     }
 
+    /// Returns true if the line starts a new entry, i.e. it has a timestamp or
+    /// a klog header. Other lines continue the preceding entry.
+    fn starts_entry(&self, line: &str) -> bool {
+        matches!(self.find_timestamp(line), Ok(Some(_)))
+            || matches!(self.matcher_klog_header.is_match(line.as_bytes()), Ok(true))
+    }
+
     fn find_timestamp(&self, line: &str) -> Result<Option<DateTime<Utc>>, Box<dyn Error>> {
         if let Some(m) = self.matcher_timestamp1.find(line.as_bytes())? {
-            Ok(Some(DateTime::parse_from_rfc3339(&line[m])?.to_utc()))
+            // offsets without a colon, e.g. +0800, aren't RFC 3339
+            let timestamp = DateTime::parse_from_rfc3339(&line[m])
+                .or_else(|_| DateTime::parse_from_str(&line[m], "%Y-%m-%dT%H:%M:%S%.f%z"))?;
+            Ok(Some(timestamp.to_utc()))
         } else if let Some(m) = self.matcher_timestamp2.find(line.as_bytes())? {
             let naive = chrono::NaiveDateTime::parse_from_str(&line[m], "%Y-%m-%d %H:%M:%S%.f")?;
-            Ok(Some(naive.and_utc()))
+            Ok(Some(
+                self.options.assume_tz.for_layout("naive").to_utc(naive),
+            ))
+        } else if let Some(m) = self.matcher_epoch.find(line.as_bytes())?
+            && let Some(timestamp) = epoch_timestamp(&line[m])
+        {
+            Ok(Some(timestamp))
+        } else if let Some(m) = self.matcher_syslog.find(line.as_bytes())?
+            && let Some(naive) = syslog_timestamp(&line[m], self.reference_time)
+        {
+            // journal lines without a timestamp in the message
+            Ok(Some(
+                self.options.assume_tz.for_layout("syslog").to_utc(naive),
+            ))
         } else {
             Ok(None)
         }
     }
 }
 
+/// Parses an epoch timestamp like `ts=1735595871.388`, in seconds with an
+/// optional fraction, or `"ts":1735595871388`, in milliseconds.
+fn epoch_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let digits = s.trim_start_matches(|c: char| !c.is_ascii_digit());
+    if !digits.contains('.') && digits.len() == 13 {
+        return DateTime::from_timestamp_millis(digits.parse().ok()?);
+    }
+
+    let (seconds, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut nanos = format!("{:0<9}", fraction);
+    nanos.truncate(9);
+    DateTime::from_timestamp(seconds.parse().ok()?, nanos.parse().ok()?)
+}
+
+/// Parses a syslog timestamp like `Dec 30 21:51:44.485722`, which has no year.
+/// The year is the one of `reference`, the time the logs were collected at,
+/// unless that puts the timestamp after it, e.g. December logs collected in
+/// January, in which case it's the year before.
+fn syslog_timestamp(s: &str, reference: DateTime<Utc>) -> Option<chrono::NaiveDateTime> {
+    use chrono::Datelike;
+
+    let parse = |year: i32| {
+        chrono::NaiveDateTime::parse_from_str(&format!("{} {}", year, s), "%Y %b %e %H:%M:%S%.f")
+            .ok()
+    };
+    let year = reference.year();
+    let timestamp = parse(year)?;
+    // allow for the timezone of the timestamp being ahead of UTC
+    if timestamp > reference.naive_utc() + chrono::TimeDelta::days(1) {
+        return parse(year - 1);
+    }
+    Some(timestamp)
+}
+
+/// Counts the files searched by the threads of the pool, and passes the
+/// progress on as each file is done.
+struct ProgressCounter<F> {
+    total_files: usize,
+    files: AtomicUsize,
+    bytes: AtomicU64,
+    progress: F,
+}
+
+impl<F: Fn(Progress)> ProgressCounter<F> {
+    fn new(total_files: usize, progress: F) -> Self {
+        progress(Progress {
+            total_files,
+            ..Default::default()
+        });
+        ProgressCounter {
+            total_files,
+            files: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            progress,
+        }
+    }
+
+    fn searched(&self, path: &Path) {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+        (self.progress)(Progress {
+            files: self.files.fetch_add(1, Ordering::Relaxed) + 1,
+            total_files: self.total_files,
+            bytes: self.bytes.fetch_add(size, Ordering::Relaxed) + size,
+        });
+    }
+}
+
+/// A multiline entry that is still collecting its continuation lines.
+struct PendingEntry {
+    line_number: u64,
+    content: String,
+    matched: bool,
+    continuations: usize,
+}
+
+fn scan_timed_out(path: &str, pattern: &str, timeout: Duration) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!(
+            "searching {} with the {} timed out after {:?}, skipping the rest of the file",
+            path, pattern, timeout
+        ),
+    )
+}
+
+/// Reads a file for the searcher, failing once its search is past the scan
+/// timeout. The lines that don't match the keyword never reach the sink, so
+/// a keyword pattern slow to match nothing is only stopped here.
+struct TimedReader<'a, R> {
+    sbsearch: &'a SBSearch,
+    path: &'a str,
+    reader: R,
+    started: Instant,
+}
+
+impl<'a, R: Read> TimedReader<'a, R> {
+    fn new(sbsearch: &'a SBSearch, path: &'a str, reader: R) -> Self {
+        TimedReader {
+            sbsearch,
+            path,
+            reader,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl<R: Read> Read for TimedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.sbsearch.is_past_deadline() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "search timed out"));
+        }
+        if let Some(timeout) = self.sbsearch.options.scan_timeout
+            && self.started.elapsed() > timeout
+        {
+            let pattern = format!("keyword pattern '{}'", self.sbsearch.keyword);
+            return Err(scan_timed_out(self.path, &pattern, timeout));
+        }
+        self.reader.read(buf)
+    }
+}
+
+/// Groups the lines of a log file into entries. Lines without a timestamp
+/// continue the preceding entry, so that stack traces and Go panics become a
+/// single entry. An entry is kept if any of its lines matches the keyword.
+/// Lines of resource manifests are entries of their own.
+struct EntrySink<'a> {
+    sbsearch: &'a SBSearch,
+    path: &'a str,
+    entries: &'a mut Vec<Entry>,
+    pending: Option<PendingEntry>,
+    manifest: bool,
+    started: Instant,
+    /// The time spent matching the exclude patterns, to tell which pattern
+    /// is to blame for a timeout.
+    excluding: Duration,
+    /// The number of lines before the searched ones, when searching the
+    /// lines appended to a file.
+    line_offset: u64,
+    /// Whether an entry was turned down for the memory budget, which stops
+    /// the search of the file.
+    over_budget: bool,
+}
+
+impl<'a> EntrySink<'a> {
+    fn new(sbsearch: &'a SBSearch, path: &'a str, entries: &'a mut Vec<Entry>) -> Self {
+        EntrySink {
+            sbsearch,
+            path,
+            entries,
+            pending: None,
+            manifest: sbsearch.is_manifest(path),
+            started: Instant::now(),
+            excluding: Duration::ZERO,
+            line_offset: 0,
+            over_budget: false,
+        }
+    }
+
+    /// Fails once the search is cancelled, so that a large file doesn't hold
+    /// up the cancellation.
+    fn check_cancelled(&self) -> Result<(), io::Error> {
+        match self.sbsearch.cancel.is_cancelled() {
+            true => Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "search cancelled",
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// Fails once the search of the file exceeds the scan timeout, naming the
+    /// pattern that took most of the time.
+    fn check_timeout(&self) -> Result<(), io::Error> {
+        if self.sbsearch.is_past_deadline() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "search timed out"));
+        }
+        let Some(timeout) = self.sbsearch.options.scan_timeout else {
+            return Ok(());
+        };
+        let elapsed = self.started.elapsed();
+        if elapsed <= timeout {
+            return Ok(());
+        }
+
+        let pattern = match self.excluding > elapsed / 2 {
+            true => format!(
+                "exclude patterns '{}'",
+                self.sbsearch.options.exclude.join("', '")
+            ),
+            false => format!("keyword pattern '{}'", self.sbsearch.keyword),
+        };
+        Err(scan_timed_out(self.path, &pattern, timeout))
+    }
+
+    fn line(&mut self, line_number: u64, bytes: &[u8], matched: bool) {
+        let line = String::from_utf8_lossy(bytes);
+        if self.manifest {
+            if matched {
+                self.push(&line, line_number);
+            }
+            return;
+        }
+
+        let starts_entry = self.sbsearch.starts_entry(&line);
+        if !starts_entry
+            && let Some(pending) = &mut self.pending
+            && pending.continuations < MAX_CONTINUATION_LINES
+        {
+            pending.content.push_str(&line);
+            pending.matched |= matched;
+            pending.continuations += 1;
+            return;
+        }
+
+        self.flush();
+        if starts_entry {
+            self.pending = Some(PendingEntry {
+                line_number,
+                content: line.into_owned(),
+                matched,
+                continuations: 0,
+            });
+        } else if matched {
+            // lines without a preceding entry stand on their own
+            self.push(&line, line_number);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(pending) = self.pending.take()
+            && pending.matched
+        {
+            self.push(&pending.content, pending.line_number);
+        }
+    }
+
+    fn push(&mut self, content: &str, line_number: u64) {
+        debug!("found matching entry in file {}", self.path);
+        let entry = Entry::from_str(content, self.path, line_number, self.sbsearch);
+        debug!("entry: {:?}", entry);
+
+        if self.over_budget || !self.sbsearch.is_included(Path::new(&entry.path)) {
+            return;
+        }
+        let started = Instant::now();
+        let excluded = self.sbsearch.is_excluded(&entry);
+        self.excluding += started.elapsed();
+        if excluded || !self.sbsearch.passes_filters(&entry) {
+            return;
+        }
+        if !self.sbsearch.reserve(entry.approx_size()) {
+            self.over_budget = true;
+            self.sbsearch.record_over_budget(self.path);
+            return;
+        }
+        self.entries.push(entry);
+    }
+}
+
+impl Sink for EntrySink<'_> {
+    type Error = io::Error;
+
+    fn matched(&mut self, _: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
+        let line_number = self.line_offset + mat.line_number().unwrap_or_default();
+        self.line(line_number, mat.bytes(), true);
+        self.check_cancelled()?;
+        self.check_timeout()?;
+        Ok(!self.over_budget)
+    }
+
+    fn context(&mut self, _: &Searcher, context: &SinkContext<'_>) -> Result<bool, io::Error> {
+        let line_number = self.line_offset + context.line_number().unwrap_or_default();
+        self.line(line_number, context.bytes(), false);
+        self.check_cancelled()?;
+        self.check_timeout()?;
+        Ok(!self.over_budget)
+    }
+
+    /// The lines skipped between the context of two matches can't continue
+    /// the entry before them.
+    fn context_break(&mut self, _: &Searcher) -> Result<bool, io::Error> {
+        self.flush();
+        Ok(true)
+    }
+
+    fn finish(&mut self, _: &Searcher, _: &SinkFinish) -> Result<(), io::Error> {
+        self.flush();
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tui;
+    /// The number of entries on a page of the TUI.
+    const MAX_ENTRIES_PER_PAGE: usize = 100;
 
     #[test]
     // this test asserts the search result of the first page
@@ -302,17 +2072,38 @@ mod tests {
         let path = Path::new("testdata/support_bundle");
         let keyword = "vm-00";
         let offset = 0;
-        let limit = tui::DEFAULT_MAX_ENTRIES_PER_PAGE;
+        let limit = MAX_ENTRIES_PER_PAGE;
         let cache: &mut Vec<Entry> = &mut Vec::new();
 
-        let result = search(path, keyword, offset, limit, cache).unwrap();
+        let result = search(
+            path,
+            keyword,
+            offset,
+            limit,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
         let entries_offset = &result.entries_offset;
         assert!(!entries_offset.is_empty());
-        assert_eq!(entries_offset.len(), tui::DEFAULT_MAX_ENTRIES_PER_PAGE);
+        assert_eq!(entries_offset.len(), MAX_ENTRIES_PER_PAGE);
         assert_eq!(cache.len(), 244);
+        assert_eq!(result.stats.as_ref().unwrap().entries, 244);
+
+        // the cached entries don't change, so neither do their stats
+        let next = search(
+            path,
+            keyword,
+            limit,
+            limit,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert!(next.stats.is_none());
 
         // validate the first entry in the search result
-        assert_eq!(entries_offset[0].level, "info");
+        assert_eq!(entries_offset[0].level, Level::Info);
         assert_eq!(
             entries_offset[0].path,
             "testdata/support_bundle/logs/harvester-system/harvester-webhook-6cb965f6d9-z24qs/harvester-webhook.log",
@@ -330,7 +2121,7 @@ mod tests {
 
         // validate the last entry in the search result
         let last_index = entries_offset.len() - 1;
-        assert_eq!(entries_offset[last_index].level, "UNKNOWN");
+        assert_eq!(entries_offset[last_index].level, Level::Info);
         assert_eq!(
             entries_offset[last_index].path,
             "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
@@ -350,18 +2141,26 @@ mod tests {
     fn test_search_with_offset1() {
         let path = Path::new("testdata/support_bundle");
         let keyword = "vm-00";
-        let offset = tui::DEFAULT_MAX_ENTRIES_PER_PAGE;
-        let limit = tui::DEFAULT_MAX_ENTRIES_PER_PAGE;
+        let offset = MAX_ENTRIES_PER_PAGE;
+        let limit = MAX_ENTRIES_PER_PAGE;
         let cache: &mut Vec<Entry> = &mut Vec::new();
 
-        let result = search(path, keyword, offset, limit, cache).unwrap();
+        let result = search(
+            path,
+            keyword,
+            offset,
+            limit,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
         let entries_offset = &result.entries_offset;
         assert!(!entries_offset.is_empty());
-        assert_eq!(entries_offset.len(), tui::DEFAULT_MAX_ENTRIES_PER_PAGE);
+        assert_eq!(entries_offset.len(), MAX_ENTRIES_PER_PAGE);
         assert_eq!(cache.len(), 244);
 
         // validate the first entry in the search result
-        assert_eq!(entries_offset[0].level, "UNKNOWN");
+        assert_eq!(entries_offset[0].level, Level::Info);
         assert_eq!(
             entries_offset[0].path,
             "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
@@ -376,7 +2175,7 @@ mod tests {
         );
 
         // validate log line 178 (on page 2)
-        assert_eq!(entries_offset[77].level, "info");
+        assert_eq!(entries_offset[77].level, Level::Info);
         assert_eq!(
             entries_offset[77].path,
             "testdata/support_bundle/logs/default/virt-launcher-vm-00-pb825/compute.log",
@@ -393,7 +2192,7 @@ mod tests {
         );
 
         // validate log line 193 (on page 2)
-        assert_eq!(entries_offset[92].level, "info");
+        assert_eq!(entries_offset[92].level, Level::Info);
         assert_eq!(
             entries_offset[92].path,
             "testdata/support_bundle/logs/default/virt-launcher-vm-00-pb825/compute.log",
@@ -411,7 +2210,7 @@ mod tests {
 
         // validate the last entry in the search result
         let last_index = entries_offset.len() - 1;
-        assert_eq!(entries_offset[last_index].level, "info");
+        assert_eq!(entries_offset[last_index].level, Level::Info);
         assert_eq!(
             entries_offset[last_index].path,
             "testdata/support_bundle/logs/harvester-system/harvester-8db57f44b-cnhts/apiserver.log",
@@ -433,18 +2232,26 @@ mod tests {
     fn test_search_with_offset2() {
         let path = Path::new("testdata/support_bundle");
         let keyword = "vm-00";
-        let offset = tui::DEFAULT_MAX_ENTRIES_PER_PAGE * 2;
-        let limit = tui::DEFAULT_MAX_ENTRIES_PER_PAGE;
+        let offset = MAX_ENTRIES_PER_PAGE * 2;
+        let limit = MAX_ENTRIES_PER_PAGE;
         let cache: &mut Vec<Entry> = &mut Vec::new();
 
-        let result = search(path, keyword, offset, limit, cache).unwrap();
+        let result = search(
+            path,
+            keyword,
+            offset,
+            limit,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
         let entries_offset = &result.entries_offset;
         assert!(!entries_offset.is_empty());
         assert_eq!(entries_offset.len(), 44);
         assert_eq!(cache.len(), 244);
 
         // validate the first entry in the search result
-        assert_eq!(entries_offset[0].level, "info");
+        assert_eq!(entries_offset[0].level, Level::Info);
         assert_eq!(
             entries_offset[0].path,
             "testdata/support_bundle/logs/default/virt-launcher-vm-00-pb825/compute.log",
@@ -460,93 +2267,668 @@ mod tests {
                 .unwrap()
         );
 
-        // validate the last entry in the search result
-        let last_index = entries_offset.len() - 1;
-        assert_eq!(entries_offset[last_index].level, "UNKNOWN");
-        assert_eq!(
-            entries_offset[last_index].path,
-            "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+        // validate the last entry in the search result
+        let last_index = entries_offset.len() - 1;
+        assert_eq!(entries_offset[last_index].level, Level::Info);
+        assert_eq!(
+            entries_offset[last_index].path,
+            "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/kubelet.log",
+        );
+        assert_eq!(
+            entries_offset[last_index].content.trim_end(),
+            r#"I1230 21:58:14.134628   34815 operation_generator.go:1002] "MapVolume.WaitForAttach succeeded for volume \"pvc-a30f7311-cc82-4e85-89d6-144156fce238\" (UniqueName: \"kubernetes.io/csi/driver.longhorn.io^pvc-a30f7311-cc82-4e85-89d6-144156fce238\") pod \"virt-launcher-vm-00-pb825\" (UID: \"e0762618-5577-4082-9f9e-eaa13b7521fa\") DevicePath \"csi-528fef6d1807735386537001c3080da57f734b22fc5a1729c89dd610ebd6a367\"" pod="default/virt-launcher-vm-00-pb825""#,
+        );
+        assert!(entries_offset[last_index].timestamp.is_none());
+    }
+
+    #[test]
+    // this test pages through matches that come from both plain files and
+    // members of the node zip archive
+    fn test_search_pages_across_sources() {
+        let path = Path::new("testdata/support_bundle");
+        let keyword = "vm-00";
+        let limit = 7;
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions::default();
+        search(path, keyword, 0, limit, cache, &options).unwrap();
+        let total = cache.len();
+
+        let is_zip_member = |e: &Entry| e.path.contains(".zip/");
+        assert!(cache.iter().any(is_zip_member));
+        assert!(cache.iter().any(|e| !is_zip_member(e)));
+
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        while offset < total {
+            let result = search(path, keyword, offset, limit, cache, &options).unwrap();
+            assert_eq!(result.entries_offset.len(), limit.min(total - offset));
+            pages.push(result.entries_offset);
+            offset += limit;
+        }
+        assert_eq!(pages.len(), total.div_ceil(limit));
+
+        // the pages join back into the sorted cache, without gaps or repeats
+        let joined: Vec<&Entry> = pages.iter().flatten().collect();
+        assert_eq!(joined.len(), total);
+        for (paged, cached) in joined.iter().zip(cache.iter()) {
+            assert_eq!(paged.path, cached.path);
+            assert_eq!(paged.line_number, cached.line_number);
+        }
+
+        // some page boundary falls between a plain file and a zip member
+        let straddles = pages.windows(2).any(|w| {
+            let (last, first) = (w[0].last().unwrap(), w[1].first().unwrap());
+            is_zip_member(last) != is_zip_member(first)
+        });
+        assert!(straddles);
+
+        // the timestamped entries stay in order across page boundaries
+        let timestamps: Vec<_> = joined.iter().filter_map(|e| e.timestamp).collect();
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+
+        // offsets at or past the end return empty pages
+        let result = search(path, keyword, total, limit, cache, &options).unwrap();
+        assert!(result.entries_offset.is_empty());
+        let result = search(path, keyword, total + limit, limit, cache, &options).unwrap();
+        assert!(result.entries_offset.is_empty());
+    }
+
+    #[test]
+    fn test_page() {
+        let entries: Vec<Entry> = (1..=5)
+            .map(|n| Entry {
+                level: Level::Info,
+                path: String::from("sb/logs/ns/pod/c.log"),
+                content: format!("line {}\n", n),
+                timestamp: None,
+                line_number: n,
+                fields: Default::default(),
+                duplicates: 0,
+            })
+            .collect();
+        let line_numbers =
+            |page: Vec<Entry>| page.iter().map(|e| e.line_number).collect::<Vec<u64>>();
+
+        assert_eq!(line_numbers(page(&entries, 0, 2)), vec![1, 2]);
+        assert_eq!(line_numbers(page(&entries, 4, 2)), vec![5]);
+        assert!(page(&entries, 5, 2).is_empty());
+        assert!(page(&entries, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_search_with_levels() {
+        let path = Path::new("testdata/support_bundle");
+        let keyword = "vm-00";
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            levels: vec![Level::Error, Level::Warn],
+            sort: "ts:desc".parse().unwrap(),
+            ..Default::default()
+        };
+
+        let result = search(path, keyword, 0, limit, cache, &options).unwrap();
+        assert!(!cache.is_empty());
+        assert!(
+            cache
+                .iter()
+                .all(|e| [Level::Error, Level::Warn].contains(&e.level))
+        );
+
+        // entries are in reverse chronological order
+        let entries_offset = &result.entries_offset;
+        for pair in entries_offset.windows(2) {
+            if let (Some(a), Some(b)) = (pair[0].timestamp, pair[1].timestamp) {
+                assert!(a >= b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_klog_levels() {
+        let path = Path::new("testdata/support_bundle");
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, 1, cache, &SearchOptions::default()).unwrap();
+
+        // the kubelet logs of the node archive are all klog
+        let kubelet: Vec<&Entry> = cache
+            .iter()
+            .filter(|e| e.path.ends_with("logs/kubelet.log"))
+            .collect();
+        assert!(!kubelet.is_empty());
+        for entry in kubelet {
+            let expected = match entry.content.chars().next() {
+                Some('E') => Level::Error,
+                Some('W') => Level::Warn,
+                Some('I') => Level::Info,
+                _ => continue,
+            };
+            assert_eq!(entry.level, expected, "{}", entry.content);
+        }
+    }
+
+    #[test]
+    fn test_search_with_time_range() {
+        let path = Path::new("testdata/support_bundle");
+        let keyword = "vm-00";
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let since = "2025-12-30T21:58:14Z".parse::<DateTime<Utc>>().unwrap();
+        let until = "2025-12-30T21:58:15Z".parse::<DateTime<Utc>>().unwrap();
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            since: Some(since),
+            until: Some(until),
+            ..Default::default()
+        };
+        search(path, keyword, 0, limit, cache, &options).unwrap();
+        assert!(!cache.is_empty());
+        assert!(cache.len() < 244);
+        for entry in cache.iter() {
+            let timestamp = entry.timestamp.unwrap();
+            assert!(timestamp >= since && timestamp <= until);
+        }
+
+        // an open-ended window only bounds one side
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            since: Some(since),
+            ..Default::default()
+        };
+        search(path, keyword, 0, limit, cache, &options).unwrap();
+        assert!(cache.iter().all(|e| e.timestamp.unwrap() >= since));
+    }
+
+    #[test]
+    fn test_accepts_levels() {
+        let entry = |level: &str| Entry {
+            level: Level::normalize(level),
+            path: String::from("/path/to/log"),
+            content: String::new(),
+            timestamp: None,
+            line_number: 1,
+            fields: BTreeMap::new(),
+            duplicates: 0,
+        };
+
+        let sb_search = SBSearch::new(
+            "./testdata/support_bundle",
+            "test",
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert!(sb_search.accepts(&entry("info")));
+        assert!(sb_search.accepts(&entry("UNKNOWN")));
+
+        let options = SearchOptions {
+            levels: vec![Level::Error, Level::Warn],
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("./testdata/support_bundle", "test", &options).unwrap();
+        assert!(sb_search.accepts(&entry("error")));
+        assert!(sb_search.accepts(&entry("warning")));
+        assert!(!sb_search.accepts(&entry("info")));
+        assert!(!sb_search.accepts(&entry("UNKNOWN")));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let entry = |path: &str, ts: Option<&str>| Entry {
+            level: Level::Info,
+            path: String::from(path),
+            content: String::new(),
+            timestamp: ts.map(|t| t.parse::<DateTime<Utc>>().unwrap()),
+            line_number: 1,
+            fields: BTreeMap::new(),
+            duplicates: 0,
+        };
+        let entries = vec![
+            entry("a.log", Some("2025-12-30T21:58:14Z")),
+            entry("b.log", None),
+            entry("a.log", Some("2025-12-30T21:57:14Z")),
+            entry("a.log", None),
+        ];
+
+        let summaries = summarize(&entries);
+        assert_eq!(
+            summaries,
+            vec![
+                FileSummary {
+                    path: String::from("a.log"),
+                    matches: 3,
+                    first: entries[2].timestamp,
+                    last: entries[0].timestamp,
+                },
+                FileSummary {
+                    path: String::from("b.log"),
+                    matches: 1,
+                    first: None,
+                    last: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let entry = |path: &str, content: &str, duplicates| Entry {
+            level: Level::Info,
+            path: String::from(path),
+            content: String::from(content),
+            timestamp: None,
+            line_number: 1,
+            fields: BTreeMap::new(),
+            duplicates,
+        };
+        let mut entries = vec![
+            entry("logs/a.log", "pulled image\n", 0),
+            entry("logs/a.log", "started container\n", 0),
+            entry("nodes/n1.zip/containerd.log", "pulled image", 0),
+            entry("nodes/n2.zip/containerd.log", "pulled image\n", 2),
+        ];
+        dedupe(&mut entries);
+        let deduped: Vec<(&str, usize)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.duplicates))
+            .collect();
+        assert_eq!(deduped, vec![("logs/a.log", 4), ("logs/a.log", 0)]);
+    }
+
+    #[test]
+    fn test_summarize_sources() {
+        let root_dir = "sb";
+        let ts = |t: &str| Some(t.parse::<DateTime<Utc>>().unwrap());
+        let summary = |path: &str, matches, first, last| FileSummary {
+            path: String::from(path),
+            matches,
+            first,
+            last,
+        };
+        let summaries = vec![
+            summary(
+                "sb/logs/default/vm/compute.log",
+                3,
+                ts("2025-12-30T21:57:00Z"),
+                ts("2025-12-30T21:59:00Z"),
+            ),
+            summary("sb/logs/default/vm/guest.log", 2, None, None),
+            summary(
+                "sb/logs/kube-system/dns/dns.log",
+                1,
+                ts("2025-12-30T21:56:00Z"),
+                ts("2025-12-30T21:58:00Z"),
+            ),
+            summary("sb/yamls/namespaced/default/v1/events.yaml", 4, None, None),
+        ];
+
+        let sources = summarize_sources(&summaries, root_dir);
+        assert_eq!(
+            sources,
+            vec![
+                SourceSummary {
+                    class: SourceClass::Management,
+                    matches: 6,
+                    files: 3,
+                    first: ts("2025-12-30T21:56:00Z"),
+                    last: ts("2025-12-30T21:59:00Z"),
+                },
+                SourceSummary {
+                    class: SourceClass::Node,
+                    matches: 0,
+                    files: 0,
+                    first: None,
+                    last: None,
+                },
+                SourceSummary {
+                    class: SourceClass::Manifest,
+                    matches: 0,
+                    files: 0,
+                    first: None,
+                    last: None,
+                },
+                SourceSummary {
+                    class: SourceClass::Event,
+                    matches: 4,
+                    files: 1,
+                    first: None,
+                    last: None,
+                },
+            ]
+        );
+        assert_eq!(
+            SourceClass::of("sb/nodes/n1.zip/n1/logs/kubelet.log", root_dir),
+            SourceClass::Node
+        );
+        assert_eq!(
+            SourceClass::of("sb/yamls/cluster/v1/nodes.yaml", root_dir),
+            SourceClass::Manifest
+        );
+    }
+
+    #[test]
+    fn test_keyword_matcher() {
+        let literal = SearchOptions::default();
+        let regex = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+
+        let matcher = keyword_matcher("vm-00.disk", &literal).unwrap();
+        assert!(matcher.is_match(b"pvc for vm-00.disk-0").unwrap());
+        assert!(!matcher.is_match(b"pvc for vm-00-disk-0").unwrap());
+
+        let matcher = keyword_matcher("vm-00.disk", &regex).unwrap();
+        assert!(matcher.is_match(b"pvc for vm-00-disk-0").unwrap());
+
+        let matcher = keyword_matcher("vm-(00|01)", &literal).unwrap();
+        assert!(matcher.is_match(b"literal vm-(00|01) match").unwrap());
+        assert!(!matcher.is_match(b"vm-01").unwrap());
+
+        let err = keyword_matcher("vm-(00", &regex).unwrap_err();
+        assert!(err.to_string().contains("invalid keyword pattern 'vm-(00'"));
+
+        // pathological patterns are rejected instead of compiled
+        assert!(keyword_matcher(r"\w{1000}{1000}", &regex).is_err());
+        assert!(keyword_matcher(&"(".repeat(100), &regex).is_err());
+        assert!(exclude_matcher(&[String::from(r"[a-z]{1000}{1000}")]).is_err());
+        assert!(user_regex(r"\w{1000}{1000}").is_err());
+    }
+
+    #[test]
+    fn test_keyword_matcher_fuzzy() {
+        let fuzzy = SearchOptions {
+            fuzzy: true,
+            ..Default::default()
+        };
+        let matcher = keyword_matcher("vm00disk", &fuzzy).unwrap();
+        assert!(matcher.is_match(b"attaching vm-00-disk-0-xx3er").unwrap());
+        assert!(matcher.is_match(b"VM_00.Disk").unwrap());
+        assert!(!matcher.is_match(b"vm-00 has no disk").unwrap());
+        assert!(!matcher.is_match(b"vm-01-disk-0").unwrap());
+
+        // special characters are matched literally
+        let matcher = keyword_matcher("vm.(0", &fuzzy).unwrap();
+        assert!(matcher.is_match(b"vm-1.(00)").unwrap());
+        assert!(!matcher.is_match(b"vm-100").unwrap());
+    }
+
+    #[test]
+    fn test_keyword_matcher_anchored() {
+        let word = SearchOptions {
+            word_regexp: true,
+            ..Default::default()
+        };
+        let matcher = keyword_matcher("disk", &word).unwrap();
+        assert!(matcher.is_match(b"attaching disk to vm-00").unwrap());
+        assert!(matcher.is_match(b"vm-00-disk-0").unwrap());
+        assert!(!matcher.is_match(b"/dev/disks/by-id/3f2a").unwrap());
+
+        let line = SearchOptions {
+            line_regexp: true,
+            regex: true,
+            ..Default::default()
+        };
+        let matcher = keyword_matcher("vm-\\d+", &line).unwrap();
+        assert!(matcher.is_match(b"vm-00").unwrap());
+        assert!(!matcher.is_match(b"pvc of vm-00").unwrap());
+
+        let matcher = keyword_matcher("etcd", &word).unwrap();
+        assert!(matcher.is_match(b"restarting etcd").unwrap());
+        assert!(!matcher.is_match(b"etcdserver: request timed out").unwrap());
+
+        assert_eq!(SearchOptions::default().keyword_mode(), "literal");
+        assert_eq!(word.keyword_mode(), "literal, word");
+        assert_eq!(line.keyword_mode(), "regex, line");
+    }
+
+    #[test]
+    fn test_search_with_scan_timeout() {
+        let path = Path::new("testdata/support_bundle");
+        let limit = MAX_ENTRIES_PER_PAGE;
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, limit, cache, &SearchOptions::default()).unwrap();
+        let all = cache.len();
+
+        // every file times out on its first line, so the search goes on with
+        // the other files instead of failing
+        let options = SearchOptions {
+            scan_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, limit, cache, &options).unwrap();
+        assert!(cache.len() < all);
+
+        // a file without any match times out too, while it's read
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(
+            log_dir.join("app.log"),
+            "nothing to see here\n".repeat(1000),
+        )
+        .unwrap();
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(root_dir.path(), "vm-00", 0, limit, cache, &options).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(result.warnings.len(), 1);
+        assert!(matches!(
+            &result.warnings[0],
+            SearchWarning::TimedOut { error, .. } if error.contains("keyword pattern 'vm-00'")
+        ));
+    }
+
+    #[test]
+    fn test_search_with_regex() {
+        let path = Path::new("testdata/support_bundle");
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00-disk-0-", 0, limit, cache, &options).unwrap();
+        assert_eq!(cache.len(), 72);
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, r"vm-\d{2}-disk-0-", 0, limit, cache, &options).unwrap();
+        assert_eq!(cache.len(), 72);
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        assert!(search(path, "vm-(00", 0, limit, cache, &options).is_err());
+    }
+
+    #[test]
+    fn test_search_with_exclude() {
+        let path = Path::new("testdata/support_bundle");
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let options = SearchOptions {
+            exclude: vec![
+                String::from("cni-plugin"),
+                String::from(r#""level":"info""#),
+            ],
+            ..Default::default()
+        };
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, limit, cache, &options).unwrap();
+        assert!(!cache.is_empty());
+        assert!(cache.len() < 244);
+        assert!(
+            cache
+                .iter()
+                .all(|e| !e.content.contains("cni-plugin")
+                    && !e.content.contains(r#""level":"info""#))
+        );
+
+        let options = SearchOptions {
+            exclude: vec![String::from("(unclosed")],
+            ..Default::default()
+        };
+        let err = exclude_matcher(&options.exclude).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("invalid exclude pattern '(unclosed'")
+        );
+    }
+
+    #[test]
+    fn test_search_with_fields() {
+        let path = Path::new("testdata/support_bundle");
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let options = SearchOptions {
+            fields: vec![(
+                String::from("app"),
+                String::from("containerized-data-importer"),
+            )],
+            ..Default::default()
+        };
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, limit, cache, &options).unwrap();
+        assert_eq!(cache.len(), 67);
+        assert!(
+            cache
+                .iter()
+                .all(|e| e.fields["workload"] == "cdi-deployment")
         );
-        assert_eq!(
-            entries_offset[last_index].content.trim_end(),
-            r#"I1230 21:58:14.297331   52196 event.go:377] Event(v1.ObjectReference{Kind:"Pod", Namespace:"default", Name:"virt-launcher-vm-00-pb825", UID:"e0762618-5577-4082-9f9e-eaa13b7521fa", APIVersion:"v1", ResourceVersion:"12670", FieldPath:""}): type: 'Normal' reason: 'AddedInterface' Add eth0 [10.52.0.87/32] from k8s-pod-network"#,
+    }
+
+    #[test]
+    fn test_search_with_json_filter() {
+        let path = Path::new("testdata/support_bundle");
+        let limit = MAX_ENTRIES_PER_PAGE;
+        let options = SearchOptions {
+            json_filter: Some(
+                r#".component=="virt-controller" and .msg contains "re-enqueuing""#
+                    .parse()
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, limit, cache, &options).unwrap();
+        assert!(!cache.is_empty());
+        assert!(
+            cache.iter().all(|e| {
+                e.path.contains("virt-controller") && e.content.contains("re-enqueuing")
+            })
         );
-        assert!(entries_offset[last_index].timestamp.is_none());
     }
 
     #[test]
     fn test_find_log_level_pattern1() {
-        let sb_search = SBSearch::new("./testdata/support_bundle", "test").unwrap();
+        let sb_search = SBSearch::new(
+            "./testdata/support_bundle",
+            "test",
+            &SearchOptions::default(),
+        )
+        .unwrap();
 
         let line = r#"2025-12-08T07:35:14.665171218Z ts=2025-12-08T07:35:14.665Z caller=kubernetes.go:331 level=info component="discovery manager scrape" discovery=kubernetes config=serviceMonitor/cattle-fleet-system/monitoring-fleet-controller/0 msg="Using pod service account via in-cluster config"#;
-        let expected = "info";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Info;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T07:35:16.192939534Z time="2025-12-08T07:35:16Z" level=info msg="Diff: [docker.io/rancher/harvester-node-disk-manager-webhook:v0.7.11 docker.io/rancher/harvester:v1.4.3 docker.io/rancher/kubectl:v1.21.5 ghcr.io/k8snetworkplumbingwg/whereabouts:v0.7.0 docker.io/longhornio/csi-node-driver-registrar:v2.13.0 docker.io/longhornio/longhorn-cli:v1.7.3 docker.io/rancher/hardened-flannel:v0.26.5-build20250306 docker.io/rancher/harvester-network-controller:v0.5.6 docker.io/rancher/mirrored-jimmidyson-configmap-reload:v0.4.0 docker.io/rancher/system-agent-installer-rancher:v2.10.1 docker.io/rancher/system-agent:v0.3.11-suc docker.io/longhornio/support-bundle-kit:v0.0.51 docker.io/rancher/harvester-node-manager:v0.3.4 docker.io/rancher/mirrored-grafana-grafana:9.1.5 docker.io/rancher/fleet:v0.11.2 docker.io/rancher/harvester-load-balancer-webhook:v0.4.4 docker.io/rancher/mirrored-kiwigrid-k8s-sidecar:1.24.6 docker.io/longhornio/csi-attacher:v4.8.0 docker.io/rancher/harvester-network-helper:v0.5.6 docker.io/rancher/mirrored-prometheus-operator-prometheus-operator:v0.65.1 docker.io/rancher/shell:v0.1.26 docker.io/rancher/mirrored-kube-state-metrics-kube-state-metrics:v2.10.1 docker.io/rancher/nginx-ingress-controller:v1.12.1-hardened1 docker.io/rancher/rancher-agent:v2.10.1 docker.io/longhornio/backing-image-manager:v1.7.3 docker.io/longhornio/longhorn-manager:v1.7.3 docker.io/longhornio/longhorn-ui:v1.7.3 docker.io/rancher/fleet-agent:v0.11.2 docker.io/rancher/system-upgrade-controller:v0.14.2 ghcr.io/kube-logging/config-reloader:v0.0.5 registry.suse.com/suse/sles/15.6/virt-controller:1.3.1-150600.5.9.1 docker.io/rancher/harvester-networkfs-manager:v0.1.2 docker.io/rancher/harvester-pcidevices:v0.4.3 docker.io/rancher/harvester-webhook:v1.4.3 docker.io/rancher/rancher-webhook:v0.6.2 docker.io/longhornio/csi-snapshotter:v7.0.2-20250204 docker.io/rancher/hardened-dns-node-cache:1.24.0-build20241211 docker.io/rancher/harvester-eventrouter:v0.3.3 registry.suse.com/suse/sles/15.6/virt-launcher:1.3.1-150600.5.9.1 docker.io/rancher/harvester-node-manager-webhook:v0.3.4 docker.io/rancher/mirrored-kube-logging-logging-operator:4.4.0 docker.io/rancher/mirrored-prometheus-adapter-prometheus-adapter:v0.10.0 docker.io/rancher/kubectl:v1.20.2 docker.io/rancher/harvester-node-disk-manager:v0.7.11 docker.io/rancher/mirrored-ingress-nginx-kube-webhook-certgen:v20221220-controller-v1.5.1-58-g787ea74b6 docker.io/rancher/mirrored-prometheus-operator-prometheus-config-reloader:v0.65.1 docker.io/rancher/hardened-etcd:v3.5.19-k3s1-build20250306 docker.io/rancher/hardened-kubernetes:v1.31.7-rke2r1-build20250312 docker.io/rancher/hardened-multus-cni:v4.1.4-build20250108 registry.suse.com/suse/sles/15.6/libguestfs-tools:1.3.1-150600.5.9.1 registry.suse.com/suse/sles/15.6/virt-operator:1.3.1-150600.5.9.1 docker.io/rancher/hardened-cluster-autoscaler:v1.9.0-build20241126 docker.io/rancher/harvester-cluster-repo:v1.4.3 docker.io/rancher/harvester-network-webhook:v0.5.6 docker.io/rancher/harvester-vm-import-controller:v0.4.3 docker.io/rancher/shell:v0.1.24 registry.suse.com/suse/sles/15.6/virt-api:1.3.1-150600.5.9.1 docker.io/fluent/fluent-bit:2.1.8 docker.io/longhornio/csi-provisioner:v4.0.1-20250204 docker.io/rancher/harvester-load-balancer:v0.4.4 docker.io/rancher/mirrored-prometheus-node-exporter:v1.3.1 docker.io/longhornio/csi-resizer:v1.13.1 docker.io/rancher/rke2-cloud-provider:v1.31.2-0.20241016053446-0955fa330f90-build20241016 docker.io/longhornio/livenessprobe:v2.15.0 docker.io/rancher/rke2-runtime:v1.31.7-rke2r1 registry.suse.com/suse/sles/15.6/virt-handler:1.3.1-150600.5.9.1 docker.io/rancher/hardened-calico:v3.29.2-build20250306 docker.io/rancher/mirrored-cluster-api-controller:v1.8.3 docker.io/rancher/mirrored-prometheus-prometheus:v2.45.0 docker.io/rancher/rancher:v2.10.1 docker.io/rancher/harvester-seeder:v0.4.3 docker.io/rancher/mirrored-prometheus-alertmanager:v0.26.0 docker.io/rancher/system-agent-installer-rke2:v1.31.7-rke2r1 ghcr.io/kube-logging/fluentd:v1.15-ruby3 docker.io/rancher/klipper-helm:v0.9.4-build20250113 docker.io/longhornio/longhorn-share-manager:v1.7.3 docker.io/rancher/hardened-coredns:v1.12.0-build20241126]"#;
-        let expected = "info";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Info;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T07:55:50.064883108Z time="2025-12-08T07:55:50Z" level=error msg="error syncing 'fleet-local/request-x49zj': handler cluster-registration: failed to delete fleet-local/request-x49zj rbac.authorization.k8s.io/v1, Kind=RoleBinding for cluster-registration fleet-local/request-x49zj: rolebindings.rbac.authorization.k8s.io \"request-x49zj\" not found, requeuing"#;
-        let expected = "error";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Error;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T10:30:36.714032412Z time="2025-12-08T10:30:36Z" level=debug msg="Prepare to encode to yaml file path: /tmp/support-bundle-kit/bundle/yamls/namespaced/fleet-local/v1/configmaps.yaml"#;
-        let expected = "debug";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Debug;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_find_log_level_pattern2() {
-        let sb_search = SBSearch::new("./testdata/support_bundle", "test").unwrap();
+        let sb_search = SBSearch::new(
+            "./testdata/support_bundle",
+            "test",
+            &SearchOptions::default(),
+        )
+        .unwrap();
 
         let line = r#"2025-12-08T07:31:53.675701835Z {"level":"warn","ts":"2025-12-08T07:31:53.675659Z","caller":"etcdserver/util.go:170","msg":"apply request took too long","took":"122.37201ms","expected-duration":"100ms","prefix":"read-only range ","request":"key:\"/registry/pods/cattle-fleet-local-system/fleet-agent-77c65c9d9d-pxttp\" limit:1 ","response":"range_response_count:0 size:7"}"#;
-        let expected = "warn";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Warn;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T07:31:53.675709316Z {"level":"info","ts":"2025-12-08T07:31:53.675686Z","caller":"traceutil/trace.go:171","msg":"trace[1928396386] range","detail":"{range_begin:/registry/pods/cattle-fleet-local-system/fleet-agent-77c65c9d9d-pxttp; range_end:; response_count:0; response_revision:89089900; }","duration":"122.440061ms","start":"2025-12-08T07:31:53.553239Z","end":"2025-12-08T07:31:53.675679Z","steps":["trace[1928396386] 'agreement among raft nodes before linearized reading'  (duration: 122.37561ms)"],"step_count":1}"#;
-        let expected = "info";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Info;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T10:27:24.459805082Z {"level":"info","ts":"2025-12-08T10:27:24Z","logger":"bundle","msg":"Unchanged bundledeployment","controller":"bundle","controllerGroup":"fleet.cattle.io","controllerKind":"Bundle","Bundle":{"name":"mcc-rancher-monitoring-crd","namespace":"fleet-local"},"namespace":"fleet-local","name":"mcc-rancher-monitoring-crd","reconcileID":"60a1cd4d-9ddf-4248-a6c6-c1353dab3e71","manifestID":"s-f2fb94554dbed0b86084cd509f78763ed14e1338a52bd90ee7a4b7ff53e0a","bundledeployment":{"metadata":{"name":"mcc-rancher-monitoring-crd","namespace":"cluster-fleet-local-local-1a3d67d0a899","creationTimestamp":null,"labels":{"fleet.cattle.io/bundle-name":"mcc-rancher-monitoring-crd","fleet.cattle.io/bundle-namespace":"fleet-local","fleet.cattle.io/cluster":"local","fleet.cattle.io/cluster-namespace":"fleet-local","fleet.cattle.io/managed":"true"},"finalizers":["fleet.cattle.io/bundle-deployment-finalizer"]},"spec":{"paused":true,"stagedOptions":{"defaultNamespace":"cattle-monitoring-system","helm":{"releaseName":"rancher-monitoring-crd","version":"105.1.2+up61.3.2","timeoutSeconds":600},"ignore":{}},"stagedDeploymentID":"s-f2fb94554dbed0b86084cd509f78763ed14e1338a52bd90ee7a4b7ff53e0a:90a578a64e92227563052c8bf1f175c182d754a1955e3222f1b8f6dcdabb5ee8","options":{"defaultNamespace":"cattle-monitoring-system","helm":{"releaseName":"rancher-monitoring-crd","version":"105.1.2+up61.3.2","timeoutSeconds":600},"ignore":{}},"deploymentID":"s-f2fb94554dbed0b86084cd509f78763ed14e1338a52bd90ee7a4b7ff53e0a:90a578a64e92227563052c8bf1f175c182d754a1955e3222f1b8f6dcdabb5ee8"},"status":{"display":{},"resourceCounts":{"ready":0,"desiredReady":0,"waitApplied":0,"modified":0,"orphaned":0,"missing":0,"unknown":0,"notReady":0}}},"deploymentID":"s-f2fb94554dbed0b86084cd509f78763ed14e1338a52bd90ee7a4b7ff53e0a:90a578a64e92227563052c8bf1f175c182d754a1955e3222f1b8f6dcdabb5ee8","operation":"unchanged"}"#;
-        let expected = "info";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Info;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn test_find_log_level_pattern3() {
-        let sb_search = SBSearch::new("./testdata/support_bundle", "test").unwrap();
+        let sb_search = SBSearch::new(
+            "./testdata/support_bundle",
+            "test",
+            &SearchOptions::default(),
+        )
+        .unwrap();
         let line = r#"2025-12-08T07:27:14.834602400Z E1208 07:27:14.834539       1 job_controller.go:631] "Unhandled Error" err="syncing job: tracking status: adding uncounted pods to status: Operation cannot be fulfilled on jobs.batch \"fleet-cleanup-clusterregistrations\": the object has been modified; please apply your changes to the latest version and try again" logger="UnhandledError"
 "#;
-        let expected = "error";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Error;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
+
+        // the klog header names the level even when the message has an error
+        let line = r#"2025-12-08T07:27:14.834602400Z W1208 07:27:14.834539       1 reflector.go:561] failed to list *v1.Secret: err="connection refused""#;
+        assert_eq!(sb_search.find_log_level(line), Level::Warn);
+        let line = "I1208 07:27:14.834539       1 controller.go:42] synced";
+        assert_eq!(sb_search.find_log_level(line), Level::Info);
+        let line = "Dec 08 07:27:14 isim-dev kubelet[5540]: E1208 07:27:14.834539    5540 pod_workers.go:1301] \"Error syncing pod\" level=info";
+        assert_eq!(sb_search.find_log_level(line), Level::Error);
+        let line = "2025-12-08T07:27:14.834602400Z stderr F F1208 07:27:14.834539       1 server.go:42] exiting";
+        assert_eq!(sb_search.find_log_level(line), Level::Fatal);
+        // a klog-like token within the message isn't a header
+        let line = "2025-12-08T07:27:14Z level=info msg=\"copied W1208 07:27:14.834539\"";
+        assert_eq!(sb_search.find_log_level(line), Level::Info);
     }
 
     #[test]
     fn test_find_log_level_pattern4() {
-        let sb_search = SBSearch::new("./testdata/support_bundle", "test").unwrap();
+        let sb_search = SBSearch::new(
+            "./testdata/support_bundle",
+            "test",
+            &SearchOptions::default(),
+        )
+        .unwrap();
         let line = r#"2025-12-08T07:47:45.565219601Z 2025/12/08 07:47:45 [error] 3099#3099: *7756 upstream prematurely closed connection while reading upstream, client: 192.168.48.101, server: rancher.192.168.48.100.example.org, request: "GET /apis/fleet.cattle.io/v1alpha1/namespaces/cluster-fleet-default-mgmt-bb69eaf374c2/bundledeployments?allowWatchBookmarks=true&resourceVersion=20055629&timeoutSeconds=479&watch=true HTTP/2.0", upstream: "http://10.52.0.2:80/apis/fleet.cattle.io/v1alpha1/namespaces/cluster-fleet-default-mgmt-bb69eaf374c2/bundledeployments?allowWatchBookmarks=true&resourceVersion=20055629&timeoutSeconds=479&watch=true", host: "rancher.192.168.48.100.example.org"
 "#;
-        let expected = "error";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Error;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
 
         let line = r#"2025-12-08T08:23:35.438311029Z 2025/12/08 08:23:35 [ERROR] error syncing 'fleet-local/local-managed-system-upgrade-controller': handler mcc-bundle: configmaps "" not found, requeuing"#;
-        let expected = "error";
-        let actual = sb_search.find_log_level(line).unwrap();
+        let expected = Level::Error;
+        let actual = sb_search.find_log_level(line);
         assert_eq!(actual, expected);
+
+        let line = "2025/12/08 07:47:45 [warn] 3099#3099: *7756 an upstream response is buffered";
+        assert_eq!(sb_search.find_log_level(line), Level::Warn);
     }
 
     #[test]
     fn test_included_path() {
-        let sb_search = SBSearch::new("testdata/support_bundle", "").unwrap();
+        let sb_search =
+            SBSearch::new("testdata/support_bundle", "", &SearchOptions::default()).unwrap();
         let path = Path::new("testdata/support_bundle");
         assert!(sb_search.is_log_dir(path));
 
@@ -579,11 +2961,204 @@ mod tests {
 
         let path = Path::new("testdata/support_bundle/yamls/namespaced/default/pods.yaml");
         assert!(!sb_search.is_log_dir(path));
+
+        let options = SearchOptions {
+            include_yamls: true,
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("testdata/support_bundle", "", &options).unwrap();
+        let path = Path::new("testdata/support_bundle/yamls/namespaced/default/v1");
+        assert!(sb_search.is_log_dir(path));
+
+        let options = SearchOptions {
+            pods: vec![
+                String::from("virt-launcher-*"),
+                String::from("rke2-canal-?????"),
+            ],
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("testdata/support_bundle", "", &options).unwrap();
+        for (path, expected) in [
+            ("testdata/support_bundle", true),
+            ("testdata/support_bundle/logs/default", true),
+            (
+                "testdata/support_bundle/logs/default/virt-launcher-vm-00-pb825",
+                true,
+            ),
+            (
+                "testdata/support_bundle/logs/kube-system/rke2-canal-jnjvb",
+                true,
+            ),
+            (
+                "testdata/support_bundle/logs/kube-system/rke2-canal-jnjvb-x",
+                false,
+            ),
+            (
+                "testdata/support_bundle/logs/cattle-system/rancher-75f954475f-tdk4p",
+                false,
+            ),
+            ("testdata/support_bundle/nodes", false),
+        ] {
+            assert_eq!(sb_search.is_log_dir(Path::new(path)), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_search_with_nodes() {
+        let path = Path::new("testdata/support_bundle");
+        let options = SearchOptions {
+            nodes: vec![String::from("isim-dev")],
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("testdata/support_bundle", "", &options).unwrap();
+        for (path, expected) in [
+            ("testdata/support_bundle/nodes/isim-dev.zip", true),
+            (
+                "testdata/support_bundle/nodes/isim-dev/logs/kubelet.log",
+                true,
+            ),
+            ("testdata/support_bundle/nodes/isim-dev-2.tar.gz", false),
+            ("testdata/support_bundle/nodes/other/logs", false),
+            ("testdata/support_bundle/logs/default", true),
+        ] {
+            assert_eq!(sb_search.is_included(Path::new(path)), expected, "{}", path);
+        }
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, 10, cache, &options).unwrap();
+        let total = cache.len();
+        assert!(
+            cache
+                .iter()
+                .any(|e| e.path.contains("/nodes/isim-dev.zip/"))
+        );
+
+        let options = SearchOptions {
+            nodes: vec![String::from("other")],
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(!cache.is_empty() && cache.len() < total);
+        assert!(cache.iter().all(|e| !e.path.contains("/nodes/")));
+    }
+
+    #[test]
+    fn test_source_fields() {
+        let root_dir = "sb";
+        let fields = source_fields(
+            "sb/logs/default/virt-launcher-vm-00-pb825/compute.log",
+            root_dir,
+        );
+        assert_eq!(fields["namespace"], "default");
+        assert_eq!(fields["pod"], "virt-launcher-vm-00-pb825");
+        assert_eq!(fields["container"], "compute");
+        assert!(!fields.contains_key("node"));
+
+        let fields = source_fields(
+            "sb/logs/kube-system/rke2-multus-fhvxv/kube-rke2-multus.log.1",
+            root_dir,
+        );
+        assert_eq!(fields["container"], "kube-rke2-multus");
+        assert_eq!(fields["rotation"], "1");
+
+        let fields = source_fields("sb/nodes/n1.zip/n1/logs/kubelet.log", root_dir);
+        assert_eq!(
+            fields,
+            BTreeMap::from([(String::from("node"), String::from("n1"))])
+        );
+        assert!(source_fields("sb/logs/events.log", root_dir).is_empty());
+
+        let entry = Entry {
+            level: Level::Info,
+            path: String::from("sb/logs/default/virt-launcher-vm-00-pb825/compute.log"),
+            content: String::new(),
+            timestamp: None,
+            line_number: 1,
+            fields: source_fields(
+                "sb/logs/default/virt-launcher-vm-00-pb825/compute.log",
+                root_dir,
+            ),
+            duplicates: 0,
+        };
+        assert_eq!(entry.pod(), Some("virt-launcher-vm-00-pb825"));
+        assert_eq!(entry.node(), None);
+        assert_eq!(
+            entry.breadcrumb().unwrap(),
+            "default › virt-launcher-vm-00-pb825 › compute"
+        );
+    }
+
+    #[test]
+    fn test_search_rotated_logs() {
+        let path = Path::new("testdata/support_bundle");
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            pods: vec![String::from("cdi-apiserver-*")],
+            ..Default::default()
+        };
+        search(path, "apiserver.go:92", 0, 10, cache, &options).unwrap();
+
+        let rotations: Vec<Option<&str>> = cache
+            .iter()
+            .map(|e| e.fields.get("rotation").map(String::as_str))
+            .collect();
+        assert!(rotations.contains(&Some("1")) && rotations.contains(&None));
+        for entry in cache.iter() {
+            assert_eq!(entry.path.ends_with(".log.1"), entry.rotation() == 1);
+        }
+        // the rotated log comes before the current one
+        assert!(cache.windows(2).all(|w| w[0].rotation() >= w[1].rotation()));
+    }
+
+    #[test]
+    fn test_search_with_pods() {
+        let path = Path::new("testdata/support_bundle");
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            pods: vec![String::from("virt-launcher-*")],
+            ..Default::default()
+        };
+        search(path, "vm-00", 0, 10, cache, &options).unwrap();
+
+        assert!(!cache.is_empty());
+        assert!(
+            cache
+                .iter()
+                .all(|e| e.path.contains("/logs/default/virt-launcher-vm-00-pb825/"))
+        );
+    }
+
+    #[test]
+    fn test_search_with_yamls() {
+        let path = Path::new("testdata/support_bundle");
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            include_yamls: true,
+            ..Default::default()
+        };
+        search(path, "vm-00", 0, 10, cache, &options).unwrap();
+
+        let manifests: Vec<&Entry> = cache
+            .iter()
+            .filter(|e| e.path.contains("/yamls/"))
+            .collect();
+        assert!(cache.len() > 244);
+        assert_eq!(cache.len() - manifests.len(), 244);
+
+        // each matching manifest line is an entry of its own
+        assert!(
+            manifests.iter().all(
+                |e| e.fields.get("kind").map(String::as_str) == Some("manifest")
+                    && e.content.lines().count() == 1
+            )
+        );
     }
 
     #[test]
     fn test_find_timestamp() {
-        let sb_search = SBSearch::new("./testdata/support_bundle", "").unwrap();
+        let sb_search =
+            SBSearch::new("./testdata/support_bundle", "", &SearchOptions::default()).unwrap();
         let line = r#"2025-12-08T08:23:35.438311029Z 2025/12/08 08:23:35 [ERROR] error syncing 'fleet-local/local-managed-system-upgrade-controller': handler mcc-bundle: configmaps "" not found, requeuing"#;
         let expected = "2025-12-08T08:23:35.438311029Z"
             .parse::<DateTime<Utc>>()
@@ -663,6 +3238,776 @@ mod tests {
         // assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_search_multiline_entries() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(
+            log_dir.join("app.log"),
+            "vm-00 orphan line\n\
+             2025-12-30T21:00:00Z level=info msg=\"starting vm-00\"\n\
+             2025-12-30T21:00:01Z level=error msg=\"reconcile failed\"\n\
+             panic: runtime error: invalid memory address\n\
+             goroutine 1 [running]:\n\
+             \tcontroller.sync(vm-00)\n\
+             2025-12-30T21:00:02Z level=info msg=\"done\"\n",
+        )
+        .unwrap();
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cache.len(), 3);
+        let entry = cache.iter().find(|e| e.line_number == 1).unwrap();
+        assert_eq!(entry.content, "vm-00 orphan line\n");
+
+        // the stack trace is joined onto the entry that logged it
+        let entry = cache.iter().find(|e| e.line_number == 3).unwrap();
+        assert_eq!(entry.level, Level::Error);
+        assert_eq!(entry.content.lines().count(), 4);
+        assert!(entry.content.ends_with("\tcontroller.sync(vm-00)\n"));
+    }
+
+    #[test]
+    fn test_search_multiline_entries_far_apart() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        let filler = "2025-12-30T21:00:01Z level=info msg=\"idle\"\n".repeat(2000);
+        fs::write(
+            log_dir.join("app.log"),
+            format!(
+                "2025-12-30T21:00:00Z level=error msg=\"vm-00 failed\"\n\
+                 \tcontroller.sync()\n\
+                 {filler}\
+                 2025-12-30T21:00:02Z level=error msg=\"reconcile failed\"\n\
+                 panic: runtime error\n\
+                 \tcontroller.sync(vm-00)\n\
+                 {filler}"
+            ),
+        )
+        .unwrap();
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+
+        // the entries are grouped from the lines around the matches only
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[0].line_number, 1);
+        assert_eq!(cache[0].content.lines().count(), 2);
+        assert_eq!(cache[1].line_number, 2003);
+        assert_eq!(cache[1].content.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_search_encodings() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        let line = "2025-12-30T21:00:00Z level=error msg=\"vm-00 failed\"\n";
+        let utf16 = |bom: &[u8]| {
+            let mut bytes = bom.to_vec();
+            bytes.extend(line.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        };
+        fs::write(log_dir.join("guest.log"), utf16(&[])).unwrap();
+        fs::write(log_dir.join("guest-bom.log"), utf16(&[0xFF, 0xFE])).unwrap();
+        fs::write(
+            log_dir.join("vendor.log"),
+            b"2025-12-30T21:00:00Z level=info msg=\"caf\xe9 vm-00\"\n",
+        )
+        .unwrap();
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cache.len(), 3);
+        for name in ["guest.log", "guest-bom.log"] {
+            let entry = cache.iter().find(|e| e.path.ends_with(name)).unwrap();
+            assert_eq!(entry.content, line);
+            assert_eq!(entry.level, Level::Error);
+        }
+        let entry = cache
+            .iter()
+            .find(|e| e.path.ends_with("vendor.log"))
+            .unwrap();
+        assert!(entry.content.contains("caf\u{FFFD} vm-00"));
+    }
+
+    #[test]
+    fn test_search_with_max_file_size() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let line = "2025-12-30T21:00:00Z level=info msg=\"attached vm-00\"\n";
+        let log = line.repeat(3);
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(log_dir.join("audit.log"), &log).unwrap();
+        fs::write(log_dir.join("app.log"), line).unwrap();
+
+        let nodes_dir = root_dir.path().join("nodes");
+        fs::create_dir_all(&nodes_dir).unwrap();
+        let mut writer = zip::ZipWriter::new(File::create(nodes_dir.join("node-0.zip")).unwrap());
+        writer
+            .start_file("node-0/logs/kubelet.log", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(log.as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        // only the first line of the large files is searched
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            max_file_size: line.len() as u64,
+            ..Default::default()
+        };
+        let result = search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(cache.len(), 3);
+        assert_eq!(result.warnings.len(), 2);
+        assert!(
+            result
+                .warnings
+                .iter()
+                .all(|w| w.to_string().starts_with("only searched the first 53 B"))
+        );
+        assert!(
+            result
+                .warnings
+                .iter()
+                .any(|w| w.path().ends_with("audit.log"))
+        );
+        assert!(
+            result
+                .warnings
+                .iter()
+                .any(|w| w.path().ends_with("kubelet.log"))
+        );
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cache.len(), 7);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_search_with_bad_archive() {
+        let line = "2025-12-30T21:00:00Z level=info msg=\"attached vm-00\"\n";
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        fs::write(log_dir.join("app.log"), line).unwrap();
+
+        // a zip archive cut short after its signature
+        let nodes_dir = root_dir.path().join("nodes");
+        fs::create_dir_all(&nodes_dir).unwrap();
+        fs::write(nodes_dir.join("node-0.zip"), b"PK\x03\x04truncated").unwrap();
+
+        // the other files are still searched
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(matches!(
+            result.warnings[0],
+            SearchWarning::BadArchive { .. }
+        ));
+        assert!(result.warnings[0].path().ends_with("node-0.zip"));
+    }
+
+    #[test]
+    fn test_search_with_heap_limit() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        let line = format!("level=info msg=\"attached vm-00 {}\"\n", "x".repeat(1024));
+        fs::write(log_dir.join("app.log"), &line).unwrap();
+
+        // the line doesn't fit in the heap of a searcher reading the file
+        let options = SearchOptions {
+            heap_limit: Some(256),
+            no_mmap: true,
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(cache.is_empty());
+        assert!(matches!(
+            result.warnings[..],
+            [SearchWarning::RegexOverflow { .. }]
+        ));
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            no_mmap: true,
+            ..Default::default()
+        };
+        let result = search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_search_with_timeout() {
+        let path = Path::new("testdata/support_bundle");
+        let options = SearchOptions {
+            timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(path, "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(
+            result.warnings,
+            vec![SearchWarning::SearchTimedOut {
+                path: String::from("testdata/support_bundle"),
+                timeout: Duration::ZERO,
+            }]
+        );
+
+        let options = SearchOptions {
+            timeout: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let result = search(path, "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(cache.len(), 244);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_search_with_memory_budget() {
+        let path = Path::new("testdata/support_bundle");
+        let search_with = |memory_budget, threads| {
+            let cache: &mut Vec<Entry> = &mut Vec::new();
+            let options = SearchOptions {
+                memory_budget,
+                threads,
+                ..Default::default()
+            };
+            let result = search(path, "vm-00", 0, 10, cache, &options).unwrap();
+            // the files left once the budget is reached are reported together
+            let over_budget: Vec<&SearchWarning> = result
+                .warnings
+                .iter()
+                .filter(|w| matches!(w, SearchWarning::MemoryBudget { .. }))
+                .collect();
+            assert_eq!(over_budget.len(), 1);
+            assert!(matches!(
+                over_budget[0],
+                SearchWarning::MemoryBudget { files, .. } if *files > 1
+            ));
+            cache.clone()
+        };
+
+        // the entries are reserved one at a time, so the budget holds
+        // however many threads search the files
+        for threads in [1, 4] {
+            assert_eq!(search_with(1, threads).len(), 1);
+
+            let budget = 20 * std::mem::size_of::<Entry>();
+            let cache = search_with(budget, threads);
+            assert!(cache.len() < 244);
+            let largest = cache.iter().map(Entry::approx_size).max().unwrap();
+            assert!(cache_size(&cache) >= budget);
+            assert!(cache_size(&cache) < budget + largest);
+        }
+    }
+
+    #[test]
+    fn test_search_with_threads() {
+        let path = Path::new("testdata/support_bundle");
+        let search_with = |threads| {
+            let cache: &mut Vec<Entry> = &mut Vec::new();
+            let options = SearchOptions {
+                threads,
+                ..Default::default()
+            };
+            search(path, "vm-00", 0, 10, cache, &options).unwrap();
+            cache
+                .iter()
+                .map(|e| (e.path.clone(), e.line_number))
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = search_with(1);
+        assert_eq!(sequential.len(), 244);
+        assert_eq!(search_with(4), sequential);
+    }
+
+    #[test]
+    fn test_search_streaming() {
+        let path = Path::new("testdata/support_bundle");
+        let options = SearchOptions::default();
+        let receiver = search_streaming(path, "vm-00", &options, &CancelToken::default()).unwrap();
+
+        let mut streamed: Vec<Entry> = Vec::new();
+        for batch in receiver {
+            let SearchEvent::Entries(batch) = batch.unwrap() else {
+                continue;
+            };
+            assert!(!batch.is_empty());
+            streamed.extend(batch);
+        }
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(path, "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(streamed.len(), cache.len());
+
+        // invalid keywords are reported before searching
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        assert!(search_streaming(path, "vm-(", &options, &CancelToken::default()).is_err());
+    }
+
+    #[test]
+    fn test_search_streaming_cancelled() {
+        let path = Path::new("testdata/support_bundle");
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let receiver = search_streaming(path, "vm-00", &SearchOptions::default(), &cancel).unwrap();
+
+        let mut failed = false;
+        for batch in receiver {
+            match batch {
+                Ok(SearchEvent::Entries(_)) => panic!("a cancelled search finds no entries"),
+                Ok(_) => {}
+                Err(e) => {
+                    assert_eq!(e, "search cancelled");
+                    failed = true;
+                }
+            }
+        }
+        assert!(failed);
+    }
+
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let path = entry.unwrap().path();
+            let dest = to.join(path.file_name().unwrap());
+            match path.is_dir() {
+                true => copy_dir(&path, &dest),
+                false => drop(fs::copy(&path, &dest).unwrap()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_warm_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("supportbundle");
+        copy_dir(
+            Path::new("testdata/support_bundle/nodes"),
+            &root.join("nodes"),
+        );
+        let cancel = CancelToken::default();
+        assert!(warm_index(&root, &SearchOptions::default(), &cancel).is_none());
+
+        // the node archives are indexed in the background, and the searches
+        // read the index
+        let options = SearchOptions {
+            index: true,
+            ..Default::default()
+        };
+        warm_index(&root, &options, &cancel)
+            .unwrap()
+            .join()
+            .unwrap();
+        let index = index::Index::locate(&root, &options).unwrap();
+        assert!(index.path().exists());
+
+        let expected: &mut Vec<Entry> = &mut Vec::new();
+        search(&root, "vm-00", 0, 10, expected, &SearchOptions::default()).unwrap();
+        assert!(!expected.is_empty());
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(cache.len(), expected.len());
+    }
+
+    #[test]
+    fn test_search_with_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("supportbundle");
+        copy_dir(
+            Path::new("testdata/support_bundle/logs"),
+            &root.join("logs"),
+        );
+
+        let options = SearchOptions {
+            index: true,
+            ..Default::default()
+        };
+        let expected: &mut Vec<Entry> = &mut Vec::new();
+        search(&root, "vm-00", 0, 10, expected, &SearchOptions::default()).unwrap();
+
+        // the first search builds the index, and the next ones read it
+        let index = index::Index::locate(&root, &options).unwrap();
+        for _ in 0..2 {
+            let cache: &mut Vec<Entry> = &mut Vec::new();
+            search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+            assert!(index.path().exists());
+            assert_eq!(cache.len(), expected.len());
+            assert!(cache.iter().all(|e| e.content.contains("vm-00")));
+        }
+
+        // a nested file rewritten in place, e.g. by an updated bundle
+        // extracted over it, is searched again
+        let log = root.join("logs/cattle-system/helm-operation-fgm6s/helm.log");
+        let mut content = fs::read_to_string(&log).unwrap();
+        content.push_str("2025-12-30T22:00:00Z level=info msg=\"attached vm-00\"\n");
+        fs::write(&log, content).unwrap();
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(cache.len(), expected.len() + 1);
+
+        // the filters apply to the indexed entries
+        let options = SearchOptions {
+            levels: vec![Level::Error],
+            ..options
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(!cache.is_empty());
+        assert!(cache.iter().all(|e| e.level == Level::Error));
+
+        // the search of the index reports its progress too
+        let receiver =
+            search_streaming(&root, "vm-00-disk-0-", &options, &CancelToken::default()).unwrap();
+        let mut streamed = 0;
+        let mut progress = Progress::default();
+        for batch in receiver {
+            match batch.unwrap() {
+                SearchEvent::Entries(entries) => streamed += entries.len(),
+                SearchEvent::Progress(p) => progress = p,
+                SearchEvent::Warning(_) => {}
+            }
+        }
+        assert!(progress.total_files > 0);
+        assert_eq!(progress.files, progress.total_files);
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            index: false,
+            ..options
+        };
+        search(&root, "vm-00-disk-0-", 0, 10, cache, &options).unwrap();
+        assert_eq!(streamed, cache.len());
+
+        // the files only partly searched when the index was built are still
+        // reported by the searches reading it
+        let options = SearchOptions {
+            index: true,
+            max_file_size: 1024,
+            ..Default::default()
+        };
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let built = search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(!built.warnings.is_empty());
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let read = search(&root, "vm-00", 0, 10, cache, &options).unwrap();
+        assert_eq!(read.warnings, built.warnings);
+    }
+
+    #[test]
+    fn test_find_timestamp_with_offset() {
+        let sb_search =
+            SBSearch::new("./testdata/support_bundle", "", &SearchOptions::default()).unwrap();
+        let expected = "2025-12-30T13:58:14.266Z".parse::<DateTime<Utc>>().unwrap();
+        for line in [
+            "2025-12-30T21:58:14.266+08:00 level=info msg=\"attached vm-00\"",
+            "time=\"2025-12-30T21:58:14.266+0800\" level=info",
+            "2025-12-30T09:13:14.266-04:45 level=info",
+        ] {
+            assert_eq!(sb_search.find_timestamp(line).unwrap(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_find_epoch_timestamp() {
+        let sb_search =
+            SBSearch::new("./testdata/support_bundle", "", &SearchOptions::default()).unwrap();
+        let expected = "2024-12-30T21:57:51.388Z".parse::<DateTime<Utc>>().unwrap();
+        for line in [
+            "level=info ts=1735595871.388 caller=main.go:42 msg=\"attached vm-00\"",
+            r#"{"level":"info","ts":1735595871.388,"msg":"attached vm-00"}"#,
+            r#"{"level":"info","ts":1735595871388,"msg":"attached vm-00"}"#,
+        ] {
+            assert_eq!(sb_search.find_timestamp(line).unwrap(), Some(expected));
+        }
+
+        let line = "ts=1735595871 msg=\"attached vm-00\"";
+        let expected = "2024-12-30T21:57:51Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(sb_search.find_timestamp(line).unwrap(), Some(expected));
+
+        // other numbers aren't timestamps
+        assert_eq!(
+            sb_search
+                .find_timestamp("ts=17355958 size=1735595871")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_syslog_timestamp() {
+        let sb_search =
+            SBSearch::new("./testdata/support_bundle", "", &SearchOptions::default()).unwrap();
+        let line = "Dec 30 21:45:44.178049 localhost kernel: BIOS-provided physical RAM map:";
+        let expected = "2025-12-30T21:45:44.178049Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        assert_eq!(sb_search.find_timestamp(line).unwrap(), Some(expected));
+        assert!(sb_search.starts_entry(line));
+
+        let options = SearchOptions {
+            assume_tz: "UTC,syslog=+08:00".parse().unwrap(),
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("./testdata/support_bundle", "", &options).unwrap();
+        let expected = "2025-12-30T13:45:44.178049Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        assert_eq!(sb_search.find_timestamp(line).unwrap(), Some(expected));
+
+        // the year is inferred from the time the logs were collected at
+        let reference = "2026-01-02T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let naive = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").ok();
+        assert_eq!(
+            syslog_timestamp("Dec 30 21:51:44.485722", reference),
+            naive("2025-12-30 21:51:44.485722")
+        );
+        assert_eq!(
+            syslog_timestamp("Jan  2 07:59:00", reference),
+            naive("2026-01-02 07:59:00")
+        );
+        assert_eq!(syslog_timestamp("Foo 30 21:51:44", reference), None);
+    }
+
+    #[test]
+    fn test_find_timestamp_assume_tz() {
+        let line = r#"2025-12-30 21:58:14.266 [INFO][52211] cni-plugin/k8s.go 446: Added Mac"#;
+        let options = SearchOptions {
+            assume_tz: "+08:00".parse().unwrap(),
+            ..Default::default()
+        };
+        let sb_search = SBSearch::new("./testdata/support_bundle", "", &options).unwrap();
+        let expected = "2025-12-30T13:58:14.266Z".parse::<DateTime<Utc>>().unwrap();
+        let actual = sb_search.find_timestamp(line).unwrap().unwrap();
+        assert_eq!(actual, expected);
+
+        // timestamps with explicit timezone aren't affected
+        let line = r#"2025-12-30T21:57:51.388772685Z time="2025-12-30T21:57:51Z" level=info"#;
+        let expected = "2025-12-30T21:57:51.388772685Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let actual = sb_search.find_timestamp(line).unwrap().unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_search_tar_gz_archive() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let nodes_dir = root_dir.path().join("nodes");
+        fs::create_dir_all(&nodes_dir).unwrap();
+
+        let archive_path = nodes_dir.join("node-0.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&archive_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let content = "2025-12-30T21:00:00Z level=info msg=\"attached vm-00\"\n\
+                       2025-12-30T21:00:01Z level=info msg=\"unrelated\"\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "node-0/logs/kubelet.log", content.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(is_tar_gz(&archive_path).unwrap());
+        assert!(!is_tar_gz(Path::new("testdata/support_bundle/nodes/isim-dev.zip")).unwrap());
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        search(
+            root_dir.path(),
+            "vm-00",
+            0,
+            10,
+            cache,
+            &SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache[0].path,
+            archive_path
+                .join("node-0/logs/kubelet.log")
+                .to_str()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_search_nested_archives() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let zip = |name: &str, content: &[u8]| {
+            let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content).unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+        let log = b"2025-12-30T21:00:00Z level=info msg=\"attached vm-00\"\n";
+        let inner = zip("app.log", log);
+        let outer = zip("node-0/logs/archive.zip", &inner);
+
+        let root_dir = tempfile::tempdir().unwrap();
+        let nodes_dir = root_dir.path().join("nodes");
+        fs::create_dir_all(&nodes_dir).unwrap();
+        fs::write(nodes_dir.join("node-0.zip"), outer).unwrap();
+
+        let search_with_depth = |max_archive_depth: usize| {
+            let cache: &mut Vec<Entry> = &mut Vec::new();
+            let options = SearchOptions {
+                max_archive_depth,
+                ..Default::default()
+            };
+            search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+            cache.clone()
+        };
+
+        // the nested archive is skipped rather than searched as raw bytes
+        assert!(search_with_depth(0).is_empty());
+
+        let entries = search_with_depth(1);
+        assert_eq!(entries.len(), 1);
+        assert!(
+            entries[0]
+                .path
+                .ends_with("nodes/node-0.zip/node-0/logs/archive.zip/app.log")
+        );
+        assert_eq!(search_with_depth(DEFAULT_MAX_ARCHIVE_DEPTH).len(), 1);
+
+        // nested zip archives over the max file size aren't read into memory
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions {
+            max_file_size: 16,
+            ..Default::default()
+        };
+        let result = search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+        assert!(cache.is_empty());
+        assert!(matches!(
+            result.warnings.as_slice(),
+            [SearchWarning::TooLarge { limit: 16, .. }]
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_search_with_symlinks() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let logs_dir = root_dir.path().join("logs");
+        fs::create_dir_all(logs_dir.join("default").join("app")).unwrap();
+        fs::write(
+            logs_dir.join("default").join("app").join("app.log"),
+            "2025-12-30T21:00:00Z level=info msg=\"attached vm-00\"\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(logs_dir.join("default"), logs_dir.join("linked")).unwrap();
+        std::os::unix::fs::symlink(&logs_dir, logs_dir.join("default").join("loop")).unwrap();
+
+        let search_with = |follow_symlinks: bool| {
+            let cache: &mut Vec<Entry> = &mut Vec::new();
+            let options = SearchOptions {
+                follow_symlinks,
+                ..Default::default()
+            };
+            search(root_dir.path(), "vm-00", 0, 10, cache, &options).unwrap();
+            cache.len()
+        };
+
+        // symlinks are skipped by default
+        assert_eq!(search_with(false), 1);
+
+        // the linked directory is the same directory, and the loop back to
+        // logs/ is cut short
+        assert_eq!(search_with(true), 1);
+
+        // a symlink to a directory outside of the searched tree is followed
+        let other_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            other_dir.path().join("other.log"),
+            "2025-12-30T21:00:01Z level=info msg=\"detached vm-00\"\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(other_dir.path(), logs_dir.join("other")).unwrap();
+        assert_eq!(search_with(false), 1);
+        assert_eq!(search_with(true), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_search_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let root_dir = tempfile::tempdir().unwrap();
+        let dir = root_dir
+            .path()
+            .join(std::ffi::OsStr::from_bytes(b"bundle-\xff"));
+        fs::create_dir_all(&dir).unwrap();
+
+        let cache: &mut Vec<Entry> = &mut Vec::new();
+        let options = SearchOptions::default();
+        assert!(search(&dir, "vm-00", 0, 10, cache, &options).is_err());
+        assert!(search_streaming(&dir, "vm-00", &options, &CancelToken::default()).is_err());
+    }
+
     #[test]
     fn test_is_zip() {
         assert!(is_zip(Path::new("testdata/support_bundle/nodes/isim-dev.zip")).unwrap());
@@ -0,0 +1,67 @@
+use log::*;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// The magic number at the start of zstd frames.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Returns true if the path is a zstd-compressed bundle, e.g. `.tar.zst`.
+pub fn is_compressed_bundle(path: &Path) -> io::Result<bool> {
+    if !path.is_file() {
+        return Ok(false);
+    }
+
+    let mut file = File::open(path)?;
+    let mut signature = [0u8; 4];
+    match file.read_exact(&mut signature) {
+        Ok(_) => Ok(signature == ZSTD_MAGIC),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Extracts the zstd-compressed tarball at `path` into `dest`, and returns the
+/// root directory of the support bundle. Bundles are usually archived with a
+/// single top-level directory, which becomes the root.
+pub fn extract(path: &Path, dest: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    info!("extracting {} into {}", path.display(), dest.display());
+    let decoder = zstd::Decoder::new(File::open(path)?)?;
+    Archive::new(decoder).unpack(dest)?;
+
+    let children: Vec<PathBuf> = fs::read_dir(dest)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    match children.as_slice() {
+        [root] if root.is_dir() => Ok(root.clone()),
+        _ => Ok(dest.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("supportbundle.tar.zst");
+        let encoder = zstd::Encoder::new(File::create(&bundle).unwrap(), 0).unwrap();
+        let mut builder = tar::Builder::new(encoder);
+        builder
+            .append_dir_all("supportbundle", "testdata/support_bundle/logs")
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(is_compressed_bundle(&bundle).unwrap());
+        assert!(!is_compressed_bundle(Path::new("testdata/support_bundle")).unwrap());
+        assert!(!is_compressed_bundle(Path::new("testdata/support_bundle/metadata.yaml")).unwrap());
+
+        let dest = dir.path().join("extracted");
+        fs::create_dir(&dest).unwrap();
+        let root = extract(&bundle, &dest).unwrap();
+        assert_eq!(root, dest.join("supportbundle"));
+        assert!(root.join("default").is_dir());
+    }
+}
@@ -0,0 +1,61 @@
+use grep_searcher::Encoding;
+
+/// The number of bytes at the start of a file examined to detect its
+/// encoding.
+pub const SNIFF_LEN: u64 = 512;
+
+/// Detects UTF-16 text without a byte order mark from the start of a file.
+/// Files with a BOM are transcoded by the searcher already, and other files are
+/// searched as UTF-8, with invalid sequences replaced when the lines are
+/// decoded.
+pub fn sniff(head: &[u8]) -> Option<Encoding> {
+    // mostly-ASCII UTF-16 has a NUL in every other byte
+    let units = head.len() / 2;
+    if units < 2 || head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF]) {
+        return None;
+    }
+    let even = head
+        .iter()
+        .step_by(2)
+        .take(units)
+        .filter(|b| **b == 0)
+        .count();
+    let odd = head.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+
+    let label = match (even * 10 / units, odd * 10 / units) {
+        (0, 8..) => "utf-16le",
+        (8.., 0) => "utf-16be",
+        _ => return None,
+    };
+    Encoding::new(label).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str, le: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|u| match le {
+                true => u.to_le_bytes(),
+                false => u.to_be_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sniff() {
+        let line = "2024-05-01 10:00:00.000 error: disk full\r\n";
+        assert_eq!(sniff(&utf16(line, true)), Encoding::new("utf-16le").ok());
+        assert_eq!(sniff(&utf16(line, false)), Encoding::new("utf-16be").ok());
+
+        // the searcher sniffs the BOM itself
+        let mut bom = vec![0xFF, 0xFE];
+        bom.extend(utf16(line, true));
+        assert_eq!(sniff(&bom), None);
+
+        assert_eq!(sniff(line.as_bytes()), None);
+        assert_eq!(sniff(b"caf\xe9 au lait"), None);
+        assert_eq!(sniff(b""), None);
+    }
+}
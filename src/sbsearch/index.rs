@@ -0,0 +1,548 @@
+use super::{Entry, Level, Progress, SearchOptions, SearchWarning};
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::*;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The directory next to the support bundle holding its indexes.
+const INDEX_DIR: &str = ".sbsearch";
+
+/// The first line of index files, changed whenever the format changes.
+const INDEX_VERSION: &str = "sbsearch-index-v4";
+
+/// Held while an index is checked and built, so that only one search of the
+/// process builds it.
+static BUILDING: Mutex<()> = Mutex::new(());
+
+/// An on-disk index of all the entries of a support bundle. Searching the
+/// index reads one file instead of walking the bundle and unpacking its
+/// archives again.
+///
+/// The index is a header, with the size and modification time of each file
+/// searched, followed by one record per entry, and one per file that was
+/// skipped or only partly searched, so that the searches of the index warn
+/// about them too. The paths, the contents and the errors are prefixed by
+/// their length, as they can hold tabs and newlines:
+///
+/// ```text
+/// sbsearch-index-v4
+/// <key>
+/// <number of files>
+/// <size>\t<modification time>\t<path length>
+/// <path>
+/// <line number>\t<timestamp or ->\t<content length>\t<level>\t<path length>
+/// <path><content>
+/// !<warning kind>\t<limit>\t<path length>\t<error length>
+/// <path><error>
+/// ```
+#[derive(Debug, Clone)]
+pub struct Index {
+    path: PathBuf,
+    key: String,
+}
+
+/// The size and modification time of a file of the index, which is stale
+/// once either changes.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Stamp {
+    path: String,
+    size: u64,
+    mtime: u128,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> io::Result<Stamp> {
+        let metadata = fs::metadata(path)?;
+        Ok(Stamp {
+            path: path.to_string_lossy().into_owned(),
+            size: metadata.len(),
+            mtime: metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+        })
+    }
+
+    fn all(files: &[PathBuf]) -> io::Result<Vec<Stamp>> {
+        let mut stamps = files
+            .iter()
+            .map(|path| Stamp::of(path))
+            .collect::<io::Result<Vec<_>>>()?;
+        stamps.sort();
+        Ok(stamps)
+    }
+}
+
+/// The key and the file stamps at the start of the index.
+struct Header {
+    key: String,
+    stamps: Vec<Stamp>,
+}
+
+impl Index {
+    /// Locates the index of the support bundle at `root_dir`, in the `.sbsearch`
+    /// directory next to it. The index is keyed by the bundle path and the
+    /// options that change how entries are parsed. As the entries hold the
+    /// paths the bundle was searched with, the path given is part of the key
+    /// too.
+    pub fn locate(root_dir: &Path, options: &SearchOptions) -> io::Result<Index> {
+        let root = fs::canonicalize(root_dir)?;
+        let key = format!(
+            "{:?} {:?} {:?} {} {} {} {} {:?}",
+            root,
+            root_dir,
+            options.assume_tz,
+            options.include_yamls,
+            options.max_archive_depth,
+            options.max_file_size,
+            options.follow_symlinks,
+            options.heap_limit
+        );
+
+        let name = root.file_name().unwrap_or_default().to_string_lossy();
+        let path = root.parent().unwrap_or(&root).join(INDEX_DIR).join(format!(
+            "{}-{:016x}.idx",
+            name,
+            fnv1a(key.as_bytes())
+        ));
+        Ok(Index { path, key })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for the index being built by another search of the process, and
+    /// keeps the others from building it until the guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'static, ()> {
+        BUILDING.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns true if the index exists, was built for the same key, and from
+    /// the same `files`, none of which changed since.
+    pub fn is_valid(&self, files: &[PathBuf]) -> bool {
+        let Ok(file) = File::open(&self.path) else {
+            return false;
+        };
+        let Ok(current) = Stamp::all(files) else {
+            return false;
+        };
+        match read_header(&mut BufReader::new(file)) {
+            Ok(Some(Header { key, stamps })) => key == self.key && stamps == current,
+            _ => false,
+        }
+    }
+
+    /// Reads the entries of the index, passing them to `emit` in batches, and
+    /// returns the files that were skipped or only partly searched when the
+    /// index was built. The entries don't have any fields. As the entries of
+    /// the files are interleaved, the `progress` is estimated from how much of
+    /// the index is read.
+    pub fn read(
+        &self,
+        progress: impl Fn(Progress),
+        mut emit: impl FnMut(Vec<Entry>) -> Result<(), String>,
+    ) -> Result<Vec<SearchWarning>, Box<dyn Error>> {
+        const BATCH_SIZE: usize = 10_000;
+
+        let file = File::open(&self.path)?;
+        let length = file.metadata()?.len().max(1);
+        let mut reader = BufReader::new(file);
+        let Some(header) = read_header(&mut reader)? else {
+            return Err(format!("invalid index {}", self.path.display()).into());
+        };
+        let total_files = header.stamps.len();
+        let total_bytes: u64 = header.stamps.iter().map(|stamp| stamp.size).sum();
+        let report = |position: u64| {
+            let ratio = position.min(length) as f64 / length as f64;
+            progress(Progress {
+                files: (total_files as f64 * ratio) as usize,
+                total_files,
+                bytes: (total_bytes as f64 * ratio) as u64,
+            });
+        };
+        report(0);
+
+        let mut line = String::new();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut warnings = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            if let Some(header) = line.trim_end_matches('\n').strip_prefix('!') {
+                warnings.push(read_warning(&mut reader, header)?);
+                continue;
+            }
+            batch.push(read_entry(&mut reader, line.trim_end_matches('\n'))?);
+            if batch.len() == BATCH_SIZE {
+                emit(std::mem::take(&mut batch))?;
+                report(reader.stream_position()?);
+            }
+        }
+        if !batch.is_empty() {
+            emit(batch)?;
+        }
+        report(length);
+        Ok(warnings)
+    }
+
+    /// Creates the index of `files`, stamped with their current size and
+    /// modification time. The entries are written to a temporary file, which
+    /// replaces the index once finished, and is removed if the index isn't,
+    /// so an interrupted build leaves nothing behind.
+    pub fn create(&self, files: &[PathBuf]) -> io::Result<IndexWriter> {
+        let stamps = Stamp::all(files)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("idx.tmp");
+        let writer = IndexWriter {
+            writer: Mutex::new(BufWriter::new(File::create(&tmp)?)),
+            tmp,
+            path: self.path.clone(),
+        };
+        {
+            let mut writer = writer.writer.lock().unwrap();
+            writeln!(writer, "{}", INDEX_VERSION)?;
+            writeln!(writer, "{}", self.key)?;
+            writeln!(writer, "{}", stamps.len())?;
+            for stamp in stamps {
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}",
+                    stamp.size,
+                    stamp.mtime,
+                    stamp.path.len()
+                )?;
+                writer.write_all(stamp.path.as_bytes())?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(writer)
+    }
+}
+
+/// Writes the entries of an index, from any number of threads.
+pub struct IndexWriter {
+    writer: Mutex<BufWriter<File>>,
+    tmp: PathBuf,
+    path: PathBuf,
+}
+
+impl IndexWriter {
+    pub fn write(&self, entries: &[Entry]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        for entry in entries {
+            let timestamp = entry
+                .timestamp
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Nanos, true))
+                .unwrap_or_else(|| String::from("-"));
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.line_number,
+                timestamp,
+                entry.content.len(),
+                entry.level,
+                entry.path.len()
+            )?;
+            writer.write_all(entry.path.as_bytes())?;
+            writer.write_all(entry.content.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the files that were skipped or only partly searched, and
+    /// replaces the index with the entries written.
+    pub fn finish(self, warnings: &[SearchWarning]) -> io::Result<()> {
+        {
+            let mut writer = self.writer.lock().unwrap();
+            for warning in warnings {
+                write_warning(&mut *writer, warning)?;
+            }
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+        fs::rename(&self.tmp, &self.path)?;
+        info!("saved the index to {}", self.path.display());
+        Ok(())
+    }
+}
+
+impl Drop for IndexWriter {
+    fn drop(&mut self) {
+        // the temporary file is already renamed once the index is finished
+        if fs::remove_file(&self.tmp).is_ok() {
+            debug!("removed the unfinished index {}", self.tmp.display());
+        }
+    }
+}
+
+/// Hashes the bytes with 64-bit FNV-1a, which unlike `DefaultHasher` stays
+/// the same across Rust releases, so the index is found again after an
+/// upgrade.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Reads the key and the file stamps of the index, or None if the index is
+/// of another version.
+fn read_header(reader: &mut impl BufRead) -> Result<Option<Header>, Box<dyn Error>> {
+    if next_line(reader)? != INDEX_VERSION {
+        return Ok(None);
+    }
+    let key = next_line(reader)?;
+    let count: usize = next_line(reader)?.parse()?;
+
+    let mut stamps = Vec::with_capacity(count);
+    for _ in 0..count {
+        let header = next_line(reader)?;
+        let invalid = || format!("invalid index file '{}'", header);
+        let mut parts = header.splitn(3, '\t');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let size = next()?.parse()?;
+        let mtime = next()?.parse()?;
+        let length = next()?.parse()?;
+        let path = read_string(reader, length)?;
+        next_line(reader)?;
+        stamps.push(Stamp { path, size, mtime });
+    }
+    Ok(Some(Header { key, stamps }))
+}
+
+fn next_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    line.truncate(line.trim_end_matches('\n').len());
+    Ok(line)
+}
+
+fn read_entry(reader: &mut impl Read, header: &str) -> Result<Entry, Box<dyn Error>> {
+    let invalid = || format!("invalid index record '{}'", header);
+    let mut parts = header.splitn(5, '\t');
+    let mut next = || parts.next().ok_or_else(invalid);
+    let line_number = next()?.parse()?;
+    let timestamp = match next()? {
+        "-" => None,
+        t => Some(DateTime::parse_from_rfc3339(t)?.with_timezone(&Utc)),
+    };
+    let length = next()?.parse()?;
+    let level = Level::normalize(next()?);
+    let path_length = next()?.parse()?;
+
+    Ok(Entry {
+        level,
+        path: read_string(reader, path_length)?,
+        content: read_string(reader, length)?,
+        timestamp,
+        line_number,
+        fields: Default::default(),
+        duplicates: 0,
+    })
+}
+
+fn write_warning(writer: &mut impl Write, warning: &SearchWarning) -> io::Result<()> {
+    let (kind, limit, path, error) = match warning {
+        SearchWarning::Unreadable { path, error } => ("unreadable", 0, path, error.clone()),
+        SearchWarning::BadArchive { path, error } => ("bad-archive", 0, path, error.clone()),
+        SearchWarning::RegexOverflow { path, error } => ("regex-overflow", 0, path, error.clone()),
+        SearchWarning::TimedOut { path, error } => ("timed-out", 0, path, error.clone()),
+        // the number of files turned down stands in for the error
+        SearchWarning::MemoryBudget {
+            path,
+            budget,
+            files,
+        } => ("memory-budget", *budget as u64, path, files.to_string()),
+        SearchWarning::Truncated { path, limit } => ("truncated", *limit, path, String::new()),
+        SearchWarning::TooLarge { path, limit } => ("too-large", *limit, path, String::new()),
+        SearchWarning::SearchTimedOut { path, timeout } => (
+            "search-timed-out",
+            timeout.as_millis() as u64,
+            path,
+            String::new(),
+        ),
+    };
+    writeln!(
+        writer,
+        "!{}\t{}\t{}\t{}",
+        kind,
+        limit,
+        path.len(),
+        error.len()
+    )?;
+    writer.write_all(path.as_bytes())?;
+    writer.write_all(error.as_bytes())
+}
+
+fn read_warning(reader: &mut impl Read, header: &str) -> Result<SearchWarning, Box<dyn Error>> {
+    let invalid = || format!("invalid index warning '{}'", header);
+    let mut parts = header.splitn(4, '\t');
+    let mut next = || parts.next().ok_or_else(invalid);
+    let kind = next()?;
+    let limit: u64 = next()?.parse()?;
+    let path_length = next()?.parse()?;
+    let error_length = next()?.parse()?;
+    let path = read_string(reader, path_length)?;
+    let error = read_string(reader, error_length)?;
+
+    Ok(match kind {
+        "unreadable" => SearchWarning::Unreadable { path, error },
+        "bad-archive" => SearchWarning::BadArchive { path, error },
+        "regex-overflow" => SearchWarning::RegexOverflow { path, error },
+        "timed-out" => SearchWarning::TimedOut { path, error },
+        "memory-budget" => SearchWarning::MemoryBudget {
+            path,
+            budget: limit as usize,
+            files: error.parse()?,
+        },
+        "truncated" => SearchWarning::Truncated { path, limit },
+        "too-large" => SearchWarning::TooLarge { path, limit },
+        "search-timed-out" => SearchWarning::SearchTimedOut {
+            path,
+            timeout: Duration::from_millis(limit),
+        },
+        _ => return Err(invalid().into()),
+    })
+}
+
+fn read_string(reader: &mut impl Read, length: usize) -> Result<String, Box<dyn Error>> {
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("supportbundle");
+        fs::create_dir(&root).unwrap();
+        let files = vec![root.join("a.log"), root.join("b\tb.log")];
+        for file in &files {
+            fs::write(file, "first line\n").unwrap();
+        }
+
+        let index = Index::locate(&root, &SearchOptions::default()).unwrap();
+        assert!(
+            index
+                .path()
+                .starts_with(fs::canonicalize(dir.path()).unwrap())
+        );
+        assert!(!index.is_valid(&files));
+
+        let entries = vec![
+            Entry {
+                level: Level::Error,
+                path: String::from("sb/logs/a.log"),
+                content: String::from("first line\n  continued\n"),
+                timestamp: Some(Utc::now()),
+                line_number: 3,
+                fields: Default::default(),
+                duplicates: 0,
+            },
+            Entry {
+                level: Level::Unknown,
+                path: String::from("sb/logs/b\tb\n.log"),
+                content: String::from("no timestamp\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            },
+        ];
+        let writer = index.create(&files).unwrap();
+        writer.write(&entries).unwrap();
+        assert!(!index.is_valid(&files));
+        let warnings = vec![
+            SearchWarning::Truncated {
+                path: String::from("sb/logs/a.log"),
+                limit: 1024,
+            },
+            SearchWarning::Unreadable {
+                path: String::from("sb/logs/b\tb\n.log"),
+                error: String::from("permission denied\n"),
+            },
+        ];
+        writer.finish(&warnings).unwrap();
+        assert!(index.is_valid(&files));
+
+        let mut read = Vec::new();
+        let last = Mutex::new(Progress::default());
+        let read_warnings = index
+            .read(
+                |progress| *last.lock().unwrap() = progress,
+                |batch| {
+                    read.extend(batch);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read_warnings, warnings);
+        let last = last.into_inner().unwrap();
+        assert_eq!((last.files, last.total_files), (2, 2));
+        assert_eq!(last.bytes, 2 * "first line\n".len() as u64);
+        for (a, b) in read.iter().zip(&entries) {
+            assert_eq!(a.content, b.content);
+            assert_eq!(a.path, b.path);
+            assert_eq!(a.level, b.level);
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.line_number, b.line_number);
+        }
+
+        // other parsing options use another index
+        let options = SearchOptions {
+            include_yamls: true,
+            ..Default::default()
+        };
+        let other = Index::locate(&root, &options).unwrap();
+        assert_ne!(other.path(), index.path());
+        assert!(!other.is_valid(&files));
+        let options = SearchOptions {
+            heap_limit: Some(256),
+            ..Default::default()
+        };
+        let other = Index::locate(&root, &options).unwrap();
+        assert_ne!(other.path(), index.path());
+
+        // the index is stale once a file changes, or files are added
+        assert!(!index.is_valid(&files[..1]));
+        fs::write(&files[0], "first line\nsecond line\n").unwrap();
+        assert!(!index.is_valid(&files));
+    }
+
+    #[test]
+    fn test_unfinished_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("supportbundle");
+        fs::create_dir(&root).unwrap();
+
+        // the temporary file of an index that isn't finished, e.g. of a
+        // cancelled search, is removed
+        let index = Index::locate(&root, &SearchOptions::default()).unwrap();
+        let writer = index.create(&[]).unwrap();
+        let tmp = index.path().with_extension("idx.tmp");
+        assert!(tmp.exists());
+        drop(writer);
+        assert!(!tmp.exists());
+        assert!(!index.path().exists());
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
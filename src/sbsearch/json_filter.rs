@@ -0,0 +1,517 @@
+use std::fmt;
+use std::str::FromStr;
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// A filter over the fields of JSON log lines, in a jq-like syntax, e.g.
+/// `.component=="virt-launcher" and .msg contains "Domain"`.
+///
+/// Fields are paths like `.msg` or `.labels."app.kubernetes.io/name"`, and are
+/// compared with `==`, `!=`, `<`, `<=`, `>`, `>=` and `contains` to strings,
+/// numbers, `true`, `false` and `null`. A path on its own holds when the field
+/// is set to anything but `null` or `false`. Comparisons combine with `and`,
+/// `or`, `not` and parentheses. Missing fields are `null`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonFilter {
+    expr: Expr,
+    source: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Truthy(Vec<String>),
+    Compare(Vec<String>, Op, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+/// A value of the JSON object of a log line, with the fields of objects in
+/// the order they appear, e.g. to pretty-print it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Object(Vec<(String, JsonValue)>),
+    Array(Vec<JsonValue>),
+    String(String),
+    /// A number, as it's written.
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Path(Vec<String>),
+    String(String),
+    Number(f64),
+    Word(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl JsonFilter {
+    /// Returns true if the line holds a JSON object whose fields match the
+    /// filter. Anything before the object, such as the CRI prefix of
+    /// container logs, is skipped.
+    pub fn matches(&self, line: &str) -> bool {
+        parse(line).is_some_and(|doc| self.expr.eval(&doc))
+    }
+}
+
+/// Returns the fields of the JSON object in the line, flattened to paths like
+/// `.msg` or `.labels.app` and `.items[0]`, with their values in the order
+/// they appear. Anything before the object is skipped, like by `matches`.
+pub fn json_fields(line: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    if let Some(doc) = parse(line) {
+        flatten(&doc, String::new(), &mut fields);
+    }
+    fields
+}
+
+/// Returns the JSON object in the line, if there's one. Anything before the
+/// object is skipped, like by `matches`.
+pub fn json_value(line: &str) -> Option<JsonValue> {
+    parse(line).map(|doc| value(&doc))
+}
+
+fn value(node: &Yaml) -> JsonValue {
+    match node {
+        Yaml::Hash(hash) => JsonValue::Object(
+            hash.iter()
+                .map(|(key, value_node)| (scalar(key), value(value_node)))
+                .collect(),
+        ),
+        Yaml::Array(items) => JsonValue::Array(items.iter().map(value).collect()),
+        Yaml::String(s) => JsonValue::String(s.clone()),
+        Yaml::Real(s) => JsonValue::Number(s.clone()),
+        Yaml::Integer(i) => JsonValue::Number(i.to_string()),
+        Yaml::Boolean(b) => JsonValue::Bool(*b),
+        _ => JsonValue::Null,
+    }
+}
+
+fn parse(line: &str) -> Option<Yaml> {
+    let start = line.find('{')?;
+    let docs = YamlLoader::load_from_str(line[start..].trim_end()).ok()?;
+    docs.into_iter()
+        .next()
+        .filter(|doc| matches!(doc, Yaml::Hash(_)))
+}
+
+fn flatten(node: &Yaml, path: String, fields: &mut Vec<(String, String)>) {
+    match node {
+        Yaml::Hash(hash) => {
+            for (key, value) in hash {
+                flatten(value, format!("{}.{}", path, scalar(key)), fields);
+            }
+        }
+        Yaml::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten(item, format!("{}[{}]", path, index), fields);
+            }
+        }
+        _ => fields.push((path, scalar(node))),
+    }
+}
+
+fn scalar(node: &Yaml) -> String {
+    match node {
+        Yaml::String(s) | Yaml::Real(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        _ => String::from("null"),
+    }
+}
+
+impl Expr {
+    fn eval(&self, doc: &Yaml) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(doc) && b.eval(doc),
+            Expr::Or(a, b) => a.eval(doc) || b.eval(doc),
+            Expr::Not(e) => !e.eval(doc),
+            Expr::Truthy(path) => !matches!(
+                lookup(doc, path),
+                None | Some(Yaml::Null) | Some(Yaml::Boolean(false))
+            ),
+            Expr::Compare(path, op, value) => compare(lookup(doc, path), *op, value),
+        }
+    }
+}
+
+fn lookup<'a>(doc: &'a Yaml, path: &[String]) -> Option<&'a Yaml> {
+    path.iter().try_fold(doc, |node, key| match node {
+        Yaml::Hash(hash) => hash.get(&Yaml::String(key.clone())),
+        _ => None,
+    })
+}
+
+fn number(field: &Yaml) -> Option<f64> {
+    match field {
+        Yaml::Integer(i) => Some(*i as f64),
+        Yaml::Real(r) => r.parse().ok(),
+        _ => None,
+    }
+}
+
+fn compare(field: Option<&Yaml>, op: Op, value: &Value) -> bool {
+    let field = field.unwrap_or(&Yaml::Null);
+    let equal = || match (field, value) {
+        (Yaml::String(s), Value::String(v)) => s == v,
+        (Yaml::Boolean(b), Value::Bool(v)) => b == v,
+        (Yaml::Null, Value::Null) => true,
+        (field, Value::Number(v)) => number(field) == Some(*v),
+        _ => false,
+    };
+    let ordering = || match (field, value) {
+        (Yaml::String(s), Value::String(v)) => Some(s.as_str().cmp(v.as_str())),
+        (field, Value::Number(v)) => number(field).and_then(|n| n.partial_cmp(v)),
+        _ => None,
+    };
+
+    match op {
+        Op::Eq => equal(),
+        Op::Ne => !equal(),
+        Op::Lt => ordering().is_some_and(|o| o.is_lt()),
+        Op::Le => ordering().is_some_and(|o| o.is_le()),
+        Op::Gt => ordering().is_some_and(|o| o.is_gt()),
+        Op::Ge => ordering().is_some_and(|o| o.is_ge()),
+        Op::Contains => match (field, value) {
+            (Yaml::String(s), Value::String(v)) => s.contains(v.as_str()),
+            _ => false,
+        },
+    }
+}
+
+/// Splits the filter into tokens.
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::String(string(&mut chars)?));
+            }
+            '.' => {
+                let mut path = Vec::new();
+                while chars.next_if_eq(&'.').is_some() {
+                    let key = match chars.next_if_eq(&'"') {
+                        Some(_) => string(&mut chars)?,
+                        None => {
+                            let mut key = String::new();
+                            while let Some(c) = chars.next_if(|c| is_key_char(*c)) {
+                                key.push(c);
+                            }
+                            key
+                        }
+                    };
+                    if key.is_empty() {
+                        return Err(String::from("expected a field name after '.'"));
+                    }
+                    path.push(key);
+                }
+                tokens.push(Token::Path(path));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', true) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(format!("unknown operator '{}'", c)),
+                };
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut literal = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| c.is_ascii_alphanumeric() || "-+.".contains(*c))
+                {
+                    literal.push(c);
+                }
+                let n = literal
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", literal))?;
+                tokens.push(Token::Number(n));
+            }
+            c if c.is_ascii_alphabetic() => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Reads a string literal up to its closing quote, which is consumed.
+fn string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some(c) => s.push(c),
+                None => break,
+            },
+            Some(c) => s.push(c),
+            None => break,
+        }
+    }
+    Err(String::from("unterminated string"))
+}
+
+/// Parses the tokens by precedence: `or` binds loosest, then `and`, then
+/// `not`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == word) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_word("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat_word("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_word("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(String::from("expected ')'")),
+                }
+            }
+            Some(Token::Path(path)) => self.comparison(path),
+            Some(token) => Err(format!("expected a field, found {}", token)),
+            None => Err(String::from("expected a field")),
+        }
+    }
+
+    fn comparison(&mut self, path: Vec<String>) -> Result<Expr, String> {
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            Some(Token::Word(w)) if w == "contains" => Op::Contains,
+            _ => return Ok(Expr::Truthy(path)),
+        };
+        self.pos += 1;
+
+        let value = match self.next() {
+            Some(Token::String(s)) => Value::String(s),
+            Some(Token::Number(n)) => Value::Number(n),
+            Some(Token::Word(w)) if w == "true" => Value::Bool(true),
+            Some(Token::Word(w)) if w == "false" => Value::Bool(false),
+            Some(Token::Word(w)) if w == "null" => Value::Null,
+            Some(token) => return Err(format!("expected a value, found {}", token)),
+            None => return Err(String::from("expected a value")),
+        };
+        if op == Op::Contains && !matches!(value, Value::String(_)) {
+            return Err(String::from("contains expects a string"));
+        }
+        Ok(Expr::Compare(path, op, value))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Path(path) => write!(f, "'.{}'", path.join(".")),
+            Token::String(s) => write!(f, "\"{}\"", s),
+            Token::Number(n) => write!(f, "'{}'", n),
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::Op(_) => write!(f, "an operator"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+impl FromStr for JsonFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {}", token));
+        }
+        Ok(JsonFilter {
+            expr,
+            source: String::from(s.trim()),
+        })
+    }
+}
+
+impl fmt::Display for JsonFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let line = r#"{"level":"info","component":"virt-launcher","msg":"Domain started: vm-00","pos":"manager.go:42","retries":3,"labels":{"app.kubernetes.io/name":"kubevirt"},"ready":true}"#;
+        let matches = |filter: &str| filter.parse::<JsonFilter>().unwrap().matches(line);
+
+        assert!(matches(
+            r#".component=="virt-launcher" and .msg contains "Domain""#
+        ));
+        assert!(!matches(
+            r#".component=="virt-handler" and .msg contains "Domain""#
+        ));
+        assert!(matches(r#".level=="error" or .retries >= 3"#));
+        assert!(matches(r#"not .level=="error""#));
+        assert!(matches(r#".labels."app.kubernetes.io/name"=="kubevirt""#));
+        assert!(matches(".ready and .missing == null"));
+        assert!(!matches(".missing"));
+        assert!(matches(
+            r#"(.retries < 2 or .retries != 3) or .pos contains ":42""#
+        ));
+
+        // the CRI prefix of container logs is skipped
+        let cri = format!("2025-12-30T21:00:00Z stdout F {}", line);
+        let filter: JsonFilter = ".retries==3".parse().unwrap();
+        assert!(filter.matches(&cri));
+        assert!(!filter.matches("level=info msg=\"not json\""));
+    }
+
+    #[test]
+    fn test_json_fields() {
+        let line = r#"2025-12-30T21:00:00Z stdout F {"msg":"Domain started","retries":3,"labels":{"app":"kubevirt"},"disks":["rootdisk"],"error":null}"#;
+        let fields = json_fields(line);
+        assert_eq!(
+            fields,
+            vec![
+                (String::from(".msg"), String::from("Domain started")),
+                (String::from(".retries"), String::from("3")),
+                (String::from(".labels.app"), String::from("kubevirt")),
+                (String::from(".disks[0]"), String::from("rootdisk")),
+                (String::from(".error"), String::from("null")),
+            ]
+        );
+        assert!(json_fields("level=info msg=\"not json\"").is_empty());
+    }
+
+    #[test]
+    fn test_json_value() {
+        let line = r#"I0101 {"msg":"ok","n":1.5,"tags":["a",true],"meta":{"x":null}}"#;
+        assert_eq!(
+            json_value(line),
+            Some(JsonValue::Object(vec![
+                (String::from("msg"), JsonValue::String(String::from("ok"))),
+                (String::from("n"), JsonValue::Number(String::from("1.5"))),
+                (
+                    String::from("tags"),
+                    JsonValue::Array(vec![
+                        JsonValue::String(String::from("a")),
+                        JsonValue::Bool(true)
+                    ])
+                ),
+                (
+                    String::from("meta"),
+                    JsonValue::Object(vec![(String::from("x"), JsonValue::Null)])
+                ),
+            ]))
+        );
+        assert_eq!(json_value("no json here"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for filter in [
+            "",
+            ".",
+            ".msg ==",
+            ".msg contains 3",
+            r#".msg == "open"#,
+            r#"(.msg == "a""#,
+            r#".msg == "a" .level"#,
+            "msg == 1",
+            ".msg =~ 1",
+        ] {
+            assert!(filter.parse::<JsonFilter>().is_err(), "{}", filter);
+        }
+        let filter: JsonFilter = r#" .msg contains "a" "#.parse().unwrap();
+        assert_eq!(filter.to_string(), r#".msg contains "a""#);
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The log level of an entry, normalized from the spellings of the different
+/// loggers, e.g. `warn`, `warning` and klog's `W` are all `Warn`. Levels are
+/// ordered by severity, with `Unknown` the lowest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    #[default]
+    Unknown,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// All the levels, most severe first.
+    pub const ALL: [Level; 6] = [
+        Level::Fatal,
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Unknown,
+    ];
+
+    /// Normalizes a level as written in a log line. Unrecognized levels are
+    /// `Unknown`.
+    pub fn normalize(s: &str) -> Level {
+        s.parse().unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Unknown => "UNKNOWN",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fatal" | "f" | "panic" | "critical" | "crit" | "emerg" | "alert" => Ok(Level::Fatal),
+            "error" | "err" | "e" => Ok(Level::Error),
+            "warn" | "warning" | "w" => Ok(Level::Warn),
+            "info" | "i" | "notice" => Ok(Level::Info),
+            "debug" | "dbg" | "d" | "trace" => Ok(Level::Debug),
+            "unknown" => Ok(Level::Unknown),
+            _ => Err(format!("unknown log level '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        for (s, level) in [
+            ("warn", Level::Warn),
+            ("WARNING", Level::Warn),
+            ("W", Level::Warn),
+            ("err", Level::Error),
+            ("E", Level::Error),
+            ("I", Level::Info),
+            ("dbg", Level::Debug),
+            ("trace", Level::Debug),
+            ("panic", Level::Fatal),
+            ("UNKNOWN", Level::Unknown),
+            ("verbose", Level::Unknown),
+        ] {
+            assert_eq!(Level::normalize(s), level, "{}", s);
+        }
+        assert!("verbose".parse::<Level>().is_err());
+        assert_eq!(
+            Level::normalize(&Level::Unknown.to_string()),
+            Level::Unknown
+        );
+        assert!(Level::Error > Level::Warn && Level::Debug > Level::Unknown);
+    }
+}
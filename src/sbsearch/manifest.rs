@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use log::*;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Labels whose value names the application of a pod, in order of preference.
+const APP_LABELS: &[&str] = &["app.kubernetes.io/name", "app", "k8s-app", "name"];
+
+/// Labels whose value names the component of a pod, in order of preference.
+const COMPONENT_LABELS: &[&str] = &[
+    "app.kubernetes.io/component",
+    "longhorn.io/component",
+    "component",
+];
+
+/// Labels whose value names the Helm chart of a pod, in order of preference.
+const CHART_LABELS: &[&str] = &["helm.sh/chart", "chart"];
+
+/// Workload metadata of the pods in the support bundle, read from the pod
+/// manifests in `yamls/namespaced/<namespace>/v1/pods.yaml`.
+#[derive(Debug, Clone, Default)]
+pub struct Manifests {
+    pods: HashMap<(String, String), BTreeMap<String, String>>,
+}
+
+impl Manifests {
+    /// Loads the pod manifests of the support bundle. Manifests that can't be
+    /// read or parsed are skipped.
+    pub fn load(root_dir: &Path) -> Self {
+        let mut manifests = Manifests::default();
+        let namespaced = root_dir.join("yamls").join("namespaced");
+        let Ok(dir) = fs::read_dir(&namespaced) else {
+            debug!("no pod manifests found in {}", namespaced.display());
+            return manifests;
+        };
+
+        for entry in dir.flatten() {
+            let path = entry.path().join("v1").join("pods.yaml");
+            if !path.is_file() {
+                continue;
+            }
+            if let Err(e) = manifests.load_pods(&path) {
+                warn!("failed to read pod manifests {}: {}", path.display(), e);
+            }
+        }
+        info!("loaded metadata of {} pods", manifests.pods.len());
+        manifests
+    }
+
+    fn load_pods(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        for doc in YamlLoader::load_from_str(&content)? {
+            let Some(items) = doc["items"].as_vec() else {
+                continue;
+            };
+            for item in items {
+                let metadata = &item["metadata"];
+                let (Some(namespace), Some(name)) =
+                    (metadata["namespace"].as_str(), metadata["name"].as_str())
+                else {
+                    continue;
+                };
+                self.pods.insert(
+                    (String::from(namespace), String::from(name)),
+                    pod_fields(metadata),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the workload fields of the pod whose logs are at `path`, i.e.
+    /// `<root_dir>/logs/<namespace>/<pod>/<container>.log`.
+    pub fn fields_for(&self, path: &str, root_dir: &str) -> BTreeMap<String, String> {
+        let relative = path.strip_prefix(root_dir).unwrap_or(path);
+        let parts: Vec<&str> = relative.split('/').filter(|p| !p.is_empty()).collect();
+        if let ["logs", namespace, pod, ..] = parts.as_slice()
+            && let Some(fields) = self
+                .pods
+                .get(&(String::from(*namespace), String::from(*pod)))
+        {
+            return fields.clone();
+        }
+        BTreeMap::new()
+    }
+}
+
+/// A resource in the support bundle whose name makes a good search keyword.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Resource {
+    pub kind: &'static str,
+    pub name: String,
+}
+
+/// Lists the virtual machines, PVCs, volumes and nodes of the support bundle,
+/// read from the manifests in `yamls/`. Manifests that can't be read or
+/// parsed are skipped.
+pub fn resources(root_dir: &Path) -> Vec<Resource> {
+    let yamls = root_dir.join("yamls");
+    let mut resources = Vec::new();
+    let mut add = |kind, path: &Path, name: &dyn Fn(&Yaml) -> Option<String>| {
+        if !path.is_file() {
+            return;
+        }
+        match load_items(path) {
+            Ok(items) => resources.extend(
+                items
+                    .iter()
+                    .filter_map(name)
+                    .map(|name| Resource { kind, name }),
+            ),
+            Err(e) => warn!("failed to read manifests {}: {}", path.display(), e),
+        }
+    };
+    let metadata_name = |item: &Yaml| item["metadata"]["name"].as_str().map(String::from);
+
+    if let Ok(dir) = fs::read_dir(yamls.join("namespaced")) {
+        for entry in dir.flatten() {
+            let namespace = entry.path();
+            let pvcs = namespace.join("v1").join("persistentvolumeclaims.yaml");
+            add(
+                "vm",
+                &namespace.join("kubevirt.io/v1/virtualmachines.yaml"),
+                &metadata_name,
+            );
+            add("pvc", &pvcs, &metadata_name);
+            add("volume", &pvcs, &|item| {
+                item["spec"]["volumeName"].as_str().map(String::from)
+            });
+        }
+    }
+    add("node", &yamls.join("cluster/v1/nodes.yaml"), &metadata_name);
+
+    resources.sort();
+    resources.dedup();
+    info!("found {} resources in {}", resources.len(), yamls.display());
+    resources
+}
+
+/// Returns the time the support bundle was created at, from its
+/// `metadata.yaml`.
+pub fn bundle_created_at(root_dir: &Path) -> Option<DateTime<Utc>> {
+    let content = fs::read_to_string(root_dir.join("metadata.yaml")).ok()?;
+    let docs = YamlLoader::load_from_str(&content).ok()?;
+    docs.first()?["bundlecreatedat"].as_str()?.parse().ok()
+}
+
+fn load_items(path: &Path) -> Result<Vec<Yaml>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let mut items = Vec::new();
+    for doc in YamlLoader::load_from_str(&content)? {
+        if let Some(list) = doc["items"].as_vec() {
+            items.extend(list.iter().cloned());
+        }
+    }
+    Ok(items)
+}
+
+fn pod_fields(metadata: &Yaml) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let labels = &metadata["labels"];
+    let label = |keys: &[&str]| keys.iter().find_map(|key| labels[*key].as_str());
+
+    if let Some(app) = label(APP_LABELS) {
+        fields.insert(String::from("app"), String::from(app));
+    }
+    if let Some(component) = label(COMPONENT_LABELS) {
+        fields.insert(String::from("component"), String::from(component));
+    }
+    if let Some(chart) = label(CHART_LABELS) {
+        fields.insert(String::from("chart"), String::from(chart));
+    }
+
+    let owner = &metadata["ownerReferences"][0];
+    let (kind, workload) = match (owner["kind"].as_str(), owner["name"].as_str()) {
+        // pods of a deployment are owned by a replicaset named after the
+        // deployment and the pod template hash
+        (Some("ReplicaSet"), Some(name)) => match labels["pod-template-hash"].as_str() {
+            Some(hash) => (
+                "Deployment",
+                name.strip_suffix(format!("-{}", hash).as_str())
+                    .unwrap_or(name),
+            ),
+            None => ("ReplicaSet", name),
+        },
+        (Some(kind), Some(name)) => (kind, name),
+        _ => ("Pod", metadata["name"].as_str().unwrap_or_default()),
+    };
+    fields.insert(String::from("kind"), String::from(kind));
+    fields.insert(String::from("workload"), String::from(workload));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let root_dir = "testdata/support_bundle";
+        let manifests = Manifests::load(Path::new(root_dir));
+
+        let fields = manifests.fields_for(
+            "testdata/support_bundle/logs/harvester-system/cdi-deployment-ccd4878d6-477ck/cdi-deployment.log",
+            root_dir,
+        );
+        assert_eq!(fields["kind"], "Deployment");
+        assert_eq!(fields["workload"], "cdi-deployment");
+        assert_eq!(fields["app"], "containerized-data-importer");
+        assert_eq!(fields["component"], "storage");
+
+        let fields = manifests.fields_for(
+            "testdata/support_bundle/logs/harvester-system/virt-handler-wsl8k/virt-handler.log",
+            root_dir,
+        );
+        assert_eq!(fields["kind"], "DaemonSet");
+        assert_eq!(fields["workload"], "virt-handler");
+
+        let fields = manifests.fields_for(
+            "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+            root_dir,
+        );
+        assert!(fields.is_empty());
+    }
+
+    #[test]
+    fn test_load_missing_manifests() {
+        let manifests = Manifests::load(Path::new("testdata/noexist"));
+        assert!(manifests.pods.is_empty());
+        assert!(resources(Path::new("testdata/noexist")).is_empty());
+    }
+
+    #[test]
+    fn test_bundle_created_at() {
+        assert_eq!(
+            bundle_created_at(Path::new("testdata/support_bundle")),
+            "2025-12-30T22:00:32Z".parse::<DateTime<Utc>>().ok()
+        );
+        assert_eq!(bundle_created_at(Path::new("/noexist")), None);
+    }
+
+    #[test]
+    fn test_resources() {
+        let resources = resources(Path::new("testdata/support_bundle"));
+        let names: Vec<(&str, &str)> = resources
+            .iter()
+            .map(|r| (r.kind, r.name.as_str()))
+            .collect();
+        assert!(names.contains(&("vm", "vm-00")));
+        assert!(names.contains(&("pvc", "vm-00-disk-0-xx3er")));
+        assert!(names.contains(&("node", "isim-dev")));
+        assert!(
+            names
+                .iter()
+                .any(|(kind, name)| *kind == "volume" && name.starts_with("pvc-"))
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use super::{Entry, Level, SBSearch, SearchOptions, SearchStats};
+use chrono::{DateTime, SecondsFormat};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// The `sbsearch` key of the first line of saved results, telling them apart
+/// from JSON logs.
+const SAVED_RESULTS: &str = "results-v1";
+
+/// Search results saved to a file, to be browsed again without the support
+/// bundle.
+#[derive(Debug, Default)]
+pub struct SavedResults {
+    /// The root directory of the support bundle the entries were found in.
+    pub root_dir: String,
+    pub keyword: String,
+    pub entries: Vec<Entry>,
+}
+
+/// Writes the entries as JSON Lines: a header line with the support bundle, the
+/// keyword and the statistics of the entries, followed by one line per entry.
+pub fn write_saved<'a>(
+    writer: &mut impl Write,
+    root_dir: &str,
+    keyword: &str,
+    entries: impl IntoIterator<Item = &'a Entry>,
+) -> io::Result<()> {
+    let entries: Vec<&Entry> = entries.into_iter().collect();
+    writeln!(
+        writer,
+        r#"{{"sbsearch":{},"root_dir":{},"keyword":{},"stats":{}}}"#,
+        json_string(SAVED_RESULTS),
+        json_string(root_dir),
+        json_string(keyword),
+        json_stats(&SearchStats::of(entries.iter().copied()))
+    )?;
+    for entry in entries {
+        let timestamp = match entry.timestamp {
+            Some(t) => json_string(&t.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+            None => String::from("null"),
+        };
+        let fields: Vec<String> = entry
+            .fields
+            .iter()
+            .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+            .collect();
+        writeln!(
+            writer,
+            r#"{{"path":{},"line_number":{},"level":{},"timestamp":{},"fields":{{{}}},"duplicates":{},"content":{}}}"#,
+            json_string(&entry.path),
+            entry.line_number,
+            json_string(entry.level.as_str()),
+            timestamp,
+            fields.join(","),
+            entry.duplicates,
+            json_string(&entry.content)
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads the results saved to `path`, either as JSON Lines by `write_saved`,
+/// or as plain log lines. The entries of plain saves are parsed like the logs
+/// of a support bundle, and belong to the saved file.
+pub fn read_saved(path: &Path) -> Result<SavedResults, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| format!("failed to open saved results {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let Some(header) = lines.next().transpose()?.and_then(|line| parse(&line)) else {
+        return read_plain(path);
+    };
+    if header["sbsearch"].as_str() != Some(SAVED_RESULTS) {
+        return read_plain(path);
+    }
+
+    let mut saved = SavedResults {
+        root_dir: String::from(header["root_dir"].as_str().unwrap_or_default()),
+        keyword: String::from(header["keyword"].as_str().unwrap_or_default()),
+        entries: Vec::new(),
+    };
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = parse(&line)
+            .and_then(|doc| entry(&doc))
+            .ok_or_else(|| format!("invalid saved entry at {}:{}", path.display(), index + 2))?;
+        saved.entries.push(entry);
+    }
+    Ok(saved)
+}
+
+/// Reads plain log lines, skipping the `#` lines of the statistics at the top.
+fn read_plain(path: &Path) -> Result<SavedResults, Box<dyn Error>> {
+    let root_dir = path.parent().and_then(Path::to_str).unwrap_or_default();
+    let sbsearch = SBSearch::new(root_dir, "", &SearchOptions::default())?;
+    let mut header_lines = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        if !line?.starts_with('#') {
+            break;
+        }
+        header_lines += 1;
+    }
+    let mut entries = sbsearch.search_path(path)?;
+    entries.retain(|entry| entry.line_number > header_lines);
+    Ok(SavedResults {
+        root_dir: String::from(root_dir),
+        keyword: String::new(),
+        entries,
+    })
+}
+
+fn parse(line: &str) -> Option<Yaml> {
+    let doc = YamlLoader::load_from_str(line).ok()?.into_iter().next()?;
+    matches!(doc, Yaml::Hash(_)).then_some(doc)
+}
+
+fn entry(doc: &Yaml) -> Option<Entry> {
+    let timestamp = match &doc["timestamp"] {
+        Yaml::String(t) => Some(DateTime::parse_from_rfc3339(t).ok()?.to_utc()),
+        _ => None,
+    };
+    let mut fields = BTreeMap::new();
+    if let Yaml::Hash(hash) = &doc["fields"] {
+        for (k, v) in hash {
+            fields.insert(String::from(k.as_str()?), String::from(v.as_str()?));
+        }
+    }
+    Some(Entry {
+        level: Level::normalize(doc["level"].as_str().unwrap_or_default()),
+        path: String::from(doc["path"].as_str()?),
+        content: String::from(doc["content"].as_str()?),
+        timestamp,
+        line_number: doc["line_number"].as_i64()? as u64,
+        fields,
+        duplicates: doc["duplicates"].as_i64().unwrap_or_default() as usize,
+    })
+}
+
+fn json_stats(stats: &SearchStats) -> String {
+    let object = |counts: Vec<(String, usize)>| {
+        let members: Vec<String> = counts
+            .iter()
+            .map(|(k, v)| format!("{}:{}", json_string(k), v))
+            .collect();
+        format!("{{{}}}", members.join(","))
+    };
+    let time = |t: Option<DateTime<chrono::Utc>>| match t {
+        Some(t) => json_string(&t.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+        None => String::from("null"),
+    };
+    format!(
+        r#"{{"entries":{},"levels":{},"namespaces":{},"files":{},"first":{},"last":{}}}"#,
+        stats.entries,
+        object(
+            Level::ALL
+                .iter()
+                .filter_map(|l| Some((l.to_string(), *stats.by_level.get(l)?)))
+                .collect()
+        ),
+        object(stats.by_namespace.clone().into_iter().collect()),
+        object(stats.by_file.clone().into_iter().collect()),
+        time(stats.first),
+        time(stats.last)
+    )
+}
+
+/// Quotes a string for JSON. Control characters are escaped, so that every
+/// entry takes a single line.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_saved() {
+        let entries = vec![
+            Entry {
+                level: Level::Error,
+                path: String::from("sb/logs/default/vm-00/compute.log"),
+                content: String::from(
+                    "2025-12-30T21:00:00Z level=error msg=\"failed: \\\"vm-00\\\"\"\n\tat main.go:42\x1b\n",
+                ),
+                timestamp: Some("2025-12-30T21:00:00Z".parse().unwrap()),
+                line_number: 7,
+                fields: BTreeMap::from([(String::from("app"), String::from("virt-launcher"))]),
+                duplicates: 2,
+            },
+            Entry {
+                level: Level::Unknown,
+                path: String::from("sb/nodes/n1.zip/n1/logs/kubelet.log"),
+                content: String::from("café vm-00 # not: a comment\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: BTreeMap::new(),
+                duplicates: 0,
+            },
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sbsearch_saved.jsonl");
+        let mut file = File::create(&path).unwrap();
+        write_saved(&mut file, "sb", "vm-00", &entries).unwrap();
+        let header = std::fs::read_to_string(&path).unwrap();
+        let header = header.lines().next().unwrap();
+        assert!(header.contains(r#""stats":{"entries":2,"levels":{"error":1,"UNKNOWN":1}"#));
+
+        let saved = read_saved(&path).unwrap();
+        assert_eq!(saved.root_dir, "sb");
+        assert_eq!(saved.keyword, "vm-00");
+        assert_eq!(saved.entries.len(), entries.len());
+        for (a, b) in saved.entries.iter().zip(&entries) {
+            assert_eq!(a.level, b.level);
+            assert_eq!(a.path, b.path);
+            assert_eq!(a.content, b.content);
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.line_number, b.line_number);
+            assert_eq!(a.fields, b.fields);
+            assert_eq!(a.duplicates, b.duplicates);
+        }
+    }
+
+    #[test]
+    fn test_read_plain_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sbsearch_20251230210000.log");
+        std::fs::write(
+            &path,
+            "# entries: 2\n\
+             # time range: 2025-12-30T21:00:00.000Z .. 2025-12-30T21:00:01.000Z\n\
+             2025-12-30T21:00:00Z level=error msg=\"attach vm-00 failed\"\n\
+             \tat main.go:42\n\
+             2025-12-30T21:00:01Z level=info msg=\"attached vm-00\"\n",
+        )
+        .unwrap();
+
+        let saved = read_saved(&path).unwrap();
+        assert_eq!(saved.root_dir, dir.path().to_str().unwrap());
+        assert_eq!(saved.entries.len(), 2);
+        assert_eq!(saved.entries[0].level, Level::Error);
+        assert!(saved.entries[0].content.contains("main.go:42"));
+        assert_eq!(saved.entries[1].line_number, 5);
+
+        std::fs::write(&path, "{\"sbsearch\":\"results-v1\"}\nnot json\n").unwrap();
+        assert!(read_saved(&path).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use super::{Entry, rotated_log};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Timestamp,
+    Level,
+    Path,
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// An ordered list of sort keys. Entries are compared by the first key, with
+/// ties broken by the following keys, e.g. `ts,level:desc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortSpec {
+    keys: Vec<(SortKey, Direction)>,
+}
+
+impl Default for SortSpec {
+    fn default() -> Self {
+        SortSpec {
+            keys: vec![(SortKey::Timestamp, Direction::Asc)],
+        }
+    }
+}
+
+impl SortSpec {
+    pub fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        for (key, direction) in &self.keys {
+            let ordering = key.compare(a, b, *direction);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    pub fn sort(&self, entries: &mut [Entry]) {
+        entries.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// Compares the entries, breaking ties by path and line number, so entries
+    /// collected in any order end up in the same order.
+    pub fn compare_by_position(&self, a: &Entry, b: &Entry) -> Ordering {
+        let (a_path, a_rotation) = rotated_log(&a.path);
+        let (b_path, b_rotation) = rotated_log(&b.path);
+        self.compare(a, b)
+            .then_with(|| a_path.cmp(b_path))
+            .then(b_rotation.cmp(&a_rotation))
+            .then(a.line_number.cmp(&b.line_number))
+    }
+
+    /// Sorts the entries by `compare_by_position`, then orders the rotated
+    /// logs with `order_rotations`.
+    pub fn sort_by_position(&self, entries: &mut [Entry]) {
+        entries.sort_by(|a, b| self.compare_by_position(a, b));
+        self.order_rotations(entries);
+    }
+
+    /// Orders the entries of a log and of its rotated logs by their position,
+    /// the oldest log first, within the places they take in the sorted
+    /// entries. Otherwise the entries of a restarted container, e.g. without
+    /// timestamps, interleave with the ones of the container before the
+    /// restart. Only applies to entries sorted by ascending time, which is the
+    /// order of the logs.
+    pub fn order_rotations(&self, entries: &mut [Entry]) {
+        if self.keys.first() != Some(&(SortKey::Timestamp, Direction::Asc)) {
+            return;
+        }
+
+        let rotated: HashSet<&str> = entries
+            .iter()
+            .map(|entry| rotated_log(&entry.path))
+            .filter(|(_, rotation)| *rotation > 0)
+            .map(|(path, _)| path)
+            .collect();
+        if rotated.is_empty() {
+            return;
+        }
+        let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let (path, _) = rotated_log(&entry.path);
+            if rotated.contains(path) {
+                groups.entry(path).or_default().push(index);
+            }
+        }
+
+        let mut moves = Vec::new();
+        for slots in groups.into_values() {
+            let mut order = slots.clone();
+            order.sort_by(|&a, &b| {
+                let (a, b) = (&entries[a], &entries[b]);
+                b.rotation()
+                    .cmp(&a.rotation())
+                    .then_with(|| a.path.cmp(&b.path))
+                    .then(a.line_number.cmp(&b.line_number))
+            });
+            moves.extend(slots.into_iter().zip(order));
+        }
+        let moved: Vec<(usize, Entry)> = moves
+            .into_iter()
+            .filter(|(slot, from)| slot != from)
+            .map(|(slot, from)| (slot, entries[from].clone()))
+            .collect();
+        for (slot, entry) in moved {
+            entries[slot] = entry;
+        }
+    }
+
+    /// Merges the new entries into the entries already sorted by position,
+    /// without sorting those again. The rotated logs are left to
+    /// `order_rotations`.
+    pub fn merge_by_position(&self, sorted: &mut Vec<Entry>, mut entries: Vec<Entry>) {
+        if entries.is_empty() {
+            return;
+        }
+        entries.sort_by(|a, b| self.compare_by_position(a, b));
+        let mut merged = Vec::with_capacity(sorted.len() + entries.len());
+        let mut old = std::mem::take(sorted).into_iter().peekable();
+        let mut new = entries.into_iter().peekable();
+        loop {
+            let next = match (old.peek(), new.peek()) {
+                (Some(a), Some(b)) => match self.compare_by_position(a, b) {
+                    Ordering::Greater => new.next(),
+                    _ => old.next(),
+                },
+                (Some(_), None) => old.next(),
+                (None, _) => new.next(),
+            };
+            match next {
+                Some(entry) => merged.push(entry),
+                None => break,
+            }
+        }
+        *sorted = merged;
+    }
+}
+
+impl SortKey {
+    fn compare(&self, a: &Entry, b: &Entry, direction: Direction) -> Ordering {
+        let apply = |ordering: Ordering| match direction {
+            Direction::Asc => ordering,
+            Direction::Desc => ordering.reverse(),
+        };
+
+        match self {
+            // entries with incomplete timestamp are placed at the end, regardless
+            // of the sort direction
+            SortKey::Timestamp => match (a.timestamp, b.timestamp) {
+                (Some(a), Some(b)) => apply(a.cmp(&b)),
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (None, None) => Ordering::Equal,
+            },
+            SortKey::Level => apply(a.level.cmp(&b.level)),
+            SortKey::Path => apply(a.path.cmp(&b.path)),
+            SortKey::Line => apply(a.line_number.cmp(&b.line_number)),
+        }
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, direction) = match part.split_once(':') {
+                Some((name, "asc")) => (name, Direction::Asc),
+                Some((name, "desc")) => (name, Direction::Desc),
+                Some((_, other)) => return Err(format!("unknown sort direction '{}'", other)),
+                None => (part, Direction::Asc),
+            };
+            let key = match name {
+                "ts" | "time" | "timestamp" => SortKey::Timestamp,
+                "level" => SortKey::Level,
+                "path" => SortKey::Path,
+                "line" => SortKey::Line,
+                other => return Err(format!("unknown sort key '{}'", other)),
+            };
+            keys.push((key, direction));
+        }
+
+        if keys.is_empty() {
+            return Err(String::from("sort spec must contain at least one key"));
+        }
+        Ok(SortSpec { keys })
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .map(|(key, direction)| {
+                let name = match key {
+                    SortKey::Timestamp => "ts",
+                    SortKey::Level => "level",
+                    SortKey::Path => "path",
+                    SortKey::Line => "line",
+                };
+                match direction {
+                    Direction::Asc => String::from(name),
+                    Direction::Desc => format!("{}:desc", name),
+                }
+            })
+            .collect();
+        write!(f, "{}", keys.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sbsearch::Level;
+    use chrono::{DateTime, Utc};
+
+    fn entry(ts: Option<&str>, level: &str, path: &str, line_number: u64) -> Entry {
+        Entry {
+            level: Level::normalize(level),
+            path: String::from(path),
+            content: String::new(),
+            timestamp: ts.map(|t| t.parse::<DateTime<Utc>>().unwrap()),
+            line_number,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_sort_spec() {
+        let spec = "ts,level:desc".parse::<SortSpec>().unwrap();
+        assert_eq!(
+            spec,
+            SortSpec {
+                keys: vec![
+                    (SortKey::Timestamp, Direction::Asc),
+                    (SortKey::Level, Direction::Desc),
+                ]
+            }
+        );
+        assert_eq!(spec.to_string(), "ts,level:desc");
+
+        let spec = "path:asc, line".parse::<SortSpec>().unwrap();
+        assert_eq!(
+            spec,
+            SortSpec {
+                keys: vec![
+                    (SortKey::Path, Direction::Asc),
+                    (SortKey::Line, Direction::Asc)
+                ]
+            }
+        );
+
+        assert!("".parse::<SortSpec>().is_err());
+        assert!("size".parse::<SortSpec>().is_err());
+        assert!("ts:up".parse::<SortSpec>().is_err());
+    }
+
+    #[test]
+    fn test_sort_by_timestamp() {
+        let mut entries = vec![
+            entry(None, "info", "a", 1),
+            entry(Some("2025-12-30T21:58:14Z"), "info", "b", 1),
+            entry(Some("2025-12-30T21:57:14Z"), "info", "c", 1),
+        ];
+
+        SortSpec::default().sort(&mut entries);
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["c", "b", "a"]);
+
+        // entries without timestamp stay at the end in reverse order too
+        "ts:desc".parse::<SortSpec>().unwrap().sort(&mut entries);
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_sort_by_multiple_keys() {
+        let ts = Some("2025-12-30T21:58:14Z");
+        let mut entries = vec![
+            entry(ts, "info", "a", 2),
+            entry(ts, "error", "b", 1),
+            entry(ts, "warning", "c", 1),
+            entry(ts, "info", "a", 1),
+        ];
+
+        "ts,level:desc,path,line"
+            .parse::<SortSpec>()
+            .unwrap()
+            .sort(&mut entries);
+        let actual: Vec<(&str, u64)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.line_number))
+            .collect();
+        assert_eq!(actual, vec![("b", 1), ("c", 1), ("a", 1), ("a", 2)]);
+    }
+
+    #[test]
+    fn test_sort_rotated_logs() {
+        let ts = Some("2025-12-30T21:58:14Z");
+        let mut entries = vec![
+            entry(ts, "info", "pod/c.log", 1),
+            entry(ts, "info", "pod/c.log.1", 2),
+            entry(None, "info", "pod/c.log", 1),
+            entry(ts, "info", "pod/c.log.2", 1),
+            entry(None, "info", "pod/c.log.previous", 1),
+            entry(ts, "info", "pod/b.log", 1),
+            entry(ts, "info", "pod/c.log.1", 1),
+        ];
+
+        SortSpec::default().sort_by_position(&mut entries);
+        let actual: Vec<(&str, u64)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.line_number))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("pod/b.log", 1),
+                ("pod/c.log.2", 1),
+                ("pod/c.log.1", 1),
+                ("pod/c.log.1", 2),
+                ("pod/c.log.previous", 1),
+                ("pod/c.log", 1),
+                ("pod/c.log", 1),
+            ]
+        );
+        assert_eq!(entries[1].rotation(), 2);
+        assert_eq!(entries[4].rotation(), 1);
+        assert_eq!(entries[5].rotation(), 0);
+
+        // the rotated logs come first even when their entries are newer,
+        // e.g. with the timestamps of another timezone, while the entries of
+        // other logs keep their place
+        let mut entries = vec![
+            entry(Some("2025-12-30T21:00:00Z"), "info", "pod/c.log", 1),
+            entry(Some("2025-12-30T21:30:00Z"), "info", "pod/b.log", 1),
+            entry(Some("2025-12-30T22:00:00Z"), "info", "pod/c.log.1", 1),
+            entry(
+                Some("2025-12-30T22:00:00Z"),
+                "info",
+                "pod/c.log.20251229",
+                1,
+            ),
+        ];
+        SortSpec::default().sort_by_position(&mut entries);
+        let actual: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            actual,
+            vec![
+                "pod/c.log.1",
+                "pod/b.log",
+                "pod/c.log",
+                "pod/c.log.20251229"
+            ]
+        );
+        assert_eq!(entries[3].rotation(), 0);
+
+        // other orders are left alone
+        "ts:desc"
+            .parse::<SortSpec>()
+            .unwrap()
+            .sort_by_position(&mut entries);
+        let actual: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            actual,
+            vec![
+                "pod/c.log.1",
+                "pod/c.log.20251229",
+                "pod/b.log",
+                "pod/c.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_by_position() {
+        let spec = SortSpec::default();
+        let mut sorted = vec![
+            entry(Some("2025-12-30T21:57:14Z"), "info", "a", 1),
+            entry(Some("2025-12-30T21:59:14Z"), "info", "a", 2),
+            entry(None, "info", "a", 3),
+        ];
+        spec.merge_by_position(
+            &mut sorted,
+            vec![
+                entry(None, "info", "b", 1),
+                entry(Some("2025-12-30T21:58:14Z"), "info", "b", 2),
+                entry(Some("2025-12-30T21:57:14Z"), "info", "b", 3),
+            ],
+        );
+
+        let actual: Vec<(&str, u64)> = sorted
+            .iter()
+            .map(|e| (e.path.as_str(), e.line_number))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![("a", 1), ("b", 3), ("b", 2), ("a", 2), ("a", 3), ("b", 1)]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use tar::Archive;
+use zip::ZipArchive;
+
+/// Reads the file at `path`, which, like the paths of the entries, may point
+/// into a zip or tar.gz archive, e.g. `nodes/node1.zip/node1/logs/kubelet.log`.
+/// Archives nested in archives are unpacked as well.
+pub fn read_source(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    // the file on disk is the path itself, or the archive holding it
+    let mut file_path = path;
+    while !file_path.is_file() {
+        file_path = file_path
+            .parent()
+            .ok_or_else(|| format!("{} not found", path.display()))?;
+    }
+
+    let mut file = File::open(file_path)?;
+    if file_path == path {
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        return Ok(content);
+    }
+    read_archived(file, path.strip_prefix(file_path)?)
+}
+
+/// Reads the file at `inner` in the archive, recursing into nested archives.
+fn read_archived<R: Read + Seek>(mut archive: R, inner: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut signature = [0u8; 4];
+    archive.read_exact(&mut signature)?;
+    archive.seek(SeekFrom::Start(0))?;
+
+    if signature == [0x50, 0x4B, 0x03, 0x04] {
+        let mut zip = ZipArchive::new(archive)?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            if let Ok(rest) = inner.strip_prefix(file.name()) {
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)?;
+                return unpack(content, rest);
+            }
+        }
+    } else if signature[..2] == [0x1F, 0x8B] {
+        let mut tar = Archive::new(GzDecoder::new(archive));
+        for file in tar.entries()? {
+            let mut file = file?;
+            if !file.header().entry_type().is_file() {
+                continue;
+            }
+            if let Ok(rest) = inner.strip_prefix(file.path()?) {
+                let rest = rest.to_path_buf();
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)?;
+                return unpack(content, &rest);
+            }
+        }
+    }
+    Err(format!("{} not found in archive", inner.display()).into())
+}
+
+fn unpack(content: Vec<u8>, rest: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    match rest.as_os_str().is_empty() {
+        true => Ok(content),
+        false => read_archived(Cursor::new(content), rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_source() {
+        let path = Path::new("testdata/support_bundle/metadata.yaml");
+        assert_eq!(read_source(path).unwrap(), std::fs::read(path).unwrap());
+
+        let content = read_source(Path::new(
+            "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+        ))
+        .unwrap();
+        assert!(!content.is_empty());
+
+        assert!(
+            read_source(Path::new(
+                "testdata/support_bundle/nodes/isim-dev.zip/noexist"
+            ))
+            .is_err()
+        );
+        assert!(read_source(Path::new("/noexist/file.log")).is_err());
+    }
+}
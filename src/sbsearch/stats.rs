@@ -0,0 +1,146 @@
+use super::{Entry, Level};
+use crate::format;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The number of files listed by the summary, most entries first.
+const MAX_SUMMARY_FILES: usize = 10;
+
+/// Aggregate statistics of the matching entries, to show the error and warning
+/// breakdown at a glance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchStats {
+    pub entries: usize,
+    pub by_level: BTreeMap<Level, usize>,
+    pub by_file: BTreeMap<String, usize>,
+    /// The entries of pod logs per namespace.
+    pub by_namespace: BTreeMap<String, usize>,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl SearchStats {
+    pub fn of<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Self {
+        let mut stats = SearchStats::default();
+        for entry in entries {
+            stats.add(entry);
+        }
+        stats
+    }
+
+    pub fn add(&mut self, entry: &Entry) {
+        self.entries += 1;
+        *self.by_level.entry(entry.level).or_default() += 1;
+        *self.by_file.entry(entry.path.clone()).or_default() += 1;
+        if let Some(namespace) = entry.namespace() {
+            *self
+                .by_namespace
+                .entry(String::from(namespace))
+                .or_default() += 1;
+        }
+        if let Some(timestamp) = entry.timestamp {
+            self.first = self.first.min(Some(timestamp)).or(Some(timestamp));
+            self.last = self.last.max(Some(timestamp));
+        }
+    }
+
+    /// Counts the entries of each level, most severe first, e.g.
+    /// `3 error, 12 warn, 200 info`.
+    pub fn levels_summary(&self) -> String {
+        let levels: Vec<String> = Level::ALL
+            .iter()
+            .filter_map(|level| {
+                let count = self.by_level.get(level)?;
+                Some(format!("{} {}", format::count(*count), level))
+            })
+            .collect();
+        levels.join(", ")
+    }
+
+    /// The files with the most entries first.
+    pub fn busiest_files(&self) -> Vec<(&str, usize)> {
+        let mut files: Vec<(&str, usize)> = self
+            .by_file
+            .iter()
+            .map(|(path, count)| (path.as_str(), *count))
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        files
+    }
+}
+
+/// Summarizes the statistics over a few lines, for the top of saved results.
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |t: Option<DateTime<Utc>>| match t {
+            Some(t) => t.to_rfc3339_opts(SecondsFormat::Millis, true),
+            None => String::from("-"),
+        };
+        writeln!(f, "entries: {}", format::count(self.entries))?;
+        writeln!(f, "levels: {}", self.levels_summary())?;
+        writeln!(f, "time range: {} .. {}", time(self.first), time(self.last))?;
+        let namespaces: Vec<String> = self
+            .by_namespace
+            .iter()
+            .map(|(namespace, count)| format!("{} {}", format::count(*count), namespace))
+            .collect();
+        writeln!(f, "namespaces: {}", namespaces.join(", "))?;
+        writeln!(f, "files: {}", format::count(self.by_file.len()))?;
+        let files = self.busiest_files();
+        for (path, count) in files.iter().take(MAX_SUMMARY_FILES) {
+            writeln!(f, "  {:>8}  {}", format::count(*count), path)?;
+        }
+        if files.len() > MAX_SUMMARY_FILES {
+            writeln!(f, "  ... and {} more", files.len() - MAX_SUMMARY_FILES)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, level: Level, timestamp: Option<&str>) -> Entry {
+        Entry {
+            level,
+            path: String::from(path),
+            content: String::new(),
+            timestamp: timestamp.map(|t| t.parse().unwrap()),
+            line_number: 1,
+            fields: BTreeMap::from([(String::from("namespace"), String::from("default"))]),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_search_stats() {
+        let entries = [
+            entry("a.log", Level::Error, Some("2025-12-30T21:58:14Z")),
+            entry("a.log", Level::Info, None),
+            entry("b.log", Level::Error, Some("2025-12-30T21:00:00Z")),
+            entry("b.log", Level::Warn, Some("2025-12-30T22:00:00Z")),
+            entry("b.log", Level::Unknown, None),
+        ];
+        let stats = SearchStats::of(&entries);
+        assert_eq!(stats.entries, 5);
+        assert_eq!(stats.by_level[&Level::Error], 2);
+        assert_eq!(stats.by_namespace["default"], 5);
+        assert_eq!(stats.busiest_files(), vec![("b.log", 3), ("a.log", 2)]);
+        assert_eq!(stats.first, Some("2025-12-30T21:00:00Z".parse().unwrap()));
+        assert_eq!(stats.last, Some("2025-12-30T22:00:00Z".parse().unwrap()));
+        assert_eq!(stats.levels_summary(), "2 error, 1 warn, 1 info, 1 UNKNOWN");
+        assert_eq!(
+            stats.to_string(),
+            "entries: 5\n\
+             levels: 2 error, 1 warn, 1 info, 1 UNKNOWN\n\
+             time range: 2025-12-30T21:00:00.000Z .. 2025-12-30T22:00:00.000Z\n\
+             namespaces: 5 default\n\
+             files: 2\n\
+             \x20        3  b.log\n\
+             \x20        2  a.log\n"
+        );
+        assert_eq!(SearchStats::of([]).levels_summary(), "");
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of the timestamp layouts that carry no timezone information.
+pub const NAIVE_LAYOUTS: &[&str] = &["naive", "syslog"];
+
+/// The timezone assumed for timestamps that don't specify one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AssumeTz {
+    #[default]
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl AssumeTz {
+    /// Converts a naive timestamp to UTC. Timestamps that are ambiguous because
+    /// of a DST transition resolve to the earliest instant, and timestamps that
+    /// fall into a DST gap are shifted forward past the gap.
+    pub fn to_utc(self, naive: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            AssumeTz::Utc => naive.and_utc(),
+            AssumeTz::Fixed(offset) => resolve(&offset, naive),
+            AssumeTz::Local => resolve(&Local, naive),
+        }
+    }
+}
+
+fn resolve<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&naive).earliest() {
+        Some(t) => t.to_utc(),
+        None => match tz
+            .from_local_datetime(&(naive + TimeDelta::hours(1)))
+            .earliest()
+        {
+            Some(t) => t.to_utc(),
+            None => naive.and_utc(),
+        },
+    }
+}
+
+impl AssumeTz {
+    /// Formats a UTC timestamp in this timezone.
+    pub fn format(self, timestamp: DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            AssumeTz::Utc => timestamp.format(fmt).to_string(),
+            AssumeTz::Fixed(offset) => timestamp.with_timezone(&offset).format(fmt).to_string(),
+            AssumeTz::Local => timestamp.with_timezone(&Local).format(fmt).to_string(),
+        }
+    }
+}
+
+impl FromStr for AssumeTz {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "utc" | "UTC" | "Z" => Ok(AssumeTz::Utc),
+            "local" | "LOCAL" => Ok(AssumeTz::Local),
+            offset => offset
+                .parse::<FixedOffset>()
+                .map(AssumeTz::Fixed)
+                .map_err(|_| format!("invalid timezone '{}', expected UTC, local or ±HH:MM", s)),
+        }
+    }
+}
+
+impl fmt::Display for AssumeTz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssumeTz::Utc => write!(f, "UTC"),
+            AssumeTz::Local => write!(f, "local"),
+            AssumeTz::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+/// The timezone assumed for each naive timestamp layout, e.g. `+08:00` or
+/// `UTC,naive=local`. Layouts without a hint use the default timezone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimezoneHints {
+    default: AssumeTz,
+    layouts: Vec<(String, AssumeTz)>,
+}
+
+impl TimezoneHints {
+    pub fn for_layout(&self, layout: &str) -> AssumeTz {
+        self.layouts
+            .iter()
+            .find(|(name, _)| name == layout)
+            .map(|(_, tz)| *tz)
+            .unwrap_or(self.default)
+    }
+}
+
+impl FromStr for TimezoneHints {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hints = TimezoneHints::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((layout, tz)) => {
+                    if !NAIVE_LAYOUTS.contains(&layout) {
+                        return Err(format!(
+                            "unknown timestamp layout '{}', expected one of: {}",
+                            layout,
+                            NAIVE_LAYOUTS.join(", ")
+                        ));
+                    }
+                    hints.layouts.push((String::from(layout), tz.parse()?));
+                }
+                None => hints.default = part.parse()?,
+            }
+        }
+        Ok(hints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").unwrap()
+    }
+
+    #[test]
+    fn test_to_utc() {
+        let t = naive("2025-12-30 21:58:14.266");
+        assert_eq!(
+            AssumeTz::Utc.to_utc(t),
+            "2025-12-30T21:58:14.266Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let tz = "+08:00".parse::<AssumeTz>().unwrap();
+        assert_eq!(
+            tz.to_utc(t),
+            "2025-12-30T13:58:14.266Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let tz = "-05:30".parse::<AssumeTz>().unwrap();
+        assert_eq!(
+            tz.to_utc(t),
+            "2025-12-31T03:28:14.266Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        assert!("Mars/Olympus".parse::<AssumeTz>().is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let t = "2025-12-30T21:58:14.266Z".parse::<DateTime<Utc>>().unwrap();
+        let fmt = "%Y-%m-%d %H:%M:%S%.3f %:z";
+        assert_eq!(
+            AssumeTz::Utc.format(t, fmt),
+            "2025-12-30 21:58:14.266 +00:00"
+        );
+        let tz = "+08:00".parse::<AssumeTz>().unwrap();
+        assert_eq!(tz.format(t, fmt), "2025-12-31 05:58:14.266 +08:00");
+    }
+
+    #[test]
+    fn test_parse_timezone_hints() {
+        let hints = "+08:00".parse::<TimezoneHints>().unwrap();
+        assert_eq!(hints.for_layout("naive"), "+08:00".parse().unwrap());
+
+        let hints = "UTC,naive=local".parse::<TimezoneHints>().unwrap();
+        assert_eq!(hints.for_layout("naive"), AssumeTz::Local);
+        assert_eq!(hints.for_layout("other"), AssumeTz::Utc);
+
+        assert!("syslog2=UTC".parse::<TimezoneHints>().is_err());
+        assert!("naive=+25:00".parse::<TimezoneHints>().is_err());
+    }
+}
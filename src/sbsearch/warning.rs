@@ -0,0 +1,196 @@
+use crate::format;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// A file that was skipped or only partly searched. The search goes on with
+/// the other files, and the entries found before the problem are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchWarning {
+    /// The file or directory couldn't be read, e.g. for lack of permissions.
+    Unreadable { path: String, error: String },
+    /// The archive is corrupt or truncated, so only its files before the
+    /// damage were searched.
+    BadArchive { path: String, error: String },
+    /// A line of the file outgrew the memory the searcher holds a line in,
+    /// e.g. a binary file without newlines.
+    RegexOverflow { path: String, error: String },
+    /// The search of the file took longer than the scan timeout.
+    TimedOut { path: String, error: String },
+    /// The cached entries reached the memory budget before the file at
+    /// `path`, or the rest of it, was searched, and so did the `files` files
+    /// in all, including it.
+    MemoryBudget {
+        path: String,
+        budget: usize,
+        files: usize,
+    },
+    /// Only the first `limit` bytes of the file were searched.
+    Truncated { path: String, limit: u64 },
+    /// The archive nested in a node archive is larger than `limit` bytes, so
+    /// it wasn't read into memory to be searched.
+    TooLarge { path: String, limit: u64 },
+    /// The whole search took longer than its `timeout`, so the rest of the
+    /// support bundle at `path` wasn't searched.
+    SearchTimedOut { path: String, timeout: Duration },
+}
+
+impl SearchWarning {
+    /// Tells apart the errors of a file that leave it unsearched, or partly
+    /// searched, from the others.
+    pub(super) fn from_error(path: &Path, error: &(dyn Error + 'static)) -> Self {
+        let path = path.display().to_string();
+        let message = error.to_string();
+        if error.is::<zip::result::ZipError>() {
+            return SearchWarning::BadArchive {
+                path,
+                error: message,
+            };
+        }
+        match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+            // the searcher fails this way when a line is over its heap limit
+            Some(io::ErrorKind::Other) if message.contains("allocation limit") => {
+                SearchWarning::RegexOverflow {
+                    path,
+                    error: message,
+                }
+            }
+            Some(io::ErrorKind::TimedOut) => SearchWarning::TimedOut {
+                path,
+                error: message,
+            },
+            Some(io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof)
+                if is_archive(&path) =>
+            {
+                SearchWarning::BadArchive {
+                    path,
+                    error: message,
+                }
+            }
+            _ => SearchWarning::Unreadable {
+                path,
+                error: message,
+            },
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            SearchWarning::Unreadable { path, .. }
+            | SearchWarning::BadArchive { path, .. }
+            | SearchWarning::RegexOverflow { path, .. }
+            | SearchWarning::TimedOut { path, .. }
+            | SearchWarning::MemoryBudget { path, .. }
+            | SearchWarning::Truncated { path, .. }
+            | SearchWarning::TooLarge { path, .. }
+            | SearchWarning::SearchTimedOut { path, .. } => path,
+        }
+    }
+
+    /// Returns true if some of the file was searched.
+    pub fn is_partial(&self) -> bool {
+        matches!(
+            self,
+            SearchWarning::BadArchive { .. }
+                | SearchWarning::TimedOut { .. }
+                | SearchWarning::Truncated { .. }
+                | SearchWarning::SearchTimedOut { .. }
+        )
+    }
+}
+
+/// Returns true if the path is of a node archive, or of a file within one.
+fn is_archive(path: &str) -> bool {
+    [".zip", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| path.ends_with(ext) || path.contains(&format!("{}/", ext)))
+}
+
+impl fmt::Display for SearchWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchWarning::Unreadable { path, error } => {
+                write!(f, "can't read {}: {}", path, error)
+            }
+            SearchWarning::BadArchive { path, error } => {
+                write!(f, "bad archive {}: {}", path, error)
+            }
+            SearchWarning::RegexOverflow { path, error } => {
+                write!(f, "line too long to match in {}: {}", path, error)
+            }
+            // the timeout already names the file and the pattern to blame
+            SearchWarning::TimedOut { error, .. } => write!(f, "{}", error),
+            SearchWarning::MemoryBudget {
+                path,
+                budget,
+                files,
+            } => match files {
+                1 => write!(
+                    f,
+                    "memory budget of {} reached, skipping file: {}",
+                    format::bytes(*budget),
+                    path
+                ),
+                _ => write!(
+                    f,
+                    "memory budget of {} reached, skipping {} files, starting with: {}",
+                    format::bytes(*budget),
+                    format::count(*files),
+                    path
+                ),
+            },
+            SearchWarning::Truncated { path, limit } => write!(
+                f,
+                "only searched the first {} of file: {}",
+                format::bytes(*limit as usize),
+                path
+            ),
+            SearchWarning::TooLarge { path, limit } => write!(
+                f,
+                "nested archive larger than {}, skipping it: {}",
+                format::bytes(*limit as usize),
+                path
+            ),
+            SearchWarning::SearchTimedOut { path, timeout } => write!(
+                f,
+                "search timed out after {:?}, the rest of {} wasn't searched",
+                timeout, path
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let path = Path::new("sb/nodes/node-0.zip");
+        let error = zip::result::ZipError::InvalidArchive("bad central directory".into());
+        let warning = SearchWarning::from_error(path, &error);
+        assert!(matches!(warning, SearchWarning::BadArchive { .. }));
+        assert!(warning.is_partial());
+        assert_eq!(warning.path(), "sb/nodes/node-0.zip");
+
+        let error = io::Error::new(io::ErrorKind::UnexpectedEof, "corrupt deflate stream");
+        let warning =
+            SearchWarning::from_error(Path::new("sb/nodes/node-0.tgz/kubelet.log"), &error);
+        assert!(matches!(warning, SearchWarning::BadArchive { .. }));
+
+        let error = io::Error::other("configured allocation limit (268435456) exceeded");
+        let warning = SearchWarning::from_error(Path::new("sb/logs/a.log"), &error);
+        assert!(matches!(warning, SearchWarning::RegexOverflow { .. }));
+
+        let error = io::Error::from(io::ErrorKind::PermissionDenied);
+        let warning = SearchWarning::from_error(Path::new("sb/logs/a.log"), &error);
+        assert!(matches!(warning, SearchWarning::Unreadable { .. }));
+        assert!(!warning.is_partial());
+        assert_eq!(
+            warning.to_string(),
+            "can't read sb/logs/a.log: permission denied"
+        );
+    }
+}
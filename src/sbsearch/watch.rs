@@ -0,0 +1,404 @@
+use super::{
+    CancelToken, Entry, Progress, ProgressCounter, SBSearch, SearchBatch, SearchEvent,
+    SearchOptions, SearchWarning, is_tar_gz, is_zip, manifest, root_dir,
+};
+use log::*;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// What changed in the watched files since the last check.
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// The entries of the lines appended to the files, and of the files that
+    /// were rewritten or created.
+    pub entries: Vec<Entry>,
+    /// The files that were rewritten, e.g. truncated or rotated, whose
+    /// earlier entries are replaced by the ones in `entries`.
+    pub rescanned: Vec<String>,
+    /// The files that were skipped or only partly searched.
+    pub warnings: Vec<SearchWarning>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.rescanned.is_empty() && self.warnings.is_empty()
+    }
+}
+
+/// How much of a file was searched.
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// The number of lines in the first `len` bytes, counted once the file
+    /// grows.
+    lines: Option<u64>,
+}
+
+impl Mark {
+    fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Mark {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            lines: None,
+        })
+    }
+}
+
+/// Watches the files of a live log directory, searching the lines appended
+/// to them since the last check. Files that shrink or are rewritten are
+/// searched again in full, as are the node archives that change.
+pub struct Watcher {
+    sbsearch: SBSearch,
+    root: PathBuf,
+    marks: HashMap<PathBuf, Mark>,
+}
+
+/// The changes found by a watcher in the background, see `watch_streaming`.
+/// The watcher stops once this is dropped.
+#[derive(Debug)]
+pub struct WatchStream {
+    changes: Receiver<Result<Changes, String>>,
+    _stop: Sender<()>,
+}
+
+impl WatchStream {
+    /// Returns the changes found since the last call, without waiting for
+    /// more.
+    pub fn changes(&self) -> impl Iterator<Item = Result<Changes, String>> + '_ {
+        self.changes.try_iter()
+    }
+}
+
+/// Searches the support bundle in the background like `search_streaming`,
+/// then keeps checking its files for changes every `interval`, on the same
+/// thread. Each file is marked at the size the search read, so the lines
+/// appended meanwhile are found by the first check. Cancelling the token
+/// stops the search, and only the files searched so far are watched for
+/// their new lines.
+pub fn watch_streaming(
+    dir: &Path,
+    keyword: &str,
+    options: &SearchOptions,
+    cancel: &CancelToken,
+    interval: Duration,
+) -> Result<(Receiver<SearchBatch>, WatchStream), Box<dyn Error>> {
+    let mut watcher = Watcher::new(dir, keyword, options)?;
+    watcher.sbsearch.cancel = cancel.clone();
+
+    let (sender, receiver) = mpsc::channel();
+    let (changes_sender, changes) = mpsc::channel();
+    let (stop, stopped) = mpsc::channel();
+    thread::spawn(move || {
+        let emit = |entries| {
+            sender
+                .send(Ok(SearchEvent::Entries(entries)))
+                .map_err(|_| String::from("search cancelled"))
+        };
+        let progress = |progress| {
+            let _ = sender.send(Ok(SearchEvent::Progress(progress)));
+        };
+        match watcher.scan(progress, emit) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    let _ = sender.send(Ok(SearchEvent::Warning(warning)));
+                }
+            }
+            Err(e) => {
+                debug!("streaming search stopped: {}", e);
+                let _ = sender.send(Err(e.to_string()));
+            }
+        }
+        drop(sender);
+
+        // each check searches a little, so the timeout and the cancellation
+        // of the search don't apply
+        watcher.sbsearch.deadline = None;
+        watcher.sbsearch.cancel = CancelToken::default();
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            let changes = watcher.poll().map_err(|e| e.to_string());
+            if changes.as_ref().is_ok_and(Changes::is_empty) {
+                continue;
+            }
+            if changes_sender.send(changes).is_err() {
+                break;
+            }
+        }
+        debug!("stopped watching {}", watcher.root.display());
+    });
+    Ok((
+        receiver,
+        WatchStream {
+            changes,
+            _stop: stop,
+        },
+    ))
+}
+
+impl Watcher {
+    /// Watches the files under `dir`, which `scan` searches and marks first.
+    pub fn new(dir: &Path, keyword: &str, options: &SearchOptions) -> Result<Self, Box<dyn Error>> {
+        let mut sbsearch = SBSearch::new(root_dir(dir)?, keyword, options)?;
+        sbsearch.manifests = manifest::Manifests::load(dir);
+        Ok(Watcher {
+            sbsearch,
+            root: dir.to_path_buf(),
+            marks: HashMap::new(),
+        })
+    }
+
+    fn files(&mut self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut files = Vec::new();
+        self.sbsearch.visited_dirs.clear();
+        self.sbsearch.collect_files(&self.root, &mut files)?;
+        Ok(files)
+    }
+
+    /// Searches the files in parallel, passing the entries of each file to
+    /// `emit`, and marks each file at the size searched. Every file is marked
+    /// before it's searched, so the files left by a cancelled search are only
+    /// searched for the lines appended to them after.
+    pub fn scan(
+        &mut self,
+        progress: impl Fn(Progress) + Sync,
+        emit: impl Fn(Vec<Entry>) -> Result<(), String> + Sync,
+    ) -> Result<Vec<SearchWarning>, Box<dyn Error>> {
+        let files = self.files()?;
+        let marked: Vec<(PathBuf, Mark)> = files
+            .into_iter()
+            .filter_map(|path| Mark::of(&path).ok().map(|mark| (path, mark)))
+            .collect();
+        self.marks = marked.iter().cloned().collect();
+        info!("watching {} files", marked.len());
+
+        let counter = ProgressCounter::new(marked.len(), progress);
+        let pool = self.sbsearch.thread_pool()?;
+        let sbsearch = &self.sbsearch;
+        pool.install(|| {
+            marked.par_iter().try_for_each(|(path, mark)| {
+                let entries = match is_archive(path) {
+                    true => sbsearch.search_path(path),
+                    false => sbsearch.search_path_head(path, mark.len),
+                };
+                counter.searched(path);
+                let entries = entries.map_err(|e| e.to_string())?;
+                match entries.is_empty() {
+                    true => Ok(()),
+                    false => emit(entries),
+                }
+            })
+        })?;
+        self.sbsearch.record_timed_out();
+        Ok(self.sbsearch.take_warnings())
+    }
+
+    /// Searches the files that changed since the last check.
+    pub fn poll(&mut self) -> Result<Changes, Box<dyn Error>> {
+        let mut changes = Changes::default();
+        let files = self.files()?;
+        let present: HashSet<&PathBuf> = files.iter().collect();
+        self.marks.retain(|path, _| present.contains(path));
+        for path in files {
+            let Ok(current) = Mark::of(&path) else {
+                continue;
+            };
+            match self.marks.get(&path).copied() {
+                // a new file is searched from the start
+                None => {
+                    debug!("searching new file: {}", path.display());
+                    changes.entries.extend(self.sbsearch.search_path(&path)?);
+                    self.marks.insert(path, current);
+                }
+                Some(mark) if current.len == mark.len && current.modified == mark.modified => {}
+                Some(mark) if current.len > mark.len && !is_archive(&path) => {
+                    match self.search_appended(&path, mark) {
+                        Ok((entries, mark)) => {
+                            changes.entries.extend(entries);
+                            self.marks.insert(path, mark);
+                        }
+                        Err(e) => changes
+                            .warnings
+                            .push(SearchWarning::from_error(&path, e.as_ref())),
+                    }
+                }
+                Some(_) => {
+                    debug!("searching rewritten file: {}", path.display());
+                    changes.entries.extend(self.sbsearch.search_path(&path)?);
+                    changes.rescanned.push(path.display().to_string());
+                    self.marks.insert(path, current);
+                }
+            }
+        }
+        changes.warnings.extend(self.sbsearch.take_warnings());
+        Ok(changes)
+    }
+
+    /// Searches the complete lines appended to the file after `mark`. A line
+    /// still being written is left for the next check.
+    fn search_appended(
+        &self,
+        path: &Path,
+        mark: Mark,
+    ) -> Result<(Vec<Entry>, Mark), Box<dyn Error>> {
+        let mut file = File::open(path)?;
+        let lines = match mark.lines {
+            Some(lines) => lines,
+            None => count_lines((&mut file).take(mark.len))?,
+        };
+        file.seek(SeekFrom::Start(mark.len))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)?;
+        let Some(end) = appended.iter().rposition(|&b| b == b'\n') else {
+            return Ok((
+                Vec::new(),
+                Mark {
+                    lines: Some(lines),
+                    ..mark
+                },
+            ));
+        };
+        let appended = &appended[..=end];
+
+        debug!(
+            "searching {} bytes appended to file: {}",
+            appended.len(),
+            path.display()
+        );
+        let entries = self.sbsearch.search_appended(path, appended, lines)?;
+        let mark = Mark {
+            len: mark.len + appended.len() as u64,
+            modified: Mark::of(path)?.modified,
+            lines: Some(lines + count_lines(appended)?),
+        };
+        Ok((entries, mark))
+    }
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("root", &self.root)
+            .field("files", &self.marks.len())
+            .finish()
+    }
+}
+
+fn is_archive(path: &Path) -> bool {
+    is_zip(path).unwrap_or(false) || is_tar_gz(path).unwrap_or(false)
+}
+
+fn count_lines(mut reader: impl Read) -> io::Result<u64> {
+    let mut lines = 0;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf)? {
+            0 => return Ok(lines),
+            n => lines += buf[..n].iter().filter(|&&b| b == b'\n').count() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_watch() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        let log = log_dir.join("app.log");
+        fs::write(
+            &log,
+            "level=info msg=\"attached vm-00\"\nlevel=info msg=\"idle\"\n",
+        )
+        .unwrap();
+
+        let mut watcher =
+            Watcher::new(root_dir.path(), "vm-00", &SearchOptions::default()).unwrap();
+        let found = Mutex::new(Vec::new());
+        watcher
+            .scan(
+                |_| {},
+                |entries| {
+                    found.lock().unwrap().extend(entries);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(found.into_inner().unwrap().len(), 1);
+        assert!(watcher.poll().unwrap().is_empty());
+
+        // only the complete lines appended are searched, numbered after the
+        // lines already there
+        let mut file = File::options().append(true).open(&log).unwrap();
+        write!(
+            file,
+            "level=error msg=\"detach vm-00 failed\"\nlevel=info msg=\"vm-00"
+        )
+        .unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.entries.len(), 1);
+        assert_eq!(changes.entries[0].line_number, 3);
+        assert!(changes.rescanned.is_empty());
+
+        writeln!(file, " detached\"").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.entries.len(), 1);
+        assert_eq!(changes.entries[0].line_number, 4);
+        assert!(changes.entries[0].content.contains("vm-00 detached"));
+
+        // a truncated file is searched again
+        fs::write(&log, "level=info msg=\"vm-00\"\n").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.entries.len(), 1);
+        assert_eq!(changes.rescanned, vec![log.display().to_string()]);
+
+        // and so is a new file
+        fs::write(log_dir.join("sidecar.log"), "vm-00 started\n").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.entries.len(), 1);
+        assert!(changes.entries[0].path.ends_with("sidecar.log"));
+    }
+
+    #[test]
+    fn test_scan_head() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        fs::create_dir_all(&log_dir).unwrap();
+        let log = log_dir.join("app.log");
+        let head = "level=info msg=\"attached vm-00\"\n";
+        fs::write(&log, format!("{}level=info msg=\"detached vm-00\"\n", head)).unwrap();
+
+        // a line appended while the file is searched is past its mark, and
+        // left to the next check
+        let mut watcher =
+            Watcher::new(root_dir.path(), "vm-00", &SearchOptions::default()).unwrap();
+        let entries = watcher
+            .sbsearch
+            .search_path_head(&log, head.len() as u64)
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        let mark = Mark {
+            len: head.len() as u64,
+            ..Mark::of(&log).unwrap()
+        };
+        watcher.marks.insert(log.clone(), mark);
+
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.entries.len(), 1);
+        assert_eq!(changes.entries[0].line_number, 2);
+        assert!(changes.entries[0].content.contains("detached"));
+    }
+}
@@ -0,0 +1,1148 @@
+use crate::query::Query;
+use crate::sbsearch::{
+    self, CancelToken, Changes, Entry, Progress, SearchBatch, SearchEvent, SearchOptions,
+    SearchStats, SearchWarning, WatchStream,
+};
+use chrono::SecondsFormat;
+use log::*;
+use regex::Regex;
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+/// The name of the latest autosave file, saved as JSON Lines to be replayed.
+/// Older autosaves are rotated to `<name>.1`, `<name>.2`, ... up to
+/// `MAX_AUTOSAVES`.
+const AUTOSAVE_NAME: &str = "autosave.jsonl";
+
+/// The number of autosave files kept, including the latest.
+const MAX_AUTOSAVES: usize = 3;
+
+/// How often the files are checked for new entries in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A search over a support bundle. It owns the entries matching the keyword,
+/// the query that narrows them down, and serves them a page at a time.
+#[derive(Debug, Default)]
+pub struct Session {
+    root_dir: String,
+    keyword: String,
+    options: SearchOptions,
+    query: Query,
+    search: Option<Regex>,
+    search_text: String,
+    search_regex: bool,
+    path: Option<String>,
+    cache: Vec<Entry>,
+    stream: Option<Receiver<SearchBatch>>,
+    cancel: CancelToken,
+    streamed: bool,
+    normalize_timestamps: bool,
+    save_locations: bool,
+    export_format: ExportFormat,
+    replayed: bool,
+    warnings: Vec<SearchWarning>,
+    progress: Option<Progress>,
+    watch: bool,
+    watch_interval: Duration,
+    watcher: Option<WatchStream>,
+    /// The statistics of the entries, computed once after they change.
+    stats: OnceCell<Stats>,
+    /// The number of entries in each file, counted once after the entries
+    /// change.
+    file_counts: OnceCell<BTreeMap<String, usize>>,
+}
+
+/// The format the entries are saved in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExportFormat {
+    /// Plain log lines
+    #[default]
+    Log,
+    /// JSON Lines keeping the path, level and fields of each entry, which can be replayed
+    Json,
+    /// CSV with the timestamp, level, path, line number and content of each entry
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Log => "log",
+            ExportFormat::Json => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Statistics of a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// The number of entries matching the keyword.
+    pub entries: usize,
+    /// The number of entries matching the keyword, the query and the search.
+    pub matching: usize,
+    /// The approximate memory held by the entries, in bytes.
+    pub cache_bytes: usize,
+    /// The breakdown of the entries matching the keyword, the query and the
+    /// search.
+    pub summary: SearchStats,
+}
+
+impl Session {
+    pub fn new(root_dir: &str, keyword: &str) -> Self {
+        Session {
+            root_dir: String::from(root_dir),
+            keyword: String::from(keyword),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_options(mut self, options: SearchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Prefixes each line of the exported entries with the UTC timestamp of
+    /// the entry, so that the exported files sort and diff line by line.
+    pub fn with_normalized_timestamps(mut self, normalize: bool) -> Self {
+        self.normalize_timestamps = normalize;
+        self
+    }
+
+    /// Prefixes each line of the exported entries with its `path:line` in the
+    /// support bundle, like grep.
+    pub fn with_saved_locations(mut self, locations: bool) -> Self {
+        self.save_locations = locations;
+        self
+    }
+
+    pub fn with_export_format(mut self, format: ExportFormat) -> Self {
+        self.export_format = format;
+        self
+    }
+
+    /// Keeps searching the files of the support bundle as they change once the
+    /// search is done, see `watch`.
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self.watch_interval = WATCH_INTERVAL;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_watch_interval(mut self, interval: Duration) -> Self {
+        self.watch_interval = interval;
+        self
+    }
+
+    /// Opens the entries saved to `path` by `export`, to browse them without
+    /// the support bundle. Plain log saves don't record the keyword, so they
+    /// go by the name of the file instead.
+    pub fn replay(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let saved = sbsearch::read_saved(path)?;
+        let keyword = match saved.keyword.is_empty() {
+            true => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            false => saved.keyword,
+        };
+        info!(
+            "replaying {} entries from '{}'",
+            saved.entries.len(),
+            path.display()
+        );
+        Ok(Session {
+            root_dir: saved.root_dir,
+            keyword,
+            cache: saved.entries,
+            streamed: true,
+            replayed: true,
+            ..Default::default()
+        })
+    }
+
+    /// Serves `entries` instead of searching the support bundle for them.
+    pub fn with_entries(mut self, entries: Vec<Entry>) -> Self {
+        self.cache = entries;
+        self.stats.take();
+        self.file_counts.take();
+        self
+    }
+
+    pub fn root_dir(&self) -> &str {
+        &self.root_dir
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    pub fn normalizes_timestamps(&self) -> bool {
+        self.normalize_timestamps
+    }
+
+    pub fn saves_locations(&self) -> bool {
+        self.save_locations
+    }
+
+    pub fn export_format(&self) -> ExportFormat {
+        self.export_format
+    }
+
+    /// Returns true if the entries were opened from a saved file instead of
+    /// searched for.
+    pub fn is_replay(&self) -> bool {
+        self.replayed
+    }
+
+    /// Returns true if the entries are kept up to date with the files of the
+    /// support bundle.
+    pub fn is_watching(&self) -> bool {
+        self.watch && !self.replayed
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Returns all the entries matching the keyword, regardless of the query.
+    pub fn entries(&self) -> &[Entry] {
+        &self.cache
+    }
+
+    /// Returns the files that were skipped or only partly searched.
+    pub fn warnings(&self) -> &[SearchWarning] {
+        &self.warnings
+    }
+
+    /// Returns true if the search ran out of time, so that the entries are
+    /// only the ones found until then.
+    pub fn timed_out(&self) -> bool {
+        self.warnings
+            .iter()
+            .any(|warning| matches!(warning, SearchWarning::SearchTimedOut { .. }))
+    }
+
+    /// Returns how far the background search is, once it's reported.
+    pub fn progress(&self) -> Option<Progress> {
+        self.progress
+    }
+
+    /// Returns the entries matching the keyword, the query, the search and
+    /// the path.
+    pub fn matching(&self) -> impl Iterator<Item = &Entry> {
+        self.cache.iter().filter(|entry| {
+            self.matches_query(entry)
+                && self
+                    .search
+                    .as_ref()
+                    .is_none_or(|search| search.is_match(&entry.content))
+                && self.path.as_ref().is_none_or(|path| {
+                    entry
+                        .path
+                        .strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                })
+        })
+    }
+
+    /// Returns the number of entries matching the keyword in each file, by
+    /// path.
+    pub fn file_counts(&self) -> Vec<(String, usize)> {
+        self.counts()
+            .iter()
+            .map(|(path, count)| (path.clone(), *count))
+            .collect()
+    }
+
+    /// Returns the number of entries matching the keyword in the file at
+    /// `path`, whatever the filter.
+    pub fn file_count(&self, path: &str) -> usize {
+        self.counts().get(path).copied().unwrap_or_default()
+    }
+
+    fn counts(&self) -> &BTreeMap<String, usize> {
+        self.file_counts.get_or_init(|| {
+            let mut counts = BTreeMap::new();
+            for entry in &self.cache {
+                *counts.entry(entry.path.clone()).or_default() += 1;
+            }
+            counts
+        })
+    }
+
+    /// Starts searching the support bundle in the background. The entries
+    /// found so far are collected by `poll`, and served by `search`.
+    pub fn start_search(&mut self) -> Result<(), Box<dyn Error>> {
+        // the saved entries are all there is to a replay
+        if self.replayed {
+            return Ok(());
+        }
+
+        let cancel = CancelToken::default();
+        let dir = Path::new(&self.root_dir);
+        self.watcher = None;
+        let stream = match self.is_watching() {
+            true => {
+                let (stream, watcher) = sbsearch::watch_streaming(
+                    dir,
+                    &self.keyword,
+                    &self.options,
+                    &cancel,
+                    self.watch_interval,
+                )?;
+                self.watcher = Some(watcher);
+                stream
+            }
+            false => sbsearch::search_streaming(dir, &self.keyword, &self.options, &cancel)?,
+        };
+        self.cancel = cancel;
+        self.cache.clear();
+        self.stats.take();
+        self.file_counts.take();
+        self.warnings.clear();
+        self.progress = None;
+        self.stream = Some(stream);
+        self.streamed = true;
+        Ok(())
+    }
+
+    /// Searches the support bundle, passing the entries matching the query to
+    /// `emit` as soon as their file is searched. Entries of a file come in
+    /// order, but the files finish in any order. With `dedupe`, the entries
+    /// are only passed once the search is done, sorted and collapsed. Once
+    /// done, the entries are served by `search` like after `start_search`.
+    pub fn search_each(
+        &mut self,
+        mut emit: impl FnMut(&Entry) -> io::Result<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.start_search()?;
+        let Some(stream) = self.stream.take() else {
+            return Ok(());
+        };
+        for batch in stream {
+            let entries = match batch? {
+                SearchEvent::Entries(entries) => entries,
+                SearchEvent::Warning(warning) => {
+                    self.warnings.push(warning);
+                    continue;
+                }
+                SearchEvent::Progress(progress) => {
+                    self.progress = Some(progress);
+                    continue;
+                }
+            };
+            // duplicates can be in any file, so they are only collapsed once
+            // all the files are searched
+            if !self.options.dedupe {
+                for entry in entries.iter().filter(|entry| self.matches_query(entry)) {
+                    emit(entry)?;
+                }
+            }
+            self.cache.extend(entries);
+        }
+        self.stats.take();
+        self.file_counts.take();
+        self.options.sort.sort_by_position(&mut self.cache);
+        if self.options.dedupe {
+            sbsearch::dedupe(&mut self.cache);
+            for entry in self.cache.iter().filter(|entry| self.matches_query(entry)) {
+                emit(entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn matches_query(&self, entry: &Entry) -> bool {
+        self.query.is_empty() || self.query.matches(entry, &self.root_dir)
+    }
+
+    /// Stops the background search, keeping the entries found so far.
+    pub fn cancel_search(&mut self) {
+        if !self.is_searching() {
+            return;
+        }
+
+        info!("cancelling the search for '{}'", self.keyword);
+        self.cancel.cancel();
+        self.poll();
+        if self.is_searching() {
+            self.finish_search();
+        }
+    }
+
+    /// Returns true while the background search is running.
+    pub fn is_searching(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Collects the entries found by the background search since the last
+    /// call. Returns true if there are new entries, or the search is done.
+    pub fn poll(&mut self) -> bool {
+        let Some(stream) = &self.stream else {
+            return false;
+        };
+
+        let mut changed = false;
+        let mut found = Vec::new();
+        let mut done = false;
+        loop {
+            match stream.try_recv() {
+                Ok(Ok(SearchEvent::Entries(entries))) => {
+                    found.extend(entries);
+                    changed = true;
+                }
+                Ok(Ok(SearchEvent::Warning(warning))) => self.warnings.push(warning),
+                Ok(Ok(SearchEvent::Progress(progress))) => self.progress = Some(progress),
+                Ok(Err(e)) => error!("error searching the support bundle: {}", e),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    done = true;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+
+        // the files finish in any order, so ties are broken by position to
+        // keep the order stable between runs
+        self.options.sort.merge_by_position(&mut self.cache, found);
+        if done {
+            self.finish_search();
+        }
+        if changed {
+            self.stats.take();
+            self.file_counts.take();
+        }
+        changed
+    }
+
+    /// Orders the rotated logs and collapses the duplicates once the
+    /// background search is done or cancelled, rather than after every batch.
+    fn finish_search(&mut self) {
+        self.options.sort.order_rotations(&mut self.cache);
+        if self.options.dedupe {
+            sbsearch::dedupe(&mut self.cache);
+        }
+        self.stats.take();
+        self.file_counts.take();
+        info!(
+            "found {} entries matching '{}'",
+            self.cache.len(),
+            self.keyword
+        );
+        self.stream = None;
+    }
+
+    /// Collects the entries of the lines appended to the files, and of the
+    /// files that were rewritten, found in the background since the last
+    /// call. The files are checked once the search started by `start_search`
+    /// is done. Returns true if the entries changed.
+    pub fn watch(&mut self) -> bool {
+        if !self.is_watching() || self.is_searching() {
+            return false;
+        }
+        let Some(watcher) = &self.watcher else {
+            return false;
+        };
+
+        let mut changed = false;
+        for changes in watcher.changes().collect::<Vec<_>>() {
+            match changes {
+                Ok(changes) => {
+                    self.apply_changes(changes);
+                    changed = true;
+                }
+                Err(e) => error!("error watching the support bundle: {}", e),
+            }
+        }
+        if changed {
+            self.options.sort.order_rotations(&mut self.cache);
+            if self.options.dedupe {
+                sbsearch::dedupe(&mut self.cache);
+            }
+            self.stats.take();
+            self.file_counts.take();
+        }
+        changed
+    }
+
+    fn apply_changes(&mut self, changes: Changes) {
+        info!(
+            "found {} new entries matching '{}'",
+            changes.entries.len(),
+            self.keyword
+        );
+        // the entries of a rewritten file, or of the files of a rewritten
+        // archive, are replaced
+        if !changes.rescanned.is_empty() {
+            self.cache.retain(|entry| {
+                !changes
+                    .rescanned
+                    .iter()
+                    .any(|path| Path::new(&entry.path).starts_with(path))
+            });
+        }
+        self.warnings.extend(changes.warnings);
+        self.options
+            .sort
+            .merge_by_position(&mut self.cache, changes.entries);
+    }
+
+    /// Searches the support bundle on the first call, and returns the page of
+    /// matching entries at `offset`. After `start_search`, the page holds the
+    /// entries found so far instead.
+    pub fn search(&mut self, offset: usize, limit: usize) -> Result<Vec<Entry>, Box<dyn Error>> {
+        if self.streamed {
+            return Ok(self.matching().skip(offset).take(limit).cloned().collect());
+        }
+
+        let result = sbsearch::search(
+            Path::new(&self.root_dir),
+            &self.keyword,
+            offset,
+            limit,
+            &mut self.cache,
+            &self.options,
+        )?;
+        self.warnings.extend(result.warnings);
+        if let Some(stats) = result.stats {
+            self.stats.take();
+            self.file_counts.take();
+            // the stats of the search are of all the entries, which are the
+            // matching ones without a query
+            if self.query.is_empty() && self.search.is_none() && self.path.is_none() {
+                let _ = self.stats.set(Stats {
+                    entries: self.cache.len(),
+                    matching: stats.entries,
+                    cache_bytes: sbsearch::cache_size(&self.cache),
+                    summary: stats,
+                });
+            }
+        }
+        info!(
+            "found {} entries matching '{}'",
+            self.cache.len(),
+            self.keyword
+        );
+        if self.query.is_empty() && self.search.is_none() && self.path.is_none() {
+            return Ok(result.entries_offset);
+        }
+
+        // paginate over the entries that match the query and the search
+        // instead
+        Ok(self.matching().skip(offset).take(limit).cloned().collect())
+    }
+
+    /// Narrows the entries down to the ones matching the query.
+    pub fn filter(&mut self, query: Query) {
+        info!("applying filter query '{}'", query);
+        self.query = query;
+        self.stats.take();
+    }
+
+    /// Narrows the entries down to the ones containing `text`, or matching it
+    /// as a regular expression if `regex`. An invalid regular expression is
+    /// returned, keeping the search as it was.
+    pub fn set_search(&mut self, text: &str, regex: bool) -> Result<(), regex::Error> {
+        self.search = search_regex(text, regex)?;
+        self.search_text = String::from(text);
+        self.search_regex = regex;
+        self.stats.take();
+        Ok(())
+    }
+
+    pub fn search_text(&self) -> &str {
+        &self.search_text
+    }
+
+    /// Returns true if the search text is a regular expression.
+    pub fn search_is_regex(&self) -> bool {
+        self.search_regex
+    }
+
+    /// Narrows the entries down further to the ones of the file at `path`, or
+    /// of the files under the directory at `path`. None shows them all again.
+    pub fn set_path(&mut self, path: Option<&str>) {
+        self.path = path.map(String::from);
+        self.stats.take();
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Writes the matching entries to the file at `path`, in the export
+    /// format.
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<&Entry> = self.matching().collect();
+        self.export_entries(path, &entries, self.export_format)
+    }
+
+    /// Writes `entries` to the file at `path`, in `format`, e.g. a page of the
+    /// matching entries, or all of them regardless of the query.
+    pub fn export_entries(
+        &self,
+        path: &Path,
+        entries: &[&Entry],
+        format: ExportFormat,
+    ) -> io::Result<()> {
+        info!(
+            "saving {} entries to file '{}'",
+            entries.len(),
+            path.display()
+        );
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(&file);
+        if format == ExportFormat::Csv {
+            writeln!(writer, "timestamp,level,path,line,content")?;
+            for entry in entries {
+                let timestamp = entry
+                    .timestamp
+                    .map(|t| t.to_rfc3339_opts(SecondsFormat::Nanos, true))
+                    .unwrap_or_default();
+                let path = entry
+                    .path
+                    .strip_prefix(&self.root_dir)
+                    .unwrap_or(&entry.path)
+                    .trim_start_matches('/');
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    timestamp,
+                    entry.level.as_str(),
+                    csv_field(path),
+                    entry.line_number,
+                    csv_field(entry.content.trim_end_matches('\n'))
+                )?;
+            }
+            return writer.flush();
+        }
+        if format == ExportFormat::Json {
+            sbsearch::write_saved(
+                &mut writer,
+                &self.root_dir,
+                &self.keyword,
+                entries.iter().copied(),
+            )?;
+            return writer.flush();
+        }
+
+        // the statistics are commented out, so that the saved entries can be
+        // replayed
+        for line in SearchStats::of(entries.iter().copied()).to_string().lines() {
+            writeln!(writer, "# {}", line)?;
+        }
+        for entry in entries {
+            if !self.normalize_timestamps && !self.save_locations {
+                write!(writer, "{}", entry)?;
+                continue;
+            }
+
+            // the timestamps have a fixed width to sort as text, and entries
+            // without one get a placeholder to keep the columns aligned
+            let timestamp = entry
+                .timestamp
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Nanos, true))
+                .unwrap_or_else(|| String::from("-"));
+            let path = entry
+                .path
+                .strip_prefix(&self.root_dir)
+                .unwrap_or(&entry.path);
+            let path = path.trim_start_matches('/');
+            for (n, line) in entry.content.lines().enumerate() {
+                if self.normalize_timestamps {
+                    write!(writer, "{} ", timestamp)?;
+                }
+                if self.save_locations {
+                    write!(writer, "{}:{}: ", path, entry.line_number + n as u64)?;
+                }
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Saves the matching entries to the autosave file in `dir`, rotating the
+    /// previous autosaves. The entries are saved like the JSON exports, so the
+    /// autosave is opened again with `replay`, whatever the export format.
+    /// They are written to a temporary file first and renamed into place, so a
+    /// crash mid-write leaves the last autosave intact. Returns the path of the
+    /// autosave file.
+    pub fn autosave(&self, dir: &Path) -> io::Result<PathBuf> {
+        let latest = dir.join(AUTOSAVE_NAME);
+        let rotated = |n: usize| dir.join(format!("{}.{}", AUTOSAVE_NAME, n));
+
+        let tmp = tempfile::NamedTempFile::new_in(dir)?;
+        let entries: Vec<&Entry> = self.matching().collect();
+        self.export_entries(tmp.path(), &entries, ExportFormat::Json)?;
+        tmp.as_file().sync_all()?;
+
+        for n in (1..MAX_AUTOSAVES).rev() {
+            let from = if n == 1 {
+                latest.clone()
+            } else {
+                rotated(n - 1)
+            };
+            if from.exists() {
+                std::fs::rename(&from, rotated(n))?;
+            }
+        }
+        tmp.persist(&latest).map_err(|e| e.error)?;
+        debug!("autosaved to {}", latest.display());
+        Ok(latest)
+    }
+
+    /// Returns the statistics of the entries, computed once after the entries
+    /// or the query change.
+    pub fn stats(&self) -> &Stats {
+        self.stats.get_or_init(|| {
+            let summary = SearchStats::of(self.matching());
+            Stats {
+                entries: self.cache.len(),
+                matching: summary.entries,
+                cache_bytes: sbsearch::cache_size(&self.cache),
+                summary,
+            }
+        })
+    }
+}
+
+/// Creates the directory the session autosaves to, named after the support
+/// bundle at `root_dir` and the process, in the temp directory. The directory
+/// is only accessible to the user and has a random suffix, so that sessions
+/// don't overwrite each other's autosaves. It's kept after the session for
+/// the autosaves to be replayed.
+pub fn autosave_dir(root_dir: &str) -> io::Result<PathBuf> {
+    let name = Path::new(root_dir)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let dir = tempfile::Builder::new()
+        .prefix(&format!("sbsearch-{}-{}-", name, std::process::id()))
+        .tempdir()?;
+    Ok(dir.keep())
+}
+
+/// Builds the regular expression of a search, which matches
+/// case-insensitively: `text` is a regular expression if `regex`, or else
+/// matched as typed. An empty search is none.
+pub fn search_regex(text: &str, regex: bool) -> Result<Option<Regex>, regex::Error> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let pattern = match regex {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(regex::escape(text)),
+    };
+    Regex::new(&format!("(?i){}", pattern)).map(Some)
+}
+
+/// Quotes the field of a CSV row if it holds a comma, a quote or a line
+/// break, doubling its quotes.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => Cow::Owned(format!("\"{}\"", value.replace('"', "\"\""))),
+        false => Cow::Borrowed(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_and_filter() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        let page = session.search(0, 100).unwrap();
+        assert_eq!(page.len(), 100);
+        assert_eq!(session.stats().entries, 244);
+        assert_eq!(session.stats().matching, 244);
+
+        session.filter("ns=default".parse().unwrap());
+        let page = session.search(0, 100).unwrap();
+        assert_eq!(page.len(), 44);
+        assert!(page.iter().all(|e| e.path.contains("/logs/default/")));
+
+        let stats = session.stats();
+        assert_eq!(stats.entries, 244);
+        assert_eq!(stats.matching, 44);
+        assert!(stats.cache_bytes > 0);
+    }
+
+    #[test]
+    fn test_set_search() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.filter("ns=default".parse().unwrap());
+        session.search(0, 100).unwrap();
+        assert_eq!(session.stats().matching, 44);
+
+        // the search narrows down the entries matching the query
+        session.set_search("HOOK", false).unwrap();
+        let page = session.search(0, 100).unwrap();
+        assert!(!page.is_empty() && page.len() < 44);
+        assert!(
+            page.iter()
+                .all(|e| e.content.to_lowercase().contains("hook"))
+        );
+        assert_eq!(session.stats().matching, page.len());
+        assert_eq!(session.stats().entries, 244);
+
+        session.set_search("", false).unwrap();
+        assert_eq!(session.search(0, 100).unwrap().len(), 44);
+
+        // as a regex, the search matches the pattern, not the text
+        session.set_search("hook|vm-00-disk", false).unwrap();
+        assert!(session.search(0, 100).unwrap().is_empty());
+        session.set_search("hook|vm-00-disk", true).unwrap();
+        let page = session.search(0, 100).unwrap();
+        assert!(page.len() > 1 && page.len() < 44);
+        assert!(session.search_is_regex());
+
+        // an invalid regex keeps the search as it was
+        assert!(session.set_search("hook(", true).is_err());
+        assert_eq!(session.search_text(), "hook|vm-00-disk");
+        assert_eq!(session.search(0, 100).unwrap().len(), page.len());
+        assert!(session.set_search("hook(", false).is_ok());
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.search(0, 100).unwrap();
+        let counts = session.file_counts();
+        assert!(counts.len() > 1);
+        assert_eq!(counts.iter().map(|(_, count)| count).sum::<usize>(), 244);
+
+        let (path, count) = &counts[0];
+        assert_eq!(session.file_count(path), *count);
+        assert_eq!(session.file_count("testdata/support_bundle/noexist.log"), 0);
+        session.set_path(Some(path));
+        let page = session.search(0, 300).unwrap();
+        assert_eq!(page.len(), *count);
+        assert!(page.iter().all(|e| &e.path == path));
+
+        // a directory keeps the entries of all the files under it
+        session.set_path(Some("testdata/support_bundle/logs/default"));
+        let page = session.search(0, 300).unwrap();
+        assert_eq!(page.len(), 44);
+        session.set_path(Some("testdata/support_bundle/logs/def"));
+        assert!(session.search(0, 300).unwrap().is_empty());
+
+        session.set_path(None);
+        assert_eq!(session.search(0, 300).unwrap().len(), 244);
+    }
+
+    #[test]
+    fn test_start_search() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.start_search().unwrap();
+        while session.is_searching() {
+            session.poll();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(!session.poll());
+        assert_eq!(session.stats().entries, 244);
+
+        // every file is accounted for once the search is done
+        let progress = session.progress().unwrap();
+        assert!(progress.total_files > 0);
+        assert_eq!(progress.files, progress.total_files);
+        assert!(progress.bytes > 0);
+
+        let mut blocking = Session::new("testdata/support_bundle", "vm-00");
+        blocking.search(0, 100).unwrap();
+        let positions = |s: &Session| {
+            s.entries()
+                .iter()
+                .map(|e| (e.path.clone(), e.line_number))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(&session), positions(&blocking));
+
+        session.filter("ns=default".parse().unwrap());
+        assert_eq!(session.search(0, 100).unwrap().len(), 44);
+    }
+
+    #[test]
+    fn test_cancel_search() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.start_search().unwrap();
+        session.cancel_search();
+        assert!(!session.is_searching());
+        assert!(!session.poll());
+        assert!(session.stats().entries <= 244);
+    }
+
+    #[test]
+    fn test_watch() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let log_dir = root_dir.path().join("logs").join("default").join("app");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let log = log_dir.join("app.log");
+        std::fs::write(&log, "vm-00 attached\nvm-00 idle\n").unwrap();
+
+        let mut session = Session::new(root_dir.path().to_str().unwrap(), "vm-00")
+            .with_watch(true)
+            .with_watch_interval(Duration::from_millis(10));
+        session.start_search().unwrap();
+        assert!(!session.watch());
+        while session.is_searching() {
+            session.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(session.stats().entries, 2);
+
+        // the files are checked in the background once searched
+        let watch = |session: &mut Session| {
+            for _ in 0..500 {
+                if session.watch() {
+                    return true;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            false
+        };
+        let mut file = File::options().append(true).open(&log).unwrap();
+        writeln!(file, "vm-00 detached").unwrap();
+        assert!(watch(&mut session));
+        assert_eq!(session.stats().entries, 3);
+        assert_eq!(session.entries()[2].line_number, 3);
+
+        // the entries of a rewritten file are replaced
+        std::fs::write(&log, "vm-00 restarted\n").unwrap();
+        assert!(watch(&mut session));
+        assert_eq!(session.stats().entries, 1);
+    }
+
+    #[test]
+    fn test_search_each() {
+        let mut session = Session::new("testdata/support_bundle", "vm-00");
+        session.filter("ns=default".parse().unwrap());
+        let mut emitted = 0;
+        session
+            .search_each(|entry| {
+                assert!(entry.path.contains("/logs/default/"));
+                emitted += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert!(!session.is_searching());
+        assert_eq!(emitted, 44);
+        assert_eq!(session.stats().entries, 244);
+        assert_eq!(session.search(0, 100).unwrap().len(), 44);
+    }
+
+    #[test]
+    fn test_search_each_dedupe() {
+        let root_dir = tempfile::tempdir().unwrap();
+        for (pod, content) in [
+            ("app-a", "vm-00 attached\nvm-00 idle\n"),
+            ("app-b", "vm-00 attached\nvm-00 detached\n"),
+        ] {
+            let log_dir = root_dir.path().join("logs").join("default").join(pod);
+            std::fs::create_dir_all(&log_dir).unwrap();
+            std::fs::write(log_dir.join("app.log"), content).unwrap();
+        }
+
+        // the duplicates are collapsed before the entries are passed on, in
+        // the same order as the entries served afterwards
+        let options = SearchOptions {
+            dedupe: true,
+            ..Default::default()
+        };
+        let mut session =
+            Session::new(root_dir.path().to_str().unwrap(), "vm-00").with_options(options);
+        let mut emitted = Vec::new();
+        session
+            .search_each(|entry| {
+                emitted.push(entry.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(emitted.len(), 3);
+        assert_eq!(emitted[0].content.trim_end(), "vm-00 attached");
+        assert_eq!(emitted[0].duplicates, 1);
+        let served = session.search(0, 10).unwrap();
+        assert_eq!(served.len(), 3);
+        assert!(
+            served
+                .iter()
+                .zip(&emitted)
+                .all(|(a, b)| a.path == b.path && a.line_number == b.line_number)
+        );
+    }
+
+    /// Drops the `#` lines of the statistics at the top of an export.
+    fn without_stats(content: &str) -> String {
+        content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let entry = |content: &str, timestamp: Option<&str>| Entry {
+            level: sbsearch::Level::Warn,
+            path: String::from("sb/logs/a.log"),
+            content: String::from(content),
+            timestamp: timestamp.map(|t| t.parse().unwrap()),
+            line_number: 7,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        let session = Session::new("sb", "vm-00")
+            .with_entries(vec![
+                entry(
+                    "vm-00 \"stuck\", retrying\n  continued\n",
+                    Some("2025-12-30T21:51:44Z"),
+                ),
+                entry("vm-00 without timestamp\n", None),
+            ])
+            .with_export_format(ExportFormat::Csv);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        session.export(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "timestamp,level,path,line,content\n\
+             2025-12-30T21:51:44.000000000Z,warn,logs/a.log,7,\"vm-00 \"\"stuck\"\", retrying\n  continued\"\n\
+             ,warn,logs/a.log,7,vm-00 without timestamp\n"
+        );
+    }
+
+    #[test]
+    fn test_export_normalized_timestamps() {
+        let entry = |content: &str, timestamp: Option<&str>| Entry {
+            level: sbsearch::Level::Info,
+            path: String::from("sb/logs/a.log"),
+            content: String::from(content),
+            timestamp: timestamp.map(|t| t.parse().unwrap()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        let session = Session::new("sb", "vm-00")
+            .with_entries(vec![
+                entry(
+                    "Dec 30 21:51:44 node1 kernel: vm-00\n  continued\n",
+                    Some("2025-12-30T21:51:44Z"),
+                ),
+                entry(
+                    "I1230 21:52:00.5 vm-00 attached\n",
+                    Some("2025-12-30T21:52:00.5Z"),
+                ),
+                entry("vm-00 without timestamp\n", None),
+            ])
+            .with_normalized_timestamps(true);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.log");
+        session.export(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# entries: 3\n# levels: 3 info\n"));
+        assert_eq!(
+            without_stats(&content),
+            "2025-12-30T21:51:44.000000000Z Dec 30 21:51:44 node1 kernel: vm-00\n\
+             2025-12-30T21:51:44.000000000Z   continued\n\
+             2025-12-30T21:52:00.500000000Z I1230 21:52:00.5 vm-00 attached\n\
+             - vm-00 without timestamp\n"
+        );
+
+        let session = session
+            .with_normalized_timestamps(false)
+            .with_saved_locations(true);
+        session.export(&path).unwrap();
+        assert_eq!(
+            without_stats(&std::fs::read_to_string(&path).unwrap()),
+            "logs/a.log:1: Dec 30 21:51:44 node1 kernel: vm-00\n\
+             logs/a.log:2:   continued\n\
+             logs/a.log:1: I1230 21:52:00.5 vm-00 attached\n\
+             logs/a.log:1: vm-00 without timestamp\n"
+        );
+    }
+
+    #[test]
+    fn test_autosave() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut session = Session::new("testdata/support_bundle", "vm-00-disk-0-");
+        session.search(0, 100).unwrap();
+
+        for _ in 0..MAX_AUTOSAVES + 1 {
+            let path = session.autosave(dir.path()).unwrap();
+            assert_eq!(path, dir.path().join("autosave.jsonl"));
+        }
+
+        // the autosave is replayed like the saved entries
+        let replayed = Session::replay(&dir.path().join("autosave.jsonl")).unwrap();
+        assert_eq!(replayed.keyword(), "vm-00-disk-0-");
+        assert_eq!(replayed.entries().len(), 72);
+        for (a, b) in replayed.entries().iter().zip(session.entries()) {
+            assert_eq!((&a.path, a.line_number), (&b.path, b.line_number));
+        }
+
+        // only the latest autosaves are kept
+        let mut files: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["autosave.jsonl", "autosave.jsonl.1", "autosave.jsonl.2"]
+        );
+
+        // each session autosaves to a directory of its own
+        let first = autosave_dir("testdata/support_bundle").unwrap();
+        let second = autosave_dir("testdata/support_bundle").unwrap();
+        assert_ne!(first, second);
+        let name = first.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with(&format!("sbsearch-support_bundle-{}-", std::process::id())));
+        std::fs::remove_dir(first).unwrap();
+        std::fs::remove_dir(second).unwrap();
+    }
+
+    #[test]
+    fn test_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sbsearch_saved.jsonl");
+        let mut session =
+            Session::new("testdata/support_bundle", "vm-00").with_export_format(ExportFormat::Json);
+        session.filter("ns=default".parse().unwrap());
+        session.search(0, 100).unwrap();
+        session.export(&path).unwrap();
+
+        let mut replay = Session::replay(&path).unwrap();
+        assert!(replay.is_replay());
+        assert_eq!(replay.root_dir(), "testdata/support_bundle");
+        assert_eq!(replay.keyword(), "vm-00");
+        replay.start_search().unwrap();
+        assert!(!replay.is_searching());
+        assert_eq!(replay.stats().entries, 44);
+
+        // the saved paths still tell the sources apart
+        replay.filter("pod=no-such-pod".parse().unwrap());
+        assert!(replay.search(0, 100).unwrap().is_empty());
+        replay.filter("ns=default".parse().unwrap());
+        let page = replay.search(0, 100).unwrap();
+        assert_eq!(page.len(), 44);
+        assert_eq!(page[0].content, session.search(0, 1).unwrap()[0].content);
+    }
+}
@@ -0,0 +1,119 @@
+use ratatui::widgets::TableState;
+use sbsearch::Entry;
+
+/// A row of the comparison: the entries of the two keywords found at the same
+/// time, or only one of them.
+pub type Row<'a> = (Option<&'a Entry>, Option<&'a Entry>);
+
+/// The comparison of the active tab with another tab, shown side by side.
+#[derive(Debug, Default)]
+pub struct Compare {
+    /// The index of the tab shown on the right.
+    pub other: usize,
+    pub state: TableState,
+}
+
+impl Compare {
+    pub fn new(other: usize) -> Self {
+        Compare {
+            other,
+            state: TableState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn select_next(&mut self, rows: usize) {
+        let index = self.state.selected().map_or(0, |index| index + 1);
+        self.state.select(Some(index.min(rows.saturating_sub(1))));
+    }
+
+    pub fn select_prev(&mut self) {
+        let index = self.state.selected().unwrap_or(0).saturating_sub(1);
+        self.state.select(Some(index));
+    }
+}
+
+/// Merges the entries of the two keywords by time, so that the rows read
+/// down as one timeline. Entries with the same timestamp share a row, and the
+/// ones without a timestamp come first.
+pub fn align<'a>(
+    left: impl Iterator<Item = &'a Entry>,
+    right: impl Iterator<Item = &'a Entry>,
+) -> Vec<Row<'a>> {
+    let mut left: Vec<&Entry> = left.collect();
+    let mut right: Vec<&Entry> = right.collect();
+    left.sort_by_key(|entry| entry.timestamp);
+    right.sort_by_key(|entry| entry.timestamp);
+
+    let mut rows = Vec::with_capacity(left.len().max(right.len()));
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    loop {
+        let row = match (left.peek(), right.peek()) {
+            (None, None) => break,
+            (Some(_), None) => (left.next(), None),
+            (None, Some(_)) => (None, right.next()),
+            (Some(l), Some(r)) if l.timestamp.is_some() && l.timestamp == r.timestamp => {
+                (left.next(), right.next())
+            }
+            (Some(l), Some(r)) if l.timestamp <= r.timestamp => (left.next(), None),
+            (Some(_), Some(_)) => (None, right.next()),
+        };
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(content: &str, second: Option<u32>) -> Entry {
+        Entry {
+            level: sbsearch::Level::Info,
+            path: String::from("/path/to/log"),
+            content: String::from(content),
+            timestamp: second.map(|s| Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, s).unwrap()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_align() {
+        let left = [
+            entry("l3", Some(3)),
+            entry("l1", Some(1)),
+            entry("l5", Some(5)),
+        ];
+        let right = [
+            entry("r2", Some(2)),
+            entry("r-", None),
+            entry("r3", Some(3)),
+            entry("r6", Some(6)),
+        ];
+        let rows: Vec<(Option<&str>, Option<&str>)> = align(left.iter(), right.iter())
+            .into_iter()
+            .map(|(l, r)| (l.map(|e| e.content.as_str()), r.map(|e| e.content.as_str())))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (None, Some("r-")),
+                (Some("l1"), None),
+                (None, Some("r2")),
+                (Some("l3"), Some("r3")),
+                (Some("l5"), None),
+                (None, Some("r6")),
+            ]
+        );
+
+        let mut compare = Compare::new(1);
+        compare.select_prev();
+        assert_eq!(compare.state.selected(), Some(0));
+        for _ in 0..10 {
+            compare.select_next(rows.len());
+        }
+        assert_eq!(compare.state.selected(), Some(5));
+    }
+}
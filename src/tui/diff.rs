@@ -0,0 +1,219 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use sbsearch::Entry;
+
+/// The most characters compared one by one between the changed middles of
+/// the two entries, past which the whole middles are taken as changed rather
+/// than spending seconds and hundreds of megabytes on two unrelated dumps.
+const MAX_CELLS: usize = 4_000_000;
+
+/// Whether a run of characters is in both entries, or only in one of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// The differences between two entries, the older one first, shown one above
+/// the other.
+#[derive(Debug)]
+pub struct Diff {
+    pub old: Entry,
+    pub new: Entry,
+    pub ops: Vec<(Op, String)>,
+    pub scroll: u16,
+}
+
+impl Diff {
+    pub fn new(a: Entry, b: Entry) -> Self {
+        let (old, new) = match b.timestamp < a.timestamp {
+            true => (b, a),
+            false => (a, b),
+        };
+        let ops = diff(&old.content, &new.content);
+        Diff {
+            old,
+            new,
+            ops,
+            scroll: 0,
+        }
+    }
+
+    /// Returns the number of runs of characters that changed.
+    pub fn changes(&self) -> usize {
+        let mut changes = 0;
+        let mut changed = false;
+        for (op, _) in &self.ops {
+            if *op != Op::Same && !changed {
+                changes += 1;
+            }
+            changed = *op != Op::Same;
+        }
+        changes
+    }
+
+    /// Returns the lines of the older entry if `old`, with what was removed
+    /// from it highlighted, or else the lines of the newer one with what was
+    /// added to it.
+    pub fn lines(&self, old: bool) -> Vec<Line<'static>> {
+        let (shown, style) = match old {
+            true => (
+                Op::Removed,
+                Style::default().fg(Color::White).bg(Color::Red),
+            ),
+            false => (
+                Op::Added,
+                Style::default().fg(Color::Black).bg(Color::Green),
+            ),
+        };
+        let mut lines = vec![Line::default()];
+        for (op, text) in &self.ops {
+            let style = match op {
+                Op::Same => Style::default(),
+                op if *op == shown => style,
+                _ => continue,
+            };
+            for (index, part) in text.split('\n').enumerate() {
+                if index > 0 {
+                    lines.push(Line::default());
+                }
+                if !part.is_empty() {
+                    let line = lines.last_mut().unwrap();
+                    line.spans.push(Span::styled(String::from(part), style));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Returns the runs of characters that are the same in `old` and `new`, and
+/// the ones removed from `old` and added to `new`, in order. The common prefix
+/// and suffix are skipped first, so that two near-identical entries, like the
+/// same reconcile error logged twice, are compared quickly.
+pub fn diff(old: &str, new: &str) -> Vec<(Op, String)> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut chars: Vec<(Op, char)> = old[..prefix].iter().map(|c| (Op::Same, *c)).collect();
+    if a.len() * b.len() > MAX_CELLS {
+        chars.extend(a.iter().map(|c| (Op::Removed, *c)));
+        chars.extend(b.iter().map(|c| (Op::Added, *c)));
+    } else {
+        chars.extend(lcs(a, b));
+    }
+    chars.extend(old[old.len() - suffix..].iter().map(|c| (Op::Same, *c)));
+
+    let mut ops: Vec<(Op, String)> = Vec::new();
+    for (op, c) in chars {
+        match ops.last_mut() {
+            Some((last, text)) if *last == op => text.push(c),
+            _ => ops.push((op, String::from(c))),
+        }
+    }
+    ops
+}
+
+/// Walks the longest common subsequence of `a` and `b`, marking the
+/// characters out of it as removed or added. The removals come before the
+/// additions where both are possible, so that a replaced word reads as one
+/// removal and one addition.
+fn lcs(a: &[char], b: &[char]) -> Vec<(Op, char)> {
+    // lengths[i][j] is the length of the common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = match a[i] == b[j] {
+                true => lengths[(i + 1) * width + j + 1] + 1,
+                false => lengths[(i + 1) * width + j].max(lengths[i * width + j + 1]),
+            };
+        }
+    }
+
+    let mut chars = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            chars.push((Op::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len()
+            || (i < a.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            chars.push((Op::Removed, a[i]));
+            i += 1;
+        } else {
+            chars.push((Op::Added, b[j]));
+            j += 1;
+        }
+    }
+    chars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(content: &str, second: u32) -> Entry {
+        Entry {
+            level: sbsearch::Level::Error,
+            path: String::from("/path/to/log"),
+            content: String::from(content),
+            timestamp: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, second).unwrap()),
+            line_number: second as u64,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff("reconcile pvc-1: 3 replicas", "reconcile pvc-2: 2 replicas"),
+            vec![
+                (Op::Same, String::from("reconcile pvc-")),
+                (Op::Removed, String::from("1")),
+                (Op::Added, String::from("2")),
+                (Op::Same, String::from(": ")),
+                (Op::Removed, String::from("3")),
+                (Op::Added, String::from("2")),
+                (Op::Same, String::from(" replicas")),
+            ]
+        );
+        assert_eq!(
+            diff("same ✓", "same ✓"),
+            vec![(Op::Same, String::from("same ✓"))]
+        );
+        assert_eq!(diff("", "new"), vec![(Op::Added, String::from("new"))]);
+
+        // the newer entry goes second, whichever was picked first
+        let diff = Diff::new(entry("a\nstate: ok\nz", 9), entry("a\nstate: failed\nz", 1));
+        assert_eq!(diff.old.line_number, 1);
+        assert_eq!(diff.changes(), 1);
+        let text = |lines: Vec<Line>| -> Vec<String> {
+            lines.iter().map(|line| line.to_string()).collect()
+        };
+        assert_eq!(text(diff.lines(true)), vec!["a", "state: failed", "z"]);
+        assert_eq!(text(diff.lines(false)), vec!["a", "state: ok", "z"]);
+        assert_eq!(
+            diff.lines(false)[1].spans[1],
+            Span::styled("ok", Style::default().fg(Color::Black).bg(Color::Green))
+        );
+    }
+}
@@ -1,35 +1,197 @@
-use crate::tui::{Screen, SearchMode};
-use crossterm::event::{Event, KeyCode, KeyEventKind};
+use crate::tui::{Focus, Screen, SearchMode};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use sbsearch::Level;
 use std::io;
+use std::time::Duration;
 use tui_input::backend::crossterm::EventHandler;
 
+/// How long to wait for an event before returning, so that periodic work
+/// like autosaves runs while the user is idle.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for an event while the search is running, so that the
+/// entries are shown as they're found.
+const SEARCH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many columns the detail view, and the entries shown on one line,
+/// scroll sideways by.
+const SCROLL_COLUMNS: i32 = 8;
+
+/// The largest count of a motion, so that a mistyped one doesn't hang.
+const MAX_COUNT: usize = 9999;
+
 pub fn handle(tui: &mut super::Tui) -> io::Result<()> {
+    // the compared tab searches in the background too
+    let searching = tui.session.is_searching()
+        || tui
+            .compare
+            .as_ref()
+            .and_then(|compare| tui.tabs.get(compare.other))
+            .is_some_and(|tab| tab.session.is_searching());
+    let interval = match searching {
+        true => SEARCH_POLL_INTERVAL,
+        false => POLL_INTERVAL,
+    };
+    if !crossterm::event::poll(interval)? {
+        return Ok(());
+    }
     let event = crossterm::event::read()?;
     handle_key_event(tui, event);
     Ok(())
 }
 
+/// Collects the digits of a count after `#`, like vim's `15j` but as `#15j`
+/// so that the digits keep switching tabs on their own, and applies it to
+/// the motion key that follows: Up/Down and `j`/`k` move by as many lines,
+/// Left/Right by as many pages, and `G` goes to the entry with this number.
+/// Esc drops the count, and any other key drops it and does what it does.
+/// Returns true if the key was taken by the count.
+fn handle_count(tui: &mut super::Tui, code: KeyCode) -> bool {
+    let Some(mut digits) = tui.pending_count.take() else {
+        if code == KeyCode::Char('#') {
+            tui.pending_count = Some(String::new());
+            return true;
+        }
+        return false;
+    };
+    let count = match digits.parse::<usize>() {
+        Ok(n) => n.min(MAX_COUNT),
+        Err(_) if digits.is_empty() => 1,
+        Err(_) => MAX_COUNT,
+    };
+    match code {
+        KeyCode::Char(c @ '0'..='9') => {
+            digits.push(c);
+            tui.pending_count = Some(digits);
+        }
+        KeyCode::Down | KeyCode::Char('j') => (0..count).for_each(|_| tui.nav_next_line()),
+        KeyCode::Up | KeyCode::Char('k') => (0..count).for_each(|_| tui.nav_prev_line()),
+        KeyCode::Right => (0..count).for_each(|_| tui.nav_next_page()),
+        KeyCode::Left => (0..count).for_each(|_| tui.nav_prev_page()),
+        KeyCode::Char('G') if !digits.is_empty() => tui.nav_goto_entry(&digits),
+        KeyCode::Esc => {}
+        _ => return false,
+    }
+    true
+}
+
 fn handle_key_event(tui: &mut super::Tui, event: Event) {
     if let Event::Key(key_event) = event {
         if key_event.kind != KeyEventKind::Press {
             return;
         }
 
+        // the terminal is in raw mode, so Ctrl-C arrives as a key press
+        if key_event.code == KeyCode::Char('c')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            tui.exit();
+            return;
+        }
+
         match tui.current_screen {
-            Screen::Main => match tui.search_mode {
+            Screen::Main => match tui.search_mode.clone() {
+                SearchMode::Normal if tui.focus == Focus::Files => match key_event.code {
+                    KeyCode::Char('q') => tui.current_screen = Screen::ConfirmExit,
+                    KeyCode::Esc | KeyCode::Tab => tui.switch_focus(),
+                    KeyCode::Char('b') => tui.toggle_files(),
+                    KeyCode::Up | KeyCode::Char('k') => tui.file_tree.select_prev(),
+                    KeyCode::Down | KeyCode::Char('j') => tui.file_tree.select_next(),
+                    KeyCode::Enter => tui.pick_file(),
+                    _ => {}
+                },
+                SearchMode::Normal if tui.focus == Focus::Detail => match key_event.code {
+                    KeyCode::Char('q') => tui.current_screen = Screen::ConfirmExit,
+                    KeyCode::Esc | KeyCode::Tab => tui.switch_focus(),
+                    KeyCode::Char('D') => tui.toggle_split(),
+                    KeyCode::Up | KeyCode::Char('k') => tui.scroll_detail(-1, 0),
+                    KeyCode::Down | KeyCode::Char('j') => tui.scroll_detail(1, 0),
+                    KeyCode::Left | KeyCode::Char('h') => tui.scroll_detail(0, -SCROLL_COLUMNS),
+                    KeyCode::Right | KeyCode::Char('l') => tui.scroll_detail(0, SCROLL_COLUMNS),
+                    KeyCode::Char('g') => tui.detail_scroll = (0, 0),
+                    KeyCode::Char('-') => tui.fold_json(true),
+                    KeyCode::Char('+') | KeyCode::Char('=') => tui.fold_json(false),
+                    KeyCode::Char('v') => tui.open_source(),
+                    KeyCode::Char('e') => tui.external_pending = true,
+                    KeyCode::Char('y') => tui.copy_selected(false),
+                    KeyCode::Char('Y') => tui.copy_selected(true),
+                    _ => {}
+                },
+                SearchMode::Normal if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    match key_event.code {
+                        KeyCode::Char('d') => tui.nav_lines(0.5),
+                        KeyCode::Char('u') => tui.nav_lines(-0.5),
+                        KeyCode::Char('f') => tui.nav_lines(1.0),
+                        KeyCode::Char('b') => tui.nav_lines(-1.0),
+                        _ => {}
+                    }
+                }
+                SearchMode::Normal if handle_count(tui, key_event.code) => {}
                 SearchMode::Normal => match key_event.code {
                     KeyCode::Char('q') => tui.current_screen = Screen::ConfirmExit,
+                    KeyCode::Esc => tui.cancel_search(),
                     KeyCode::Char('/') => {
                         tui.search_mode = SearchMode::Insert;
                         tui.search_input.reset();
+                        tui.search_history.reset();
                     }
                     KeyCode::Char('c') => {
                         tui.search = String::new();
                         tui.search_input.reset();
+                        tui.apply_search();
+                    }
+                    KeyCode::Char('m') => tui.toggle_search_filter(),
+                    KeyCode::Char('E') => tui.filter_levels(Some(Level::Error)),
+                    KeyCode::Char('W') => tui.filter_levels(Some(Level::Warn)),
+                    KeyCode::Char('I') => tui.filter_levels(Some(Level::Info)),
+                    KeyCode::Char('A') => tui.filter_levels(None),
+                    KeyCode::Char('s') => tui.open_save(),
+                    KeyCode::Char('t') => tui.current_screen = Screen::Suspects,
+                    KeyCode::Char('w') => tui.current_screen = Screen::Warnings,
+                    KeyCode::Char('f') => tui.open_filter_form(),
+                    KeyCode::Char('p') | KeyCode::Char('P') => tui.toggle_pin(),
+                    KeyCode::Char('r') => tui.show_rate = !tui.show_rate,
+                    KeyCode::Char('R') => tui.reload(),
+                    KeyCode::Char('n') => {
+                        tui.tab_input.reset();
+                        tui.current_screen = Screen::NewTab;
+                    }
+                    KeyCode::Char('o') => {
+                        tui.open_input.reset();
+                        tui.current_screen = Screen::OpenSaved;
                     }
-                    KeyCode::Char('s') => {
-                        tui.current_screen = Screen::ConfirmSave;
+                    KeyCode::Char(':') => {
+                        tui.goto_input.reset();
+                        tui.current_screen = Screen::GoTo;
                     }
+                    KeyCode::Char('C') => tui.open_compare(),
+                    KeyCode::Char('M') => tui.toggle_bookmark(),
+                    KeyCode::Char(']') => tui.nav_bookmark(true),
+                    KeyCode::Char('[') => tui.nav_bookmark(false),
+                    KeyCode::Char('\'') => tui.open_bookmarks(),
+                    KeyCode::Char('d') => tui.open_diff(),
+                    KeyCode::Char('x') => tui.close_tab(),
+                    KeyCode::Char(c @ '1'..='8') => {
+                        tui.switch_tab(c as usize - '1' as usize);
+                    }
+                    KeyCode::Char('?') => tui.open_help(),
+                    KeyCode::Char('b') => tui.toggle_files(),
+                    KeyCode::Tab => tui.switch_focus(),
+                    KeyCode::Char('z') => tui.toggle_wrap(),
+                    KeyCode::Char('T') => tui.toggle_columns(),
+                    KeyCode::Char('S') => tui.toggle_source(),
+                    KeyCode::Char('L') => tui.toggle_logfmt(),
+                    KeyCode::Char('Z') => tui.toggle_local_time(),
+                    KeyCode::Char('a') => tui.toggle_anchor(),
+                    KeyCode::Char('D') => tui.toggle_split(),
+                    KeyCode::Char('h') => tui.scroll_logs(-SCROLL_COLUMNS as isize),
+                    KeyCode::Char('l') => tui.scroll_logs(SCROLL_COLUMNS as isize),
+                    KeyCode::Enter => tui.open_detail(),
+                    KeyCode::Char('v') => tui.open_source(),
+                    KeyCode::Char('X') => tui.toggle_context(),
+                    KeyCode::Char('e') => tui.external_pending = true,
+                    KeyCode::Char('y') => tui.copy_selected(false),
+                    KeyCode::Char('Y') => tui.copy_selected(true),
                     KeyCode::Char('G') => tui.nav_last_line(),
                     KeyCode::Char('g') => tui.nav_first_line(),
                     KeyCode::Up | KeyCode::Char('k') => tui.nav_prev_line(),
@@ -38,20 +200,30 @@ fn handle_key_event(tui: &mut super::Tui, event: Event) {
                     KeyCode::Right => tui.nav_next_page(),
                     KeyCode::Char('0') => tui.nav_first_page(),
                     KeyCode::Char('9') => tui.nav_last_page(),
+                    KeyCode::Char('!') => tui.nav_first_error(),
                     _ => {}
                 },
                 SearchMode::Insert => match key_event.code {
                     KeyCode::Enter => {
                         tui.search = String::from(tui.search_input.value());
+                        tui.search_history.push(&tui.search);
                         tui.search_mode = SearchMode::Normal;
                     }
                     KeyCode::Esc => {
                         tui.search = String::new();
                         tui.search_input.reset();
                         tui.search_mode = SearchMode::Normal;
+                        tui.apply_search();
+                    }
+                    KeyCode::Up => tui.recall_search(true),
+                    KeyCode::Down => tui.recall_search(false),
+                    KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        tui.toggle_search_regex()
                     }
                     _ => {
                         tui.search_input.handle_event(&event);
+                        tui.search_history.reset();
+                        tui.apply_search();
                     }
                 },
             },
@@ -61,12 +233,208 @@ fn handle_key_event(tui: &mut super::Tui, event: Event) {
                 _ => {}
             },
             Screen::ConfirmSave => match key_event.code {
+                KeyCode::Enter => {
+                    if let Err(e) = tui.confirm_save() {
+                        tui.save_failed(e);
+                    }
+                }
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::BackTab => tui.save_scope = tui.save_scope.cycle(-1),
+                KeyCode::Tab => tui.save_scope = tui.save_scope.cycle(1),
+                KeyCode::Up => tui.cycle_save_format(-1),
+                KeyCode::Down => tui.cycle_save_format(1),
+                _ => {
+                    tui.save_input.handle_event(&event);
+                }
+            },
+            Screen::ConfirmOverwrite => match key_event.code {
                 KeyCode::Char('y') => {
                     if let Err(e) = tui.save_to_file() {
-                        println!("Error saving to file: {}", e);
+                        tui.save_failed(e);
                     }
                 }
-                KeyCode::Char('n') => tui.current_screen = Screen::Main,
+                KeyCode::Char('n') | KeyCode::Esc => tui.current_screen = Screen::ConfirmSave,
+                _ => {}
+            },
+            Screen::Help => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+                    tui.current_screen = Screen::Main
+                }
+                KeyCode::Up | KeyCode::Char('k') => tui.scroll_help(-1),
+                KeyCode::Down | KeyCode::Char('j') => tui.scroll_help(1),
+                KeyCode::Char('g') => tui.help_scroll = 0,
+                _ => {}
+            },
+            Screen::Detail => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => {
+                    tui.current_screen = Screen::Main
+                }
+                KeyCode::Up | KeyCode::Char('k') => tui.scroll_detail(-1, 0),
+                KeyCode::Down | KeyCode::Char('j') => tui.scroll_detail(1, 0),
+                KeyCode::Left | KeyCode::Char('h') => tui.scroll_detail(0, -SCROLL_COLUMNS),
+                KeyCode::Right | KeyCode::Char('l') => tui.scroll_detail(0, SCROLL_COLUMNS),
+                KeyCode::Char('g') => tui.detail_scroll = (0, 0),
+                KeyCode::Char('-') => tui.fold_json(true),
+                KeyCode::Char('+') | KeyCode::Char('=') => tui.fold_json(false),
+                KeyCode::Char('v') => tui.open_source(),
+                KeyCode::Char('e') => tui.external_pending = true,
+                KeyCode::Char('y') => tui.copy_selected(false),
+                KeyCode::Char('Y') => tui.copy_selected(true),
+                _ => {}
+            },
+            Screen::Source => {
+                let Some(view) = &mut tui.source_view else {
+                    tui.current_screen = Screen::Main;
+                    return;
+                };
+                match key_event.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('v') => tui.close_source(),
+                    KeyCode::Up | KeyCode::Char('k') => view.scroll(-1),
+                    KeyCode::Down | KeyCode::Char('j') => view.scroll(1),
+                    KeyCode::Left | KeyCode::PageUp => view.scroll_page(-1),
+                    KeyCode::Right | KeyCode::PageDown => view.scroll_page(1),
+                    KeyCode::Char('g') => view.scroll_to_start(),
+                    KeyCode::Char('G') => view.scroll_to_end(),
+                    KeyCode::Char('e') => view.scroll_to_entry(),
+                    _ => {}
+                }
+            }
+            Screen::FilterBuilder => match key_event.code {
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::Enter => tui.apply_filter_form(),
+                KeyCode::Up => tui.filter_form.focus_prev(),
+                KeyCode::Down | KeyCode::Tab => tui.filter_form.focus_next(),
+                KeyCode::Char(' ') if tui.filter_form.focused_input().is_none() => {
+                    tui.filter_form.toggle()
+                }
+                _ => {
+                    if let Some(input) = tui.filter_form.focused_input() {
+                        input.handle_event(&event);
+                        tui.filter_form.error = None;
+                    }
+                }
+            },
+            Screen::NewTab => match key_event.code {
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::Enter => {
+                    let keyword = String::from(tui.tab_input.value().trim());
+                    tui.current_screen = Screen::Main;
+                    tui.open_tab(&keyword);
+                }
+                _ => {
+                    tui.tab_input.handle_event(&event);
+                }
+            },
+            Screen::OpenSaved => match key_event.code {
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::Enter => {
+                    let path = String::from(tui.open_input.value().trim());
+                    tui.current_screen = Screen::Main;
+                    tui.open_saved(&path);
+                }
+                _ => {
+                    tui.open_input.handle_event(&event);
+                }
+            },
+            Screen::Bookmarks => match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('\'') => {
+                    tui.current_screen = Screen::Main
+                }
+                KeyCode::Enter => tui.pick_bookmark(),
+                KeyCode::Char('M') => tui.remove_bookmark(),
+                KeyCode::Up | KeyCode::Char('k') => tui.bookmark_state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => tui.bookmark_state.select_next(),
+                _ => {}
+            },
+            Screen::Diff => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('d') => {
+                    tui.diff = None;
+                    tui.current_screen = Screen::Main;
+                }
+                KeyCode::Up | KeyCode::Char('k') => tui.scroll_diff(-1),
+                KeyCode::Down | KeyCode::Char('j') => tui.scroll_diff(1),
+                KeyCode::Char('g') => {
+                    if let Some(diff) = &mut tui.diff {
+                        diff.scroll = 0;
+                    }
+                }
+                _ => {}
+            },
+            Screen::CompareWith => match key_event.code {
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::Enter => {
+                    let keyword = String::from(tui.compare_input.value().trim());
+                    tui.start_compare(&keyword);
+                }
+                _ => {
+                    tui.compare_input.handle_event(&event);
+                }
+            },
+            Screen::Compare => match key_event.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+                    tui.compare = None;
+                    tui.current_screen = Screen::Main;
+                }
+                KeyCode::Enter => tui.pick_compare(),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    if let Some(compare) = &mut tui.compare {
+                        compare.select_prev();
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    let rows = tui.compare_rows().len();
+                    if let Some(compare) = &mut tui.compare {
+                        compare.select_next(rows);
+                    }
+                }
+                KeyCode::Char('g') => {
+                    if let Some(compare) = &mut tui.compare {
+                        compare.state.select(Some(0));
+                    }
+                }
+                KeyCode::Char('G') => {
+                    let rows = tui.compare_rows().len();
+                    if let Some(compare) = &mut tui.compare {
+                        compare.state.select(Some(rows.saturating_sub(1)));
+                    }
+                }
+                _ => {}
+            },
+            Screen::Error => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => tui.close_error(false),
+                KeyCode::Char('r') => tui.close_error(true),
+                _ => {}
+            },
+            Screen::GoTo => match key_event.code {
+                KeyCode::Esc => tui.current_screen = Screen::Main,
+                KeyCode::Enter => {
+                    let number = String::from(tui.goto_input.value());
+                    tui.current_screen = Screen::Main;
+                    tui.nav_goto_entry(&number);
+                }
+                _ => {
+                    tui.goto_input.handle_event(&event);
+                }
+            },
+            Screen::Suspects => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => {
+                    tui.current_screen = Screen::Main
+                }
+                _ => {}
+            },
+            Screen::Progress => match key_event.code {
+                KeyCode::Enter => tui.current_screen = Screen::Main,
+                KeyCode::Esc => {
+                    tui.cancel_search();
+                    tui.current_screen = Screen::Main;
+                }
+                KeyCode::Char('q') => tui.current_screen = Screen::ConfirmExit,
+                _ => {}
+            },
+            Screen::Warnings => match key_event.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => {
+                    tui.current_screen = Screen::Main
+                }
                 _ => {}
             },
         }
@@ -76,35 +444,45 @@ fn handle_key_event(tui: &mut super::Tui, event: Event) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{sbsearch, tui::*};
-    use crossterm::event::{KeyEvent, KeyModifiers};
+    use crate::tui::*;
+    use crossterm::event::KeyEvent;
+    use sbsearch::Session;
 
     #[test]
     fn handle_key_events_on_main_screen() {
         let tui = &mut Tui::new("sb_path", "pvc_name");
         tui.entries_offset = vec![
             sbsearch::Entry {
-                level: String::from("level=info"),
+                level: sbsearch::Level::Info,
                 path: String::from("/path/to/log1"),
                 content: String::from("This is an info log entry."),
                 timestamp: Some(chrono::Utc::now()),
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
             },
             sbsearch::Entry {
-                level: String::from("level=warning"),
+                level: sbsearch::Level::Warn,
                 path: String::from("/path/to/log2"),
                 content: String::from("This is an warning log entry."),
                 timestamp: Some(chrono::Utc::now()),
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
             },
             sbsearch::Entry {
-                level: String::from("level=error"),
+                level: sbsearch::Level::Error,
                 path: String::from("/path/to/log3"),
                 content: String::from("This is an error log entry."),
                 timestamp: Some(chrono::Utc::now()),
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
             },
         ];
 
-        assert_eq!(tui.sbpath, "sb_path");
-        assert_eq!(tui.keyword, "pvc_name");
+        assert_eq!(tui.session.root_dir(), "sb_path");
+        assert_eq!(tui.session.keyword(), "pvc_name");
         assert_eq!(tui.current_screen, Screen::Main);
 
         // navigation keys
@@ -148,6 +526,13 @@ mod tests {
         handle_key_event(tui, event);
         assert_eq!(tui.page_goto, tui.page_final);
 
+        // toggle the rate panel
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.show_rate);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.show_rate);
+
         // confirm exit
         let key_event = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
         let event = Event::Key(key_event);
@@ -189,20 +574,1151 @@ mod tests {
         assert_eq!(tui.search, String::new());
     }
 
+    #[test]
+    fn handle_key_events_on_search_regex() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+        let press = |tui: &mut Tui, code: KeyCode, modifiers: KeyModifiers| {
+            let key_event = KeyEvent::new(code, modifiers);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+        press(tui, KeyCode::Char('/'), KeyModifiers::NONE);
+        for c in "hook|disk-(".chars() {
+            press(tui, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert!(!tui.search_regex);
+        assert!(tui.search_error.is_none());
+        assert_eq!(tui.session.search_text(), "hook|disk-(");
+
+        // as a regex, the open group is pointed out until it's closed
+        press(tui, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(tui.search_regex);
+        assert_eq!(tui.search_error.as_deref(), Some("unclosed group"));
+        assert!(!tui.session.search_is_regex());
+        press(tui, KeyCode::Char('0'), KeyModifiers::NONE);
+        press(tui, KeyCode::Char(')'), KeyModifiers::NONE);
+        assert!(tui.search_error.is_none());
+        assert!(tui.session.search_is_regex());
+        assert_eq!(tui.session.search_text(), "hook|disk-(0)");
+        tui.read_entries_from_sb();
+        assert!(!tui.entries_offset.is_empty());
+
+        press(tui, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert!(!tui.search_regex);
+        assert!(!tui.session.search_is_regex());
+    }
+
+    #[test]
+    fn handle_key_events_on_search_history() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        for search in ["timeout", "detach"] {
+            let key_event = KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+            for c in search.chars() {
+                let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                handle_key_event(tui, Event::Key(key_event));
+            }
+            let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        }
+
+        // Up recalls the previous searches, and applies them
+        let key_event = KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let key_event = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(up));
+        assert_eq!(tui.search_input.value(), "detach");
+        assert_eq!(tui.session.search_text(), "detach");
+        handle_key_event(tui, Event::Key(up));
+        assert_eq!(tui.search_input.value(), "timeout");
+
+        // Down goes forward, back to what was being typed
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(down));
+        assert_eq!(tui.search_input.value(), "detach");
+        handle_key_event(tui, Event::Key(down));
+        assert_eq!(tui.search_input.value(), "a");
+    }
+
+    #[test]
+    fn handle_key_events_on_search_filter() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+        assert_eq!(tui.stats.matching, 244);
+
+        // the entries without the search are hidden as it's typed
+        let key_event = KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        for c in "hook".chars() {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        assert!(tui.page_reload);
+        tui.read_entries_from_sb();
+        let filtered = tui.stats.matching;
+        assert!(filtered > 0 && filtered < 244);
+        assert!(
+            tui.entries_offset
+                .iter()
+                .all(|e| e.content.to_lowercase().contains("hook"))
+        );
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+
+        // in highlight mode, they're all shown again
+        let key_event = KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.search_filter);
+        tui.read_entries_from_sb();
+        assert_eq!(tui.stats.matching, 244);
+        handle_key_event(tui, Event::Key(key_event));
+        tui.read_entries_from_sb();
+        assert_eq!(tui.stats.matching, filtered);
+
+        // clearing the search shows them all
+        let key_event = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        tui.read_entries_from_sb();
+        assert_eq!(tui.stats.matching, 244);
+    }
+
+    #[test]
+    fn handle_key_events_on_levels() {
+        let tui = &mut Tui::new("sb", "pvc_name");
+        tui.session.filter("ns=default".parse().unwrap());
+
+        let key_event = KeyEvent::new(KeyCode::Char('W'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(
+            tui.session.query().to_string(),
+            "level=fatal,error,warn ns=default"
+        );
+        assert!(tui.page_reload);
+
+        let key_event = KeyEvent::new(KeyCode::Char('E'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(
+            tui.session.query().to_string(),
+            "level=fatal,error ns=default"
+        );
+
+        let key_event = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.session.query().to_string(), "ns=default");
+    }
+
+    #[test]
+    fn handle_key_events_on_files() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+
+        let key_event = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.show_files && tui.focus == Focus::Files);
+        assert_eq!(tui.file_tree.rows[0].count, 244);
+
+        // the keys move through the tree while it's focused
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        handle_key_event(tui, Event::Key(key_event));
+        let row = tui.file_tree.selected().unwrap().clone();
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.session.path(), row.path.as_deref());
+        assert_eq!(tui.session.stats().matching, row.count);
+        assert!(tui.page_reload);
+
+        let key_event = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.focus, Focus::Logs);
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.file_tree.selected(), Some(&row));
+
+        // the first row shows all the files again
+        let key_event = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        tui.file_tree.state.select(Some(0));
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.session.path(), None);
+
+        let key_event = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.show_files && tui.focus == Focus::Logs);
+    }
+
+    #[test]
+    fn handle_key_events_on_goto() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+        let goto = |tui: &mut Tui, number: &str| {
+            let key_event = KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+            assert_eq!(tui.current_screen, Screen::GoTo);
+            for c in number.chars() {
+                let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                handle_key_event(tui, Event::Key(key_event));
+            }
+            let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+            assert_eq!(tui.current_screen, Screen::Main);
+        };
+
+        goto(tui, "178");
+        assert_eq!(tui.page_goto, 2);
+        assert_eq!(tui.nav_state.selected(), Some(77));
+        let entry = tui.session.matching().nth(177).unwrap();
+        let selected = tui.selected_entry().unwrap();
+        assert_eq!(
+            (&selected.path, selected.line_number),
+            (&entry.path, entry.line_number)
+        );
+
+        goto(tui, "5");
+        assert_eq!(tui.page_goto, 1);
+        assert_eq!(tui.nav_state.selected(), Some(4));
+
+        // out of range numbers keep the selection
+        goto(tui, "245");
+        assert_eq!(tui.nav_state.selected(), Some(4));
+        assert!(tui.notice.is_some());
+    }
+
+    #[test]
+    fn handle_key_events_with_counts() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+        let keys = |tui: &mut Tui, codes: &[KeyCode]| {
+            for code in codes {
+                let key_event = KeyEvent::new(*code, KeyModifiers::NONE);
+                handle_key_event(tui, Event::Key(key_event));
+            }
+        };
+
+        keys(
+            tui,
+            &[
+                KeyCode::Char('#'),
+                KeyCode::Char('1'),
+                KeyCode::Char('5'),
+                KeyCode::Char('j'),
+            ],
+        );
+        assert_eq!(tui.nav_state.selected(), Some(15));
+        assert!(tui.pending_count.is_none());
+        keys(tui, &[KeyCode::Char('#'), KeyCode::Char('4'), KeyCode::Up]);
+        assert_eq!(tui.nav_state.selected(), Some(11));
+
+        keys(
+            tui,
+            &[KeyCode::Char('#'), KeyCode::Char('2'), KeyCode::Right],
+        );
+        assert_eq!(tui.page_goto, 3);
+        keys(
+            tui,
+            &[
+                KeyCode::Char('#'),
+                KeyCode::Char('1'),
+                KeyCode::Char('0'),
+                KeyCode::Left,
+            ],
+        );
+        assert_eq!(tui.page_goto, 1);
+
+        // G goes to the entry with the number
+        keys(
+            tui,
+            &[
+                KeyCode::Char('#'),
+                KeyCode::Char('1'),
+                KeyCode::Char('7'),
+                KeyCode::Char('8'),
+                KeyCode::Char('G'),
+            ],
+        );
+        assert_eq!(tui.page_goto, 2);
+        assert_eq!(tui.nav_state.selected(), Some(77));
+
+        // Esc drops the count
+        keys(tui, &[KeyCode::Char('#'), KeyCode::Char('3'), KeyCode::Esc]);
+        assert!(tui.pending_count.is_none());
+        assert_eq!(tui.nav_state.selected(), Some(77));
+
+        // a lone digit still does what it does on its own, right away
+        keys(tui, &[KeyCode::Char('9')]);
+        assert!(tui.pending_count.is_none());
+        assert_eq!(tui.page_goto, tui.page_final);
+
+        // any other key drops the count and does what it does
+        keys(
+            tui,
+            &[KeyCode::Char('#'), KeyCode::Char('2'), KeyCode::Char('r')],
+        );
+        assert!(tui.pending_count.is_none());
+        assert!(tui.show_rate);
+    }
+
+    #[test]
+    fn handle_key_events_on_half_pages() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        tui.entries_offset = vec![
+            sbsearch::Entry {
+                level: sbsearch::Level::Info,
+                path: String::from("sb_path/logs/a.log"),
+                content: String::from("line\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            };
+            30
+        ];
+        tui.logs_height = 10;
+        let ctrl = |tui: &mut Tui, c: char| {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        ctrl(tui, 'd');
+        assert_eq!(tui.nav_state.selected(), Some(5));
+        ctrl(tui, 'f');
+        assert_eq!(tui.nav_state.selected(), Some(15));
+        ctrl(tui, 'f');
+        ctrl(tui, 'f');
+        assert_eq!(tui.nav_state.selected(), Some(29));
+        ctrl(tui, 'u');
+        assert_eq!(tui.nav_state.selected(), Some(24));
+        ctrl(tui, 'b');
+        ctrl(tui, 'b');
+        ctrl(tui, 'b');
+        assert_eq!(tui.nav_state.selected(), Some(0));
+
+        // without Ctrl, f and b keep opening the filter builder and the files
+        let key_event = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::FilterBuilder);
+    }
+
+    #[test]
+    fn handle_key_events_on_tall_entry() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        tui.entries_offset = vec![
+            sbsearch::Entry {
+                level: sbsearch::Level::Info,
+                path: String::from("sb_path/logs/a.log"),
+                content: String::from("tall\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            };
+            2
+        ];
+        // the selected entry has 3 rows that don't fit
+        tui.entry_overflow = 3;
+
+        let down = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        let up = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE);
+        for _ in 0..3 {
+            handle_key_event(tui, Event::Key(down));
+        }
+        assert_eq!((tui.entry_scroll, tui.nav_state.selected()), (3, Some(0)));
+        handle_key_event(tui, Event::Key(up));
+        assert_eq!(tui.entry_scroll, 2);
+
+        // once scrolled through, the next entry is selected from its top
+        handle_key_event(tui, Event::Key(down));
+        handle_key_event(tui, Event::Key(down));
+        assert_eq!((tui.entry_scroll, tui.nav_state.selected()), (0, Some(1)));
+    }
+
+    #[test]
+    fn handle_key_events_on_bookmarks() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+        let press = |tui: &mut Tui, code: KeyCode| {
+            let key_event = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+        let selected = |tui: &Tui| {
+            tui.page_goto * tui.page_max_entries - tui.page_max_entries
+                + tui.nav_state.selected().unwrap()
+                + 1
+        };
+
+        // bookmark the entries 3 and 178, on the second page
+        press(tui, KeyCode::Char(']'));
+        assert!(tui.notice.is_some());
+        tui.nav_goto(178);
+        press(tui, KeyCode::Char('M'));
+        tui.nav_goto(3);
+        press(tui, KeyCode::Char('M'));
+        assert_eq!(tui.bookmarks.len(), 2);
+
+        press(tui, KeyCode::Char(']'));
+        assert_eq!(selected(tui), 178);
+        press(tui, KeyCode::Char(']'));
+        assert_eq!(selected(tui), 3);
+        press(tui, KeyCode::Char('['));
+        assert_eq!(selected(tui), 178);
+        assert_eq!(tui.page_goto, 2);
+
+        // the list goes by the order of the logs
+        press(tui, KeyCode::Char('\''));
+        assert_eq!(tui.current_screen, Screen::Bookmarks);
+        let numbers: Vec<Option<usize>> = tui
+            .numbered_bookmarks()
+            .iter()
+            .map(|(number, _)| *number)
+            .collect();
+        assert_eq!(numbers, vec![Some(3), Some(178)]);
+        press(tui, KeyCode::Enter);
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert_eq!(selected(tui), 3);
+
+        press(tui, KeyCode::Char('\''));
+        press(tui, KeyCode::Char('M'));
+        assert_eq!(tui.bookmarks.len(), 1);
+        press(tui, KeyCode::Esc);
+
+        // pressing M again on the bookmarked entry removes it
+        tui.nav_goto(178);
+        press(tui, KeyCode::Char('M'));
+        assert!(tui.bookmarks.is_empty());
+    }
+
+    #[test]
+    fn handle_key_events_on_context() {
+        let tui = &mut Tui::new("sb_path", "pvc_name").with_context_lines(2);
+        let entry = |line_number: u64| sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from(
+                "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+            ),
+            content: String::from("line"),
+            timestamp: None,
+            line_number,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        tui.entries_offset = vec![entry(10), entry(20)];
+        let press = |tui: &mut Tui, code: KeyCode| {
+            let key_event = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        press(tui, KeyCode::Char('X'));
+        let context = tui.context.as_ref().unwrap();
+        assert_eq!(context.line_number, 10);
+        assert_eq!((context.before.len(), context.after.len()), (2, 2));
+
+        // expanding another entry collapses the first
+        press(tui, KeyCode::Char('j'));
+        press(tui, KeyCode::Char('X'));
+        assert_eq!(tui.context.as_ref().unwrap().line_number, 20);
+        press(tui, KeyCode::Char('X'));
+        assert!(tui.context.is_none());
+
+        tui.entries_offset[1].path = String::from("testdata/support_bundle/noexist.log");
+        press(tui, KeyCode::Char('X'));
+        assert!(tui.context.is_none());
+        assert_eq!(tui.current_screen, Screen::Error);
+    }
+
+    #[test]
+    fn handle_key_events_on_diff() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        let entry = |line_number: u64, content: &str| sbsearch::Entry {
+            level: sbsearch::Level::Error,
+            path: String::from("/path/to/log"),
+            content: String::from(content),
+            timestamp: None,
+            line_number,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        tui.entries_offset = vec![
+            entry(1, "reconcile pvc-1 failed\nstatus: detached"),
+            entry(2, "reconcile pvc-1 failed\nstatus: attached"),
+        ];
+        let press = |tui: &mut Tui, code: KeyCode| {
+            let key_event = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        // there's nothing to diff with until an entry is bookmarked
+        press(tui, KeyCode::Char('d'));
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(tui.notice.is_some());
+        press(tui, KeyCode::Char('M'));
+        press(tui, KeyCode::Char('d'));
+        assert_eq!(tui.current_screen, Screen::Main);
+
+        press(tui, KeyCode::Char('j'));
+        press(tui, KeyCode::Char('d'));
+        assert_eq!(tui.current_screen, Screen::Diff);
+        let diff = tui.diff.as_ref().unwrap();
+        assert_eq!(diff.old.line_number, 1);
+        assert_eq!(diff.new.line_number, 2);
+        assert_eq!(diff.changes(), 1);
+
+        press(tui, KeyCode::Char('j'));
+        press(tui, KeyCode::Char('j'));
+        assert_eq!(tui.diff.as_ref().unwrap().scroll, 1);
+        press(tui, KeyCode::Char('g'));
+        assert_eq!(tui.diff.as_ref().unwrap().scroll, 0);
+        press(tui, KeyCode::Esc);
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(tui.diff.is_none());
+    }
+
+    #[test]
+    fn handle_key_events_on_first_error() {
+        let tui = &mut Tui::new("testdata/support_bundle", "vm-00");
+        tui.page_max_entries = 10;
+        tui.read_entries_from_sb();
+        let first = tui
+            .session
+            .entries()
+            .iter()
+            .filter(|e| e.level >= sbsearch::Level::Error && e.timestamp.is_some())
+            .min_by_key(|e| e.timestamp)
+            .cloned()
+            .unwrap();
+        let key_event = KeyEvent::new(KeyCode::Char('!'), KeyModifiers::NONE);
+
+        handle_key_event(tui, Event::Key(key_event));
+        let selected = tui.selected_entry().unwrap();
+        assert_eq!(
+            (&selected.path, selected.line_number),
+            (&first.path, first.line_number)
+        );
+        assert!(tui.page_goto > 1);
+        assert!(tui.notice.is_none());
+
+        tui.session
+            .set_path(Some("testdata/support_bundle/noexist"));
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.notice.is_some());
+    }
+
+    #[test]
+    fn handle_key_events_on_suspects() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+
+        let key_event = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Suspects);
+
+        // navigation keys are ignored on the suspects screen
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Suspects);
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+    }
+
+    #[test]
+    fn handle_key_events_on_progress() {
+        let tui = &mut Tui::new("./testdata/support_bundle", "vm-00");
+        tui.current_screen = Screen::Progress;
+
+        // navigation keys wait for the search
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Progress);
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+
+        // Esc cancels the search
+        tui.session.start_search().unwrap();
+        tui.current_screen = Screen::Progress;
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(!tui.session.is_searching());
+    }
+
+    #[test]
+    fn handle_ctrl_c() {
+        let tui = &mut Tui::new("./testdata/support_bundle", "vm-00");
+        tui.session.start_search().unwrap();
+        tui.current_screen = Screen::FilterBuilder;
+
+        let key_event = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.exit);
+        assert!(!tui.session.is_searching());
+    }
+
+    #[test]
+    fn handle_key_events_on_warnings() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+
+        let key_event = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Warnings);
+
+        let key_event = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+    }
+
+    #[test]
+    fn handle_key_events_on_split() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        tui.entries_offset = vec![
+            sbsearch::Entry {
+                level: sbsearch::Level::Info,
+                path: String::from("sb_path/logs/a.log"),
+                content: String::from("first\nsecond\nthird\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            };
+            2
+        ];
+        let press = |tui: &mut Tui, code: KeyCode| {
+            let key_event = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        // the logs keep the focus once the pane shows
+        press(tui, KeyCode::Char('D'));
+        assert!(tui.show_split);
+        press(tui, KeyCode::Char('j'));
+        assert_eq!(tui.nav_state.selected(), Some(1));
+
+        // Tab goes through the file tree, the logs and the pane
+        press(tui, KeyCode::Tab);
+        assert_eq!(tui.focus, Focus::Detail);
+        press(tui, KeyCode::Char('j'));
+        assert_eq!(tui.detail_scroll, (1, 0));
+        assert_eq!(tui.nav_state.selected(), Some(1));
+        press(tui, KeyCode::Tab);
+        assert_eq!(tui.focus, Focus::Logs);
+        tui.show_files = true;
+        press(tui, KeyCode::Tab);
+        assert_eq!(tui.focus, Focus::Detail);
+        press(tui, KeyCode::Tab);
+        assert_eq!(tui.focus, Focus::Files);
+        press(tui, KeyCode::Tab);
+        assert_eq!(tui.focus, Focus::Logs);
+
+        // hiding the pane gives the focus back to the logs
+        press(tui, KeyCode::Tab);
+        press(tui, KeyCode::Char('D'));
+        assert!(!tui.show_split);
+        assert_eq!(tui.focus, Focus::Logs);
+    }
+
+    #[test]
+    fn handle_key_events_on_columns() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        let key_event = KeyEvent::new(KeyCode::Char('T'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.show_columns);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.show_columns);
+
+        let key_event = KeyEvent::new(KeyCode::Char('S'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.show_source);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.show_source);
+
+        let key_event = KeyEvent::new(KeyCode::Char('L'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.show_logfmt);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.show_logfmt);
+    }
+
+    #[test]
+    fn handle_key_events_on_time_display() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        let entry = |line_number: u64, timestamp: Option<&str>| sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from("/path/to/log"),
+            content: String::from("line"),
+            timestamp: timestamp.map(|t| t.parse().unwrap()),
+            line_number,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        tui.entries_offset = vec![entry(1, Some("2025-12-30T21:48:32Z")), entry(2, None)];
+        let press = |tui: &mut Tui, code: KeyCode| {
+            let key_event = KeyEvent::new(code, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        // Z switches to local time and back
+        let offset = AssumeTz::Fixed(chrono::FixedOffset::east_opt(3600).unwrap());
+        tui.display_tz = offset;
+        press(tui, KeyCode::Char('Z'));
+        assert_eq!(tui.display_tz, AssumeTz::Local);
+        press(tui, KeyCode::Char('Z'));
+        assert_eq!(tui.display_tz, offset);
+        tui.display_tz = AssumeTz::Local;
+        press(tui, KeyCode::Char('Z'));
+        assert_eq!(tui.display_tz, AssumeTz::Utc);
+
+        // a counts the timestamps from the selected entry, shown in columns
+        press(tui, KeyCode::Char('a'));
+        assert_eq!(tui.anchor_time(), tui.entries_offset[0].timestamp);
+        assert!(tui.show_columns);
+        press(tui, KeyCode::Char('j'));
+        press(tui, KeyCode::Char('a'));
+        assert!(tui.notice.is_some());
+        assert_eq!(tui.anchor_time(), tui.entries_offset[0].timestamp);
+        press(tui, KeyCode::Char('k'));
+        press(tui, KeyCode::Char('a'));
+        assert!(tui.anchor.is_none());
+    }
+
+    #[test]
+    fn handle_key_events_on_wrap() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        tui.entries_offset = vec![sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from("/path/to/log1"),
+            content: "x".repeat(20) + "\nsecond line",
+            timestamp: None,
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }];
+
+        // wrapped entries don't scroll sideways
+        let key_event = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.horizontal_scroll, 0);
+
+        let key_event = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(!tui.wrap);
+        let key_event = KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.horizontal_scroll, 8);
+
+        // scrolling stops at the end of the longest entry, "(+1 lines)" included
+        for _ in 0..10 {
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        assert_eq!(tui.horizontal_scroll, 30);
+        let key_event = KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.horizontal_scroll, 22);
+
+        let key_event = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.wrap);
+        assert_eq!(tui.horizontal_scroll, 0);
+    }
+
+    #[test]
+    fn handle_key_events_on_help() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+
+        let key_event = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Help);
+
+        // scrolling stops at the last line
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        for _ in 0..1000 {
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        assert_eq!(tui.help_scroll as usize, help::line_count() - 1);
+        let key_event = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.help_scroll, 0);
+
+        let key_event = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+    }
+
+    #[test]
+    fn handle_key_events_on_detail() {
+        let tui = &mut Tui::new("sb", "pvc_name");
+
+        // there's nothing to show without entries
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+
+        tui.entries_offset = vec![sbsearch::Entry {
+            level: sbsearch::Level::Error,
+            path: String::from("sb/logs/default/vm-00/compute.log"),
+            content: String::from("{\"msg\":\"vm-00 failed\",\"retries\":3}\ncaused by: timeout"),
+            timestamp: None,
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }];
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Detail);
+
+        // scrolling stops at the last line and the end of the longest one
+        let key_event = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        for _ in 0..100 {
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        let lines =
+            render::detail_lines(&tui.entries_offset[0], tui.display_tz, None, tui.json_depth);
+        assert_eq!(tui.detail_scroll.0 as usize, lines.len() - 1);
+        let key_event = KeyEvent::new(KeyCode::Right, KeyModifiers::NONE);
+        for _ in 0..100 {
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        let width = lines.iter().map(|line| line.width()).max().unwrap();
+        assert_eq!(tui.detail_scroll.1 as usize, width - 1);
+        let key_event = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.detail_scroll, (0, 0));
+
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+    }
+
+    #[test]
+    fn handle_key_events_on_json_detail() {
+        let tui = &mut Tui::new("sb_path", "pvc_name");
+        tui.entries_offset = vec![sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from("/path/to/etcd.log"),
+            content: String::from(
+                r#"{"level":"info","msg":"slow request","took":{"ms":120,"ops":[1,2]}}"#,
+            ),
+            timestamp: None,
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }];
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Detail);
+        let text = |tui: &Tui| {
+            render::detail_lines(&tui.entries_offset[0], tui.display_tz, None, tui.json_depth)
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert!(text(tui).contains(&String::from(r#"  "msg": "slow request","#)));
+        assert!(text(tui).contains(&String::from(r#"      1,"#)));
+
+        // the nesting folds one level at a time, and unfolds back
+        let fold = KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(fold));
+        assert_eq!(tui.json_depth, 2);
+        assert!(text(tui).contains(&String::from(r#"    "ops": […] 2 items"#)));
+        for _ in 0..5 {
+            handle_key_event(tui, Event::Key(fold));
+        }
+        assert_eq!(tui.json_depth, 0);
+        assert!(text(tui).contains(&String::from("{…} 3 fields")));
+        let unfold = KeyEvent::new(KeyCode::Char('+'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(unfold));
+        assert_eq!(tui.json_depth, 1);
+        handle_key_event(tui, Event::Key(unfold));
+        handle_key_event(tui, Event::Key(unfold));
+        assert_eq!(tui.json_depth, usize::MAX);
+    }
+
+    #[test]
+    fn handle_key_events_on_source() {
+        let tui = &mut Tui::new("testdata/support_bundle", "containerd");
+        let entry = sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from(
+                "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+            ),
+            content: String::from("containerd started\n"),
+            timestamp: None,
+            line_number: 100,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        tui.entries_offset = vec![
+            entry.clone(),
+            sbsearch::Entry {
+                path: String::from("testdata/support_bundle/noexist.log"),
+                ..entry
+            },
+        ];
+
+        let key_event = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Source);
+        assert_eq!(tui.source_view.as_ref().unwrap().first, 99);
+
+        let key_event = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.source_view.as_ref().unwrap().top, 0);
+        let key_event = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.source_view.as_ref().unwrap().top, 89);
+
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(tui.source_view.is_none());
+
+        // a missing file is pointed out
+        tui.nav_next_line();
+        let key_event = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Error);
+        assert!(tui.source_view.is_none());
+
+        // it can't be retried, only closed
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Error);
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(tui.error.is_none());
+    }
+
+    #[test]
+    fn handle_key_events_on_filter_builder() {
+        let tui = &mut Tui::new("sb", "pvc_name");
+        tui.session = Session::new("sb", "pvc_name").with_entries(vec![sbsearch::Entry {
+            level: sbsearch::Level::Error,
+            path: String::from("sb/logs/default/vm-00/compute.log"),
+            content: String::from("This is an error log entry."),
+            timestamp: Some(chrono::Utc::now()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }]);
+
+        let key_event = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::FilterBuilder);
+
+        // check the 'error' level, below 'fatal'
+        tui.filter_form.focus = 1;
+        let key_event = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+
+        // type an invalid 'since' timestamp
+        tui.filter_form.focus = tui.filter_form.fields.len() - 3;
+        let key_event = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::FilterBuilder);
+        assert!(tui.filter_form.error.is_some());
+
+        let key_event = KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert_eq!(tui.session.query().to_string(), "level=error");
+        assert!(tui.page_reload);
+    }
+
+    #[test]
+    fn handle_key_events_on_pin() {
+        let tui = &mut Tui::new("sb", "pvc_name");
+        tui.entries_offset = vec![
+            sbsearch::Entry {
+                level: sbsearch::Level::Error,
+                path: String::from("sb/logs/default/vm-00/compute.log"),
+                content: String::from("This is an error log entry."),
+                timestamp: Some(chrono::Utc::now()),
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            },
+            sbsearch::Entry {
+                level: sbsearch::Level::Info,
+                path: String::from("sb/logs/default/vm-00/compute.log"),
+                content: String::from("This is an info log entry."),
+                timestamp: Some(chrono::Utc::now()),
+                line_number: 2,
+                fields: Default::default(),
+                duplicates: 0,
+            },
+        ];
+
+        let key_event = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let key_event = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        let key_event = KeyEvent::new(KeyCode::Char('P'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.pinned.len(), 2);
+
+        // pinned entries outlive the page they were pinned from
+        tui.entries_offset.clear();
+        tui.apply_filter_form();
+        assert_eq!(tui.pinned.len(), 2);
+
+        // pinning a pinned entry unpins it
+        tui.entries_offset = tui.pinned.clone();
+        let key_event = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.pinned.len(), 1);
+        assert_eq!(tui.pinned[0].line_number, 1);
+    }
+
+    #[test]
+    fn handle_key_events_on_tabs() {
+        let tui = &mut Tui::new("sb", "pvc_name");
+        tui.session.filter("level=error".parse().unwrap());
+
+        // open a tab for another keyword
+        let key_event = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::NewTab);
+        for c in "vm-00".chars() {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        }
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        // the progress is shown while the new tab searches
+        assert_eq!(tui.current_screen, Screen::Progress);
+        assert!(tui.session.is_searching());
+        tui.current_screen = Screen::Main;
+        assert_eq!(tui.tab_titles(), vec!["pvc_name", "vm-00"]);
+        assert_eq!(tui.session.keyword(), "vm-00");
+        assert!(tui.session.query().is_empty());
+        assert!(tui.page_reload);
+
+        // each tab keeps its own filter
+        let key_event = KeyEvent::new(KeyCode::Char('1'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.session.keyword(), "pvc_name");
+        assert_eq!(tui.session.query().to_string(), "level=error");
+
+        // switching to a missing tab is ignored
+        let key_event = KeyEvent::new(KeyCode::Char('5'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.active_tab, 0);
+
+        // close the first tab, leaving the second one
+        let key_event = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.tab_titles(), vec!["vm-00"]);
+        assert_eq!(tui.session.keyword(), "vm-00");
+
+        // the last tab can't be closed
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.tab_titles(), vec!["vm-00"]);
+    }
+
+    #[test]
+    fn handle_key_events_on_open_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sbsearch_saved.jsonl");
+        let saved = Session::new("sb", "vm-00")
+            .with_export_format(sbsearch::session::ExportFormat::Json)
+            .with_entries(vec![sbsearch::Entry {
+                level: sbsearch::Level::Error,
+                path: String::from("sb/logs/default/vm-00/compute.log"),
+                content: String::from("vm-00 failed\n"),
+                timestamp: None,
+                line_number: 1,
+                fields: Default::default(),
+                duplicates: 0,
+            }]);
+        saved.export(&path).unwrap();
+
+        let tui = &mut Tui::new("sb", "pvc_name");
+        let open = |tui: &mut Tui, path: &str| {
+            let key_event = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+            assert_eq!(tui.current_screen, Screen::OpenSaved);
+            for c in path.chars() {
+                let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+                handle_key_event(tui, Event::Key(key_event));
+            }
+            let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+            handle_key_event(tui, Event::Key(key_event));
+        };
+
+        // the saved entries open in a new tab
+        open(tui, path.to_str().unwrap());
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert_eq!(tui.tab_titles(), vec!["pvc_name", "vm-00"]);
+        assert!(tui.session.is_replay());
+        assert_eq!(tui.session.search(0, 10).unwrap().len(), 1);
+
+        // a missing file is pointed out, and can be retried with another one
+        open(tui, "no_such_file.jsonl");
+        assert_eq!(tui.tabs.len(), 2);
+        assert_eq!(tui.current_screen, Screen::Error);
+        assert!(
+            tui.error
+                .as_ref()
+                .is_some_and(|e| e.text.contains("no_such_file.jsonl"))
+        );
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::OpenSaved);
+        assert_eq!(tui.open_input.value(), "no_such_file.jsonl");
+    }
+
     #[test]
     fn handle_key_events_on_save() {
         let tui = &mut Tui::new("sb_path", "pvc_name");
         tui.current_screen = Screen::Main;
         tui.last_saved_filename = String::new();
 
-        // show confirm save search results
+        // show the save dialog, with the default file name
         let key_event = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE);
         let event = Event::Key(key_event);
         handle_key_event(tui, event);
         assert_eq!(tui.current_screen, Screen::ConfirmSave);
+        assert!(tui.save_input.value().starts_with("sbsearch_"));
+
+        // edit the file name
+        let key_event = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert!(tui.save_input.value().ends_with(".logx"));
+
+        // pick what's saved
+        let key_event = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.save_scope, SaveScope::Page);
+        let key_event = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.save_scope, SaveScope::Bookmarked);
+
+        // a failed save shows its error, and goes back to the file name
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("saved.log");
+        let path = path.to_str().unwrap();
+        tui.save_input = tui_input::Input::new(String::from(path));
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::Error);
+        let text = &tui.error.as_ref().unwrap().text;
+        assert!(text.contains(path) && text.contains("os error"), "{}", text);
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        handle_key_event(tui, Event::Key(key_event));
+        assert_eq!(tui.current_screen, Screen::ConfirmSave);
+        assert_eq!(tui.save_input.value(), path);
+        assert!(tui.error.is_none());
 
         // exit save popup
-        let key_event = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
         let event = Event::Key(key_event);
         handle_key_event(tui, event);
         assert_eq!(tui.current_screen, Screen::Main);
@@ -0,0 +1,134 @@
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use tempfile::NamedTempFile;
+
+/// The program the files are opened with when neither `$EDITOR` nor `$PAGER`
+/// is set.
+const DEFAULT_PAGER: &str = "less";
+
+/// The editors that go to a line with `--goto <path>:<line>` instead of
+/// `+<line>`, i.e. VS Code and its builds.
+const GOTO_EDITORS: &[&str] = &["code", "code-insiders", "codium"];
+
+/// A file of the support bundle on disk, to hand over to another program.
+/// Files inside the node archives are extracted to a temporary file, removed
+/// once it's dropped.
+#[derive(Debug)]
+pub struct ExtractedFile {
+    path: PathBuf,
+    _temp: Option<NamedTempFile>,
+}
+
+impl ExtractedFile {
+    pub fn new(path: &str) -> Result<Self, Box<dyn Error>> {
+        let path = Path::new(path);
+        if path.is_file() {
+            return Ok(ExtractedFile {
+                path: path.to_path_buf(),
+                _temp: None,
+            });
+        }
+
+        // keep the name of the file, so that the editor picks its syntax
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut temp = tempfile::Builder::new()
+            .prefix("sbsearch_")
+            .suffix(&format!("_{}", name))
+            .tempfile()?;
+        temp.write_all(&sbsearch::read_source(path)?)?;
+        temp.flush()?;
+        Ok(ExtractedFile {
+            path: temp.path().to_path_buf(),
+            _temp: Some(temp),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Builds the command opening `path` at `line` with `editor`, or else
+/// `pager`, which may come with arguments of their own, e.g. `vim -R`. Most
+/// editors and pagers, like vi, nano, emacs and less, go to the line given as
+/// `+<line>`, and VS Code to the one given with `--goto`.
+pub fn command(editor: Option<&str>, pager: Option<&str>, path: &Path, line: u64) -> Command {
+    let program = [editor, pager]
+        .into_iter()
+        .flatten()
+        .find(|program| !program.trim().is_empty())
+        .unwrap_or(DEFAULT_PAGER);
+    let mut words = program.split_whitespace();
+    let name = words.next().unwrap_or(DEFAULT_PAGER);
+    let mut command = Command::new(name);
+    command.args(words);
+    let base_name = Path::new(name).file_stem().unwrap_or_default();
+    match GOTO_EDITORS.iter().any(|editor| base_name == *editor) {
+        true => command
+            .arg("--goto")
+            .arg(format!("{}:{}", path.display(), line)),
+        false => command.arg(format!("+{}", line)).arg(path),
+    };
+    command
+}
+
+/// Opens the file at `line` in `$EDITOR`, or else `$PAGER`, and waits for it
+/// to exit.
+pub fn open(file: &ExtractedFile, line: u64) -> std::io::Result<ExitStatus> {
+    let editor = std::env::var("EDITOR").ok();
+    let pager = std::env::var("PAGER").ok();
+    command(editor.as_deref(), pager.as_deref(), file.path(), line).status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let path = Path::new("sb/logs/a.log");
+        let args = |command: &Command| {
+            let mut args = vec![command.get_program().to_string_lossy().into_owned()];
+            args.extend(
+                command
+                    .get_args()
+                    .map(|arg| arg.to_string_lossy().into_owned()),
+            );
+            args
+        };
+
+        let opened = command(Some("vim -R"), Some("more"), path, 42);
+        assert_eq!(args(&opened), vec!["vim", "-R", "+42", "sb/logs/a.log"]);
+        let opened = command(Some("/usr/bin/code -w"), None, path, 42);
+        assert_eq!(
+            args(&opened),
+            vec!["/usr/bin/code", "-w", "--goto", "sb/logs/a.log:42"]
+        );
+        let opened = command(Some(" "), Some("more"), path, 42);
+        assert_eq!(args(&opened), vec!["more", "+42", "sb/logs/a.log"]);
+        let opened = command(None, None, path, 1);
+        assert_eq!(args(&opened), vec!["less", "+1", "sb/logs/a.log"]);
+    }
+
+    #[test]
+    fn test_extracted_file() {
+        let path = "testdata/support_bundle/metadata.yaml";
+        let file = ExtractedFile::new(path).unwrap();
+        assert_eq!(file.path(), Path::new(path));
+
+        let path = "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log";
+        let file = ExtractedFile::new(path).unwrap();
+        let temp = file.path().to_path_buf();
+        assert!(temp.to_string_lossy().ends_with("_containerd.log"));
+        assert_eq!(
+            std::fs::read(&temp).unwrap(),
+            sbsearch::read_source(Path::new(path)).unwrap()
+        );
+
+        // the extracted file is removed once it's dropped
+        drop(file);
+        assert!(!temp.exists());
+    }
+}
@@ -0,0 +1,167 @@
+use ratatui::widgets::ListState;
+
+/// A row of the file tree: a directory, a file, or the row showing all the
+/// files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRow {
+    pub depth: usize,
+    pub name: String,
+    /// The path the entries are narrowed down to once the row is picked, or
+    /// none for all the files.
+    pub path: Option<String>,
+    /// The number of entries in the file, or in the files under the
+    /// directory.
+    pub count: usize,
+}
+
+/// The files of the support bundle that contain matches, as a tree of
+/// directories with their number of entries, shown in the sidebar.
+#[derive(Debug, Default)]
+pub struct FileTree {
+    pub rows: Vec<FileRow>,
+    pub state: ListState,
+    /// The keyword and the number of entries the tree was built from, to tell
+    /// when it's out of date.
+    built_from: (String, usize),
+}
+
+impl FileTree {
+    /// Builds the tree from the number of entries per path, sorted by path.
+    /// The paths are shown relative to `root_dir`.
+    pub fn new(counts: &[(String, usize)], root_dir: &str) -> Self {
+        let mut rows = vec![FileRow {
+            depth: 0,
+            name: String::from("All files"),
+            path: None,
+            count: counts.iter().map(|(_, count)| count).sum(),
+        }];
+        // the directories of the previous path, with their rows
+        let mut open: Vec<(&str, usize)> = Vec::new();
+        for (path, count) in counts {
+            let relative = path
+                .strip_prefix(root_dir)
+                .unwrap_or(path)
+                .trim_start_matches('/');
+            let base = path.len() - relative.len();
+            let parts: Vec<&str> = relative.split('/').collect();
+            let Some((name, dirs)) = parts.split_last() else {
+                continue;
+            };
+
+            let shared = open
+                .iter()
+                .zip(dirs)
+                .take_while(|((open, _), dir)| open == *dir)
+                .count();
+            open.truncate(shared);
+            for (depth, dir) in dirs.iter().enumerate().skip(shared) {
+                let end = base + dirs[..=depth].iter().map(|d| d.len() + 1).sum::<usize>() - 1;
+                rows.push(FileRow {
+                    depth,
+                    name: format!("{}/", dir),
+                    path: Some(String::from(&path[..end])),
+                    count: 0,
+                });
+                open.push((dir, rows.len() - 1));
+            }
+            for (_, row) in &open {
+                rows[*row].count += count;
+            }
+            rows.push(FileRow {
+                depth: dirs.len(),
+                name: String::from(*name),
+                path: Some(path.clone()),
+                count: *count,
+            });
+        }
+        FileTree {
+            rows,
+            state: ListState::default().with_selected(Some(0)),
+            built_from: (String::new(), 0),
+        }
+    }
+
+    /// Rebuilds the tree from the entries of `session` if they changed since
+    /// it was built, e.g. as the search finds more, keeping the selected row.
+    pub fn refresh(&mut self, session: &sbsearch::session::Session) {
+        let built_from = (String::from(session.keyword()), session.entries().len());
+        if self.built_from == built_from {
+            return;
+        }
+        let selected = self.selected().and_then(|row| row.path.clone());
+        *self = FileTree::new(&session.file_counts(), session.root_dir());
+        self.built_from = built_from;
+        if let Some(index) = self.rows.iter().position(|row| row.path == selected) {
+            self.state.select(Some(index));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&FileRow> {
+        self.state.selected().and_then(|index| self.rows.get(index))
+    }
+
+    pub fn select_next(&mut self) {
+        let index = self.state.selected().map_or(0, |index| index + 1);
+        self.state
+            .select(Some(index.min(self.rows.len().saturating_sub(1))));
+    }
+
+    pub fn select_prev(&mut self) {
+        let index = self.state.selected().unwrap_or(0).saturating_sub(1);
+        self.state.select(Some(index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let counts = vec![
+            (String::from("sb/logs/default/a/0.log"), 2),
+            (String::from("sb/logs/default/b/0.log"), 3),
+            (String::from("sb/logs/kube-system/c/0.log"), 4),
+            (String::from("sb/nodes/n.zip/n/logs/kubelet.log"), 1),
+        ];
+        let tree = FileTree::new(&counts, "sb");
+        let rows: Vec<(usize, &str, Option<&str>, usize)> = tree
+            .rows
+            .iter()
+            .map(|row| (row.depth, row.name.as_str(), row.path.as_deref(), row.count))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0, "All files", None, 10),
+                (0, "logs/", Some("sb/logs"), 9),
+                (1, "default/", Some("sb/logs/default"), 5),
+                (2, "a/", Some("sb/logs/default/a"), 2),
+                (3, "0.log", Some("sb/logs/default/a/0.log"), 2),
+                (2, "b/", Some("sb/logs/default/b"), 3),
+                (3, "0.log", Some("sb/logs/default/b/0.log"), 3),
+                (1, "kube-system/", Some("sb/logs/kube-system"), 4),
+                (2, "c/", Some("sb/logs/kube-system/c"), 4),
+                (3, "0.log", Some("sb/logs/kube-system/c/0.log"), 4),
+                (0, "nodes/", Some("sb/nodes"), 1),
+                (1, "n.zip/", Some("sb/nodes/n.zip"), 1),
+                (2, "n/", Some("sb/nodes/n.zip/n"), 1),
+                (3, "logs/", Some("sb/nodes/n.zip/n/logs"), 1),
+                (
+                    4,
+                    "kubelet.log",
+                    Some("sb/nodes/n.zip/n/logs/kubelet.log"),
+                    1
+                ),
+            ]
+        );
+
+        let mut tree = tree;
+        tree.select_prev();
+        assert_eq!(tree.state.selected(), Some(0));
+        for _ in 0..20 {
+            tree.select_next();
+        }
+        assert_eq!(tree.selected().unwrap().name, "kubelet.log");
+    }
+}
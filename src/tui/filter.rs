@@ -0,0 +1,239 @@
+use sbsearch::query::{Query, Source};
+use sbsearch::{Entry, Level};
+use tui_input::Input;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Group {
+    Level,
+    Namespace,
+    Pod,
+    Node,
+}
+
+impl Group {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Group::Level => "Levels",
+            Group::Namespace => "Namespaces",
+            Group::Pod => "Pods",
+            Group::Node => "Nodes",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Field {
+    Checkbox {
+        group: Group,
+        value: String,
+        checked: bool,
+    },
+    Since(Input),
+    Until(Input),
+    /// Space-separated `<field>~<pattern>` terms.
+    Patterns(Input),
+}
+
+/// The state of the filter builder screen. Its fields compile to a query.
+#[derive(Debug, Default)]
+pub struct FilterForm {
+    pub fields: Vec<Field>,
+    pub focus: usize,
+    pub error: Option<String>,
+}
+
+impl FilterForm {
+    /// Builds the form with the levels, namespaces, pods and nodes found in the
+    /// entries, pre-filled from the current query.
+    pub fn new(entries: &[Entry], root_dir: &str, query: &Query) -> Self {
+        let mut namespaces: Vec<String> = Vec::new();
+        let mut pods: Vec<String> = Vec::new();
+        let mut nodes: Vec<String> = Vec::new();
+        for entry in entries {
+            let source = Source::from_path(&entry.path, root_dir);
+            for (values, value) in [
+                (&mut namespaces, source.namespace),
+                (&mut pods, source.pod),
+                (&mut nodes, source.node),
+            ] {
+                if let Some(value) = value
+                    && !values.contains(&value)
+                {
+                    values.push(value);
+                }
+            }
+        }
+        namespaces.sort();
+        pods.sort();
+        nodes.sort();
+
+        let mut fields = Vec::new();
+        let levels: Vec<String> = Level::ALL.iter().map(Level::to_string).collect();
+        let selected_levels: Vec<String> = query.levels.iter().map(Level::to_string).collect();
+        for (group, values, selected) in [
+            (Group::Level, &levels, &selected_levels),
+            (Group::Namespace, &namespaces, &query.namespaces),
+            (Group::Pod, &pods, &query.pods),
+            (Group::Node, &nodes, &query.nodes),
+        ] {
+            for value in values {
+                fields.push(Field::Checkbox {
+                    group,
+                    value: value.clone(),
+                    checked: selected.contains(value),
+                });
+            }
+        }
+
+        let timestamp = |t: Option<chrono::DateTime<chrono::Utc>>| {
+            Input::default().with_value(t.map(|t| t.to_rfc3339()).unwrap_or_default())
+        };
+        fields.push(Field::Since(timestamp(query.since)));
+        fields.push(Field::Until(timestamp(query.until)));
+        let patterns: Vec<String> = query.patterns.iter().map(|p| p.to_string()).collect();
+        fields.push(Field::Patterns(
+            Input::default().with_value(patterns.join(" ")),
+        ));
+
+        FilterForm {
+            fields,
+            focus: 0,
+            error: None,
+        }
+    }
+
+    pub fn focus_next(&mut self) {
+        if self.focus + 1 < self.fields.len() {
+            self.focus += 1;
+        }
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.focus = self.focus.saturating_sub(1);
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(Field::Checkbox { checked, .. }) = self.fields.get_mut(self.focus) {
+            *checked = !*checked;
+        }
+    }
+
+    pub fn focused_input(&mut self) -> Option<&mut Input> {
+        match self.fields.get_mut(self.focus) {
+            Some(Field::Since(input))
+            | Some(Field::Until(input))
+            | Some(Field::Patterns(input)) => Some(input),
+            _ => None,
+        }
+    }
+
+    /// Compiles the form into its query string.
+    pub fn compile(&self) -> String {
+        let mut terms = Vec::new();
+        for group in [Group::Level, Group::Namespace, Group::Pod, Group::Node] {
+            let values: Vec<&str> = self
+                .fields
+                .iter()
+                .filter_map(|field| match field {
+                    Field::Checkbox {
+                        group: g,
+                        value,
+                        checked: true,
+                    } if *g == group => Some(value.as_str()),
+                    _ => None,
+                })
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+
+            let key = match group {
+                Group::Level => "level",
+                Group::Namespace => "ns",
+                Group::Pod => "pod",
+                Group::Node => "node",
+            };
+            terms.push(format!("{}={}", key, values.join(",")));
+        }
+
+        for field in &self.fields {
+            match field {
+                Field::Since(input) if !input.value().trim().is_empty() => {
+                    terms.push(format!("since={}", input.value().trim()))
+                }
+                Field::Until(input) if !input.value().trim().is_empty() => {
+                    terms.push(format!("until={}", input.value().trim()))
+                }
+                Field::Patterns(input) if !input.value().trim().is_empty() => {
+                    terms.push(String::from(input.value().trim()))
+                }
+                _ => {}
+            }
+        }
+        terms.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> Entry {
+        Entry {
+            level: Level::Info,
+            path: String::from(path),
+            content: String::new(),
+            timestamp: None,
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let entries = vec![
+            entry("sb/logs/ns-b/pod-1/c.log"),
+            entry("sb/logs/ns-a/pod-2/c.log"),
+            entry("sb/nodes/n1.zip/n1/logs/kubelet.log"),
+        ];
+        let query = "level=warning ns=ns-b".parse::<Query>().unwrap();
+        let mut form = FilterForm::new(&entries, "sb", &query);
+        assert_eq!(form.compile(), "level=warn ns=ns-b");
+
+        let query = "ns=ns-b msg~timeout".parse::<Query>().unwrap();
+        let form_with_patterns = FilterForm::new(&entries, "sb", &query);
+        assert_eq!(form_with_patterns.compile(), r#"ns=ns-b msg~"timeout""#);
+
+        // levels come first, most severe first, followed by the sorted
+        // namespaces
+        form.focus_next();
+        form.focus_next();
+        form.focus_next();
+        form.toggle();
+        form.focus_next();
+        form.focus_next();
+        form.focus_next();
+        form.toggle();
+        assert_eq!(form.compile(), "level=warn,info ns=ns-a,ns-b");
+
+        form.focus = form.fields.len() - 3;
+        let input = form.focused_input().unwrap();
+        *input = input
+            .clone()
+            .with_value(String::from("2025-12-30T21:50:00Z"));
+        assert_eq!(
+            form.compile(),
+            "level=warn,info ns=ns-a,ns-b since=2025-12-30T21:50:00Z"
+        );
+        assert!(form.compile().parse::<Query>().is_ok());
+
+        form.focus = form.fields.len() - 1;
+        let input = form.focused_input().unwrap();
+        *input = input
+            .clone()
+            .with_value(String::from(r#"msg~"attach timeout""#));
+        let query = form.compile().parse::<Query>().unwrap();
+        assert_eq!(query.patterns[0].pattern.as_str(), "attach timeout");
+    }
+}
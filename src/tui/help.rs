@@ -0,0 +1,256 @@
+/// The keybindings listed by the help screen, grouped by what they act on.
+pub const KEYMAP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Lines",
+        &[
+            (
+                "Up/Down, k/j",
+                "Move up/down by one line, through entries taller than the screen first",
+            ),
+            ("Ctrl-d/Ctrl-u", "Move down/up by half a screen"),
+            ("Ctrl-f/Ctrl-b", "Move down/up by a screen"),
+            ("g", "Go to the first line of the page"),
+            ("G", "Go to the last line of the page"),
+            ("Enter", "Show the whole of the selected entry"),
+            (
+                "v",
+                "Show the file of the selected entry, scrolled to the entry",
+            ),
+            (
+                "X",
+                "Expand the selected entry with the lines around it, or collapse it",
+            ),
+            (
+                "e",
+                "Open the file of the selected entry in $EDITOR or $PAGER",
+            ),
+            (
+                "y",
+                "Copy the content of the selected entry to the clipboard",
+            ),
+            (
+                "Y",
+                "Copy the selected entry with its path:line to the clipboard",
+            ),
+            ("p/P", "Pin or unpin the selected entry"),
+            ("M", "Bookmark the selected entry, or remove its bookmark"),
+            ("[/]", "Go to the previous/next bookmark, on any page"),
+            ("'", "List the bookmarks"),
+            ("d", "Diff the selected entry with the last bookmarked one"),
+            ("z", "Wrap the entries, or show each on one line"),
+            (
+                "T",
+                "Show or hide the timestamp and level columns before the entries",
+            ),
+            (
+                "S",
+                "Show or hide the namespace/pod/file of the entries before them",
+            ),
+            (
+                "L",
+                "Highlight the key=value pairs of logfmt entries, or stop",
+            ),
+            ("Z", "Show the timestamps in local time, or back"),
+            (
+                "a",
+                "Show the timestamps relative to the selected entry, or stop",
+            ),
+            ("h/l", "Scroll the entries shown on one line left/right"),
+        ],
+    ),
+    (
+        "Pages",
+        &[
+            ("Left/Right", "Move to the previous/next page"),
+            (
+                "#<count>",
+                "Repeat the next move, e.g. #15j, #3Right, or #178G for the entry 178",
+            ),
+            ("0", "Go to the first page"),
+            ("9", "Go to the last page"),
+            ("!", "Go to the first error in time, on any page"),
+            (":", "Go to the entry with this number, on any page"),
+        ],
+    ),
+    (
+        "Search and Filter",
+        &[
+            (
+                "/",
+                "Search the entries, hiding the ones without the search",
+            ),
+            ("Enter", "Run the search, while typing it"),
+            ("Esc", "Cancel the search, while typing it"),
+            (
+                "Up/Down",
+                "Recall the previous/next search, while typing one",
+            ),
+            (
+                "Ctrl-r",
+                "Switch the search between text and regex, while typing it",
+            ),
+            ("c", "Clear the search"),
+            (
+                "m",
+                "Switch between hiding and highlighting the entries without the search",
+            ),
+            ("f", "Open the filter builder"),
+            (
+                "E/W/I",
+                "Only show the errors, warnings or info entries and above",
+            ),
+            ("A", "Show the entries of all levels"),
+        ],
+    ),
+    (
+        "Tabs",
+        &[
+            ("n", "Open a new tab searching for another keyword"),
+            ("o", "Open a saved results file in a new tab"),
+            ("x", "Close the current tab"),
+            (
+                "C",
+                "Compare the tab with another keyword, side by side by time",
+            ),
+            (
+                "1-8",
+                "Switch to the tab with this number, unless a move follows",
+            ),
+        ],
+    ),
+    (
+        "Screens",
+        &[
+            ("t", "Show the top suspects"),
+            (
+                "w",
+                "Show the files that were skipped or only partly searched",
+            ),
+            ("r", "Show or hide the matches per minute panel"),
+            (
+                "R",
+                "Search the support bundle again, keeping the page and the filters",
+            ),
+            (
+                "b",
+                "Show or hide the tree of the files with matches, and their counts",
+            ),
+            (
+                "D",
+                "Show or hide the selected entry in a pane next to the logs",
+            ),
+            (
+                "Tab",
+                "Move the focus between the file tree, the logs and the entry pane",
+            ),
+            (
+                "s",
+                "Save the filtered entries, everything, the page or the bookmarks to a file",
+            ),
+            ("?", "Show this help"),
+        ],
+    ),
+    (
+        "Detail View",
+        &[
+            ("Up/Down, k/j", "Scroll up/down"),
+            ("Left/Right, h/l", "Scroll left/right"),
+            ("g", "Go back to the top"),
+            ("-/+", "Fold/unfold the JSON of the entry by one level"),
+            ("v, e, y, Y", "Like on the logs"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Save Dialog",
+        &[
+            ("Enter", "Save to the file, asking before overwriting it"),
+            ("Tab/Shift-Tab", "Change which entries are saved"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Error Popup",
+        &[
+            (
+                "r",
+                "Go back to the file name to try again, after a failed save",
+            ),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Bookmarks",
+        &[
+            ("Up/Down, k/j", "Move up/down by one bookmark"),
+            ("Enter", "Go to the bookmark"),
+            ("M", "Remove the bookmark"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Diff View",
+        &[
+            ("Up/Down, k/j", "Scroll both entries up/down"),
+            ("g", "Go back to the top"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Compare View",
+        &[
+            ("Up/Down, k/j", "Move up/down by one row"),
+            ("g/G", "Go to the first/last row"),
+            ("Enter", "Go to the entry of the row in the logs of its tab"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "File Tree",
+        &[
+            ("Up/Down, k/j", "Move up/down by one file or directory"),
+            (
+                "Enter",
+                "Only show the entries of the file, or the files under the directory",
+            ),
+            ("Esc, Tab", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Entry Pane",
+        &[
+            ("Up/Down, k/j", "Scroll up/down"),
+            ("Left/Right, h/l", "Scroll left/right"),
+            ("g", "Go back to the top"),
+            ("-/+", "Fold/unfold the JSON of the entry by one level"),
+            ("Esc, Tab", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Source View",
+        &[
+            ("Up/Down, k/j", "Scroll up/down by one line"),
+            ("Left/Right", "Scroll up/down by one page"),
+            ("g/G", "Go to the start/end of the file"),
+            ("e", "Go back to the entry"),
+            ("Esc", "Go back to the logs"),
+        ],
+    ),
+    (
+        "Others",
+        &[
+            (
+                "Esc",
+                "Cancel the running search, keeping the entries found so far",
+            ),
+            ("q", "Quit"),
+            ("Ctrl-C", "Cancel the running search and quit"),
+        ],
+    ),
+];
+
+/// Returns the number of lines of the help screen: a heading and a line per
+/// key for each group, with a blank line between the groups.
+pub fn line_count() -> usize {
+    KEYMAP.iter().map(|(_, keys)| keys.len() + 2).sum::<usize>() - 1
+}
@@ -0,0 +1,143 @@
+use log::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// The number of searches kept in the history, the oldest dropped first.
+const MAX_HISTORY: usize = 100;
+
+/// The searches submitted in the search box, oldest first, recalled with
+/// Up/Down like a shell history, and kept in a file across runs.
+#[derive(Debug, Default)]
+pub struct History {
+    searches: Vec<String>,
+    /// The search recalled, or none while typing a new one.
+    position: Option<usize>,
+    /// The search being typed before the history was recalled, given back
+    /// past the newest search.
+    draft: String,
+    file: Option<PathBuf>,
+}
+
+impl History {
+    /// Loads the history saved to `file`, which is created on the first
+    /// search. A file that can't be read starts an empty history.
+    pub fn load(file: PathBuf) -> Self {
+        let searches = match fs::read_to_string(&file) {
+            Ok(text) => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(String::from)
+                .collect(),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!(
+                        "failed to read the search history {}: {}",
+                        file.display(),
+                        e
+                    );
+                }
+                Vec::new()
+            }
+        };
+        History {
+            searches,
+            file: Some(file),
+            ..Default::default()
+        }
+    }
+
+    /// Adds `search` as the newest search, moving it there if it was already
+    /// in the history, and saves the history.
+    pub fn push(&mut self, search: &str) {
+        self.reset();
+        let search = search.trim();
+        if search.is_empty() {
+            return;
+        }
+        self.searches.retain(|s| s != search);
+        self.searches.push(String::from(search));
+        let overflow = self.searches.len().saturating_sub(MAX_HISTORY);
+        self.searches.drain(..overflow);
+
+        if let Some(file) = &self.file
+            && let Err(e) = fs::write(file, self.searches.join("\n") + "\n")
+        {
+            warn!(
+                "failed to save the search history {}: {}",
+                file.display(),
+                e
+            );
+        }
+    }
+
+    /// Returns the search before the one recalled, or the newest one if
+    /// `current`, being typed, isn't from the history yet.
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            Some(0) => 0,
+            Some(position) => position - 1,
+            None => {
+                self.draft = String::from(current);
+                self.searches.len().checked_sub(1)?
+            }
+        };
+        self.position = Some(position);
+        self.searches.get(position).map(String::as_str)
+    }
+
+    /// Returns the search after the one recalled, or the one that was being
+    /// typed past the newest.
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.searches.len() {
+            self.position = Some(position + 1);
+            return self.searches.get(position + 1).map(String::as_str);
+        }
+        self.position = None;
+        Some(&self.draft)
+    }
+
+    /// Goes back to typing a new search.
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history");
+        let mut history = History::load(file.clone());
+        assert!(history.searches.is_empty());
+        assert_eq!(history.prev("draft"), None);
+
+        history.push("timeout");
+        history.push(" ");
+        history.push("detach");
+        history.push("timeout");
+        assert_eq!(history.searches, ["detach", "timeout"]);
+
+        // Up goes back from the newest search, and Down forward to the draft
+        assert_eq!(history.prev("att"), Some("timeout"));
+        assert_eq!(history.prev("timeout"), Some("detach"));
+        assert_eq!(history.prev("detach"), Some("detach"));
+        assert_eq!(history.next(), Some("timeout"));
+        assert_eq!(history.next(), Some("att"));
+        assert_eq!(history.next(), None);
+
+        // the history survives restarts
+        let history = History::load(file);
+        assert_eq!(history.searches, ["detach", "timeout"]);
+
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY + 5 {
+            history.push(&i.to_string());
+        }
+        assert_eq!(history.searches.len(), MAX_HISTORY);
+        assert_eq!(history.searches[0], "5");
+    }
+}
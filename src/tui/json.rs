@@ -0,0 +1,162 @@
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use sbsearch::JsonValue;
+
+/// Returns the number of nesting levels of `value`, as many as can be folded.
+pub fn depth(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Object(fields) => 1 + fields.iter().map(|(_, v)| depth(v)).max().unwrap_or(0),
+        JsonValue::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Pretty-prints `value` with two spaces of indent per level, highlighting the
+/// keys, strings, numbers and literals. The objects and arrays nested deeper
+/// than `depth` levels are folded into one line with their size.
+pub fn pretty_lines(value: &JsonValue, depth: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    push(value, None, 0, depth, true, &mut lines);
+    lines
+}
+
+fn push(
+    value: &JsonValue,
+    key: Option<&str>,
+    indent: usize,
+    depth: usize,
+    last: bool,
+    lines: &mut Vec<Line<'static>>,
+) {
+    let mut spans = vec![Span::raw("  ".repeat(indent))];
+    if let Some(key) = key {
+        spans.push(Span::styled(quote(key), Style::default().fg(Color::Cyan)));
+        spans.push(Span::raw(": "));
+    }
+    let comma = if last { "" } else { "," };
+
+    let (open, close, children, unit): (_, _, Vec<(Option<&str>, &JsonValue)>, _) = match value {
+        JsonValue::Object(fields) => (
+            "{",
+            "}",
+            fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect(),
+            "fields",
+        ),
+        JsonValue::Array(items) => ("[", "]", items.iter().map(|v| (None, v)).collect(), "items"),
+        scalar => {
+            spans.push(scalar_span(scalar));
+            spans.push(Span::raw(comma));
+            lines.push(Line::from(spans));
+            return;
+        }
+    };
+    if children.is_empty() {
+        spans.push(Span::raw(format!("{}{}{}", open, close, comma)));
+        lines.push(Line::from(spans));
+        return;
+    }
+    if depth == 0 {
+        spans.push(Span::raw(format!("{}…{}", open, close)));
+        spans.push(Span::styled(
+            format!(" {} {}", children.len(), unit),
+            Style::default().fg(Color::DarkGray),
+        ));
+        spans.push(Span::raw(comma));
+        lines.push(Line::from(spans));
+        return;
+    }
+
+    spans.push(Span::raw(open));
+    lines.push(Line::from(spans));
+    let count = children.len();
+    for (index, (key, child)) in children.into_iter().enumerate() {
+        push(child, key, indent + 1, depth - 1, index + 1 == count, lines);
+    }
+    lines.push(Line::raw(format!(
+        "{}{}{}",
+        "  ".repeat(indent),
+        close,
+        comma
+    )));
+}
+
+fn scalar_span(value: &JsonValue) -> Span<'static> {
+    match value {
+        JsonValue::String(s) => Span::styled(quote(s), Style::default().fg(Color::Green)),
+        JsonValue::Number(n) => Span::styled(n.clone(), Style::default().fg(Color::Magenta)),
+        JsonValue::Bool(b) => Span::styled(b.to_string(), Style::default().fg(Color::Yellow)),
+        _ => Span::styled("null", Style::default().fg(Color::Yellow)),
+    }
+}
+
+/// Quotes `s` as a JSON string, escaping the quotes, backslashes and control
+/// characters.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_lines() {
+        let value = sbsearch::json_value(
+            r#"{"msg":"say \"hi\"","n":3,"meta":{"tags":["a",null],"empty":{}},"ok":true}"#,
+        )
+        .unwrap();
+        assert_eq!(depth(&value), 3);
+
+        let text = |depth: usize| -> Vec<String> {
+            pretty_lines(&value, depth)
+                .iter()
+                .map(|line| line.to_string())
+                .collect()
+        };
+        assert_eq!(
+            text(usize::MAX),
+            vec![
+                "{",
+                r#"  "msg": "say \"hi\"","#,
+                r#"  "n": 3,"#,
+                r#"  "meta": {"#,
+                r#"    "tags": ["#,
+                r#"      "a","#,
+                r#"      null"#,
+                r#"    ],"#,
+                r#"    "empty": {}"#,
+                r#"  },"#,
+                r#"  "ok": true"#,
+                "}",
+            ]
+        );
+        assert_eq!(
+            text(1),
+            vec![
+                "{",
+                r#"  "msg": "say \"hi\"","#,
+                r#"  "n": 3,"#,
+                r#"  "meta": {…} 2 fields,"#,
+                r#"  "ok": true"#,
+                "}",
+            ]
+        );
+        assert_eq!(text(0), vec!["{…} 4 fields"]);
+    }
+}
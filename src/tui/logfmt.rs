@@ -0,0 +1,144 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+use std::ops::Range;
+
+/// The part of a logfmt pair, like `level=info` or `msg="volume attached"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Part {
+    Key,
+    Value,
+    /// The value of the `msg` or `message` key, the gist of the line.
+    Message,
+}
+
+impl Part {
+    pub fn style(self) -> Style {
+        match self {
+            Part::Key => Style::default().fg(Color::Cyan),
+            Part::Value => Style::default().fg(Color::Green),
+            Part::Message => Style::default().bold(),
+        }
+    }
+}
+
+/// Returns the byte ranges of the keys and the values of the logfmt pairs in
+/// `line`, in order. Quoted values run to their closing quote, spaces
+/// included. Lines with fewer than two pairs aren't taken for logfmt, so that
+/// a lone `=` in a sentence isn't highlighted.
+pub fn pairs(line: &str) -> Vec<(Range<usize>, Part)> {
+    let bytes = line.as_bytes();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        // a key starts a word and runs to the `=`
+        let start = i;
+        while i < bytes.len() && is_key_byte(bytes[i]) {
+            i += 1;
+        }
+        if i > start && bytes.get(i) == Some(&b'=') {
+            let key = start..i;
+            i += 1;
+            let value = i;
+            if bytes.get(i) == Some(&b'"') {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+            } else {
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+            }
+            let part = match &line[key.clone()] {
+                "msg" | "message" => Part::Message,
+                _ => Part::Value,
+            };
+            parts.push((key, Part::Key));
+            if i > value {
+                parts.push((value..i, part));
+            }
+        }
+
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+    }
+
+    match parts.iter().filter(|(_, part)| *part == Part::Key).count() {
+        0 | 1 => Vec::new(),
+        _ => parts,
+    }
+}
+
+/// Highlights the logfmt pairs of `line`, with the rest of it in `base`.
+pub fn highlight(line: &str, base: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut end = 0;
+    for (range, part) in pairs(line) {
+        if range.start > end {
+            spans.push(Span::styled(line[end..range.start].to_string(), base));
+        }
+        spans.push(Span::styled(
+            line[range.clone()].to_string(),
+            base.patch(part.style()),
+        ));
+        end = range.end;
+    }
+    if end < line.len() {
+        spans.push(Span::styled(line[end..].to_string(), base));
+    }
+    Line::from(spans)
+}
+
+fn is_key_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let line = r#"time="2025-12-30T21:49:41Z" level=error msg="failed to attach \"pvc-1\"" node=n1 retry="#;
+        let parts: Vec<(&str, Part)> = pairs(line)
+            .into_iter()
+            .map(|(range, part)| (&line[range], part))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("time", Part::Key),
+                (r#""2025-12-30T21:49:41Z""#, Part::Value),
+                ("level", Part::Key),
+                ("error", Part::Value),
+                ("msg", Part::Key),
+                (r#""failed to attach \"pvc-1\"""#, Part::Message),
+                ("node", Part::Key),
+                ("n1", Part::Value),
+                ("retry", Part::Key),
+            ]
+        );
+
+        // a lone pair, or a comparison, isn't logfmt
+        assert!(pairs("set replicas=3 on the volume").is_empty());
+        assert!(pairs("if a == b then c=d").is_empty());
+        // an unterminated quote runs to the end of the line
+        assert!(pairs(r#"k="quote ✓ x=1 \"#).is_empty());
+
+        let line = highlight("E0101 a=1 b=✓ tail", Style::default().red());
+        assert_eq!(line.to_string(), "E0101 a=1 b=✓ tail");
+        assert_eq!(line.spans[1].style, Style::default().red().fg(Color::Cyan));
+        assert_eq!(line.spans[2].content, "=");
+        assert_eq!(line.spans[3].style, Style::default().fg(Color::Green));
+    }
+}
@@ -1,34 +1,126 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use grep_regex::RegexMatcher;
 use log::*;
 use ratatui::{
     DefaultTerminal, Frame,
+    text::Line,
     widgets::{ListState, ScrollbarState},
 };
 use std::error::Error;
-use std::io::{self, BufWriter, Write};
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use tui_input::Input;
 
-use super::sbsearch;
+use super::analyze;
+use super::kb;
+use sbsearch::format;
+use sbsearch::query::Query;
+use sbsearch::session::{self, ExportFormat, Session, Stats};
+use sbsearch::{self, AssumeTz};
 
+mod compare;
+mod diff;
 mod event;
+mod external;
+mod files;
+mod filter;
+mod help;
+mod history;
+mod json;
+mod logfmt;
+mod picker;
 mod render;
+mod source;
+mod theme;
+
+pub use picker::Picker;
+pub use theme::Capabilities;
 
 pub const DEFAULT_MAX_ENTRIES_PER_PAGE: usize = 100;
 
+/// The maximum number of tabs, switchable with the number keys 1 to 8.
+pub const MAX_TABS: usize = 8;
+
+/// The number of lines before and after an entry that it's expanded with.
+pub const CONTEXT_LINES: usize = 5;
+
+/// How long a notice, such as a reloaded knowledge base, stays on screen.
+const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
 #[derive(Debug, Default)]
 pub struct Tui {
+    capabilities: Capabilities,
+    display_tz: AssumeTz,
     current_screen: Screen,
-    entries_cache: Vec<sbsearch::Entry>,
     entries_offset: Vec<sbsearch::Entry>,
     exit: bool,
+    filter_form: filter::FilterForm,
     nav_state: ListState,
-    keyword: String,
+    pinned: Vec<sbsearch::Entry>,
+    bookmarks: Vec<sbsearch::Entry>,
+    bookmark_state: ListState,
+    rate: analyze::Rate,
+    show_rate: bool,
+    knowledge_base: kb::KnowledgeBase,
+    kb_file: Option<PathBuf>,
+    kb_modified: Option<SystemTime>,
+    notice: Option<(String, Instant)>,
+    error: Option<ErrorPopup>,
     search: String,
     search_input: Input,
+    /// Whether the search is a regular expression rather than text.
+    search_regex: bool,
+    /// Why the search isn't a valid regular expression, shown on the search
+    /// box while it's fixed.
+    search_error: Option<String>,
     search_mode: SearchMode,
-    sbpath: String,
+    search_filter: bool,
+    search_history: history::History,
+    session: Session,
+    stats: Stats,
+    suspects: Vec<analyze::Suspect>,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    tab_input: Input,
+    open_input: Input,
+    goto_input: Input,
+    compare_input: Input,
+    compare: Option<compare::Compare>,
+    diff: Option<diff::Diff>,
+    /// The lines around the entry expanded in place in the logs.
+    context: Option<source::Context>,
+    /// The number of lines before and after an entry that it's expanded with.
+    context_lines: usize,
+    /// The entry the timestamps are shown relative to, e.g. `T+00:03:12`.
+    anchor: Option<sbsearch::Entry>,
+    /// The timezone of the timestamps before they were switched to local time.
+    switched_tz: Option<AssumeTz>,
     vertical_scroll_state: ScrollbarState,
     vertical_scroll: usize,
+    wrap: bool,
+    horizontal_scroll: usize,
+    entry_scroll: usize,
+    entry_overflow: usize,
+    show_columns: bool,
+    show_source: bool,
+    show_logfmt: bool,
+    logs_height: usize,
+    keyword_matcher: Option<(String, RegexMatcher)>,
+    detail_scroll: (u16, u16),
+    json_depth: usize,
+    source_view: Option<source::SourceView>,
+    external_pending: bool,
+    help_scroll: u16,
+    spinner: usize,
+    pending_count: Option<String>,
+    file_tree: files::FileTree,
+    show_files: bool,
+    show_split: bool,
+    detail_for: Option<(String, u64)>,
+    focus: Focus,
 
     page_final: usize,
     page_goto: usize,
@@ -36,14 +128,127 @@ pub struct Tui {
     page_reload: bool,
 
     last_saved_filename: String,
+    save_input: Input,
+    save_scope: SaveScope,
+    save_format: ExportFormat,
+
+    autosave_dir: Option<PathBuf>,
+    autosave_interval: Duration,
+    last_autosave: Option<Instant>,
+    refresh_interval: Option<Duration>,
+    last_refresh: Option<Instant>,
+    reselect: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Default, PartialEq)]
 enum Screen {
     #[default]
     Main,
+    Progress,
     ConfirmExit,
     ConfirmSave,
+    ConfirmOverwrite,
+    Suspects,
+    Warnings,
+    Detail,
+    Source,
+    Help,
+    FilterBuilder,
+    NewTab,
+    OpenSaved,
+    GoTo,
+    Bookmarks,
+    CompareWith,
+    Compare,
+    Diff,
+    Error,
+}
+
+/// The search of a tab. Each tab has its own keyword and filter over the same
+/// support bundle. The active tab's state lives in the `Tui`, while the other
+/// tabs are kept here until they're switched to.
+#[derive(Debug, Default)]
+struct Tab {
+    session: Session,
+    entries_offset: Vec<sbsearch::Entry>,
+    nav_state: ListState,
+    rate: analyze::Rate,
+    stats: Stats,
+    page_final: usize,
+    page_goto: usize,
+    /// Whether the session found more entries while the tab wasn't active,
+    /// e.g. while compared, so that its page is re-read when switched to.
+    stale: bool,
+}
+
+impl Tab {
+    fn new(session: Session) -> Self {
+        Tab {
+            session,
+            nav_state: ListState::default().with_selected(Some(0)),
+            page_final: 1,
+            page_goto: 1,
+            ..Default::default()
+        }
+    }
+}
+
+/// An error shown in a popup until it's dismissed, unlike a notice, e.g. a
+/// failed save.
+#[derive(Debug)]
+struct ErrorPopup {
+    title: &'static str,
+    text: String,
+    /// The screen to go back to, to try again, e.g. with another file name.
+    retry: Option<Screen>,
+}
+
+/// The pane of the main screen the keys go to.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+enum Focus {
+    #[default]
+    Logs,
+    Files,
+    Detail,
+}
+
+/// Which entries the save action writes.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+enum SaveScope {
+    /// Every entry matching the keyword, regardless of the filter and search.
+    All,
+    /// The entries of the current page.
+    Page,
+    /// The entries matching the filter and the search.
+    #[default]
+    Matching,
+    /// The bookmarked entries, or the selected one if there are none.
+    Bookmarked,
+}
+
+impl SaveScope {
+    const ALL: [SaveScope; 4] = [
+        SaveScope::All,
+        SaveScope::Page,
+        SaveScope::Matching,
+        SaveScope::Bookmarked,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SaveScope::All => "everything",
+            SaveScope::Page => "the current page",
+            SaveScope::Matching => "the entries matching the filter",
+            SaveScope::Bookmarked => "the bookmarked entries",
+        }
+    }
+
+    /// Returns the next scope, or the previous one if `steps` is negative,
+    /// wrapping around.
+    fn cycle(self, steps: isize) -> Self {
+        let index = Self::ALL.iter().position(|s| *s == self).unwrap_or(0) as isize;
+        Self::ALL[(index + steps).rem_euclid(Self::ALL.len() as isize) as usize]
+    }
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -56,18 +261,68 @@ enum SearchMode {
 impl Tui {
     pub fn new(support_bundle_path: &str, keyword: &str) -> Self {
         Self {
+            capabilities: Capabilities::default(),
+            display_tz: AssumeTz::default(),
             current_screen: Screen::Main,
             entries_offset: Vec::new(),
-            entries_cache: Vec::new(),
             exit: false,
+            filter_form: filter::FilterForm::default(),
             nav_state: ListState::default().with_selected(Some(0)),
-            keyword: String::from(keyword),
+            pinned: Vec::new(),
+            bookmarks: Vec::new(),
+            bookmark_state: ListState::default(),
+            rate: analyze::Rate::default(),
+            show_rate: false,
+            knowledge_base: kb::KnowledgeBase::default(),
+            kb_file: None,
+            kb_modified: None,
+            notice: None,
+            error: None,
             search: String::new(),
             search_input: Input::default(),
+            search_regex: false,
+            search_error: None,
             search_mode: SearchMode::default(),
-            sbpath: String::from(support_bundle_path),
+            search_filter: true,
+            search_history: history::History::default(),
+            session: Session::new(support_bundle_path, keyword),
+            stats: Stats::default(),
+            suspects: Vec::new(),
+            tabs: vec![Tab::default()],
+            active_tab: 0,
+            tab_input: Input::default(),
+            open_input: Input::default(),
+            goto_input: Input::default(),
+            compare_input: Input::default(),
+            compare: None,
+            diff: None,
+            context: None,
+            context_lines: CONTEXT_LINES,
+            anchor: None,
+            switched_tz: None,
             vertical_scroll_state: ScrollbarState::default(),
             vertical_scroll: 0,
+            wrap: true,
+            horizontal_scroll: 0,
+            entry_scroll: 0,
+            entry_overflow: 0,
+            show_columns: false,
+            show_source: false,
+            show_logfmt: false,
+            logs_height: 0,
+            keyword_matcher: None,
+            detail_scroll: (0, 0),
+            json_depth: usize::MAX,
+            source_view: None,
+            external_pending: false,
+            help_scroll: 0,
+            spinner: 0,
+            pending_count: None,
+            file_tree: files::FileTree::default(),
+            show_files: false,
+            show_split: false,
+            detail_for: None,
+            focus: Focus::default(),
 
             page_final: 1,
             page_goto: 1,
@@ -75,133 +330,1618 @@ impl Tui {
             page_reload: true,
 
             last_saved_filename: String::new(),
+            save_input: Input::default(),
+            save_scope: SaveScope::default(),
+            save_format: ExportFormat::default(),
+
+            autosave_dir: None,
+            autosave_interval: Duration::default(),
+            last_autosave: None,
+            refresh_interval: None,
+            last_refresh: None,
+            reselect: None,
         }
     }
 
+    pub fn with_options(mut self, options: sbsearch::SearchOptions) -> Self {
+        self.session = self.session.with_options(options);
+        self
+    }
+
+    /// Browses the entries of `session` instead, e.g. of a replayed file.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn with_export_format(mut self, format: ExportFormat) -> Self {
+        self.session = self.session.with_export_format(format);
+        self
+    }
+
+    /// Prefixes each line of the saved entries with their UTC timestamp.
+    pub fn with_normalized_timestamps(mut self, normalize: bool) -> Self {
+        self.session = self.session.with_normalized_timestamps(normalize);
+        self
+    }
+
+    /// Prefixes each line of the saved entries with its `path:line`.
+    pub fn with_saved_locations(mut self, locations: bool) -> Self {
+        self.session = self.session.with_saved_locations(locations);
+        self
+    }
+
+    /// Shows the timestamps in `display_tz` instead of UTC.
+    pub fn with_display_timezone(mut self, display_tz: AssumeTz) -> Self {
+        self.display_tz = display_tz;
+        self
+    }
+
+    /// Keeps the submitted searches in `file`, to recall them in later runs.
+    pub fn with_search_history(mut self, file: PathBuf) -> Self {
+        self.search_history = history::History::load(file);
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Periodically saves the matching entries to a rotating file in `dir`.
+    pub fn with_autosave(mut self, dir: PathBuf, interval: Duration) -> Self {
+        self.autosave_dir = Some(dir);
+        self.autosave_interval = interval;
+        self
+    }
+
+    /// Keeps the entries up to date as the files of the support bundle
+    /// change, following the new entries when the last one is selected.
+    pub fn with_watch(mut self, watch: bool) -> Self {
+        self.session = self.session.with_watch(watch);
+        self
+    }
+
+    /// Expands the entries with `lines` lines of their file before and after
+    /// them, instead of `CONTEXT_LINES`.
+    pub fn with_context_lines(mut self, lines: usize) -> Self {
+        self.context_lines = lines;
+        self
+    }
+
+    /// Searches the support bundle again every `interval`, e.g. while an
+    /// updated bundle is extracted over it.
+    pub fn with_refresh(mut self, interval: Duration) -> Self {
+        self.refresh_interval = Some(interval);
+        self
+    }
+
+    pub fn with_knowledge_base(mut self, knowledge_base: kb::KnowledgeBase) -> Self {
+        self.knowledge_base = knowledge_base;
+        self
+    }
+
+    /// Reloads the knowledge base from `path` whenever the file changes, so
+    /// that rules can be tuned without restarting the search.
+    pub fn with_knowledge_base_file(mut self, path: PathBuf) -> Self {
+        self.kb_modified = modified(&path);
+        self.kb_file = Some(path);
+        self
+    }
+
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
-        info!(
-            "searching for '{}' in support bundle at '{}'",
-            self.keyword, self.sbpath
-        );
+        match self.session.is_replay() {
+            true => info!("browsing the saved entries of '{}'", self.session.keyword()),
+            false => info!(
+                "searching for '{}' in support bundle at '{}'",
+                self.session.keyword(),
+                self.session.root_dir()
+            ),
+        }
+
+        // the entries are shown as they're found, and the ranked list of
+        // suspicious components once the search is done
+        match self.session.start_search() {
+            Ok(()) => self.current_screen = Screen::Progress,
+            Err(e) => error!("error searching the support bundle: {}", e),
+        }
+        let mut suspects_pending = true;
+
         while !self.exit {
+            if self.session.poll() {
+                self.refresh_entries_from_sb();
+            }
+            if !self.session.is_searching()
+                && let Some(timestamp) = self.reselect.take()
+            {
+                self.nav_goto_time(timestamp);
+            }
+            if let Some(compare) = &self.compare
+                && let Some(tab) = self.tabs.get_mut(compare.other)
+                && tab.session.poll()
+            {
+                tab.stale = true;
+            }
+            if self.current_screen == Screen::Progress && !self.session.is_searching() {
+                self.current_screen = Screen::Main;
+            }
+            if suspects_pending && !self.session.is_searching() {
+                suspects_pending = false;
+                self.find_suspects();
+                self.notify_warnings();
+            }
             if self.page_reload {
                 self.read_entries_from_sb();
             }
+            self.poll_watch();
+            self.refresh_if_due();
+            self.autosave_if_due();
+            self.reload_knowledge_base_if_changed();
 
-            terminal.draw(|frame| match self.current_screen {
-                Screen::ConfirmExit => self.draw_popup(
-                    "Confirm Exit",
-                    "are you sure you want to exit? (y/n)",
-                    30,
-                    15,
-                    frame,
-                ),
-                Screen::ConfirmSave => {
-                    let filename =
-                        format!("sbsearch_{}.log", chrono::Utc::now().format("%Y%m%d%H%M%S"));
-                    self.draw_popup(
-                        "Confirm Save",
-                        format!("save search result to ./{}? (y/n)", filename).as_str(),
+            let capabilities = self.capabilities;
+            terminal.draw(|frame| {
+                // the sections would overlap, so nothing else is drawn until
+                // the terminal is resized
+                if render::too_small(frame.area()) {
+                    render::draw_too_small(frame);
+                    return;
+                }
+                match self.current_screen {
+                    Screen::ConfirmExit => self.draw_popup(
+                        "Confirm Exit",
+                        "are you sure you want to exit? (y/n)",
+                        30,
+                        15,
+                        frame,
+                    ),
+                    Screen::ConfirmSave => {
+                        self.draw_main(frame);
+                        let count = self.entries_to_save().len();
+                        render::draw_save(
+                            &self.save_input,
+                            self.save_scope.label(),
+                            count,
+                            self.save_format,
+                            frame,
+                        );
+                    }
+                    Screen::ConfirmOverwrite => self.draw_popup(
+                        "Confirm Overwrite",
+                        format!(
+                            "{} already exists, overwrite it? (y/n)",
+                            self.last_saved_filename
+                        )
+                        .as_str(),
+                        50,
+                        15,
+                        frame,
+                    ),
+                    Screen::Progress => {
+                        self.draw_main(frame);
+                        self.spinner = self.spinner.wrapping_add(1);
+                        render::draw_progress(self.session.progress(), self.spinner, frame);
+                    }
+                    Screen::Suspects => render::draw_suspects(&self.suspects, frame),
+                    Screen::Warnings => {
+                        self.draw_main(frame);
+                        render::draw_warnings(self.session.warnings(), frame);
+                    }
+                    Screen::Detail => self.draw_detail(frame),
+                    Screen::Help => {
+                        self.draw_main(frame);
+                        render::draw_help(self.help_scroll, frame);
+                    }
+                    Screen::Source => match &mut self.source_view {
+                        Some(view) => render::draw_source(view, frame),
+                        None => self.draw_main(frame),
+                    },
+                    Screen::FilterBuilder => render::draw_filter_form(&self.filter_form, frame),
+                    Screen::NewTab => self.draw_popup(
+                        "New Tab",
+                        format!("keyword: {}", self.tab_input.value()).as_str(),
                         40,
                         15,
                         frame,
-                    );
-                    self.last_saved_filename = filename;
+                    ),
+                    Screen::OpenSaved => self.draw_popup(
+                        "Open Saved Results",
+                        format!("file: {}", self.open_input.value()).as_str(),
+                        60,
+                        15,
+                        frame,
+                    ),
+                    Screen::Bookmarks => {
+                        self.draw_main(frame);
+                        let mut state = self.bookmark_state.clone();
+                        render::draw_bookmarks(&self.numbered_bookmarks(), &mut state, frame);
+                        self.bookmark_state = state;
+                    }
+                    Screen::CompareWith => {
+                        self.draw_main(frame);
+                        self.draw_popup(
+                            "Compare With",
+                            format!("keyword: {}", self.compare_input.value()).as_str(),
+                            40,
+                            15,
+                            frame,
+                        );
+                    }
+                    Screen::Compare => {
+                        let keywords = (
+                            self.session.keyword(),
+                            self.compare
+                                .as_ref()
+                                .and_then(|compare| self.tabs.get(compare.other))
+                                .map_or("", |tab| tab.session.keyword()),
+                        );
+                        let mut state = self
+                            .compare
+                            .as_ref()
+                            .map(|compare| compare.state.clone())
+                            .unwrap_or_default();
+                        render::draw_compare(
+                            keywords,
+                            &self.compare_rows(),
+                            &mut state,
+                            self.display_tz,
+                            frame,
+                        );
+                        if let Some(compare) = &mut self.compare {
+                            compare.state = state;
+                        }
+                    }
+                    Screen::Diff => match &self.diff {
+                        Some(diff) => render::draw_diff(diff, self.display_tz, frame),
+                        None => self.draw_main(frame),
+                    },
+                    Screen::Error => {
+                        self.draw_main(frame);
+                        if let Some(popup) = &self.error {
+                            render::draw_error(
+                                popup.title,
+                                &popup.text,
+                                popup.retry.is_some(),
+                                frame,
+                            );
+                        }
+                    }
+                    Screen::GoTo => {
+                        self.draw_main(frame);
+                        self.draw_popup(
+                            "Go to Entry",
+                            format!(
+                                "entry (1-{}): {}",
+                                self.stats.matching,
+                                self.goto_input.value()
+                            )
+                            .as_str(),
+                            40,
+                            15,
+                            frame,
+                        );
+                    }
+                    _ => self.draw_main(frame),
+                }
+                if let Some((text, shown)) = &self.notice
+                    && shown.elapsed() < NOTICE_DURATION
+                {
+                    render::draw_notice(text, frame);
                 }
-                _ => self.draw_main(frame),
+                capabilities.degrade(frame.buffer_mut());
             })?;
             event::handle(self)?;
+            if self.external_pending {
+                self.external_pending = false;
+                self.open_external(terminal);
+            }
         }
         Ok(())
     }
 
+    pub fn entries(&self) -> &[sbsearch::Entry] {
+        self.session.entries()
+    }
+
     fn read_entries_from_sb(&mut self) {
-        let root_path = Path::new(self.sbpath.as_str());
-        let keyword = self.keyword.as_str();
         let offset = self.page_goto * self.page_max_entries - self.page_max_entries;
         let limit = self.page_max_entries;
-        let cache = &mut self.entries_cache;
-
-        self.entries_offset = match sbsearch::search(root_path, keyword, offset, limit, cache) {
-            Ok(result) => {
-                info!("found {} entries matching '{}'", cache.len(), keyword);
-                result.entries_offset
-            }
+        // the files that can't be searched are reported as warnings, so an
+        // error means the search itself failed, e.g. an invalid keyword
+        self.entries_offset = match self.session.search(offset, limit) {
+            Ok(entries) => entries,
             Err(e) => {
                 error!("error reading entries from support bundle: {}", e);
+                self.notice = Some((format!("search failed: {}", e), Instant::now()));
                 Vec::new()
             }
         };
-        self.page_final = self.entries_cache.len().div_ceil(self.page_max_entries);
-        self.page_reload = false;
-        self.nav_state = ListState::default().with_selected(Some(0));
+        self.stats = self.session.stats().clone();
+        self.rate = analyze::rate_per_minute(self.session.matching());
+        self.page_final = self.stats.matching.div_ceil(self.page_max_entries);
+        self.page_reload = false;
+        self.nav_state = ListState::default().with_selected(Some(0));
+        self.entry_scroll = 0;
+    }
+
+    /// Re-reads the current page as more entries are found, keeping the
+    /// selected line.
+    fn refresh_entries_from_sb(&mut self) {
+        let selected = self.nav_state.selected();
+        self.read_entries_from_sb();
+        if !self.entries_offset.is_empty() {
+            let last = self.entries_offset.len() - 1;
+            self.nav_state.select(selected.map(|pos| pos.min(last)));
+        }
+    }
+
+    /// Opens on the ranked list of suspicious components, if there are any.
+    fn find_suspects(&mut self) {
+        self.suspects = analyze::suspects(
+            self.session.entries(),
+            self.session.root_dir(),
+            &self.knowledge_base,
+        );
+        if !self.suspects.is_empty() && self.current_screen == Screen::Main {
+            self.current_screen = Screen::Suspects;
+        }
+    }
+
+    /// Points out the files that were skipped or only partly searched.
+    fn notify_warnings(&mut self) {
+        let count = self.session.warnings().len();
+        if self.session.timed_out() {
+            let text = format!(
+                "search timed out, showing the {} entries found so far, see <w>",
+                format::count(self.stats.entries)
+            );
+            self.notice = Some((text, Instant::now()));
+        } else if count > 0 {
+            let text = format!("{} files skipped or partly searched, see <w>", count);
+            self.notice = Some((text, Instant::now()));
+        }
+    }
+
+    fn open_filter_form(&mut self) {
+        self.filter_form = filter::FilterForm::new(
+            self.session.entries(),
+            self.session.root_dir(),
+            self.session.query(),
+        );
+        self.current_screen = Screen::FilterBuilder;
+    }
+
+    fn apply_filter_form(&mut self) {
+        match self.filter_form.compile().parse::<Query>() {
+            Ok(query) => {
+                self.session.filter(query);
+                self.page_goto = 1;
+                self.page_reload = true;
+                self.current_screen = Screen::Main;
+            }
+            Err(e) => self.filter_form.error = Some(e),
+        }
+    }
+
+    /// Only shows the entries at `level` or more severe, or of any level if
+    /// there's none, keeping the rest of the filter query.
+    fn filter_levels(&mut self, level: Option<sbsearch::Level>) {
+        let mut query = self.session.query().clone();
+        query.set_min_level(level);
+        self.session.filter(query);
+        self.page_goto = 1;
+        self.page_reload = true;
+    }
+
+    /// Shows the file tree sidebar, focused, or hides it. The entries stay
+    /// narrowed down to the picked file once it's hidden.
+    fn toggle_files(&mut self) {
+        self.show_files = !self.show_files;
+        self.focus = match self.show_files {
+            true => Focus::Files,
+            false => Focus::Logs,
+        };
+        if self.show_files {
+            self.file_tree.refresh(&self.session);
+        }
+    }
+
+    /// Shows the selected entry in a pane on the right of the logs, following
+    /// the selection, or hides it.
+    fn toggle_split(&mut self) {
+        self.show_split = !self.show_split;
+        self.detail_scroll = (0, 0);
+        if !self.show_split && self.focus == Focus::Detail {
+            self.focus = Focus::Logs;
+        }
+    }
+
+    /// Moves the focus to the next pane shown, from left to right: the file
+    /// tree, the logs and the detail pane.
+    fn switch_focus(&mut self) {
+        let panes: Vec<Focus> = [
+            (Focus::Files, self.show_files),
+            (Focus::Logs, true),
+            (Focus::Detail, self.show_split),
+        ]
+        .into_iter()
+        .filter_map(|(pane, shown)| shown.then_some(pane))
+        .collect();
+        let index = panes.iter().position(|p| *p == self.focus).unwrap_or(0);
+        self.focus = panes[(index + 1) % panes.len()];
+    }
+
+    /// Narrows the entries down to the file, or the directory, selected in
+    /// the file tree.
+    fn pick_file(&mut self) {
+        let Some(row) = self.file_tree.selected() else {
+            return;
+        };
+        let path = row.path.clone();
+        self.session.set_path(path.as_deref());
+        self.page_goto = 1;
+        self.page_reload = true;
+    }
+
+    /// Pins the selected entry, or unpins it if it's already pinned. Pinned
+    /// entries stay visible across filter and search changes.
+    fn toggle_pin(&mut self) {
+        let Some(entry) = self
+            .nav_state
+            .selected()
+            .and_then(|pos| self.entries_offset.get(pos))
+        else {
+            return;
+        };
+
+        match self
+            .pinned
+            .iter()
+            .position(|p| p.path == entry.path && p.line_number == entry.line_number)
+        {
+            Some(index) => {
+                self.pinned.remove(index);
+            }
+            None => self.pinned.push(entry.clone()),
+        }
+    }
+
+    /// Bookmarks the selected entry, or removes its bookmark. Unlike pinned
+    /// entries, bookmarks stay in the logs, to jump between with `[` and `]`.
+    fn toggle_bookmark(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        match self
+            .bookmarks
+            .iter()
+            .position(|b| b.path == entry.path && b.line_number == entry.line_number)
+        {
+            Some(index) => {
+                self.bookmarks.remove(index);
+            }
+            None => self.bookmarks.push(entry.clone()),
+        }
+    }
+
+    /// Returns the bookmarks with their number among the matching entries,
+    /// in the order of the logs, followed by the ones hidden by the filter.
+    fn numbered_bookmarks(&self) -> Vec<(Option<usize>, &sbsearch::Entry)> {
+        let mut numbered: Vec<(Option<usize>, &sbsearch::Entry)> = self
+            .bookmarks
+            .iter()
+            .map(|bookmark| {
+                let number = self
+                    .session
+                    .matching()
+                    .position(|e| e.path == bookmark.path && e.line_number == bookmark.line_number)
+                    .map(|index| index + 1);
+                (number, bookmark)
+            })
+            .collect();
+        numbered.sort_by_key(|(number, _)| number.unwrap_or(usize::MAX));
+        numbered
+    }
+
+    /// Selects the next bookmark after the selected entry, or the previous one
+    /// before it, wrapping around, on whichever page it is.
+    fn nav_bookmark(&mut self, forward: bool) {
+        let numbers: Vec<usize> = self
+            .numbered_bookmarks()
+            .into_iter()
+            .filter_map(|(number, _)| number)
+            .collect();
+        let (Some(first), Some(last)) = (numbers.first(), numbers.last()) else {
+            self.notice = Some((String::from("no bookmarks to go to"), Instant::now()));
+            return;
+        };
+        let offset = self.page_goto * self.page_max_entries - self.page_max_entries;
+        let current = offset + self.nav_state.selected().unwrap_or(0) + 1;
+        let number = match forward {
+            true => *numbers.iter().find(|n| **n > current).unwrap_or(first),
+            false => *numbers.iter().rev().find(|n| **n < current).unwrap_or(last),
+        };
+        self.nav_goto(number);
+    }
+
+    fn open_bookmarks(&mut self) {
+        self.bookmark_state = ListState::default().with_selected(Some(0));
+        self.current_screen = Screen::Bookmarks;
+    }
+
+    /// Goes to the bookmark selected in the bookmark list, unless it's hidden
+    /// by the filter.
+    fn pick_bookmark(&mut self) {
+        self.current_screen = Screen::Main;
+        let numbered = self.numbered_bookmarks();
+        let Some((number, _)) = self
+            .bookmark_state
+            .selected()
+            .and_then(|index| numbered.get(index))
+        else {
+            return;
+        };
+        match *number {
+            Some(number) => self.nav_goto(number),
+            None => {
+                let text = "the bookmark is hidden by the filter";
+                self.notice = Some((String::from(text), Instant::now()));
+            }
+        }
+    }
+
+    /// Removes the bookmark selected in the bookmark list.
+    fn remove_bookmark(&mut self) {
+        let numbered = self.numbered_bookmarks();
+        let Some(index) = self
+            .bookmark_state
+            .selected()
+            .and_then(|index| numbered.get(index))
+            .and_then(|(_, entry)| {
+                self.bookmarks
+                    .iter()
+                    .position(|b| b.path == entry.path && b.line_number == entry.line_number)
+            })
+        else {
+            return;
+        };
+        self.bookmarks.remove(index);
+        if self.bookmark_state.selected() >= Some(self.bookmarks.len()) {
+            self.bookmark_state.select_previous();
+        }
+    }
+
+    /// Shows the differences between the selected entry and the last
+    /// bookmarked one, or the bookmark before it if that's the selected entry.
+    fn open_diff(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let Some(bookmark) = self
+            .bookmarks
+            .iter()
+            .rev()
+            .find(|b| b.path != entry.path || b.line_number != entry.line_number)
+        else {
+            let text = "bookmark an entry with M to diff the selected one with";
+            self.notice = Some((String::from(text), Instant::now()));
+            return;
+        };
+        self.diff = Some(diff::Diff::new(bookmark.clone(), entry.clone()));
+        self.current_screen = Screen::Diff;
+    }
+
+    /// Scrolls both entries of the diff view by `rows` down, up to the last
+    /// line of the longest.
+    fn scroll_diff(&mut self, rows: i32) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        let last = diff.lines(true).len().max(diff.lines(false).len()) as i32 - 1;
+        diff.scroll = (diff.scroll as i32 + rows).clamp(0, last.max(0)) as u16;
+    }
+
+    /// Opens a tab searching for `keyword` with the same options, and switches
+    /// to it.
+    fn open_tab(&mut self, keyword: &str) {
+        if keyword.is_empty() || self.tabs.len() >= MAX_TABS {
+            return;
+        }
+
+        let session = self.start_session(keyword);
+        self.push_tab(session);
+        self.current_screen = match self.session.is_searching() {
+            true => Screen::Progress,
+            false => Screen::Main,
+        };
+    }
+
+    /// Starts searching for `keyword` in the background, with the same
+    /// options as the active tab.
+    fn start_session(&self, keyword: &str) -> Session {
+        let mut session = Session::new(self.session.root_dir(), keyword)
+            .with_options(self.session.options().clone())
+            .with_normalized_timestamps(self.session.normalizes_timestamps())
+            .with_saved_locations(self.session.saves_locations())
+            .with_export_format(self.session.export_format())
+            .with_watch(self.session.is_watching());
+        if let Err(e) = session.start_search() {
+            error!("error searching the support bundle: {}", e);
+        }
+        session
+    }
+
+    /// Opens the results saved to `path`, e.g. by a teammate, in a new tab
+    /// and switches to it.
+    fn open_saved(&mut self, path: &str) {
+        if path.is_empty() || self.tabs.len() >= MAX_TABS {
+            return;
+        }
+
+        match Session::replay(Path::new(path)) {
+            Ok(session) => self.push_tab(
+                session
+                    .with_normalized_timestamps(self.session.normalizes_timestamps())
+                    .with_saved_locations(self.session.saves_locations())
+                    .with_export_format(self.session.export_format()),
+            ),
+            Err(e) => self.show_error(
+                "Open Failed",
+                format!("failed to open saved results {}: {}", path, e),
+                Some(Screen::OpenSaved),
+            ),
+        }
+    }
+
+    fn push_tab(&mut self, session: Session) {
+        self.tabs.push(Tab::new(session));
+        self.switch_tab(self.tabs.len() - 1);
+        self.page_reload = true;
+    }
+
+    /// Closes the active tab and switches to the previous one. The last tab
+    /// can't be closed.
+    fn close_tab(&mut self) {
+        if self.tabs.len() <= 1 {
+            return;
+        }
+
+        self.tabs.remove(self.active_tab);
+        self.active_tab = self.active_tab.saturating_sub(1);
+        let tab = std::mem::take(&mut self.tabs[self.active_tab]);
+        self.restore_tab(tab);
+    }
+
+    /// Switches to the tab at `index`, keeping the state of the active tab.
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+
+        self.tabs[self.active_tab] = self.stash_tab();
+        let tab = std::mem::take(&mut self.tabs[index]);
+        self.restore_tab(tab);
+        self.active_tab = index;
+        self.apply_search();
+    }
+
+    /// Hides the entries that don't contain the search, in filter mode, and
+    /// goes back to the first page of the ones left.
+    fn apply_search(&mut self) {
+        // an invalid regex is pointed out even when the search only highlights
+        self.search_error = session::search_regex(self.search_input.value(), self.search_regex)
+            .err()
+            .map(|e| regex_error(&e));
+        let text = match self.search_filter {
+            true => self.search_input.value(),
+            false => "",
+        };
+        if text == self.session.search_text() && self.search_regex == self.session.search_is_regex()
+        {
+            return;
+        }
+        if self.session.set_search(text, self.search_regex).is_ok() {
+            self.page_goto = 1;
+            self.page_reload = true;
+        }
+    }
+
+    /// Switches the search between matching the text as typed and as a
+    /// regular expression.
+    fn toggle_search_regex(&mut self) {
+        self.search_regex = !self.search_regex;
+        self.apply_search();
+    }
+
+    /// Replaces the search being typed with the previous one in the history,
+    /// or the next one if `older` is false.
+    fn recall_search(&mut self, older: bool) {
+        let current = String::from(self.search_input.value());
+        let recalled = match older {
+            true => self.search_history.prev(&current),
+            false => self.search_history.next(),
+        };
+        if let Some(search) = recalled {
+            self.search_input = Input::new(String::from(search));
+            self.apply_search();
+        }
+    }
+
+    /// Switches the search between hiding the entries that don't contain it,
+    /// and only highlighting the ones that do.
+    fn toggle_search_filter(&mut self) {
+        self.search_filter = !self.search_filter;
+        self.apply_search();
+        let text = match self.search_filter {
+            true => "search hides the entries without it",
+            false => "search only highlights the entries",
+        };
+        self.notice = Some((String::from(text), Instant::now()));
+    }
+
+    fn stash_tab(&mut self) -> Tab {
+        Tab {
+            session: std::mem::take(&mut self.session),
+            entries_offset: std::mem::take(&mut self.entries_offset),
+            nav_state: std::mem::take(&mut self.nav_state),
+            rate: std::mem::take(&mut self.rate),
+            stats: std::mem::take(&mut self.stats),
+            page_final: self.page_final,
+            page_goto: self.page_goto,
+            stale: false,
+        }
+    }
+
+    fn restore_tab(&mut self, tab: Tab) {
+        let stale = tab.stale;
+        self.session = tab.session;
+        self.entries_offset = tab.entries_offset;
+        self.nav_state = tab.nav_state;
+        self.rate = tab.rate;
+        self.stats = tab.stats;
+        self.page_final = tab.page_final;
+        self.page_goto = tab.page_goto;
+        self.vertical_scroll = 0;
+        self.vertical_scroll_state = ScrollbarState::default();
+        self.reselect = None;
+        if stale {
+            self.refresh_entries_from_sb();
+        }
+    }
+
+    /// Returns the keywords of the tabs, which name them.
+    fn tab_titles(&self) -> Vec<String> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| match index == self.active_tab {
+                true => String::from(self.session.keyword()),
+                false => String::from(tab.session.keyword()),
+            })
+            .collect()
+    }
+
+    /// Opens the prompt for the keyword to compare the active tab with,
+    /// filled with the keyword of the next tab, if there's one.
+    fn open_compare(&mut self) {
+        let next = (self.active_tab + 1) % self.tabs.len();
+        let keyword = match next == self.active_tab {
+            true => "",
+            false => self.tabs[next].session.keyword(),
+        };
+        self.compare_input = Input::new(String::from(keyword));
+        self.current_screen = Screen::CompareWith;
+    }
+
+    /// Shows the entries of the active tab next to the ones of `keyword`,
+    /// aligned by time. The tab of `keyword` is opened, without switching to
+    /// it, unless there's one already.
+    fn start_compare(&mut self, keyword: &str) {
+        self.current_screen = Screen::Main;
+        if keyword.is_empty() {
+            return;
+        }
+        if keyword == self.session.keyword() {
+            let text = "pick another keyword than the one of the tab";
+            self.notice = Some((String::from(text), Instant::now()));
+            return;
+        }
+
+        let other = match self
+            .tabs
+            .iter()
+            .enumerate()
+            .find(|(index, tab)| *index != self.active_tab && tab.session.keyword() == keyword)
+            .map(|(index, _)| index)
+        {
+            Some(index) => index,
+            None if self.tabs.len() >= MAX_TABS => {
+                let text = format!("close a tab to compare with '{}'", keyword);
+                self.notice = Some((text, Instant::now()));
+                return;
+            }
+            None => {
+                let session = self.start_session(keyword);
+                self.tabs.push(Tab::new(session));
+                self.tabs.len() - 1
+            }
+        };
+        self.compare = Some(compare::Compare::new(other));
+        self.current_screen = Screen::Compare;
+    }
+
+    /// Returns the rows of the comparison, the matching entries of the active
+    /// tab and of the compared one, aligned by time.
+    fn compare_rows(&self) -> Vec<compare::Row<'_>> {
+        let Some(tab) = self
+            .compare
+            .as_ref()
+            .and_then(|compare| self.tabs.get(compare.other))
+        else {
+            return Vec::new();
+        };
+        compare::align(self.session.matching(), tab.session.matching())
+    }
+
+    /// Goes to the entry of the row selected in the comparison, in the logs
+    /// of its tab, preferring the one of the active tab.
+    fn pick_compare(&mut self) {
+        self.current_screen = Screen::Main;
+        let Some(compare) = &self.compare else {
+            return;
+        };
+        let other = compare.other;
+        let selected = compare.state.selected().unwrap_or(0);
+        let picked = match self.compare_rows().get(selected) {
+            Some((Some(entry), _)) => Some(((*entry).clone(), None)),
+            Some((None, Some(entry))) => Some(((*entry).clone(), Some(other))),
+            _ => None,
+        };
+        self.compare = None;
+        let Some((entry, tab)) = picked else {
+            return;
+        };
+        if let Some(index) = tab {
+            self.switch_tab(index);
+        }
+        let position = self
+            .session
+            .matching()
+            .position(|e| e.path == entry.path && e.line_number == entry.line_number);
+        if let Some(index) = position {
+            self.nav_goto(index + 1);
+        }
+    }
+
+    /// Returns the entries the save action writes, as of the save scope, in
+    /// the order of the logs.
+    fn entries_to_save(&self) -> Vec<&sbsearch::Entry> {
+        match self.save_scope {
+            SaveScope::All => self.session.entries().iter().collect(),
+            SaveScope::Page => self.entries_offset.iter().collect(),
+            SaveScope::Matching => self.session.matching().collect(),
+            SaveScope::Bookmarked if self.bookmarks.is_empty() => {
+                self.selected_entry().into_iter().collect()
+            }
+            SaveScope::Bookmarked => self
+                .session
+                .entries()
+                .iter()
+                .filter(|e| {
+                    self.bookmarks
+                        .iter()
+                        .any(|b| b.path == e.path && b.line_number == e.line_number)
+                })
+                .collect(),
+        }
+    }
+
+    /// Opens the save dialog, with the file name defaulting to one with the
+    /// current time in the working directory.
+    fn open_save(&mut self) {
+        self.save_format = self.session.export_format();
+        self.save_input = Input::new(self.default_save_filename());
+        self.current_screen = Screen::ConfirmSave;
+    }
+
+    fn default_save_filename(&self) -> String {
+        format!(
+            "sbsearch_{}.{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+            self.save_format.extension()
+        )
+    }
+
+    /// Switches the save dialog to the next format, or the previous one if
+    /// `steps` is negative, along with the extension of the file name.
+    fn cycle_save_format(&mut self, steps: isize) {
+        let formats = ExportFormat::value_variants();
+        let index = formats
+            .iter()
+            .position(|f| *f == self.save_format)
+            .unwrap_or(0) as isize;
+        let format = formats[(index + steps).rem_euclid(formats.len() as isize) as usize];
+        let old_extension = format!(".{}", self.save_format.extension());
+        if let Some(name) = self.save_input.value().strip_suffix(&old_extension) {
+            self.save_input = Input::new(format!("{}.{}", name, format.extension()));
+        }
+        self.save_format = format;
+    }
+
+    /// Saves to the file entered in the save dialog, unless it exists, in
+    /// which case the overwrite is confirmed first.
+    fn confirm_save(&mut self) -> io::Result<()> {
+        let path = save_path(self.save_input.value(), &self.default_save_filename());
+        self.last_saved_filename = path.to_string_lossy().into_owned();
+        if path.exists() {
+            self.current_screen = Screen::ConfirmOverwrite;
+            return Ok(());
+        }
+        self.save_to_file()
+    }
+
+    fn save_to_file(&mut self) -> io::Result<()> {
+        let result = self.session.export_entries(
+            Path::new(self.last_saved_filename.as_str()),
+            &self.entries_to_save(),
+            self.save_format,
+        );
+        self.current_screen = Screen::Main;
+        result
+    }
+
+    /// Shows the error of a failed save, with the file name kept to edit and
+    /// retry.
+    fn save_failed(&mut self, e: io::Error) {
+        let text = format!("failed to save to {}: {}", self.last_saved_filename, e);
+        self.show_error("Save Failed", text, Some(Screen::ConfirmSave));
+    }
+
+    /// Shows `text` in an error popup over the logs, until it's dismissed or
+    /// retried on the `retry` screen.
+    fn show_error(&mut self, title: &'static str, text: String, retry: Option<Screen>) {
+        warn!("{}", text);
+        self.error = Some(ErrorPopup { title, text, retry });
+        self.current_screen = Screen::Error;
+    }
+
+    /// Closes the error popup, going back to the screen that failed if `retry`
+    /// and it can be retried, or to the logs.
+    fn close_error(&mut self, retry: bool) {
+        self.current_screen = match self.error.take() {
+            Some(ErrorPopup {
+                retry: Some(screen),
+                ..
+            }) if retry => screen,
+            Some(popup) if retry => {
+                self.error = Some(popup);
+                return;
+            }
+            _ => Screen::Main,
+        };
+    }
+
+    /// Collects the new entries found by watching the files, like `tail -f`.
+    /// When the last entry is selected, the selection moves on to the newest
+    /// one.
+    fn poll_watch(&mut self) {
+        if !self.session.is_watching() {
+            return;
+        }
+
+        let following = self.page_goto >= self.page_final
+            && self.nav_state.selected() == self.entries_offset.len().checked_sub(1);
+        if !self.session.watch() {
+            return;
+        }
+        if !following {
+            self.refresh_entries_from_sb();
+            return;
+        }
+        self.page_goto = self
+            .session
+            .stats()
+            .matching
+            .div_ceil(self.page_max_entries)
+            .max(1);
+        self.read_entries_from_sb();
+        self.nav_last_line();
+    }
+
+    /// Reloads the support bundle every refresh interval, counted from the
+    /// start of the last search.
+    fn refresh_if_due(&mut self) {
+        let Some(interval) = self.refresh_interval else {
+            return;
+        };
+        if self.session.is_searching() {
+            return;
+        }
+        match self.last_refresh {
+            Some(t) if t.elapsed() >= interval => self.reload(),
+            Some(_) => {}
+            None => self.last_refresh = Some(Instant::now()),
+        }
+    }
+
+    /// Drops the entries of every tab and searches the support bundle again,
+    /// e.g. after an updated bundle was extracted over it. The page and the
+    /// filters are kept, and once the search is done, the entry closest in
+    /// time to the selected one is selected again.
+    fn reload(&mut self) {
+        if self.session.is_replay() {
+            let text = "saved results can't be reloaded";
+            self.notice = Some((String::from(text), Instant::now()));
+            return;
+        }
+
+        self.session.cancel_search();
+        if let Err(e) = self.session.start_search() {
+            error!("error searching the support bundle: {}", e);
+            self.notice = Some((format!("reload failed: {}", e), Instant::now()));
+            return;
+        }
+        for (index, tab) in self.tabs.iter_mut().enumerate() {
+            if index == self.active_tab || tab.session.is_replay() {
+                continue;
+            }
+            tab.session.cancel_search();
+            if let Err(e) = tab.session.start_search() {
+                error!("error searching the support bundle: {}", e);
+            }
+        }
+        self.reselect = self.selected_entry().and_then(|entry| entry.timestamp);
+        self.last_refresh = Some(Instant::now());
+    }
+
+    fn autosave_if_due(&mut self) {
+        let Some(dir) = &self.autosave_dir else {
+            return;
+        };
+        if self
+            .last_autosave
+            .is_some_and(|t| t.elapsed() < self.autosave_interval)
+        {
+            return;
+        }
+
+        if let Err(e) = self.session.autosave(dir) {
+            warn!("failed to autosave to {}: {}", dir.display(), e);
+        }
+        self.last_autosave = Some(Instant::now());
+    }
+
+    /// Reloads the knowledge base file if it was modified since it was last
+    /// read, and re-ranks the suspects with the new rules. A file that fails
+    /// to load leaves the current rules in place.
+    fn reload_knowledge_base_if_changed(&mut self) {
+        let Some(path) = &self.kb_file else {
+            return;
+        };
+        let modified = modified(path);
+        if modified == self.kb_modified {
+            return;
+        }
+        self.kb_modified = modified;
+
+        let text = match kb::KnowledgeBase::load(path) {
+            Ok(knowledge_base) => {
+                info!("reloaded knowledge base from {}", path.display());
+                self.knowledge_base = knowledge_base;
+                if !self.session.is_searching() {
+                    self.suspects = analyze::suspects(
+                        self.session.entries(),
+                        self.session.root_dir(),
+                        &self.knowledge_base,
+                    );
+                }
+                format!("reloaded {}", path.display())
+            }
+            Err(e) => {
+                warn!("failed to reload knowledge base: {}", e);
+                format!("failed to reload: {}", e)
+            }
+        };
+        self.notice = Some((text, Instant::now()));
     }
 
-    fn save_to_file(&mut self) -> io::Result<()> {
-        if let Ok(file) = std::fs::File::create(&self.last_saved_filename) {
-            info!("saving to file '{}'", &self.last_saved_filename);
-            let mut writer = BufWriter::new(&file);
-            for entry in &self.entries_cache {
-                write!(writer, "{}", entry)?;
-            }
+    /// Stops the search of the active tab, keeping the entries found so far.
+    fn cancel_search(&mut self) {
+        if !self.session.is_searching() {
+            return;
         }
-        self.current_screen = Screen::Main;
-        Ok(())
+
+        self.session.cancel_search();
+        self.refresh_entries_from_sb();
+        let text = format!(
+            "search cancelled, showing the {} entries found so far",
+            format::count(self.stats.entries)
+        );
+        self.notice = Some((text, Instant::now()));
     }
 
     fn exit(&mut self) {
         info!("exiting sbsearch TUI");
+        self.session.cancel_search();
+        for tab in self.tabs.iter_mut() {
+            tab.session.cancel_search();
+        }
         self.exit = true
     }
 
     fn draw_main(&mut self, frame: &mut Frame) {
         let sections = render::split_main_layout(frame.area());
         let offset = self.page_goto * self.page_max_entries - self.page_max_entries;
+        // the selected entry is located by its path and line number, to quote
+        // in bug reports
         let (filepath, selected) = match self.nav_state.selected() {
             Some(pos) => {
                 if self.entries_offset.is_empty() {
-                    ("", 0)
+                    (String::new(), 0)
                 } else {
-                    let path_str = self.entries_offset[pos].path.as_str();
-                    let name_str = self.sbpath.as_str();
+                    let entry = &self.entries_offset[pos];
+                    let path_str = entry.path.as_str();
+                    let name_str = self.session.root_dir();
                     if let Some(index) = path_str.find(name_str) {
                         (
-                            &path_str[index + name_str.len()..path_str.len()],
+                            format!(
+                                "{}:{}",
+                                &path_str[index + name_str.len()..path_str.len()],
+                                entry.line_number
+                            ),
                             offset + pos + 1,
                         )
                     } else {
-                        ("", 0)
+                        (String::new(), 0)
                     }
                 }
             }
-            None => ("", 0),
+            None => (String::new(), 0),
         };
+        let file_matches = self
+            .selected_entry()
+            .map(|entry| self.session.file_count(&entry.path));
+        let selected_timestamp = self
+            .nav_state
+            .selected()
+            .and_then(|pos| self.entries_offset.get(pos))
+            .and_then(|entry| entry.timestamp);
+        let source = self
+            .nav_state
+            .selected()
+            .and_then(|pos| self.entries_offset.get(pos))
+            .and_then(|entry| entry.breadcrumb());
+        let hint = self
+            .nav_state
+            .selected()
+            .and_then(|pos| self.entries_offset.get(pos))
+            .and_then(|entry| self.knowledge_base.lookup(&entry.content))
+            .map(String::from);
+        let tab_titles = self.tab_titles();
         let scroll_width = sections[2].width.max(3) - 3;
         let search_scroll = self.search_input.visual_scroll(scroll_width as usize);
         let search_cursor_pos =
             self.search_input.visual_cursor().max(search_scroll) - search_scroll + 8;
         let search_cursor_show = self.search_mode == SearchMode::Insert;
 
+        let anchor = self.anchor_time();
+        let timestamp = selected_timestamp.map(|t| {
+            let timestamp = self.display_tz.format(t, "%Y-%m-%d %H:%M:%S%.3f %:z");
+            match anchor {
+                Some(anchor) => format!("{} ({})", timestamp, render::relative(t, anchor)),
+                None => timestamp,
+            }
+        });
+        let summary = &self.stats.summary;
+        let summary = match (summary.first, summary.last) {
+            (Some(first), Some(last)) => format!(
+                "{} | {} → {}",
+                summary.levels_summary(),
+                self.display_tz.format(first, "%Y-%m-%d %H:%M:%S"),
+                self.display_tz.format(last, "%Y-%m-%d %H:%M:%S")
+            ),
+            _ => summary.levels_summary(),
+        };
+        // the matcher is built once per keyword, as the tabs are switched
+        let keyword = self.session.keyword();
+        if self
+            .keyword_matcher
+            .as_ref()
+            .is_none_or(|(k, _)| k != keyword)
+        {
+            self.keyword_matcher = sbsearch::keyword_matcher(keyword, self.session.options())
+                .ok()
+                .map(|matcher| (String::from(keyword), matcher));
+        }
+        // the entry pane starts from the top of each newly selected entry
+        let detail_entry = match self.show_split {
+            true => self.selected_entry().cloned(),
+            false => None,
+        };
+        let detail_for = detail_entry
+            .as_ref()
+            .map(|entry| (entry.path.clone(), entry.line_number));
+        if detail_for != self.detail_for {
+            self.detail_for = detail_for;
+            self.detail_scroll = (0, 0);
+        }
         let mut r = render::Renderer::new(
-            String::from(filepath),
-            self.keyword.clone(),
+            filepath,
+            file_matches,
+            source,
+            timestamp,
+            summary,
+            hint,
+            self.session.query().to_string(),
+            String::from(self.session.keyword()),
+            self.session.options().keyword_mode(),
             self.page_final,
             self.page_goto,
-            self.entries_cache.len(),
+            self.stats.matching,
+            render::CacheUsage {
+                entries: self.stats.entries,
+                bytes: self.stats.cache_bytes,
+                budget: self.session.options().memory_budget,
+            },
+            self.session.is_searching(),
+            self.session.timed_out(),
             selected,
-            self.sbpath.clone(),
+            String::from(self.session.root_dir()),
             search_cursor_pos as u16,
             search_cursor_show,
             search_scroll as u16,
             self.search_input.value().to_string(),
+            self.search_regex,
+            self.search_error.clone(),
+            self.keyword_matcher.as_ref().map(|(_, matcher)| matcher),
+            render::DisplayOptions {
+                wrap: self.wrap,
+                horizontal_scroll: self.horizontal_scroll,
+                entry_scroll: self.entry_scroll,
+                columns: self.show_columns,
+                show_source: self.show_source,
+                logfmt: self.show_logfmt,
+                tz: self.display_tz,
+                anchor,
+            },
+            &self.bookmarks,
+            self.context.as_ref(),
             &self.entries_offset,
             &mut self.nav_state,
             self.vertical_scroll_state,
         );
         r.render_title_section(sections[0], frame);
+        if self.tabs.len() > 1 {
+            render::draw_tabs(&tab_titles, self.active_tab, sections[0], frame);
+        }
         r.render_meta_section(sections[1], frame);
         r.render_search_section(sections[2], frame);
-        r.render_logs_section(sections[3], frame);
+        let mut logs_area = sections[3];
+        if self.show_files {
+            self.file_tree.refresh(&self.session);
+            let logs = render::split_files_layout(logs_area);
+            render::draw_files(
+                &mut self.file_tree,
+                self.session.path(),
+                self.focus == Focus::Files,
+                logs[0],
+                frame,
+            );
+            logs_area = logs[1];
+        }
+        if self.show_split {
+            let logs = render::split_detail_layout(logs_area);
+            render::draw_detail_pane(
+                detail_entry.as_ref(),
+                self.display_tz,
+                anchor,
+                self.json_depth,
+                self.detail_scroll,
+                self.focus == Focus::Detail,
+                logs[1],
+                frame,
+            );
+            logs_area = logs[0];
+        }
+        if !self.pinned.is_empty() {
+            let logs = render::split_pinned_layout(logs_area, self.pinned.len());
+            render::draw_pinned(&self.pinned, logs[0], frame);
+            logs_area = logs[1];
+        }
+        if self.show_rate {
+            let logs = render::split_rate_layout(logs_area);
+            render::draw_rate(
+                &self.rate,
+                selected_timestamp,
+                self.display_tz,
+                logs[0],
+                frame,
+            );
+            logs_area = logs[1];
+        }
+        self.logs_height = logs_area.height.saturating_sub(2) as usize;
+        r.render_logs_section(logs_area, frame);
+        self.entry_overflow = r.selected_overflow;
+        self.entry_scroll = self.entry_scroll.min(self.entry_overflow);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame) {
+        if let Some(entry) = self.selected_entry() {
+            render::draw_detail(
+                entry,
+                self.display_tz,
+                self.anchor_time(),
+                self.json_depth,
+                self.detail_scroll,
+                frame,
+            );
+        }
+    }
+
+    fn selected_entry(&self) -> Option<&sbsearch::Entry> {
+        self.nav_state
+            .selected()
+            .and_then(|pos| self.entries_offset.get(pos))
+    }
+
+    /// Shows the whole of the selected entry, unwrapped, with the fields of
+    /// its JSON line.
+    fn open_detail(&mut self) {
+        if self.selected_entry().is_some() {
+            self.detail_scroll = (0, 0);
+            self.current_screen = Screen::Detail;
+        }
+    }
+
+    /// Shows the file of the selected entry, scrolled to the entry, e.g. to
+    /// see what was logged right before it.
+    fn open_source(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        match source::SourceView::open(entry) {
+            Ok(view) => {
+                self.source_view = Some(view);
+                self.current_screen = Screen::Source;
+            }
+            Err(e) => {
+                let text = format!("failed to open {}: {}", entry.path, e);
+                self.show_error("Open Failed", text, None);
+            }
+        }
+    }
+
+    /// Expands the selected entry in place with the lines around it in its
+    /// file, or collapses it if it's already expanded. One entry is expanded
+    /// at a time.
+    fn toggle_context(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if self
+            .context
+            .as_ref()
+            .is_some_and(|context| context.is_of(entry))
+        {
+            self.context = None;
+            return;
+        }
+        match source::Context::read(entry, self.context_lines) {
+            Ok(context) => self.context = Some(context),
+            Err(e) => {
+                let text = format!("failed to open {}: {}", entry.path, e);
+                self.show_error("Open Failed", text, None);
+            }
+        }
+    }
+
+    /// Suspends the TUI to open the file of the selected entry at its line in
+    /// `$EDITOR` or `$PAGER`, and restores it once the program exits.
+    fn open_external(&mut self, terminal: &mut DefaultTerminal) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let line = entry.line_number;
+        let path = entry.path.clone();
+        let result = external::ExtractedFile::new(&path).and_then(|file| {
+            ratatui::restore();
+            let status = external::open(&file, line);
+            *terminal = ratatui::init();
+            Ok(status?)
+        });
+        match result {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                let text = format!("viewer of {} exited with {}", path, status);
+                warn!("{}", text);
+                self.notice = Some((text, Instant::now()));
+            }
+            Err(e) => {
+                let text = format!("failed to open {}: {}", path, e);
+                self.show_error("Open Failed", text, None);
+            }
+        }
+    }
+
+    /// Copies the content of the selected entry to the system clipboard, each
+    /// line prefixed with its `path:line` when `located`. The terminal sets the
+    /// clipboard (OSC 52), so it works over SSH too.
+    fn copy_selected(&mut self, located: bool) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let text = self.clipboard_text(entry, located);
+        let copied = crossterm::execute!(
+            io::stdout(),
+            crossterm::clipboard::CopyToClipboard::to_clipboard_from(&text)
+        );
+        let notice = match copied {
+            Ok(()) => format!("copied {} lines to the clipboard", text.lines().count()),
+            Err(e) => {
+                warn!("failed to copy to the clipboard: {}", e);
+                format!("failed to copy to the clipboard: {}", e)
+            }
+        };
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    fn clipboard_text(&self, entry: &sbsearch::Entry, located: bool) -> String {
+        if !located {
+            return entry.content.trim_end().to_string();
+        }
+        let path = entry
+            .path
+            .strip_prefix(self.session.root_dir())
+            .unwrap_or(&entry.path)
+            .trim_start_matches('/');
+        entry
+            .content
+            .lines()
+            .enumerate()
+            .map(|(n, line)| format!("{}:{}: {}", path, entry.line_number + n as u64, line))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn close_source(&mut self) {
+        self.source_view = None;
+        self.current_screen = Screen::Main;
+    }
+
+    /// Shows the timestamp and level of the entries in aligned columns before
+    /// their content, or hides them.
+    fn toggle_columns(&mut self) {
+        self.show_columns = !self.show_columns;
+    }
+
+    /// Prefixes the entries with where they come from, or stops.
+    fn toggle_source(&mut self) {
+        self.show_source = !self.show_source;
+    }
+
+    /// Shows the timestamps in local time, or back in the timezone they were
+    /// shown in before, which is UTC if that was local time too.
+    fn toggle_local_time(&mut self) {
+        self.display_tz = match self.display_tz {
+            AssumeTz::Local => self.switched_tz.take().unwrap_or_default(),
+            tz => {
+                self.switched_tz = Some(tz);
+                AssumeTz::Local
+            }
+        };
+        let text = match self.display_tz {
+            AssumeTz::Local => String::from("showing the timestamps in local time"),
+            AssumeTz::Utc => String::from("showing the timestamps in UTC"),
+            AssumeTz::Fixed(offset) => format!("showing the timestamps in {}", offset),
+        };
+        self.notice = Some((text, Instant::now()));
+    }
+
+    /// Counts the timestamps from the selected entry, shown in the columns,
+    /// or shows the times of day again if it's already the anchor.
+    fn toggle_anchor(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if self
+            .anchor
+            .as_ref()
+            .is_some_and(|a| a.path == entry.path && a.line_number == entry.line_number)
+        {
+            self.anchor = None;
+            return;
+        }
+        if entry.timestamp.is_none() {
+            let text = "the entry has no timestamp to count from";
+            self.notice = Some((String::from(text), Instant::now()));
+            return;
+        }
+        self.anchor = Some(entry.clone());
+        self.show_columns = true;
+    }
+
+    /// Returns the timestamp of the anchor entry, which the timestamps are
+    /// shown relative to.
+    fn anchor_time(&self) -> Option<DateTime<Utc>> {
+        self.anchor.as_ref().and_then(|entry| entry.timestamp)
+    }
+
+    /// Highlights the logfmt pairs of the entries in the logs too, not only in
+    /// the detail view, or stops.
+    fn toggle_logfmt(&mut self) {
+        self.show_logfmt = !self.show_logfmt;
+    }
+
+    /// Switches between wrapping the entries and showing each on one line,
+    /// which scrolls sideways instead.
+    fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.horizontal_scroll = 0;
+    }
+
+    /// Scrolls the entries by `columns` right, up to the end of the longest
+    /// one on the page. Wrapped entries don't scroll sideways.
+    fn scroll_logs(&mut self, columns: isize) {
+        if self.wrap {
+            return;
+        }
+        let width = self
+            .entries_offset
+            .iter()
+            .map(|entry| render::single_line(&entry.content, 0).chars().count())
+            .max()
+            .unwrap_or_default();
+        self.horizontal_scroll = self
+            .horizontal_scroll
+            .saturating_add_signed(columns)
+            .min(width.saturating_sub(1));
+    }
+
+    fn open_help(&mut self) {
+        self.help_scroll = 0;
+        self.current_screen = Screen::Help;
+    }
+
+    /// Scrolls the help screen by `rows` down, up to its last line.
+    fn scroll_help(&mut self, rows: i32) {
+        let last = help::line_count().saturating_sub(1) as i32;
+        self.help_scroll = (self.help_scroll as i32 + rows).clamp(0, last) as u16;
+    }
+
+    /// Scrolls the detail view by `rows` down and `columns` right, up to the
+    /// last line and the end of the longest one.
+    fn scroll_detail(&mut self, rows: i32, columns: i32) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let lines =
+            render::detail_lines(entry, self.display_tz, self.anchor_time(), self.json_depth);
+        let height = lines.len().saturating_sub(1) as i32;
+        let width = lines.iter().map(Line::width).max().unwrap_or_default() as i32;
+        let (row, column) = self.detail_scroll;
+        self.detail_scroll = (
+            (row as i32 + rows).clamp(0, height) as u16,
+            (column as i32 + columns).clamp(0, (width - 1).max(0)) as u16,
+        );
+    }
+
+    /// Folds the JSON of the selected entry by one more level of nesting, or
+    /// unfolds it by one if `fold` is false. The levels are kept for the
+    /// other entries.
+    fn fold_json(&mut self, fold: bool) {
+        let Some(value) = self
+            .selected_entry()
+            .and_then(|entry| sbsearch::json_value(entry.content.lines().next()?))
+        else {
+            return;
+        };
+        let depth = json::depth(&value);
+        let current = self.json_depth.min(depth);
+        self.json_depth = match fold {
+            true => current.saturating_sub(1),
+            false if current + 1 >= depth => usize::MAX,
+            false => current + 1,
+        };
+        self.scroll_detail(0, 0);
     }
 
     fn draw_popup(&self, title: &str, text: &str, width: u16, height: u16, frame: &mut Frame) {
@@ -212,6 +1952,13 @@ impl Tui {
         if self.entries_offset.is_empty() {
             return;
         }
+        // an entry taller than the logs scrolls through before moving on
+        if self.entry_scroll < self.entry_overflow {
+            self.entry_scroll += 1;
+            return;
+        }
+        self.entry_scroll = 0;
+        self.entry_overflow = 0;
 
         self.vertical_scroll = self.vertical_scroll.saturating_add(1);
         self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
@@ -229,6 +1976,11 @@ impl Tui {
     }
 
     fn nav_prev_line(&mut self) {
+        if self.entry_scroll > 0 {
+            self.entry_scroll -= 1;
+            return;
+        }
+        self.entry_overflow = 0;
         self.vertical_scroll = self.vertical_scroll.saturating_sub(1);
         self.vertical_scroll_state = self.vertical_scroll_state.position(self.vertical_scroll);
         let i = match self.nav_state.selected() {
@@ -244,12 +1996,32 @@ impl Tui {
         self.nav_state.select(Some(i));
     }
 
+    /// Moves the selection by `pages` times the height of the logs, counting
+    /// an entry per row, within the current page.
+    fn nav_lines(&mut self, pages: f32) {
+        if self.entries_offset.is_empty() {
+            return;
+        }
+        let rows = ((self.logs_height as f32 * pages.abs()) as usize).max(1);
+        let current = self.nav_state.selected().unwrap_or(0);
+        let index = match pages < 0.0 {
+            true => current.saturating_sub(rows),
+            false => (current + rows).min(self.entries_offset.len() - 1),
+        };
+        self.entry_scroll = 0;
+        self.vertical_scroll = index;
+        self.vertical_scroll_state = self.vertical_scroll_state.position(index);
+        self.nav_state.select(Some(index));
+    }
+
     fn nav_first_line(&mut self) {
+        self.entry_scroll = 0;
         self.vertical_scroll_state = self.vertical_scroll_state.position(0);
         self.nav_state.select(Some(0));
     }
 
     fn nav_last_line(&mut self) {
+        self.entry_scroll = 0;
         if !self.entries_offset.is_empty() {
             let end = self.entries_offset.len() - 1;
             self.vertical_scroll_state = self.vertical_scroll_state.position(end);
@@ -257,6 +2029,79 @@ impl Tui {
         }
     }
 
+    /// Selects the entry numbered `number` among the matching entries, as
+    /// shown by the meta section, on whichever page it is.
+    fn nav_goto_entry(&mut self, number: &str) {
+        let number = match number.trim().parse::<usize>() {
+            Ok(number) if (1..=self.stats.matching).contains(&number) => number,
+            _ => {
+                let text = format!(
+                    "no entry '{}', expected 1 to {}",
+                    number.trim(),
+                    self.stats.matching
+                );
+                self.notice = Some((text, Instant::now()));
+                return;
+            }
+        };
+        self.nav_goto(number);
+    }
+
+    /// Selects the entry numbered `number`, from 1, loading its page.
+    fn nav_goto(&mut self, number: usize) {
+        let page = (number - 1) / self.page_max_entries + 1;
+        if page != self.page_goto || self.page_reload {
+            self.page_goto = page;
+            self.read_entries_from_sb();
+        }
+        let index = (number - 1) % self.page_max_entries;
+        self.entry_scroll = 0;
+        self.vertical_scroll_state = self.vertical_scroll_state.position(index);
+        self.nav_state.select(Some(index));
+    }
+
+    /// Selects the first error in time among the entries matching the
+    /// keyword, on whichever page it is, which is usually where to start.
+    fn nav_first_error(&mut self) {
+        // the errors without a timestamp come after the ones with one
+        let Some(first) = self
+            .session
+            .entries()
+            .iter()
+            .filter(|entry| entry.level >= sbsearch::Level::Error)
+            .min_by_key(|entry| (entry.timestamp.is_none(), entry.timestamp))
+        else {
+            self.notice = Some((String::from("no errors to go to"), Instant::now()));
+            return;
+        };
+        let index = self
+            .session
+            .matching()
+            .position(|e| e.path == first.path && e.line_number == first.line_number);
+        match index {
+            Some(index) => self.nav_goto(index + 1),
+            None => {
+                let text = "the first error is hidden by the filter";
+                self.notice = Some((String::from(text), Instant::now()));
+            }
+        }
+    }
+
+    /// Selects the matching entry closest in time to `timestamp`, on
+    /// whichever page it is.
+    fn nav_goto_time(&mut self, timestamp: DateTime<Utc>) {
+        let closest = self
+            .session
+            .matching()
+            .enumerate()
+            .filter_map(|(index, entry)| entry.timestamp.map(|t| (index, (t - timestamp).abs())))
+            .min_by_key(|(_, delta)| *delta)
+            .map(|(index, _)| index);
+        if let Some(index) = closest {
+            self.nav_goto(index + 1);
+        }
+    }
+
     fn nav_next_page(&mut self) {
         if self.page_goto < self.page_final {
             self.page_goto = self.page_goto.saturating_add(1);
@@ -284,6 +2129,36 @@ impl Tui {
     }
 }
 
+/// Resolves the file name entered in the save dialog: a leading `~` is the
+/// home directory, and directories, or names ending with a `/`, get
+/// `default_name` appended.
+fn save_path(input: &str, default_name: &str) -> PathBuf {
+    let input = input.trim();
+    let mut path = match (input.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(input),
+    };
+    if input.is_empty() || input.ends_with('/') || path.is_dir() {
+        path.push(default_name);
+    }
+    path
+}
+
+/// Returns the gist of why a search isn't a valid regex, e.g. `unclosed
+/// group`, short enough for the border of the search box.
+fn regex_error(e: &regex::Error) -> String {
+    let text = e.to_string();
+    let last = text.lines().last().unwrap_or_default();
+    String::from(last.strip_prefix("error: ").unwrap_or(last))
+}
+
+/// Returns the modification time of the file, or `None` if it can't be read.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -302,7 +2177,7 @@ mod tests {
         // there are 218 entries containing "vm-00" in the testdata support bundle.
         // after paging, only 100 entries are loaded into entries_offset with a total
         // of 3 pages.
-        assert_eq!(tui.entries_cache.len(), 244);
+        assert_eq!(tui.stats.entries, 244);
         assert_eq!(tui.entries_offset.len(), DEFAULT_MAX_ENTRIES_PER_PAGE);
         assert_eq!(tui.page_final, 3);
         assert_eq!(tui.nav_state, ListState::default().with_selected(Some(0)));
@@ -312,7 +2187,7 @@ mod tests {
         let keyword = "vm-00-disk-0-";
         let mut tui = Tui::new(path, keyword);
         tui.read_entries_from_sb();
-        assert_eq!(tui.entries_cache.len(), 72);
+        assert_eq!(tui.stats.entries, 72);
         assert_eq!(tui.entries_offset.len(), 72);
         assert_eq!(tui.page_final, 1);
         assert_eq!(tui.nav_state, ListState::default().with_selected(Some(0)));
@@ -320,6 +2195,254 @@ mod tests {
         tui.exit();
     }
 
+    #[test]
+    fn test_read_entries_with_invalid_keyword() {
+        let options = sbsearch::SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        let mut tui = Tui::new("./testdata/support_bundle", "vm-(").with_options(options);
+        tui.read_entries_from_sb();
+        assert!(tui.entries_offset.is_empty());
+        assert!(tui.notice.as_ref().unwrap().0.starts_with("search failed"));
+    }
+
+    #[test]
+    fn test_search_timed_out() {
+        let options = sbsearch::SearchOptions {
+            timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let mut tui = Tui::new("./testdata/support_bundle", "vm-00").with_options(options);
+        tui.read_entries_from_sb();
+        assert!(tui.session.timed_out());
+        assert_eq!(tui.stats.entries, 0);
+
+        tui.notify_warnings();
+        assert!(
+            tui.notice
+                .as_ref()
+                .unwrap()
+                .0
+                .starts_with("search timed out")
+        );
+    }
+
+    #[test]
+    fn test_read_entries_with_query() {
+        let path = "./testdata/support_bundle";
+        let keyword = "vm-00";
+        let mut tui = Tui::new(path, keyword);
+        tui.session.filter("ns=default".parse().unwrap());
+        tui.read_entries_from_sb();
+
+        // the cache holds all matches, while the pages only hold the entries
+        // of the 'default' namespace
+        assert_eq!(tui.stats.entries, 244);
+        assert_eq!(tui.stats.matching, 44);
+        assert_eq!(tui.entries_offset.len(), 44);
+        assert_eq!(tui.page_final, 1);
+        assert!(
+            tui.entries_offset
+                .iter()
+                .all(|e| e.path.contains("/logs/default/"))
+        );
+    }
+
+    fn wait_for_search(tui: &mut Tui) {
+        while tui.session.is_searching() {
+            if tui.session.poll() {
+                tui.refresh_entries_from_sb();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_streaming_search() {
+        let path = "./testdata/support_bundle";
+        let mut tui = Tui::new(path, "vm-00");
+        tui.session.start_search().unwrap();
+        assert!(tui.session.is_searching());
+
+        wait_for_search(&mut tui);
+        assert_eq!(tui.stats.entries, 244);
+        assert_eq!(tui.entries_offset.len(), DEFAULT_MAX_ENTRIES_PER_PAGE);
+        assert_eq!(tui.page_final, 3);
+
+        // the selected line is kept when the page is refreshed
+        tui.nav_state.select(Some(5));
+        tui.refresh_entries_from_sb();
+        assert_eq!(tui.nav_state.selected(), Some(5));
+
+        tui.find_suspects();
+        assert!(!tui.suspects.is_empty());
+        assert_eq!(tui.current_screen, Screen::Suspects);
+    }
+
+    #[test]
+    fn test_tabs() {
+        let path = "./testdata/support_bundle";
+        let mut tui = Tui::new(path, "vm-00");
+        tui.read_entries_from_sb();
+        tui.nav_next_page();
+        tui.read_entries_from_sb();
+
+        tui.open_tab("vm-00-disk-0-");
+        wait_for_search(&mut tui);
+        tui.read_entries_from_sb();
+        assert_eq!(tui.stats.entries, 72);
+        assert_eq!(tui.page_goto, 1);
+
+        // the first tab is restored on the page it was left on
+        tui.switch_tab(0);
+        assert_eq!(tui.stats.entries, 244);
+        assert_eq!(tui.page_goto, 2);
+        assert_eq!(tui.entries_offset.len(), DEFAULT_MAX_ENTRIES_PER_PAGE);
+        assert_eq!(tui.tabs[1].session.keyword(), "vm-00-disk-0-");
+    }
+
+    #[test]
+    fn test_compare() {
+        let path = "./testdata/support_bundle";
+        let mut tui = Tui::new(path, "vm-00");
+        tui.read_entries_from_sb();
+
+        // the keyword of the tab itself isn't compared
+        tui.open_compare();
+        assert_eq!(tui.current_screen, Screen::CompareWith);
+        assert_eq!(tui.compare_input.value(), "");
+        tui.start_compare("vm-00");
+        assert!(tui.compare.is_none());
+
+        // the other keyword is searched in a tab, without switching to it
+        tui.start_compare("vm-00-disk-0-");
+        assert_eq!(tui.current_screen, Screen::Compare);
+        assert_eq!(tui.tab_titles(), vec!["vm-00", "vm-00-disk-0-"]);
+        assert_eq!(tui.session.keyword(), "vm-00");
+        while tui.tabs[1].session.is_searching() {
+            tui.tabs[1].session.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let rows = tui.compare_rows();
+        assert_eq!(rows.iter().filter(|(left, _)| left.is_some()).count(), 244);
+        assert_eq!(rows.iter().filter(|(_, right)| right.is_some()).count(), 72);
+        let timestamps: Vec<_> = rows
+            .iter()
+            .map(|(left, right)| left.or(*right).unwrap().timestamp)
+            .collect();
+        assert!(timestamps.is_sorted());
+
+        // the next tab is offered the next time
+        tui.open_compare();
+        assert_eq!(tui.compare_input.value(), "vm-00-disk-0-");
+        tui.start_compare("vm-00-disk-0-");
+        assert_eq!(tui.tabs.len(), 2);
+
+        // picking a row goes to its entry in the logs of its tab
+        let last = tui.compare_rows().len() - 1;
+        let (left, right) = tui.compare_rows()[last];
+        let tab = if left.is_some() { 0 } else { 1 };
+        let expected = left.or(right).map(|e| (e.path.clone(), e.line_number));
+        tui.compare.as_mut().unwrap().state.select(Some(last));
+        tui.pick_compare();
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(tui.compare.is_none());
+        assert_eq!(tui.active_tab, tab);
+        let selected = tui
+            .selected_entry()
+            .map(|e| (e.path.clone(), e.line_number));
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = "./testdata/support_bundle";
+        let mut tui = Tui::new(path, "vm-00").with_refresh(Duration::from_secs(3600));
+        tui.session.start_search().unwrap();
+        wait_for_search(&mut tui);
+        tui.nav_goto(150);
+        let selected = tui.selected_entry().unwrap().clone();
+
+        // the refresh isn't due before an interval has passed
+        tui.refresh_if_due();
+        assert!(!tui.session.is_searching());
+        tui.last_refresh = Instant::now().checked_sub(Duration::from_secs(3601));
+        tui.refresh_if_due();
+        assert!(tui.session.is_searching());
+        assert_eq!(tui.reselect, selected.timestamp);
+
+        // the page is kept, and the entry at the same time selected again
+        wait_for_search(&mut tui);
+        assert_eq!(tui.stats.entries, 244);
+        assert_eq!(tui.page_goto, 2);
+        let timestamp = tui.reselect.take().unwrap();
+        tui.nav_goto_time(timestamp);
+        assert_eq!(tui.selected_entry().unwrap().timestamp, selected.timestamp);
+    }
+
+    #[test]
+    fn test_autosave_if_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = "./testdata/support_bundle";
+        let mut tui = Tui::new(path, "vm-00-disk-0-")
+            .with_autosave(dir.path().to_path_buf(), Duration::from_secs(3600));
+        tui.read_entries_from_sb();
+
+        tui.autosave_if_due();
+        let autosave = dir.path().join("autosave.jsonl");
+        assert!(autosave.exists());
+
+        // the next autosave isn't due yet
+        std::fs::remove_file(&autosave).unwrap();
+        tui.autosave_if_due();
+        assert!(!autosave.exists());
+    }
+
+    #[test]
+    fn test_reload_knowledge_base_if_changed() {
+        let file = NamedTempFile::new().unwrap();
+        let write = |content: &str, secs: u64| {
+            std::fs::write(file.path(), content).unwrap();
+            File::options()
+                .write(true)
+                .open(file.path())
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        write("", 1);
+        let mut tui = Tui::new("./testdata/support_bundle", "vm-00")
+            .with_knowledge_base_file(file.path().to_path_buf());
+
+        // nothing changed
+        tui.reload_knowledge_base_if_changed();
+        assert!(tui.notice.is_none());
+
+        write("stuck in reconcile loop => https://example.com/kb/1", 2);
+        tui.reload_knowledge_base_if_changed();
+        assert_eq!(
+            tui.knowledge_base.lookup("stuck in reconcile loop"),
+            Some("https://example.com/kb/1")
+        );
+        assert!(tui.notice.as_ref().unwrap().0.starts_with("reloaded"));
+
+        // an invalid file keeps the current rules
+        write("no url here", 3);
+        tui.reload_knowledge_base_if_changed();
+        assert_eq!(
+            tui.knowledge_base.lookup("stuck in reconcile loop"),
+            Some("https://example.com/kb/1")
+        );
+        assert!(
+            tui.notice
+                .as_ref()
+                .unwrap()
+                .0
+                .starts_with("failed to reload")
+        );
+    }
+
     #[test]
     fn test_save_to_file() {
         let path = "./testdata/support_bundle/logs";
@@ -337,9 +2460,137 @@ mod tests {
         let opened = File::open(file.path()).unwrap();
         let reader = BufReader::new(opened);
         let mut num_lines = 0;
-        for _line in reader.lines() {
-            num_lines += 1;
+        for line in reader.lines() {
+            if !line.unwrap().starts_with('#') {
+                num_lines += 1;
+            }
         }
-        assert_eq!(num_lines, tui.entries_cache.len());
+        assert_eq!(num_lines, tui.stats.entries);
+    }
+
+    #[test]
+    fn test_save_path() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(
+            save_path("~/out.log", "default.log"),
+            Path::new(&home).join("out.log")
+        );
+        assert_eq!(
+            save_path("~", "default.log"),
+            Path::new(&home).join("default.log")
+        );
+        assert_eq!(
+            save_path("~user/out.log", "d.log"),
+            Path::new("~user/out.log")
+        );
+        assert_eq!(
+            save_path(" testdata ", "default.log"),
+            Path::new("testdata/default.log")
+        );
+        assert_eq!(
+            save_path("missing/", "default.log"),
+            Path::new("missing/default.log")
+        );
+        assert_eq!(save_path("", "default.log"), Path::new("default.log"));
+        assert_eq!(save_path("out.log", "default.log"), Path::new("out.log"));
+    }
+
+    #[test]
+    fn test_confirm_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tui = Tui::new("testdata/support_bundle", "vm-00");
+        tui.read_entries_from_sb();
+
+        let path = dir.path().join("saved.log");
+        tui.open_save();
+        assert!(tui.save_input.value().starts_with("sbsearch_"));
+        tui.save_input = Input::new(path.to_string_lossy().into_owned());
+        tui.confirm_save().unwrap();
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(path.exists());
+
+        // an existing file is only overwritten once confirmed
+        tui.open_save();
+        tui.save_input = Input::new(path.to_string_lossy().into_owned());
+        tui.confirm_save().unwrap();
+        assert_eq!(tui.current_screen, Screen::ConfirmOverwrite);
+
+        // a directory gets the default name
+        tui.open_save();
+        tui.save_input = Input::new(dir.path().to_string_lossy().into_owned());
+        tui.confirm_save().unwrap();
+        assert_eq!(tui.current_screen, Screen::Main);
+        assert!(Path::new(&tui.last_saved_filename).starts_with(dir.path()));
+        assert!(Path::new(&tui.last_saved_filename).exists());
+    }
+
+    #[test]
+    fn test_entries_to_save() {
+        let mut tui = Tui::new("testdata/support_bundle", "vm-00");
+        tui.session.filter("ns=default".parse().unwrap());
+        tui.read_entries_from_sb();
+
+        assert_eq!(tui.save_scope, SaveScope::Matching);
+        assert_eq!(tui.entries_to_save().len(), 44);
+        tui.save_scope = SaveScope::All;
+        assert_eq!(tui.entries_to_save().len(), 244);
+        tui.page_max_entries = 10;
+        tui.read_entries_from_sb();
+        tui.save_scope = SaveScope::Page;
+        assert_eq!(tui.entries_to_save().len(), 10);
+
+        // the selected entry is saved until there are bookmarks
+        tui.save_scope = SaveScope::Bookmarked;
+        tui.nav_state.select(Some(3));
+        let saved = tui.entries_to_save();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].line_number, tui.entries_offset[3].line_number);
+        tui.toggle_bookmark();
+        tui.nav_goto(40);
+        tui.toggle_bookmark();
+        assert_eq!(tui.entries_to_save().len(), 2);
+
+        assert_eq!(SaveScope::All.cycle(-1), SaveScope::Bookmarked);
+        assert_eq!(SaveScope::Bookmarked.cycle(1), SaveScope::All);
+    }
+
+    #[test]
+    fn test_cycle_save_format() {
+        let mut tui = Tui::new("sb", "vm-00");
+        tui.open_save();
+        assert_eq!(tui.save_format, ExportFormat::Log);
+        tui.cycle_save_format(1);
+        assert_eq!(tui.save_format, ExportFormat::Json);
+        assert!(tui.save_input.value().ends_with(".jsonl"));
+        tui.cycle_save_format(1);
+        assert!(tui.save_input.value().ends_with(".csv"));
+
+        // names without the extension of the format are kept
+        tui.save_input = Input::new(String::from("out.txt"));
+        tui.cycle_save_format(-2);
+        assert_eq!(tui.save_format, ExportFormat::Log);
+        assert_eq!(tui.save_input.value(), "out.txt");
+    }
+
+    #[test]
+    fn test_clipboard_text() {
+        let tui = Tui::new("sb", "vm-00");
+        let entry = sbsearch::Entry {
+            level: sbsearch::Level::Error,
+            path: String::from("sb/logs/default/vm-00/compute.log"),
+            content: String::from("vm-00 failed\ncaused by: timeout\n"),
+            timestamp: None,
+            line_number: 7,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        assert_eq!(
+            tui.clipboard_text(&entry, false),
+            "vm-00 failed\ncaused by: timeout"
+        );
+        assert_eq!(
+            tui.clipboard_text(&entry, true),
+            "logs/default/vm-00/compute.log:7: vm-00 failed\nlogs/default/vm-00/compute.log:8: caused by: timeout"
+        );
     }
 }
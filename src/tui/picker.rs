@@ -0,0 +1,200 @@
+use super::render;
+use super::theme::Capabilities;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{DefaultTerminal, widgets::ListState};
+use sbsearch::Resource;
+use std::io;
+use tui_input::Input;
+use tui_input::backend::crossterm::EventHandler;
+
+/// A fuzzy-searchable list of the resources in the support bundle, shown on
+/// startup to pick the keyword when none is given.
+#[derive(Debug, Default)]
+pub struct Picker {
+    pub input: Input,
+    pub state: ListState,
+    candidates: Vec<Resource>,
+    matches: Vec<usize>,
+    picked: Option<String>,
+    exit: bool,
+}
+
+impl Picker {
+    pub fn new(candidates: Vec<Resource>) -> Self {
+        let mut picker = Picker {
+            candidates,
+            ..Default::default()
+        };
+        picker.update();
+        picker
+    }
+
+    /// Runs the picker until a keyword is picked, and returns it. Returns
+    /// `None` if the picker is cancelled.
+    pub fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+        capabilities: Capabilities,
+    ) -> io::Result<Option<String>> {
+        while !self.exit {
+            terminal.draw(|frame| {
+                render::draw_picker(&mut self, frame);
+                capabilities.degrade(frame.buffer_mut());
+            })?;
+            self.handle_key_event(crossterm::event::read()?);
+        }
+        Ok(self.picked)
+    }
+
+    /// Returns the candidates matching the input, best matches first.
+    pub fn matches(&self) -> impl Iterator<Item = &Resource> {
+        self.matches.iter().map(|&index| &self.candidates[index])
+    }
+
+    fn handle_key_event(&mut self, event: Event) {
+        let Event::Key(key_event) = event else {
+            return;
+        };
+        if key_event.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Esc => self.exit = true,
+            KeyCode::Enter => {
+                // without a matching resource, the input is the keyword
+                self.picked = match self
+                    .state
+                    .selected()
+                    .and_then(|pos| self.matches().nth(pos))
+                {
+                    Some(resource) => Some(resource.name.clone()),
+                    None => Some(String::from(self.input.value().trim())).filter(|k| !k.is_empty()),
+                };
+                self.exit = self.picked.is_some();
+            }
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down | KeyCode::Tab => {
+                if self
+                    .state
+                    .selected()
+                    .is_some_and(|pos| pos + 1 < self.matches.len())
+                {
+                    self.state.select_next();
+                }
+            }
+            _ => {
+                self.input.handle_event(&event);
+                self.update();
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        let query = self.input.value().to_lowercase();
+        let mut scored: Vec<(i64, usize)> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, c)| fuzzy_score(&query, &c.name).map(|score| (score, index)))
+            .collect();
+        // shorter names win ties, as the input covers more of them
+        scored.sort_by_key(|&(score, index)| (-score, self.candidates[index].name.len()));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+
+        let selected = (!self.matches.is_empty()).then_some(0);
+        self.state = ListState::default().with_selected(selected);
+    }
+}
+
+/// Scores how well `candidate` matches `query`, if all the characters of
+/// `query` appear in order in `candidate`. Consecutive characters and matches
+/// at the start of a word, e.g. after a '-', score higher.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.chars() {
+        let found = pos + candidate[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(candidate[found - 1], '-' | '_' | '.' | '/') {
+            score += 3;
+        }
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn resource(kind: &'static str, name: &str) -> Resource {
+        Resource {
+            kind,
+            name: String::from(name),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "vm-00"), Some(0));
+        assert!(fuzzy_score("vm0", "vm-00").is_some());
+        assert!(fuzzy_score("0vm", "vm-00").is_none());
+        assert!(fuzzy_score("disk", "vm-00-disk-0") > fuzzy_score("disk", "vm-d-i-s-k"));
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut picker = Picker::new(vec![
+            resource("node", "isim-dev"),
+            resource("pvc", "vm-00-disk-0-xx3er"),
+            resource("vm", "vm-00"),
+        ]);
+        assert_eq!(picker.matches().count(), 3);
+
+        for c in "vm00".chars() {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            picker.handle_key_event(Event::Key(key_event));
+        }
+        let names: Vec<&str> = picker.matches().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["vm-00", "vm-00-disk-0-xx3er"]);
+
+        // the selection stops at the last match
+        let key_event = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        picker.handle_key_event(Event::Key(key_event));
+        picker.handle_key_event(Event::Key(key_event));
+        assert_eq!(picker.state.selected(), Some(1));
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        picker.handle_key_event(Event::Key(key_event));
+        assert!(picker.exit);
+        assert_eq!(picker.picked.as_deref(), Some("vm-00-disk-0-xx3er"));
+    }
+
+    #[test]
+    fn test_pick_unmatched_input() {
+        let mut picker = Picker::new(vec![resource("vm", "vm-00")]);
+        for c in "pvc-1".chars() {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            picker.handle_key_event(Event::Key(key_event));
+        }
+        assert_eq!(picker.matches().count(), 0);
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        picker.handle_key_event(Event::Key(key_event));
+        assert_eq!(picker.picked.as_deref(), Some("pvc-1"));
+
+        let mut picker = Picker::new(Vec::new());
+        let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        picker.handle_key_event(Event::Key(key_event));
+        assert!(picker.exit);
+        assert!(picker.picked.is_none());
+    }
+}
@@ -1,15 +1,42 @@
+use chrono::{DateTime, Utc};
+use grep_matcher::Matcher;
+use grep_regex::RegexMatcher;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
-        ScrollbarState,
+        Block, Borders, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Table, TableState, Wrap,
     },
 };
+use regex::Regex;
+use sbsearch::format;
+use sbsearch::{AssumeTz, Level, Progress, SearchWarning};
 use std::rc::Rc;
 use textwrap::Options;
+use tui_input::Input;
+
+use sbsearch::session::ExportFormat;
+
+/// The cache indicator turns yellow once the cache takes up this share of the
+/// memory budget.
+const MEMORY_BUDGET_WARNING_RATIO: f64 = 0.8;
+
+/// The width of the timestamp and level columns shown before the content.
+const COLUMNS_WIDTH: usize = 32;
+
+/// The frames of the spinner of the progress popup.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The smallest terminal the main screen fits in: the title, meta and search
+/// sections, with a couple of lines of logs under them.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 16;
+
+/// The maximum number of pinned entries shown above the logs.
+const MAX_PINNED_ROWS: usize = 5;
 
 pub fn draw_popup(title: &str, text: &str, percent_x: u16, percent_y: u16, frame: &mut Frame) {
     let popup_area = split_popup_layout(percent_x, percent_y, frame.area());
@@ -23,12 +50,887 @@ pub fn draw_popup(title: &str, text: &str, percent_x: u16, percent_y: u16, frame
     frame.render_widget(popup_para, popup_area);
 }
 
+/// Returns true if `area` is too small for the sections of the main screen,
+/// which would overlap.
+pub fn too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Asks for a larger terminal in place of the screens, until it's resized.
+pub fn draw_too_small(frame: &mut Frame) {
+    let area = frame.area();
+    let text = vec![
+        Line::from("terminal too small").bold(),
+        Line::from(format!(
+            "{}x{}, need ≥ {}x{}",
+            area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+        )),
+    ];
+    let top = area.height.saturating_sub(text.len() as u16) / 2;
+    let para = Paragraph::new(text)
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        para,
+        Rect::new(area.x, area.y + top, area.width, area.height - top),
+    );
+}
+
+/// Shows an error in a popup over the logs, with the key to retry the
+/// action that failed if it's `retryable`.
+pub fn draw_error(title: &str, text: &str, retryable: bool, frame: &mut Frame) {
+    let popup_area = split_popup_layout(60, 30, frame.area());
+    let hint = match retryable {
+        true => " Retry<r> Close<Esc> ",
+        false => " Close<Esc> ",
+    };
+    let popup_block = Block::default()
+        .title(Line::from(title).centered())
+        .title_bottom(Line::from(hint).centered())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+    let popup_para = Paragraph::new(text)
+        .block(popup_block)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup_para, popup_area);
+}
+
+/// Draws a one-line notice in the bottom right corner, over the logs.
+pub fn draw_notice(text: &str, frame: &mut Frame) {
+    let area = frame.area();
+    let width = (text.chars().count() as u16 + 4).min(area.width);
+    let height = 3.min(area.height);
+    let notice_area = Rect::new(
+        area.x + area.width - width,
+        area.y + area.height - height,
+        width,
+        height,
+    );
+    let notice = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center);
+    frame.render_widget(Clear, notice_area);
+    frame.render_widget(notice, notice_area);
+}
+
+pub fn draw_suspects(suspects: &[super::analyze::Suspect], frame: &mut Frame) {
+    let popup_area = split_popup_layout(80, 60, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from("Top Suspects - Start Here").centered())
+        .title_bottom(Line::from(" Continue<Enter> ").centered())
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:>5}  {:<48} {:>6} {:>8} {:>8} {:>5} {:>6}",
+            "SCORE", "COMPONENT", "ERRORS", "WARNINGS", "RESTARTS", "GAPS", "KNOWN"
+        ),
+        Style::default().fg(Color::Green).bold(),
+    ))];
+    for suspect in suspects {
+        let line = format!(
+            "{:>5}  {:<48} {:>6} {:>8} {:>8} {:>5} {:>6}",
+            suspect.score,
+            suspect.component,
+            suspect.errors,
+            suspect.warnings,
+            suspect.restarts,
+            suspect.gaps,
+            suspect.known_issues
+        );
+        let line = if suspect.errors > 0 {
+            Line::from(line).red()
+        } else if suspect.warnings > 0 {
+            Line::from(line).yellow()
+        } else {
+            Line::from(line)
+        };
+        lines.push(line);
+    }
+
+    let popup_para = Paragraph::new(lines).block(popup_block);
+    frame.render_widget(popup_para, popup_area);
+}
+
+/// Shows how many of the files of the support bundle were searched, while
+/// the search runs, with a spinner turning at each `tick`, so that it's
+/// clear the search isn't stuck on a large file.
+pub fn draw_progress(progress: Option<Progress>, tick: usize, frame: &mut Frame) {
+    let popup_area = split_popup_layout(60, 20, frame.area());
+    let spinner = SPINNER[tick % SPINNER.len()];
+    let popup_block = Block::default()
+        .title(Line::from(format!("{} Searching", spinner)).centered())
+        .title_bottom(Line::from(" Browse<Enter> Cancel<Esc> Quit<q> ").centered())
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    let progress = progress.unwrap_or_default();
+    let ratio = match progress.total_files {
+        0 => 0.0,
+        total => progress.files as f64 / total as f64,
+    };
+    let label = match progress.total_files {
+        0 => String::from("looking for files..."),
+        total => format!(
+            "{} of {} files ({})",
+            format::count(progress.files),
+            format::count(total),
+            format::bytes(progress.bytes as usize)
+        ),
+    };
+    let gauge = Gauge::default()
+        .block(popup_block)
+        .gauge_style(Style::default().fg(Color::Green))
+        .ratio(ratio.min(1.0))
+        .label(label);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(gauge, popup_area);
+}
+
+/// Lists the files that were skipped, then the ones only partly searched,
+/// over the logs.
+pub fn draw_warnings(warnings: &[SearchWarning], frame: &mut Frame) {
+    let popup_area = split_popup_layout(80, 60, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from(format!("Warnings ({})", format::count(warnings.len()))).centered())
+        .title_bottom(Line::from(" Continue<Enter> ").centered())
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (partial, heading) in [(false, "Skipped"), (true, "Partly searched")] {
+        let group: Vec<&SearchWarning> = warnings
+            .iter()
+            .filter(|warning| warning.is_partial() == partial)
+            .collect();
+        if group.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(Span::styled(
+            heading,
+            Style::default().fg(Color::Green).bold(),
+        )));
+        lines.extend(
+            group
+                .iter()
+                .map(|warning| Line::from(format!("  {}", warning)).yellow()),
+        );
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("every file was searched in full"));
+    }
+    let popup_para = Paragraph::new(lines)
+        .block(popup_block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup_para, popup_area);
+}
+
+/// Draws the save dialog, with the file name being edited, which entries are
+/// saved and in which format.
+pub fn draw_save(
+    input: &Input,
+    scope: &str,
+    count: usize,
+    format: ExportFormat,
+    frame: &mut Frame,
+) {
+    let popup_area = split_popup_layout(70, 30, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from("Save").centered())
+        .title_bottom(
+            Line::from(" Save<Enter> Change Entries<Tab> Change Format<Up/Down> Cancel<Esc> ")
+                .centered(),
+        )
+        .borders(Borders::ALL);
+    let inner = popup_block.inner(popup_area);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup_block, popup_area);
+
+    const LABEL: &str = "file: ";
+    let width = inner.width.saturating_sub(LABEL.len() as u16 + 1) as usize;
+    let scroll = input.visual_scroll(width);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(LABEL, Style::default().fg(Color::Green).bold()),
+            Span::raw(input.value().chars().skip(scroll).collect::<String>()),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("entries: "),
+            Span::styled(format!("< {} >", scope), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" ({})", format::count(count))),
+        ]),
+        Line::from(vec![
+            Span::raw("format:  "),
+            Span::styled(
+                format!("< {} >", format.extension()),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(lines), inner);
+    frame.set_cursor_position((
+        inner.x + (LABEL.len() + input.visual_cursor().saturating_sub(scroll)) as u16,
+        inner.y,
+    ));
+}
+
+/// Lists the bookmarks with their number among the matching entries, or a
+/// dash if they're hidden by the filter.
+pub fn draw_bookmarks(
+    bookmarks: &[(Option<usize>, &sbsearch::Entry)],
+    state: &mut ListState,
+    frame: &mut Frame,
+) {
+    let popup_area = split_popup_layout(80, 60, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from(format!("Bookmarks ({})", bookmarks.len())).centered())
+        .title_bottom(Line::from(" Go<Enter> Remove<M> Back<Esc> ").centered())
+        .borders(Borders::ALL);
+    let items: Vec<ListItem> = match bookmarks.is_empty() {
+        true => vec![ListItem::new(
+            "No bookmarks yet, press M on an entry to add one.",
+        )],
+        false => bookmarks
+            .iter()
+            .map(|(number, entry)| {
+                let number = number.map_or(String::from("-"), |n| n.to_string());
+                let text = format!("{}", entry);
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>7} ", number), Style::default().fg(Color::Cyan)),
+                    Span::raw(String::from(text.lines().next().unwrap_or_default())),
+                ]))
+            })
+            .collect(),
+    };
+    let list = List::new(items)
+        .block(popup_block)
+        .highlight_style(Style::default().bg(Color::LightMagenta));
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, popup_area, state);
+}
+
+/// Lists every keybinding by group, scrolled by `scroll` lines.
+pub fn draw_help(scroll: u16, frame: &mut Frame) {
+    let popup_area = split_popup_layout(70, 80, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from("Help").centered())
+        .title_bottom(Line::from(" Scroll<Up/Down> Back<Esc> ").centered())
+        .borders(Borders::ALL);
+
+    let width = super::help::KEYMAP
+        .iter()
+        .flat_map(|(_, keys)| keys.iter().map(|(key, _)| key.len()))
+        .max()
+        .unwrap_or_default();
+    let mut lines = Vec::new();
+    for (group, keys) in super::help::KEYMAP {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(Span::styled(
+            *group,
+            Style::default().fg(Color::Green).bold(),
+        )));
+        for (key, action) in *keys {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$}  ", key, width = width),
+                    Style::default().fg(Color::Blue).bold(),
+                ),
+                Span::raw(*action),
+            ]));
+        }
+    }
+    let popup_para = Paragraph::new(lines).block(popup_block).scroll((scroll, 0));
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup_para, popup_area);
+}
+
+/// Shows the whole of an entry over the full screen, unwrapped and scrolled
+/// by `scroll` rows and columns, with its JSON unfolded `json_depth` levels.
+pub fn draw_detail(
+    entry: &sbsearch::Entry,
+    display_tz: AssumeTz,
+    anchor: Option<DateTime<Utc>>,
+    json_depth: usize,
+    scroll: (u16, u16),
+    frame: &mut Frame,
+) {
+    let area = frame.area();
+    let block = Block::default()
+        .title(Line::from("Entry").centered())
+        .title_bottom(
+            Line::from(
+                " Scroll<Up/Down/Left/Right> Fold<-/+> Source<v> Editor<e> Copy<y/Y> Back<Esc> ",
+            )
+            .centered(),
+        )
+        .borders(Borders::ALL);
+    let para = Paragraph::new(detail_lines(entry, display_tz, anchor, json_depth))
+        .block(block)
+        .scroll(scroll);
+    frame.render_widget(Clear, area);
+    frame.render_widget(para, area);
+}
+
+/// Draws the selected entry in the detail pane on the right of the logs.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_detail_pane(
+    entry: Option<&sbsearch::Entry>,
+    display_tz: AssumeTz,
+    anchor: Option<DateTime<Utc>>,
+    json_depth: usize,
+    scroll: (u16, u16),
+    focused: bool,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let border = match focused {
+        true => Style::default().fg(Color::Yellow),
+        false => Style::default(),
+    };
+    let block = Block::default()
+        .title(Line::from(" Entry "))
+        .borders(Borders::ALL)
+        .border_style(border);
+    let lines = match entry {
+        Some(entry) => detail_lines(entry, display_tz, anchor, json_depth),
+        None => vec![Line::from("No entry selected.")],
+    };
+    frame.render_widget(Paragraph::new(lines).block(block).scroll(scroll), area);
+}
+
+/// Shows the file of an entry over the full screen, with the lines of the
+/// entry highlighted.
+pub fn draw_source(view: &mut super::source::SourceView, frame: &mut Frame) {
+    let area = frame.area();
+    let block = Block::default()
+        .title(Line::from(view.path.clone()).centered())
+        .title_bottom(
+            Line::from(" Line<Up/Down> Page<Left/Right> Start<g> End<G> Entry<e> Back<Esc> ")
+                .centered(),
+        )
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    view.height = inner.height as usize;
+    view.scroll(0);
+
+    let width = view.lines.len().to_string().len();
+    let lines: Vec<Line> =
+        view.lines
+            .iter()
+            .enumerate()
+            .skip(view.top)
+            .take(view.height)
+            .map(|(index, line)| {
+                let number = Span::styled(
+                    format!("{:>width$} ", index + 1, width = width),
+                    Style::default().fg(Color::DarkGray),
+                );
+                let line = Span::raw(line.clone());
+                match view.is_entry_line(index) {
+                    true => Line::from(vec![number, line])
+                        .style(Style::default().bg(Color::LightMagenta)),
+                    false => Line::from(vec![number, line]),
+                }
+            })
+            .collect();
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// The lines of the detail view: the location, time and level of the entry,
+/// the fields of its source and JSON line, then its content.
+pub fn detail_lines(
+    entry: &sbsearch::Entry,
+    display_tz: AssumeTz,
+    anchor: Option<DateTime<Utc>>,
+    json_depth: usize,
+) -> Vec<Line<'static>> {
+    let label =
+        |text: &str| Span::styled(text.to_string(), Style::default().fg(Color::Green).bold());
+    let timestamp = match (entry.timestamp, anchor) {
+        (Some(t), Some(anchor)) => format!(
+            "{} ({})",
+            display_tz.format(t, "%Y-%m-%d %H:%M:%S%.3f %:z"),
+            relative(t, anchor)
+        ),
+        (Some(t), None) => display_tz.format(t, "%Y-%m-%d %H:%M:%S%.3f %:z"),
+        (None, _) => String::from("-"),
+    };
+    let mut lines = vec![
+        Line::from(vec![label("Path: "), Span::raw(entry.path.clone())]),
+        Line::from(vec![
+            label("Line: "),
+            Span::raw(entry.line_number.to_string()),
+        ]),
+        Line::from(vec![label("Time: "), Span::raw(timestamp)]),
+        Line::from(vec![label("Level: "), Span::raw(entry.level.as_str())]),
+    ];
+    if entry.duplicates > 0 {
+        lines.push(Line::from(vec![
+            label("Duplicates: "),
+            Span::raw(format::count(entry.duplicates)),
+        ]));
+    }
+    if !entry.fields.is_empty() {
+        let fields: Vec<String> = entry
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        lines.push(Line::from(vec![
+            label("Fields: "),
+            Span::raw(fields.join(" ")),
+        ]));
+    }
+
+    let first_line = entry.content.lines().next().unwrap_or_default();
+    if let Some(json) = sbsearch::json_value(first_line) {
+        lines.push(Line::default());
+        lines.push(Line::from(label("JSON")));
+        lines.extend(super::json::pretty_lines(&json, json_depth));
+    }
+
+    lines.push(Line::default());
+    lines.push(Line::from(label("Content")));
+    let style = match entry.level {
+        Level::Error | Level::Fatal => Style::default().fg(Color::Red),
+        Level::Warn => Style::default().fg(Color::Yellow),
+        _ => Style::default(),
+    };
+    lines.extend(
+        entry
+            .content
+            .lines()
+            .map(|line| super::logfmt::highlight(line, style)),
+    );
+    lines
+}
+
+pub fn draw_filter_form(form: &super::filter::FilterForm, frame: &mut Frame) {
+    use super::filter::Field;
+
+    let popup_area = split_popup_layout(60, 80, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from("Filter Builder").centered())
+        .title_bottom(
+            Line::from(" Move<Up/Down> Toggle<Space> Apply<Enter> Cancel<Esc> ").centered(),
+        )
+        .borders(Borders::ALL)
+        .style(Style::default());
+    let inner = popup_block.inner(popup_area);
+    frame.render_widget(popup_block, popup_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Fill(1), Constraint::Length(2)])
+        .split(inner);
+
+    let mut items = Vec::new();
+    let mut last_group = None;
+    let mut cursor = None;
+    let mut focused_row = 0;
+    for (index, field) in form.fields.iter().enumerate() {
+        let focused = index == form.focus;
+        let line = match field {
+            Field::Checkbox {
+                group,
+                value,
+                checked,
+            } => {
+                if last_group != Some(*group) {
+                    items.push(ListItem::new(Line::from(Span::styled(
+                        group.label(),
+                        Style::default().fg(Color::Green).bold(),
+                    ))));
+                    last_group = Some(*group);
+                }
+                let mark = if *checked { "[x]" } else { "[ ]" };
+                Line::from(format!("  {} {}", mark, value))
+            }
+            Field::Since(input) | Field::Until(input) => {
+                if last_group.is_some() {
+                    items.push(ListItem::new(Line::from(Span::styled(
+                        "Time Range",
+                        Style::default().fg(Color::Green).bold(),
+                    ))));
+                    last_group = None;
+                }
+                let label = match field {
+                    Field::Since(_) => "  Since: ",
+                    _ => "  Until: ",
+                };
+                if focused {
+                    cursor = Some((items.len(), label.len() + input.visual_cursor()));
+                }
+                Line::from(format!("{}{}", label, input.value()))
+            }
+            Field::Patterns(input) => {
+                items.push(ListItem::new(Line::from(Span::styled(
+                    "Field Patterns (e.g. msg~\"timeout\")",
+                    Style::default().fg(Color::Green).bold(),
+                ))));
+                let label = "  ";
+                if focused {
+                    cursor = Some((items.len(), label.len() + input.visual_cursor()));
+                }
+                Line::from(format!("{}{}", label, input.value()))
+            }
+        };
+        let item = ListItem::new(line);
+        if focused {
+            focused_row = items.len();
+        }
+        items.push(if focused {
+            item.style(Style::default().bg(Color::LightMagenta))
+        } else {
+            item
+        });
+    }
+
+    // keep the focused field visible
+    let height = layout[0].height as usize;
+    let offset = focused_row.saturating_sub(height.saturating_sub(1));
+    let list = List::new(items.into_iter().skip(offset).collect::<Vec<ListItem>>());
+    frame.render_widget(list, layout[0]);
+    if let Some((row, col)) = cursor {
+        frame.set_cursor_position((
+            layout[0].x + col as u16,
+            layout[0].y + (row - offset) as u16,
+        ));
+    }
+
+    let footer = match &form.error {
+        Some(error) => Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))),
+        None => Line::from(vec![
+            Span::styled("Query: ", Style::default().fg(Color::Green).bold()),
+            Span::raw(form.compile()),
+        ]),
+    };
+    frame.render_widget(Paragraph::new(footer), layout[1]);
+}
+
+pub fn draw_pinned(pinned: &[sbsearch::Entry], area: Rect, frame: &mut Frame) {
+    let items: Vec<ListItem> = pinned
+        .iter()
+        .map(|entry| {
+            let text = format!("{}", entry);
+            let line = text.lines().next().unwrap_or_default().to_string();
+            match entry.level {
+                Level::Error | Level::Fatal => ListItem::new(line).red(),
+                Level::Warn => ListItem::new(line).yellow(),
+                _ => ListItem::new(line),
+            }
+        })
+        .collect();
+
+    // show the most recently pinned entries if they don't fit, and say so
+    let skip = items.len().saturating_sub(MAX_PINNED_ROWS);
+    let title = match skip {
+        0 => format!(" Pinned ({}) ", pinned.len()),
+        _ => format!(" Pinned ({} of {}) ", MAX_PINNED_ROWS, pinned.len()),
+    };
+    let block = Block::default()
+        .title(Line::from(title))
+        .borders(Borders::ALL);
+    let list = List::new(items.into_iter().skip(skip).collect::<Vec<ListItem>>()).block(block);
+    frame.render_widget(list, area);
+}
+
+pub fn draw_rate(
+    rate: &super::analyze::Rate,
+    selected: Option<DateTime<Utc>>,
+    display_tz: AssumeTz,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let block = Block::default()
+        .title(Line::from(" Matches per minute "))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let (Some(start), Some(end), Some(peak)) = (rate.start(), rate.end(), rate.peak()) else {
+        frame.render_widget(Paragraph::new("No timestamped entries."), inner);
+        return;
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Fill(1), Constraint::Length(1)])
+        .split(inner);
+
+    let (counts, per_column) = rate.resample(inner.width as usize);
+    let sparkline = Sparkline::default()
+        .data(&counts)
+        .style(Style::default().fg(Color::Red));
+    frame.render_widget(sparkline, rows[0]);
+
+    // mark the minute of the selected entry under the sparkline
+    let label = match selected.and_then(|ts| rate.index_of(ts)) {
+        Some(index) => format!(
+            "{}^ {} {}/min (peak {}/min)",
+            " ".repeat(index / per_column),
+            display_tz.format(selected.unwrap(), "%H:%M"),
+            format::count(rate.count_at(selected.unwrap()) as usize),
+            format::count(peak as usize)
+        ),
+        None => format!(
+            "{} - {} (peak {}/min)",
+            display_tz.format(start, "%H:%M"),
+            display_tz.format(end, "%H:%M"),
+            format::count(peak as usize)
+        ),
+    };
+    frame.render_widget(
+        Paragraph::new(Span::styled(label, Style::default().fg(Color::Green))),
+        rows[1],
+    );
+}
+
+pub fn draw_picker(picker: &mut super::picker::Picker, frame: &mut Frame) {
+    let popup_area = split_popup_layout(60, 60, frame.area());
+    let popup_block = Block::default()
+        .title(Line::from("Pick a Keyword").centered())
+        .title_bottom(Line::from(" Pick<Enter> Quit<Esc> ").centered())
+        .borders(Borders::ALL);
+    let inner = popup_block.inner(popup_area);
+    frame.render_widget(popup_block, popup_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Fill(1)])
+        .split(inner);
+    let input = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Green).bold()),
+        Span::raw(picker.input.value()),
+    ]);
+    frame.render_widget(Paragraph::new(input), layout[0]);
+    frame.set_cursor_position((
+        layout[0].x + 2 + picker.input.visual_cursor() as u16,
+        layout[0].y,
+    ));
+
+    let items: Vec<ListItem> = picker
+        .matches()
+        .map(|resource| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<8}", resource.kind),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(resource.name.clone()),
+            ]))
+        })
+        .collect();
+    let list = List::new(items).highlight_style(Style::default().bg(Color::LightMagenta));
+    frame.render_stateful_widget(list, layout[1], &mut picker.state);
+}
+
+/// Draws the file tree sidebar, with the file or directory the entries are
+/// narrowed down to in bold.
+pub fn draw_files(
+    tree: &mut super::files::FileTree,
+    current: Option<&str>,
+    focused: bool,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let items: Vec<ListItem> = tree
+        .rows
+        .iter()
+        .map(|row| {
+            let style = match row.path.as_deref() == current {
+                true => Style::default().fg(Color::Green).bold(),
+                false => Style::default(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}{}", "  ".repeat(row.depth), row.name), style),
+                Span::styled(
+                    format!(" ({})", format::count(row.count)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let border = match focused {
+        true => Style::default().fg(Color::Yellow),
+        false => Style::default(),
+    };
+    let block = Block::default()
+        .title(Line::from(" Files "))
+        .borders(Borders::ALL)
+        .border_style(border);
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::LightMagenta));
+    frame.render_stateful_widget(list, area, &mut tree.state);
+}
+
+/// Shows the entries of two keywords side by side over the full screen, a
+/// row per time, so that what happened to one can be read against the other.
+pub fn draw_compare(
+    keywords: (&str, &str),
+    rows: &[super::compare::Row],
+    state: &mut TableState,
+    display_tz: AssumeTz,
+    frame: &mut Frame,
+) {
+    let area = frame.area();
+    let block = Block::default()
+        .title(Line::from("Compare").centered())
+        .title_bottom(Line::from(" Move<Up/Down> Go<Enter> Back<Esc> ").centered())
+        .borders(Borders::ALL);
+
+    let cell = |entry: Option<&sbsearch::Entry>| match entry {
+        Some(entry) => {
+            let line = String::from(entry.content.lines().next().unwrap_or_default());
+            match entry.level {
+                Level::Error | Level::Fatal => Cell::from(line).red(),
+                Level::Warn => Cell::from(line).yellow(),
+                _ => Cell::from(line),
+            }
+        }
+        None => Cell::default(),
+    };
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .map(|(left, right)| {
+            let timestamp = left
+                .or(*right)
+                .and_then(|entry| entry.timestamp)
+                .map(|t| display_tz.format(t, "%m-%d %H:%M:%S%.3f"))
+                .unwrap_or_default();
+            Row::new(vec![
+                Cell::from(timestamp).fg(Color::DarkGray),
+                cell(*left),
+                cell(*right),
+            ])
+        })
+        .collect();
+    let count = |left: bool| {
+        rows.iter()
+            .filter(|(l, r)| if left { l.is_some() } else { r.is_some() })
+            .count()
+    };
+    let header = Row::new(vec![
+        String::from("TIME"),
+        format!("{} ({})", keywords.0, format::count(count(true))),
+        format!("{} ({})", keywords.1, format::count(count(false))),
+    ])
+    .style(Style::default().fg(Color::Green).bold());
+    let table = Table::new(
+        table_rows,
+        [
+            Constraint::Length(18),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .block(block)
+    .row_highlight_style(Style::default().bg(Color::LightMagenta));
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(table, area, state);
+}
+
+/// Shows the differences between two entries over the full screen, the
+/// older above the newer, with what was removed from the older on red and
+/// what was added to the newer on green.
+pub fn draw_diff(diff: &super::diff::Diff, display_tz: AssumeTz, frame: &mut Frame) {
+    let area = frame.area();
+    let block = Block::default()
+        .title(Line::from(format!("Diff ({} changes)", diff.changes())).centered())
+        .title_bottom(Line::from(" Scroll<Up/Down> Top<g> Back<Esc> ").centered())
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Fill(1), Constraint::Fill(1)])
+        .split(inner);
+    for (old, entry, area) in [(true, &diff.old, layout[0]), (false, &diff.new, layout[1])] {
+        let timestamp = entry
+            .timestamp
+            .map(|t| display_tz.format(t, "%Y-%m-%d %H:%M:%S%.3f"))
+            .unwrap_or_default();
+        let title = format!(" {}:{} {} ", entry.path, entry.line_number, timestamp);
+        let block = Block::default()
+            .title(Line::from(title))
+            .borders(Borders::TOP);
+        let para = Paragraph::new(diff.lines(old))
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((diff.scroll, 0));
+        frame.render_widget(para, area);
+    }
+}
+
+/// Draws the numbered tabs over the top border of the title section.
+pub fn draw_tabs(titles: &[String], active: usize, area: Rect, frame: &mut Frame) {
+    let mut spans = Vec::new();
+    for (index, title) in titles.iter().enumerate() {
+        let style = match index == active {
+            true => Style::default().fg(Color::Black).bg(Color::Green).bold(),
+            false => Style::default().fg(Color::White),
+        };
+        spans.push(Span::styled(format!(" {}:{} ", index + 1, title), style));
+    }
+    let tabs_area = Rect::new(area.x + 1, area.y, area.width.saturating_sub(2), 1);
+    frame.render_widget(Paragraph::new(Line::from(spans)), tabs_area);
+}
+
+/// How the entries are laid out in the logs section.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayOptions {
+    pub wrap: bool,
+    pub horizontal_scroll: usize,
+    pub entry_scroll: usize,
+    pub columns: bool,
+    pub show_source: bool,
+    pub logfmt: bool,
+    pub tz: AssumeTz,
+    /// The timestamp of the anchor entry, which the columns count from.
+    pub anchor: Option<DateTime<Utc>>,
+}
+
+/// How much memory the cached entries take, against the budget.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: usize,
+    /// The memory budget in bytes, 0 if there is none.
+    pub budget: usize,
+}
+
 pub struct Renderer<'a> {
     filepath: String,
+    /// The number of entries matching the keyword in the file of the
+    /// selected entry.
+    file_matches: Option<usize>,
+    source: Option<String>,
+    timestamp: Option<String>,
+    summary: String,
+    hint: Option<String>,
+    filter: String,
     keyword: String,
+    keyword_mode: String,
     page_final: usize,
     page_goto: usize,
     page_total_entries: usize,
+    cache: CacheUsage,
+    searching: bool,
+    timed_out: bool,
     selected: usize,
     title: String,
 
@@ -36,8 +938,17 @@ pub struct Renderer<'a> {
     search_cursor_show: bool,
     search_scroll: u16,
     search_value: String,
+    search_regex: bool,
+    search_error: Option<String>,
+    keyword_matcher: Option<&'a RegexMatcher>,
+    display: DisplayOptions,
+    bookmarks: &'a [sbsearch::Entry],
+    context: Option<&'a super::source::Context>,
+    /// The number of rows of the selected entry that don't fit in the logs,
+    /// as of the last render.
+    pub selected_overflow: usize,
 
-    entries: &'a Vec<super::sbsearch::Entry>,
+    entries: &'a Vec<sbsearch::Entry>,
     nav_state: &'a mut ListState,
     vertical_scroll_state: ScrollbarState,
 }
@@ -46,32 +957,65 @@ impl<'a> Renderer<'a> {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         filepath: String,
+        file_matches: Option<usize>,
+        source: Option<String>,
+        timestamp: Option<String>,
+        summary: String,
+        hint: Option<String>,
+        filter: String,
         keyword: String,
+        keyword_mode: String,
         page_final: usize,
         page_goto: usize,
         page_total_entries: usize,
+        cache: CacheUsage,
+        searching: bool,
+        timed_out: bool,
         selected: usize,
         title: String,
         search_cursor_pos: u16,
         search_cursor_show: bool,
         search_scroll: u16,
         search_value: String,
-        entries: &'a Vec<super::sbsearch::Entry>,
+        search_regex: bool,
+        search_error: Option<String>,
+        keyword_matcher: Option<&'a RegexMatcher>,
+        display: DisplayOptions,
+        bookmarks: &'a [sbsearch::Entry],
+        context: Option<&'a super::source::Context>,
+        entries: &'a Vec<sbsearch::Entry>,
         nav_state: &'a mut ListState,
         vertical_scroll_state: ScrollbarState,
     ) -> Self {
         Renderer {
             filepath,
+            file_matches,
+            source,
+            timestamp,
+            summary,
+            hint,
+            filter,
             keyword,
+            keyword_mode,
             page_final,
             page_goto,
             page_total_entries,
+            cache,
+            searching,
+            timed_out,
             selected,
             title,
             search_cursor_pos,
             search_cursor_show,
             search_scroll,
             search_value,
+            search_regex,
+            search_error,
+            keyword_matcher,
+            display,
+            bookmarks,
+            context,
+            selected_overflow: 0,
             entries,
             nav_state,
             vertical_scroll_state,
@@ -80,32 +1024,19 @@ impl<'a> Renderer<'a> {
 
     pub fn render_title_section(&self, area: Rect, frame: &mut Frame) {
         let instructions = Line::from(vec![
-            Span::styled(" | (Line)", Style::default().fg(Color::White)),
-            Span::styled(" Up", Style::default()),
-            Span::styled("<Up>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" Down", Style::default()),
-            Span::styled("<Down>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" Start", Style::default()),
-            Span::styled("<g>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" End", Style::default()),
-            Span::styled("<G>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" | (Page)", Style::default().fg(Color::White)),
-            Span::styled(" Previous", Style::default()),
-            Span::styled("<Left>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" Next", Style::default()),
-            Span::styled("<Right>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" First", Style::default()),
-            Span::styled("<0>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" Last", Style::default()),
-            Span::styled("<9>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" | (Search)", Style::default().fg(Color::White)),
-            Span::styled(" Edit", Style::default()),
-            Span::styled("</>", Style::default().fg(Color::Blue).bold()),
-            Span::styled(" Clear", Style::default()),
-            Span::styled("<c>", Style::default().fg(Color::Blue).bold()),
             Span::styled(" | ", Style::default().fg(Color::White)),
-            Span::styled(" Save", Style::default()),
-            Span::styled("<s>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Line", Style::default()),
+            Span::styled("<Up/Down>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Page", Style::default()),
+            Span::styled("<Left/Right>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Detail", Style::default()),
+            Span::styled("<Enter>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Search", Style::default()),
+            Span::styled("</>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Filter", Style::default()),
+            Span::styled("<f>", Style::default().fg(Color::Blue).bold()),
+            Span::styled(" Help", Style::default()),
+            Span::styled("<?>", Style::default().fg(Color::Blue).bold()),
             Span::styled(" Quit", Style::default()),
             Span::styled("<q>", Style::default().fg(Color::Blue).bold()),
             Span::styled(" | ", Style::default().fg(Color::White)),
@@ -125,20 +1056,32 @@ impl<'a> Renderer<'a> {
 
     pub fn render_meta_section(&self, area: Rect, frame: &mut Frame) {
         let meta_block = Block::default().borders(Borders::ALL);
-        let meta_lines = vec![
+        let mut meta_lines = vec![
             Line::from(vec![
                 Span::styled("Keyword: ", Style::default().fg(Color::Green).bold()),
                 Span::styled(&self.keyword, Style::default().fg(Color::Green).bold()),
+                Span::styled(
+                    format!(" ({})", self.keyword_mode),
+                    Style::default().fg(Color::DarkGray),
+                ),
                 Span::styled(" | ", Style::default().fg(Color::White)),
                 Span::styled("Line: ", Style::default().fg(Color::Green).bold()),
                 Span::styled(
-                    format!("{}/{}", self.selected, self.page_total_entries),
+                    format!(
+                        "{}/{}",
+                        format::count(self.selected),
+                        format::count(self.page_total_entries)
+                    ),
                     Style::default().fg(Color::Green).bold(),
                 ),
                 Span::styled(" | ", Style::default().fg(Color::White)),
                 Span::styled("Page: ", Style::default().fg(Color::Green).bold()),
                 Span::styled(
-                    format!("{}/{}", self.page_goto, self.page_final),
+                    format!(
+                        "{}/{}",
+                        format::count(self.page_goto),
+                        format::count(self.page_final)
+                    ),
                     Style::default().fg(Color::Green).bold(),
                 ),
             ]),
@@ -150,14 +1093,127 @@ impl<'a> Renderer<'a> {
                 ),
             ]),
         ];
+        if let Some(matches) = self.file_matches {
+            let text = match matches {
+                1 => String::from(" (this file: 1 match)"),
+                n => format!(" (this file: {} matches)", format::count(n)),
+            };
+            meta_lines[1]
+                .spans
+                .push(Span::styled(text, Style::default().fg(Color::DarkGray)));
+        }
+        if let Some(source) = &self.source {
+            meta_lines[1].spans.splice(
+                0..0,
+                vec![
+                    Span::styled("Source: ", Style::default().fg(Color::Green).bold()),
+                    Span::styled(source.clone(), Style::default().fg(Color::Green).bold()),
+                    Span::styled(" | ", Style::default().fg(Color::White)),
+                ],
+            );
+        }
+        if let Some(timestamp) = &self.timestamp {
+            meta_lines[1].spans.extend(vec![
+                Span::styled(" | ", Style::default().fg(Color::White)),
+                Span::styled("Time: ", Style::default().fg(Color::Green).bold()),
+                Span::styled(timestamp.clone(), Style::default().fg(Color::Green).bold()),
+            ]);
+        }
+        if self.page_total_entries != self.cache.entries {
+            meta_lines[0].spans.extend(vec![
+                Span::styled(" | ", Style::default().fg(Color::White)),
+                Span::styled("Shown: ", Style::default().fg(Color::Green).bold()),
+                Span::styled(
+                    format!(
+                        "{} of {} entries",
+                        format::count(self.page_total_entries),
+                        format::count(self.cache.entries)
+                    ),
+                    Style::default().fg(Color::Green).bold(),
+                ),
+            ]);
+        }
+        meta_lines[0].spans.extend(self.cache_spans());
+        if !self.filter.is_empty() {
+            meta_lines[0].spans.extend(vec![
+                Span::styled(" | ", Style::default().fg(Color::White)),
+                Span::styled("Filter: ", Style::default().fg(Color::Green).bold()),
+                Span::styled(
+                    self.filter.clone(),
+                    Style::default().fg(Color::Green).bold(),
+                ),
+            ]);
+        }
+        if !self.summary.is_empty() {
+            meta_lines.push(Line::from(vec![
+                Span::styled("Summary: ", Style::default().fg(Color::Green).bold()),
+                Span::styled(self.summary.clone(), Style::default().fg(Color::Green)),
+            ]));
+        }
+        if let Some(hint) = &self.hint {
+            meta_lines.push(Line::from(vec![
+                Span::styled("Hint: ", Style::default().fg(Color::Cyan).bold()),
+                Span::styled(hint.clone(), Style::default().fg(Color::Cyan).underlined()),
+            ]));
+        }
         let meta_para = Paragraph::new(meta_lines)
             .block(meta_block)
             .alignment(Alignment::Center);
         frame.render_widget(meta_para, area);
     }
 
+    fn cache_spans(&self) -> Vec<Span<'static>> {
+        let mut text = format!(
+            "{} entries ({}",
+            format::count(self.cache.entries),
+            format::bytes(self.cache.bytes)
+        );
+        let mut color = Color::Green;
+        if self.cache.budget > 0 {
+            text.push_str(&format!(" of {}", format::bytes(self.cache.budget)));
+            if self.cache.bytes >= self.cache.budget {
+                text.push_str(", budget reached, results truncated");
+                color = Color::Red;
+            } else if self.cache.bytes as f64
+                >= self.cache.budget as f64 * MEMORY_BUDGET_WARNING_RATIO
+            {
+                color = Color::Yellow;
+            }
+        }
+        text.push(')');
+
+        let mut spans = vec![
+            Span::styled(" | ", Style::default().fg(Color::White)),
+            Span::styled("Cache: ", Style::default().fg(color).bold()),
+            Span::styled(text, Style::default().fg(color).bold()),
+        ];
+        if self.searching {
+            spans.push(Span::styled(
+                " still searching…",
+                Style::default().fg(Color::Yellow).bold(),
+            ));
+        } else if self.timed_out {
+            spans.push(Span::styled(
+                " partial, search timed out",
+                Style::default().fg(Color::Red).bold(),
+            ));
+        }
+        spans
+    }
+
     pub fn render_search_section(&self, area: Rect, frame: &mut Frame) {
-        let search_block = Block::default().borders(Borders::ALL);
+        let mut search_block = Block::default().borders(Borders::ALL);
+        if let Some(error) = &self.search_error {
+            search_block = search_block
+                .border_style(Style::default().fg(Color::Red))
+                .title(Line::from(format!(" invalid regex: {} ", error)).right_aligned());
+        } else if self.search_regex {
+            search_block = search_block.title(
+                Line::from(" regex ")
+                    .right_aligned()
+                    .style(Style::default().fg(Color::DarkGray)),
+            );
+        }
         let search_lines = Line::from(vec![
             Span::styled("Search: ", Style::default().fg(Color::Green).bold()),
             Span::styled(self.search_value.clone(), Style::default()),
@@ -175,31 +1231,113 @@ impl<'a> Renderer<'a> {
     }
 
     pub fn render_logs_section(&mut self, area: Rect, frame: &mut Frame) {
+        // the search matches case-insensitively, as typed or as a regex
+        let search = sbsearch::session::search_regex(&self.search_value, self.search_regex)
+            .ok()
+            .flatten();
+        let height = area.height.saturating_sub(2) as usize;
+        let selected = self.nav_state.selected();
+        let mut overflow = 0;
         let mut lines: Vec<ListItem> = self
             .entries
             .iter()
-            .map(|entry| {
+            .enumerate()
+            .map(|(index, entry)| {
                 let width = frame.area().as_size().width as usize;
-                let options = Options::new(width);
-                let text = format!("{}", entry);
-                let wrapped = textwrap::fill(text.as_str(), options);
-                let list_item = match entry.level.as_str() {
-                    "error" => ListItem::new(wrapped).red(),
-                    "warn" | "warning" => ListItem::new(wrapped).yellow(),
-                    _ => ListItem::new(wrapped),
+                let indent = " ".repeat(COLUMNS_WIDTH);
+                let mut options = Options::new(width);
+                if self.display.columns {
+                    options = options.subsequent_indent(&indent);
+                }
+                let mut text = match entry.duplicates {
+                    0 => format!("{}", entry),
+                    n => format!("×{} {}", n + 1, entry),
+                };
+                if self.display.show_source {
+                    text = format!("[{}] {}", short_source(entry), text);
+                }
+                let wrapped = match (self.display.wrap, self.display.columns) {
+                    (true, true) => textwrap::fill(
+                        &columns(entry, &text, self.display.tz, self.display.anchor),
+                        options,
+                    ),
+                    (true, false) => textwrap::fill(text.as_str(), options),
+                    (false, true) => format!(
+                        "{}{}",
+                        columns(entry, "", self.display.tz, self.display.anchor),
+                        single_line(&text, self.display.horizontal_scroll)
+                    ),
+                    (false, false) => single_line(&text, self.display.horizontal_scroll),
                 };
-                if !self.search_value.is_empty()
-                    && text
-                        .clone()
-                        .to_lowercase()
-                        .contains(self.search_value.clone().to_lowercase().as_str())
-                {
-                    list_item.on_blue()
-                } else {
-                    list_item
+                let mut highlighted: Vec<Line> = wrapped
+                    .lines()
+                    .map(|line| {
+                        highlight(
+                            line,
+                            self.keyword_matcher,
+                            search.as_ref(),
+                            self.display.logfmt,
+                        )
+                    })
+                    .collect();
+                // the bookmarks are marked in a gutter, shown once there are any
+                if !self.bookmarks.is_empty() {
+                    let bookmarked = self
+                        .bookmarks
+                        .iter()
+                        .any(|b| b.path == entry.path && b.line_number == entry.line_number);
+                    for (index, line) in highlighted.iter_mut().enumerate() {
+                        let mark = match bookmarked && index == 0 {
+                            true => Span::styled("◆ ", Style::default().fg(Color::Cyan).bold()),
+                            false => Span::raw("  "),
+                        };
+                        line.spans.insert(0, mark);
+                    }
+                }
+                // the expanded entry is shown between the lines around it, dimmed
+                if let Some(context) = self.context.filter(|context| context.is_of(entry)) {
+                    let gutter = match self.bookmarks.is_empty() {
+                        true => "",
+                        false => "  ",
+                    };
+                    let dimmed = |lines: &[String]| -> Vec<Line<'static>> {
+                        lines
+                            .iter()
+                            .flat_map(|line| {
+                                let text = match self.display.wrap {
+                                    true => textwrap::fill(line, Options::new(width)),
+                                    false => single_line(line, self.display.horizontal_scroll),
+                                };
+                                text.lines()
+                                    .map(|row| {
+                                        Line::styled(
+                                            format!("{}{}", gutter, row),
+                                            Style::default().fg(Color::DarkGray),
+                                        )
+                                    })
+                                    .collect::<Vec<Line>>()
+                            })
+                            .collect()
+                    };
+                    let mut expanded = dimmed(&context.before);
+                    expanded.append(&mut highlighted);
+                    expanded.extend(dimmed(&context.after));
+                    highlighted = expanded;
+                }
+                if selected == Some(index) {
+                    (highlighted, overflow) =
+                        clip_rows(highlighted, self.display.entry_scroll, height);
+                }
+                let manifest = entry.fields.get("kind").is_some_and(|k| k == "manifest");
+                match entry.level {
+                    _ if manifest => ListItem::new(highlighted).cyan().italic(),
+                    Level::Error | Level::Fatal => ListItem::new(highlighted).red(),
+                    Level::Warn => ListItem::new(highlighted).yellow(),
+                    _ => ListItem::new(highlighted),
                 }
             })
             .collect();
+        self.selected_overflow = overflow;
         if lines.is_empty() {
             lines = vec![ListItem::new("No log entries found.".to_string())];
         }
@@ -225,18 +1363,207 @@ impl<'a> Renderer<'a> {
     }
 }
 
+/// Returns where the entry comes from, shortened to fit before its content,
+/// e.g. `default/virt-launcher-vm-00/compute.log` for pod logs, or
+/// `node1/kubelet.log` for node logs.
+pub fn short_source(entry: &sbsearch::Entry) -> String {
+    let file = entry.path.rsplit('/').next().unwrap_or_default();
+    match (entry.namespace(), entry.pod(), entry.node()) {
+        (Some(namespace), Some(pod), _) => format!("{}/{}/{}", namespace, pod, file),
+        (_, _, Some(node)) => format!("{}/{}", node, file),
+        _ => String::from(file),
+    }
+}
+
+/// Prefixes the first line of `text`, the content of `entry`, with the
+/// timestamp and the level of the entry in aligned columns, and indents the
+/// other lines under the content.
+pub fn columns(
+    entry: &sbsearch::Entry,
+    text: &str,
+    display_tz: AssumeTz,
+    anchor: Option<DateTime<Utc>>,
+) -> String {
+    let timestamp = entry
+        .timestamp
+        .map(|t| match anchor {
+            Some(anchor) => relative(t, anchor),
+            None => display_tz.format(t, "%Y-%m-%d %H:%M:%S%.3f"),
+        })
+        .unwrap_or_default();
+    let prefix = format!(
+        "{:<23} {:<7} ",
+        timestamp,
+        entry.level.as_str().to_uppercase()
+    );
+    let indent = " ".repeat(COLUMNS_WIDTH);
+    let mut lines = text.lines();
+    let mut columned = format!("{}{}", prefix, lines.next().unwrap_or_default());
+    for line in lines {
+        columned.push('\n');
+        columned.push_str(&indent);
+        columned.push_str(line);
+    }
+    columned
+}
+
+/// Formats `timestamp` as the time since `anchor`, e.g. `T+00:03:12.041`, or
+/// `T-00:00:05.000` before it. The hours go past 24.
+pub fn relative(timestamp: DateTime<Utc>, anchor: DateTime<Utc>) -> String {
+    let delta = timestamp - anchor;
+    let sign = if delta < chrono::TimeDelta::zero() {
+        '-'
+    } else {
+        '+'
+    };
+    let millis = delta.num_milliseconds().unsigned_abs();
+    format!(
+        "T{}{:02}:{:02}:{:02}.{:03}",
+        sign,
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Keeps the rows of an entry that fit in `height` rows, from `scroll` rows
+/// down, between markers of the rows left out above and below. Returns them
+/// with the number of rows that don't fit, which is as far as it scrolls.
+pub fn clip_rows(
+    rows: Vec<Line<'static>>,
+    scroll: usize,
+    height: usize,
+) -> (Vec<Line<'static>>, usize) {
+    if rows.len() <= height || height < 3 {
+        return (rows, 0);
+    }
+    let shown = height - 2;
+    let overflow = rows.len() - shown;
+    let scroll = scroll.min(overflow);
+    let marker =
+        |text: String| Line::from(Span::styled(text, Style::default().fg(Color::DarkGray)));
+    let mut clipped = vec![marker(format!("↑ {} more lines", scroll))];
+    clipped.extend(rows.into_iter().skip(scroll).take(shown));
+    clipped.push(marker(format!("↓ {} more lines", overflow - scroll)));
+    (clipped, overflow)
+}
+
+/// Styles the matches of the keyword in the line, and over them the matches
+/// of the search, keeping the color of the level of the entry readable
+/// around them.
+pub fn highlight(
+    line: &str,
+    keyword_matcher: Option<&RegexMatcher>,
+    search: Option<&Regex>,
+    logfmt: bool,
+) -> Line<'static> {
+    // the style of each byte: 0 for none, 1 for the keyword, 2 for the search,
+    // and 3 to 5 for the keys, values and messages of logfmt pairs
+    let mut marks = vec![0u8; line.len()];
+    if logfmt {
+        for (range, part) in super::logfmt::pairs(line) {
+            let mark = match part {
+                super::logfmt::Part::Key => 3,
+                super::logfmt::Part::Value => 4,
+                super::logfmt::Part::Message => 5,
+            };
+            marks[range].fill(mark);
+        }
+    }
+    if let Some(matcher) = keyword_matcher {
+        let _ = matcher.find_iter(line.as_bytes(), |m| {
+            marks[m.start()..m.end()].fill(1);
+            true
+        });
+    }
+    if let Some(search) = search {
+        for m in search.find_iter(line) {
+            marks[m.start()..m.end()].fill(2);
+        }
+    }
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for end in 1..=line.len() {
+        if end < line.len() && (marks[end] == marks[start] || !line.is_char_boundary(end)) {
+            continue;
+        }
+        let text = line[start..end].to_string();
+        spans.push(match marks[start] {
+            1 => Span::styled(text, Style::default().bold().reversed()),
+            2 => Span::styled(text, Style::default().fg(Color::White).bg(Color::Blue)),
+            3 => Span::styled(text, super::logfmt::Part::Key.style()),
+            4 => Span::styled(text, super::logfmt::Part::Value.style()),
+            5 => Span::styled(text, super::logfmt::Part::Message.style()),
+            _ => Span::raw(text),
+        });
+        start = end;
+    }
+    Line::from(spans)
+}
+
+/// Shows an entry on one line, from the `skip`th character: its first line,
+/// followed by the number of lines left out. The list cuts it off at the edge
+/// of the screen.
+pub fn single_line(text: &str, skip: usize) -> String {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or_default();
+    let line = match lines.count() {
+        0 => first.to_string(),
+        more => format!("{} (+{} lines)", first, more),
+    };
+    line.chars().skip(skip).collect()
+}
+
 pub fn split_main_layout(r: Rect) -> Rc<[Rect]> {
     Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Length(4),
+            Constraint::Length(6),
             Constraint::Length(3),
             Constraint::Fill(1),
         ])
         .split(r)
 }
 
+/// Splits the logs section into the pinned section on top, tall enough for a
+/// few pinned entries, and the logs below.
+pub fn split_pinned_layout(r: Rect, pinned: usize) -> Rc<[Rect]> {
+    let height = pinned.min(MAX_PINNED_ROWS) as u16 + 2;
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Fill(1)])
+        .split(r)
+}
+
+/// Splits the logs section into the file tree sidebar on the left and the
+/// logs on the right.
+pub fn split_files_layout(r: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Fill(1)])
+        .split(r)
+}
+
+/// Splits the logs section into the logs on the left and the detail pane on
+/// the right.
+pub fn split_detail_layout(r: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Fill(1), Constraint::Percentage(45)])
+        .split(r)
+}
+
+/// Splits the logs section into the rate panel on top and the logs below.
+pub fn split_rate_layout(r: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Fill(1)])
+        .split(r)
+}
+
 fn split_popup_layout(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_area = Layout::default()
         .direction(Direction::Vertical)
@@ -255,3 +1582,182 @@ fn split_popup_layout(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_area[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    #[test]
+    fn test_draw_too_small() {
+        assert!(too_small(Rect::new(0, 0, MIN_WIDTH - 1, MIN_HEIGHT)));
+        assert!(too_small(Rect::new(0, 0, MIN_WIDTH, MIN_HEIGHT - 1)));
+        assert!(!too_small(Rect::new(0, 0, MIN_WIDTH, MIN_HEIGHT)));
+
+        let mut terminal = Terminal::new(TestBackend::new(30, 5)).unwrap();
+        terminal.draw(draw_too_small).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("terminal too small"));
+        assert!(text.contains("30x5, need ≥ 60x16"));
+    }
+
+    #[test]
+    fn test_short_source() {
+        let entry = |path: &str, fields: &[(&str, &str)]| sbsearch::Entry {
+            level: Level::Info,
+            path: String::from(path),
+            content: String::new(),
+            timestamp: None,
+            line_number: 1,
+            fields: fields
+                .iter()
+                .map(|(k, v)| (String::from(*k), String::from(*v)))
+                .collect(),
+            duplicates: 0,
+        };
+        let pod_log = entry(
+            "sb/logs/default/virt-launcher-vm-00/compute.log",
+            &[("namespace", "default"), ("pod", "virt-launcher-vm-00")],
+        );
+        assert_eq!(
+            short_source(&pod_log),
+            "default/virt-launcher-vm-00/compute.log"
+        );
+        let node_log = entry(
+            "sb/nodes/node1.zip/node1/logs/kubelet.log",
+            &[("node", "node1")],
+        );
+        assert_eq!(short_source(&node_log), "node1/kubelet.log");
+        assert_eq!(short_source(&entry("sb/yamls/pods.yaml", &[])), "pods.yaml");
+    }
+
+    #[test]
+    fn test_columns() {
+        let mut entry = sbsearch::Entry {
+            level: Level::Warn,
+            path: String::from("sb/logs/a.log"),
+            content: String::from("first\nsecond\n"),
+            timestamp: Some("2025-12-30T21:51:44.5Z".parse().unwrap()),
+            line_number: 1,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        let text = columns(&entry, &entry.content, AssumeTz::Utc, None);
+        assert_eq!(
+            text,
+            "2025-12-30 21:51:44.500 WARN    first\n                                second"
+        );
+        assert_eq!(
+            text.find("second").unwrap() - text.find('\n').unwrap() - 1,
+            COLUMNS_WIDTH
+        );
+
+        // entries without a timestamp keep the columns aligned
+        entry.timestamp = None;
+        entry.level = Level::Unknown;
+        assert_eq!(
+            columns(&entry, "first", AssumeTz::Utc, None),
+            format!("{}UNKNOWN first", " ".repeat(24))
+        );
+
+        // the timestamps count from the anchor once there's one
+        entry.timestamp = Some("2025-12-30T21:51:44.5Z".parse().unwrap());
+        let anchor = "2025-12-30T21:48:32.459Z".parse().unwrap();
+        assert_eq!(
+            columns(&entry, "first", AssumeTz::Utc, Some(anchor)),
+            format!("T+00:03:12.041{}UNKNOWN first", " ".repeat(10))
+        );
+        let anchor = "2025-12-31T23:51:44.5Z".parse().unwrap();
+        assert_eq!(relative(entry.timestamp.unwrap(), anchor), "T-26:00:00.000");
+    }
+
+    #[test]
+    fn test_clip_rows() {
+        let rows: Vec<Line<'static>> = (0..10).map(|n| Line::from(n.to_string())).collect();
+        let text = |rows: &[Line]| rows.iter().map(Line::to_string).collect::<Vec<String>>();
+
+        let (clipped, overflow) = clip_rows(rows.clone(), 0, 10);
+        assert_eq!((clipped.len(), overflow), (10, 0));
+
+        let (clipped, overflow) = clip_rows(rows.clone(), 0, 5);
+        assert_eq!(overflow, 7);
+        assert_eq!(
+            text(&clipped),
+            vec!["↑ 0 more lines", "0", "1", "2", "↓ 7 more lines"]
+        );
+        let (clipped, _) = clip_rows(rows.clone(), 3, 5);
+        assert_eq!(
+            text(&clipped),
+            vec!["↑ 3 more lines", "3", "4", "5", "↓ 4 more lines"]
+        );
+        let (clipped, _) = clip_rows(rows, 100, 5);
+        assert_eq!(
+            text(&clipped),
+            vec!["↑ 7 more lines", "7", "8", "9", "↓ 0 more lines"]
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let options = sbsearch::SearchOptions::default();
+        let matcher = sbsearch::keyword_matcher("vm-00", &options).unwrap();
+        let search = Regex::new("(?i)failed").unwrap();
+
+        let line = highlight(
+            "attach vm-00 FAILED: vm-00 gone",
+            Some(&matcher),
+            Some(&search),
+            false,
+        );
+        let spans: Vec<(&str, Style)> = line
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        let keyword = Style::default().bold().reversed();
+        let searched = Style::default().fg(Color::White).bg(Color::Blue);
+        assert_eq!(
+            spans,
+            vec![
+                ("attach ", Style::default()),
+                ("vm-00", keyword),
+                (" ", Style::default()),
+                ("FAILED", searched),
+                (": ", Style::default()),
+                ("vm-00", keyword),
+                (" gone", Style::default()),
+            ]
+        );
+
+        // multibyte characters are kept whole
+        let line = highlight("×2 vm-00 ✓", Some(&matcher), None, false);
+        assert_eq!(line.to_string(), "×2 vm-00 ✓");
+        assert_eq!(line.spans.len(), 3);
+
+        // the keyword stands out over the logfmt pairs
+        let line = highlight("vm=vm-00 msg=gone", Some(&matcher), None, true);
+        let spans: Vec<(&str, Style)> = line
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("vm", Style::default().fg(Color::Cyan)),
+                ("=", Style::default()),
+                ("vm-00", keyword),
+                (" ", Style::default()),
+                ("msg", Style::default().fg(Color::Cyan)),
+                ("=", Style::default()),
+                ("gone", Style::default().bold()),
+            ]
+        );
+    }
+}
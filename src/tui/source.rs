@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::path::Path;
+
+/// The number of lines shown above the entry when the source file opens.
+const LINES_BEFORE: usize = 10;
+
+/// The source file of an entry, scrolled to the lines of the entry, to see
+/// what was logged right before and after it.
+#[derive(Debug, Default)]
+pub struct SourceView {
+    pub path: String,
+    pub lines: Vec<String>,
+    /// The index of the first line of the entry.
+    pub first: usize,
+    /// The index of the last line of the entry.
+    pub last: usize,
+    /// The index of the line at the top of the screen.
+    pub top: usize,
+    /// The number of lines that fit on the screen, as of the last draw.
+    pub height: usize,
+}
+
+impl SourceView {
+    /// Reads the file of the entry, which may be inside a node archive.
+    pub fn open(entry: &sbsearch::Entry) -> Result<Self, Box<dyn Error>> {
+        let content = sbsearch::read_source(Path::new(&entry.path))?;
+        let lines: Vec<String> = String::from_utf8_lossy(&content)
+            .lines()
+            .map(String::from)
+            .collect();
+        let first = (entry.line_number as usize)
+            .saturating_sub(1)
+            .min(lines.len().saturating_sub(1));
+        let last =
+            (first + entry.content.lines().count().max(1) - 1).min(lines.len().saturating_sub(1));
+        Ok(SourceView {
+            path: entry.path.clone(),
+            lines,
+            first,
+            last,
+            top: first.saturating_sub(LINES_BEFORE),
+            height: 0,
+        })
+    }
+
+    /// Returns true if the line at `index` is of the entry.
+    pub fn is_entry_line(&self, index: usize) -> bool {
+        (self.first..=self.last).contains(&index)
+    }
+
+    /// Scrolls by `rows` down, up to the last screen of the file.
+    pub fn scroll(&mut self, rows: isize) {
+        self.top = self.top.saturating_add_signed(rows).min(self.max_top());
+    }
+
+    pub fn scroll_page(&mut self, pages: isize) {
+        self.scroll(pages * self.height.max(1) as isize);
+    }
+
+    pub fn scroll_to_start(&mut self) {
+        self.top = 0;
+    }
+
+    pub fn scroll_to_end(&mut self) {
+        self.top = self.max_top();
+    }
+
+    /// Scrolls back to the lines of the entry.
+    pub fn scroll_to_entry(&mut self) {
+        self.top = self.first.saturating_sub(LINES_BEFORE).min(self.max_top());
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height.max(1))
+    }
+}
+
+/// The lines of the source file around an entry, shown in place around it in
+/// the logs.
+#[derive(Debug, Default)]
+pub struct Context {
+    pub path: String,
+    pub line_number: u64,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl Context {
+    /// Reads up to `lines` lines before and after the entry from its file,
+    /// which may be inside a node archive.
+    pub fn read(entry: &sbsearch::Entry, lines: usize) -> Result<Self, Box<dyn Error>> {
+        let view = SourceView::open(entry)?;
+        let after = (view.last + 1).min(view.lines.len());
+        Ok(Context {
+            path: entry.path.clone(),
+            line_number: entry.line_number,
+            before: view.lines[view.first.saturating_sub(lines)..view.first].to_vec(),
+            after: view.lines[after..(after + lines).min(view.lines.len())].to_vec(),
+        })
+    }
+
+    /// Returns true if these are the lines around `entry`.
+    pub fn is_of(&self, entry: &sbsearch::Entry) -> bool {
+        self.path == entry.path && self.line_number == entry.line_number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open() {
+        let entry = sbsearch::Entry {
+            level: sbsearch::Level::Info,
+            path: String::from(
+                "testdata/support_bundle/nodes/isim-dev.zip/isim-dev/logs/containerd.log",
+            ),
+            content: String::from("first\nsecond\n"),
+            timestamp: None,
+            line_number: 20,
+            fields: Default::default(),
+            duplicates: 0,
+        };
+        let mut view = SourceView::open(&entry).unwrap();
+        assert!(view.lines.len() > 40);
+        assert_eq!((view.first, view.last), (19, 20));
+        assert_eq!(view.top, 9);
+        assert!(view.is_entry_line(20));
+        assert!(!view.is_entry_line(21));
+
+        view.height = 10;
+        view.scroll_to_end();
+        assert_eq!(view.top, view.lines.len() - 10);
+        view.scroll(5);
+        assert_eq!(view.top, view.lines.len() - 10);
+        view.scroll_page(-1);
+        assert_eq!(view.top, view.lines.len() - 20);
+        view.scroll_to_start();
+        assert_eq!(view.top, 0);
+        view.scroll_to_entry();
+        assert_eq!(view.top, 9);
+
+        let context = Context::read(&entry, 3).unwrap();
+        assert!(context.is_of(&entry));
+        assert_eq!(context.before, view.lines[16..19]);
+        assert_eq!(context.after, view.lines[21..24]);
+        let first = sbsearch::Entry {
+            line_number: 1,
+            ..entry.clone()
+        };
+        let context = Context::read(&first, 3).unwrap();
+        assert!(!context.is_of(&entry));
+        assert!(context.before.is_empty());
+        assert_eq!(context.after.len(), 3);
+
+        let entry = sbsearch::Entry {
+            path: String::from("testdata/support_bundle/noexist.log"),
+            ..entry
+        };
+        assert!(SourceView::open(&entry).is_err());
+        assert!(Context::read(&entry, 3).is_err());
+    }
+}
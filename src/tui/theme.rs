@@ -0,0 +1,152 @@
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use std::env;
+
+/// What the terminal can display. Terminals without color or unicode support,
+/// e.g. serial consoles and minimal SSH clients, get bold/underline styling and
+/// ASCII symbols instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    pub color: bool,
+    pub unicode: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            color: true,
+            unicode: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Detects the capabilities from the `NO_COLOR`, `TERM` and locale
+    /// environment variables.
+    pub fn detect() -> Self {
+        Self::from_env(|key| env::var(key).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let limited_term = term == "dumb" || term.starts_with("vt");
+        let no_color = var("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+        // an unset locale is assumed to support unicode
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|key| var(key).filter(|v| !v.is_empty()))
+            .map(|v| v.to_lowercase());
+        let utf8 = locale.is_none_or(|l| l.contains("utf-8") || l.contains("utf8"));
+
+        Capabilities {
+            color: !no_color && !limited_term,
+            unicode: utf8 && !limited_term,
+        }
+    }
+
+    /// Rewrites the rendered buffer for what the terminal can display.
+    pub fn degrade(&self, buf: &mut Buffer) {
+        if self.color && self.unicode {
+            return;
+        }
+
+        for cell in buf.content.iter_mut() {
+            if !self.color {
+                // errors stand out in bold, warnings underlined, and the
+                // highlighted lines in reverse video
+                match cell.fg {
+                    Color::Red | Color::LightRed => cell.modifier |= Modifier::BOLD,
+                    Color::Yellow | Color::LightYellow => cell.modifier |= Modifier::UNDERLINED,
+                    _ => {}
+                }
+                if cell.bg != Color::Reset {
+                    cell.modifier |= Modifier::REVERSED;
+                }
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            }
+
+            if !self.unicode && !cell.symbol().is_ascii() {
+                let symbol = ascii_symbol(cell.symbol());
+                cell.set_char(symbol);
+            }
+        }
+    }
+}
+
+fn ascii_symbol(symbol: &str) -> char {
+    match symbol {
+        "↑" => '^',
+        "↓" => 'v',
+        "…" => '.',
+        "×" => 'x',
+        "█" | "▇" | "▆" | "▅" | "▄" | "▃" | "▂" | "▁" => '#',
+        "─" | "━" | "═" => '-',
+        "│" | "┃" | "║" => '|',
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "┏" | "┓" | "┗" | "┛" | "╔" | "╗" | "╚"
+        | "╝" | "├" | "┤" | "┬" | "┴" | "┼" => '+',
+        _ => '?',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| String::from(*v))
+        }
+    }
+
+    #[test]
+    fn test_from_env() {
+        let caps =
+            Capabilities::from_env(env(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]));
+        assert_eq!(caps, Capabilities::default());
+
+        let caps = Capabilities::from_env(env(&[("TERM", "xterm"), ("NO_COLOR", "1")]));
+        assert!(!caps.color);
+        assert!(caps.unicode);
+
+        let caps = Capabilities::from_env(env(&[("TERM", "xterm"), ("LC_ALL", "C")]));
+        assert!(caps.color);
+        assert!(!caps.unicode);
+
+        let caps = Capabilities::from_env(env(&[("TERM", "vt100"), ("LANG", "en_US.UTF-8")]));
+        assert!(!caps.color);
+        assert!(!caps.unicode);
+    }
+
+    #[test]
+    fn test_degrade() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buf.set_string(0, 0, "↑", Style::default().fg(Color::Red));
+        buf.set_string(1, 0, "│", Style::default().bg(Color::LightMagenta));
+        buf.set_string(2, 0, "a", Style::default().fg(Color::Yellow));
+
+        Capabilities::default().degrade(&mut buf);
+        assert_eq!(buf.content[0].symbol(), "↑");
+        assert_eq!(buf.content[0].fg, Color::Red);
+
+        let caps = Capabilities {
+            color: false,
+            unicode: false,
+        };
+        caps.degrade(&mut buf);
+        assert_eq!(buf.content[0].symbol(), "^");
+        assert_eq!(buf.content[0].fg, Color::Reset);
+        assert!(buf.content[0].modifier.contains(Modifier::BOLD));
+        assert_eq!(buf.content[1].symbol(), "|");
+        assert!(buf.content[1].modifier.contains(Modifier::REVERSED));
+        assert_eq!(buf.content[2].symbol(), "a");
+        assert!(buf.content[2].modifier.contains(Modifier::UNDERLINED));
+    }
+}